@@ -2,6 +2,8 @@ SECTIONS
 {
   . = 0x1000;
   .text : { *(.text .text.*) }
+  .botracers.abi : { KEEP(*(.botracers.abi)) }
+  .botracers.build_id : { KEEP(*(.botracers.build_id)) }
   .data : { *(.data) }
   .bss : { *(.bss) }
 }
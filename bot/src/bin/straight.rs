@@ -3,7 +3,7 @@
 
 use core::fmt::Write;
 
-use botracers_bot_sdk::{SLOT3, driving::CarControls, log};
+use botracers_bot_sdk::{SLOT3, driving::CarControls, log, yield_tick};
 
 #[unsafe(export_name = "main")]
 fn main() -> ! {
@@ -11,7 +11,9 @@ fn main() -> ! {
 
     let mut car_controls = CarControls::bind(SLOT3);
 
-    car_controls.set_accelerator(1.0);
-
-    loop {}
+    loop {
+        // Rewritten every tick, or the controls watchdog takes over.
+        car_controls.set_accelerator(1.0);
+        yield_tick();
+    }
 }
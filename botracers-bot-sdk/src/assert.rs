@@ -0,0 +1,97 @@
+//! [`sdk_assert!`](crate::sdk_assert) support: a failed assertion is written
+//! to the log slot before the bot halts, so the host console shows why it
+//! stopped.
+
+use core::fmt::{Arguments, Write};
+
+/// Whether `sdk_assert!` checks anything; the `release` feature compiles the
+/// checks out (the condition is still type-checked, never evaluated).
+pub const ENABLED: bool = cfg!(not(feature = "release"));
+
+/// Writes the failure report for `expr` at `file:line`, followed by the
+/// optional message, as one line.
+pub fn write_failure<W: Write>(
+    out: &mut W,
+    expr: &str,
+    file: &str,
+    line: u32,
+    message: Option<Arguments<'_>>,
+) -> core::fmt::Result {
+    write!(out, "assertion failed: `{expr}` at {file}:{line}")?;
+    if let Some(message) = message {
+        write!(out, ": {message}")?;
+    }
+    writeln!(out)
+}
+
+/// Reports a failed assertion to the log slot and halts the bot.
+#[cold]
+pub fn failed(expr: &str, file: &str, line: u32, message: Option<Arguments<'_>>) -> ! {
+    write_failure(&mut crate::log(), expr, file, line, message).ok();
+    loop {
+        crate::yield_tick();
+    }
+}
+
+/// Like `assert!`, but a failure writes the expression, its location and the
+/// optional message to the log slot and then halts, instead of panicking.
+///
+/// ```ignore
+/// sdk_assert!(speed >= 0.0, "negative speed {speed}");
+/// ```
+#[macro_export]
+macro_rules! sdk_assert {
+    ($cond:expr $(,)?) => {
+        if $crate::assert::ENABLED && !($cond) {
+            $crate::assert::failed(stringify!($cond), file!(), line!(), None)
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if $crate::assert::ENABLED && !($cond) {
+            $crate::assert::failed(
+                stringify!($cond),
+                file!(),
+                line!(),
+                Some(format_args!($($arg)+)),
+            )
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::string::String;
+
+    use super::write_failure;
+
+    #[test]
+    fn failure_report_names_the_expression_and_location() {
+        let mut out = String::new();
+        write_failure(&mut out, "speed >= 0.0", "src/main.rs", 42, None).unwrap();
+        assert_eq!(out, "assertion failed: `speed >= 0.0` at src/main.rs:42\n");
+
+        let mut out = String::new();
+        let speed = -1.5;
+        write_failure(
+            &mut out,
+            "speed >= 0.0",
+            "src/main.rs",
+            42,
+            Some(format_args!("negative speed {speed}")),
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "assertion failed: `speed >= 0.0` at src/main.rs:42: negative speed -1.5\n"
+        );
+    }
+
+    #[test]
+    fn passing_assertions_do_not_halt() {
+        let laps = 3;
+        crate::sdk_assert!(laps > 0);
+        crate::sdk_assert!(laps < 10, "too many laps: {laps}");
+    }
+}
@@ -1,7 +1,11 @@
 use core::ptr;
 
-use bevy_math::Vec2;
+use bevy_math::{Vec2, ops};
 
+pub mod fixed;
+
+/// The car's accelerator, brake and steering. Write them every tick: when a
+/// bot goes about a second without writing any, the game lets its car coast.
 pub struct CarControls {
     accelerator: *mut f32,
     brake: *mut f32,
@@ -48,8 +52,24 @@ pub struct CarState {
     position_y: *const f32,
     forward_x: *const f32,
     forward_y: *const f32,
+    applied_steering: *const f32,
+    applied_throttle: *const f32,
+    applied_brake: *const f32,
+    centerline_offset: *const f32,
+    laps_completed: *const u32,
+    race_laps: *const u32,
+    tire_wear: *const f32,
+    finished: *const u32,
+    cycles_per_tick: *const u32,
+    brake_bias: *const f32,
+    brake_force_front: *const f32,
+    brake_force_rear: *const f32,
+    params: *const f32,
 }
 
+/// Number of tuning params readable through [`CarState::param`].
+pub const PARAM_COUNT: usize = 8;
+
 impl CarState {
     pub const fn bind(slot: usize) -> Self {
         Self {
@@ -58,6 +78,19 @@ impl CarState {
             position_y: (slot + 0x08) as *const f32,
             forward_x: (slot + 0x0C) as *const f32,
             forward_y: (slot + 0x10) as *const f32,
+            applied_steering: (slot + 0x14) as *const f32,
+            applied_throttle: (slot + 0x18) as *const f32,
+            applied_brake: (slot + 0x1C) as *const f32,
+            centerline_offset: (slot + 0x20) as *const f32,
+            laps_completed: (slot + 0x24) as *const u32,
+            race_laps: (slot + 0x28) as *const u32,
+            tire_wear: (slot + 0x2C) as *const f32,
+            finished: (slot + 0x30) as *const u32,
+            cycles_per_tick: (slot + 0x34) as *const u32,
+            brake_bias: (slot + 0x38) as *const f32,
+            brake_force_front: (slot + 0x3C) as *const f32,
+            brake_force_rear: (slot + 0x40) as *const f32,
+            params: (slot + 0x80) as *const f32,
         }
     }
     pub fn speed(&self) -> f32 {
@@ -79,6 +112,81 @@ impl CarState {
             )
         }
     }
+    /// Steering the car applied last tick, after actuator rate limits.
+    pub fn applied_steering(&self) -> f32 {
+        unsafe { ptr::read_volatile(self.applied_steering) }
+    }
+    /// Throttle the car applied last tick, after actuator rate limits.
+    pub fn applied_throttle(&self) -> f32 {
+        unsafe { ptr::read_volatile(self.applied_throttle) }
+    }
+    /// Brake the car applied last tick, after actuator rate limits.
+    pub fn applied_brake(&self) -> f32 {
+        unsafe { ptr::read_volatile(self.applied_brake) }
+    }
+    /// Signed distance from the track centre line: positive right of it,
+    /// negative left, looking along the lap direction. Steering by
+    /// `-centerline_offset()` pulls the car back onto the line.
+    pub fn centerline_offset(&self) -> f32 {
+        unsafe { ptr::read_volatile(self.centerline_offset) }
+    }
+    /// Laps this car has completed in the current race.
+    pub fn laps_completed(&self) -> u32 {
+        unsafe { ptr::read_volatile(self.laps_completed) }
+    }
+    /// Laps the race runs for; 0 when it has no lap target (practice).
+    pub fn race_laps(&self) -> u32 {
+        unsafe { ptr::read_volatile(self.race_laps) }
+    }
+    /// Laps left until this car finishes; 0 once it has, and in races
+    /// without a lap target (practice).
+    pub fn laps_remaining(&self) -> u32 {
+        self.race_laps().saturating_sub(self.laps_completed())
+    }
+    /// Whether this car has taken the checkered flag. Further laps do not
+    /// count, so a bot can ease off. Never set without a lap target.
+    pub fn finished(&self) -> bool {
+        unsafe { ptr::read_volatile(self.finished) != 0 }
+    }
+    /// Instructions the bot may run per physics tick before it is cut off
+    /// until the next one. Work that does not fit is better spread over
+    /// ticks, ending each with `yield_tick()`.
+    pub fn cycles_per_tick(&self) -> u32 {
+        unsafe { ptr::read_volatile(self.cycles_per_tick) }
+    }
+    /// Tire wear over the current race, from 0 on fresh tires to 1 when
+    /// worn out. Worn tires grip less, and hard cornering, braking and
+    /// acceleration wear them faster.
+    pub fn tire_wear(&self) -> f32 {
+        unsafe { ptr::read_volatile(self.tire_wear) }
+    }
+    /// Share of the brake force this car sends to the front axle, 0..1
+    /// (`--brake-bias`). More front bias is stable under braking; more
+    /// rear bias turns in but locks the rear sooner.
+    pub fn brake_bias(&self) -> f32 {
+        unsafe { ptr::read_volatile(self.brake_bias) }
+    }
+    /// Brake force on the front and rear axle last tick in newtons, after
+    /// each axle is capped at what its tires can hold. A capped axle is
+    /// locked: braking harder adds nothing there.
+    pub fn brake_forces(&self) -> (f32, f32) {
+        unsafe {
+            (
+                ptr::read_volatile(self.brake_force_front),
+                ptr::read_volatile(self.brake_force_rear),
+            )
+        }
+    }
+    /// Tuning param `index` set by the host (`--param=<index>=<value>`), so
+    /// constants can change without a rebuild. Unset params read as 0.0;
+    /// indices from [`PARAM_COUNT`] on read as NaN.
+    pub fn param(&self, index: usize) -> f32 {
+        if index >= PARAM_COUNT {
+            return f32::NAN;
+        }
+
+        unsafe { ptr::read_volatile(self.params.add(index)) }
+    }
 }
 
 pub struct SplineQuery {
@@ -227,3 +335,160 @@ impl CarRadar {
         }
     }
 }
+
+/// Steering limit the helpers clamp to, in radians (the game's full lock).
+pub const MAX_STEERING: f32 = core::f32::consts::FRAC_PI_6;
+
+/// Below this speed difference the throttle eases off proportionally.
+const THROTTLE_RAMP_MPS: f32 = 4.0;
+
+/// Steering that points the car at a target given relative to the car as
+/// `(right, forward)`, e.g. a waypoint rotated into the car's frame. Positive
+/// steering turns right. The result is the heading error clamped to
+/// [`MAX_STEERING`].
+pub fn steer_toward(target_rel: (f32, f32)) -> f32 {
+    let (right, forward) = target_rel;
+    if right == 0.0 && forward == 0.0 {
+        return 0.0;
+    }
+    ops::atan2(right, forward).clamp(-MAX_STEERING, MAX_STEERING)
+}
+
+/// Throttle in `0..=1` that approaches `target` speed from `current` (both in
+/// m/s): full throttle well below target, easing off over the last few m/s,
+/// and none at or above it. Slowing down is left to the brake.
+pub fn throttle_for_speed(current: f32, target: f32) -> f32 {
+    ((target - current) / THROTTLE_RAMP_MPS).clamp(0.0, 1.0)
+}
+
+/// Allocation-free PID controller for speed, heading or line-offset loops.
+///
+/// The integral is clamped to `±integral_limit` (error × seconds) so a long
+/// saturation cannot wind it up, and the output is clamped to
+/// `output_min..=output_max`. Both are unbounded unless set.
+#[derive(Debug, Clone, Copy)]
+pub struct Pid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub integral_limit: f32,
+    pub output_min: f32,
+    pub output_max: f32,
+    integral: f32,
+    prev_error: Option<f32>,
+}
+
+impl Pid {
+    pub const fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral_limit: f32::INFINITY,
+            output_min: f32::NEG_INFINITY,
+            output_max: f32::INFINITY,
+            integral: 0.0,
+            prev_error: None,
+        }
+    }
+
+    pub const fn with_output_limits(mut self, min: f32, max: f32) -> Self {
+        self.output_min = min;
+        self.output_max = max;
+        self
+    }
+
+    pub const fn with_integral_limit(mut self, limit: f32) -> Self {
+        self.integral_limit = limit;
+        self
+    }
+
+    /// Accumulated integral of the error, in error × seconds.
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Forgets the integral and previous error, e.g. after a respawn.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.prev_error = None;
+    }
+
+    /// Feeds `error = setpoint - measurement` after `dt` seconds and returns
+    /// the clamped control output. The first update has no derivative term;
+    /// a non-positive `dt` only applies the proportional term.
+    pub fn update(&mut self, error: f32, dt: f32) -> f32 {
+        let mut derivative = 0.0;
+        if dt > 0.0 {
+            self.integral =
+                (self.integral + error * dt).clamp(-self.integral_limit, self.integral_limit);
+            if let Some(prev) = self.prev_error {
+                derivative = (error - prev) / dt;
+            }
+            self.prev_error = Some(error);
+        }
+        let output = self.kp * error + self.ki * self.integral + self.kd * derivative;
+        output.clamp(self.output_min, self.output_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_STEERING, Pid, steer_toward, throttle_for_speed};
+
+    #[test]
+    fn steering_turns_toward_the_side_of_the_target() {
+        assert_eq!(steer_toward((0.0, 10.0)), 0.0);
+        assert!(steer_toward((2.0, 10.0)) > 0.0);
+        assert!(steer_toward((-2.0, 10.0)) < 0.0);
+        assert_eq!(steer_toward((2.0, 10.0)), -steer_toward((-2.0, 10.0)));
+
+        // Further off-axis means more steering, up to full lock.
+        assert!(steer_toward((4.0, 10.0)) > steer_toward((2.0, 10.0)));
+        assert_eq!(steer_toward((10.0, 0.0)), MAX_STEERING);
+        assert_eq!(steer_toward((-10.0, -10.0)), -MAX_STEERING);
+        assert_eq!(steer_toward((0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn throttle_eases_off_near_target_speed() {
+        assert_eq!(throttle_for_speed(0.0, 20.0), 1.0);
+        assert_eq!(throttle_for_speed(18.0, 20.0), 0.5);
+        assert_eq!(throttle_for_speed(20.0, 20.0), 0.0);
+        assert_eq!(throttle_for_speed(25.0, 20.0), 0.0);
+    }
+
+    #[test]
+    fn pid_converges_on_a_speed_setpoint() {
+        let mut pid = Pid::new(0.5, 0.2, 0.05)
+            .with_output_limits(0.0, 1.0)
+            .with_integral_limit(20.0);
+        let dt = 0.01;
+        let setpoint = 20.0;
+        let mut speed = 0.0;
+        for _ in 0..6000 {
+            let throttle = pid.update(setpoint - speed, dt);
+            assert!((0.0..=1.0).contains(&throttle));
+            // Throttle accelerates, drag grows with speed.
+            speed += (throttle * 6.0 - 0.2 * speed) * dt;
+        }
+        assert!((speed - setpoint).abs() < 0.1, "settled at {speed}");
+    }
+
+    #[test]
+    fn pid_integral_is_clamped() {
+        let mut pid = Pid::new(0.0, 1.0, 0.0).with_integral_limit(2.0);
+        for _ in 0..1000 {
+            pid.update(10.0, 0.1);
+        }
+        assert_eq!(pid.integral(), 2.0);
+        assert_eq!(pid.update(10.0, 0.1), 2.0);
+
+        // Unwinding starts immediately once the error flips.
+        pid.update(-10.0, 0.1);
+        assert!(pid.integral() < 2.0);
+
+        pid.reset();
+        assert_eq!(pid.integral(), 0.0);
+    }
+}
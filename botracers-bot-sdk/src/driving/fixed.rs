@@ -0,0 +1,317 @@
+//! Integer variants of [`CarControls`](super::CarControls) and
+//! [`CarState`](super::CarState) for bots built without the `f` extension
+//! (e.g. `riscv32imac`).
+//!
+//! The slots keep their layout and still hold `f32`s; these bindings convert
+//! on the integer side so a bot never touches a float. Every value is scaled
+//! by [`SCALE`] ("milli-units"):
+//!
+//! | Field                               | Float unit | Fixed unit      |
+//! |-------------------------------------|------------|-----------------|
+//! | accelerator, brake, applied_*       | `0..=1`    | `0..=1000`      |
+//! | steering, applied_steering          | radians    | milliradians    |
+//! | speed                               | m/s        | mm/s            |
+//! | position, centerline_offset         | m          | mm              |
+//! | forward                             | unit       | thousandths     |
+//! | tire_wear, brake_bias               | `0..=1`    | `0..=1000`      |
+//! | brake_forces                        | N          | mN              |
+//! | params                              | as set     | value × 1000    |
+//!
+//! Writes store the `f32` nearest to `milli / 1000`, the same bits as
+//! `milli as f32 / 1000.0`. Reads round `value × 1000` to the nearest
+//! integer (halves away from zero) and saturate like `as i32`, with NaN
+//! reading as 0. Lap counters, the finished flag and the cycle budget are
+//! integers already.
+
+use core::ptr;
+
+/// Fixed-point units per float unit.
+pub const SCALE: i32 = 1000;
+
+/// [`MAX_STEERING`](super::MAX_STEERING) in milliradians.
+pub const MAX_STEERING: i32 = 524;
+
+/// The car's accelerator, brake and steering in milli-units. Like
+/// [`CarControls`](super::CarControls), write them every tick.
+pub struct CarControls {
+    accelerator: *mut u32,
+    brake: *mut u32,
+    steering: *mut u32,
+}
+
+impl CarControls {
+    pub const fn bind(slot: usize) -> Self {
+        Self {
+            accelerator: slot as *mut u32,
+            brake: (slot + 0x04) as *mut u32,
+            steering: (slot + 0x08) as *mut u32,
+        }
+    }
+    /// Accelerator in `0..=1000`.
+    pub fn set_accelerator(&mut self, milli: i32) {
+        unsafe {
+            ptr::write_volatile(self.accelerator, milli_to_f32_bits(milli));
+        }
+    }
+    /// Brake in `0..=1000`.
+    pub fn set_brake(&mut self, milli: i32) {
+        unsafe {
+            ptr::write_volatile(self.brake, milli_to_f32_bits(milli));
+        }
+    }
+    /// Steering in milliradians, positive to the right, up to
+    /// ±[`MAX_STEERING`].
+    pub fn set_steering(&mut self, milli: i32) {
+        unsafe {
+            ptr::write_volatile(self.steering, milli_to_f32_bits(milli));
+        }
+    }
+    pub fn accelerator(&self) -> i32 {
+        unsafe { f32_bits_to_milli(ptr::read_volatile(self.accelerator)) }
+    }
+    pub fn brake(&self) -> i32 {
+        unsafe { f32_bits_to_milli(ptr::read_volatile(self.brake)) }
+    }
+    pub fn steering(&self) -> i32 {
+        unsafe { f32_bits_to_milli(ptr::read_volatile(self.steering)) }
+    }
+}
+
+/// The car's sensors in milli-units; see [`CarState`](super::CarState) for
+/// what each field means.
+pub struct CarState {
+    slot: usize,
+}
+
+impl CarState {
+    pub const fn bind(slot: usize) -> Self {
+        Self { slot }
+    }
+    fn read_milli(&self, offset: usize) -> i32 {
+        unsafe { f32_bits_to_milli(ptr::read_volatile((self.slot + offset) as *const u32)) }
+    }
+    fn read_u32(&self, offset: usize) -> u32 {
+        unsafe { ptr::read_volatile((self.slot + offset) as *const u32) }
+    }
+    /// Speed in mm/s.
+    pub fn speed(&self) -> i32 {
+        self.read_milli(0x00)
+    }
+    /// Position as `(x, y)` in mm.
+    pub fn position(&self) -> (i32, i32) {
+        (self.read_milli(0x04), self.read_milli(0x08))
+    }
+    /// Heading as `(x, y)` in thousandths of a unit vector.
+    pub fn forward(&self) -> (i32, i32) {
+        (self.read_milli(0x0C), self.read_milli(0x10))
+    }
+    /// Steering applied last tick in milliradians.
+    pub fn applied_steering(&self) -> i32 {
+        self.read_milli(0x14)
+    }
+    /// Throttle applied last tick in `0..=1000`.
+    pub fn applied_throttle(&self) -> i32 {
+        self.read_milli(0x18)
+    }
+    /// Brake applied last tick in `0..=1000`.
+    pub fn applied_brake(&self) -> i32 {
+        self.read_milli(0x1C)
+    }
+    /// Signed distance from the track centre line in mm, positive right.
+    pub fn centerline_offset(&self) -> i32 {
+        self.read_milli(0x20)
+    }
+    pub fn laps_completed(&self) -> u32 {
+        self.read_u32(0x24)
+    }
+    pub fn race_laps(&self) -> u32 {
+        self.read_u32(0x28)
+    }
+    pub fn laps_remaining(&self) -> u32 {
+        self.race_laps().saturating_sub(self.laps_completed())
+    }
+    /// Tire wear in `0..=1000`.
+    pub fn tire_wear(&self) -> i32 {
+        self.read_milli(0x2C)
+    }
+    /// Front share of the brake force in `0..=1000`.
+    pub fn brake_bias(&self) -> i32 {
+        self.read_milli(0x38)
+    }
+    /// Front and rear axle brake force last tick in mN.
+    pub fn brake_forces(&self) -> (i32, i32) {
+        (self.read_milli(0x3C), self.read_milli(0x40))
+    }
+    pub fn finished(&self) -> bool {
+        self.read_u32(0x30) != 0
+    }
+    pub fn cycles_per_tick(&self) -> u32 {
+        self.read_u32(0x34)
+    }
+    /// Tuning param `index` × 1000. Indices from
+    /// [`PARAM_COUNT`](super::PARAM_COUNT) on read as 0.
+    pub fn param(&self, index: usize) -> i32 {
+        if index >= super::PARAM_COUNT {
+            return 0;
+        }
+        self.read_milli(0x80 + index * 4)
+    }
+}
+
+/// Bits of the `f32` nearest to `milli / 1000`, ties to even, using only
+/// integer arithmetic.
+pub const fn milli_to_f32_bits(milli: i32) -> u32 {
+    if milli == 0 {
+        return 0;
+    }
+    let sign = if milli < 0 { 0x8000_0000 } else { 0 };
+    let n = milli.unsigned_abs() as u64;
+    // Scale n so the quotient lands in [2^23, 2^24): a 24-bit mantissa. n is
+    // below 2^32, so n << shift stays below 1000 << 24 < 2^34.
+    let mut shift = 34 - (64 - n.leading_zeros());
+    if (n << shift) / 1000 >= 1 << 24 {
+        shift -= 1;
+    }
+    while (n << shift) / 1000 < 1 << 23 {
+        shift += 1;
+    }
+    let scaled = n << shift;
+    let mut mantissa = scaled / 1000;
+    let remainder = scaled % 1000;
+    if remainder > 500 || (remainder == 500 && mantissa & 1 == 1) {
+        mantissa += 1;
+    }
+    if mantissa == 1 << 24 {
+        mantissa >>= 1;
+        shift -= 1;
+    }
+    // value = mantissa * 2^-shift, so the unbiased exponent is 23 - shift.
+    let exponent = (127 + 23 - shift as i32) as u32;
+    sign | (exponent << 23) | (mantissa as u32 & 0x007F_FFFF)
+}
+
+/// The `f32` with `bits` times 1000, rounded to the nearest integer with
+/// halves away from zero. Out-of-range values saturate and NaN reads as 0.
+pub const fn f32_bits_to_milli(bits: u32) -> i32 {
+    let negative = bits & 0x8000_0000 != 0;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let fraction = (bits & 0x007F_FFFF) as u64;
+    if exponent == 0xFF {
+        if fraction != 0 {
+            return 0;
+        }
+        return if negative { i32::MIN } else { i32::MAX };
+    }
+    if exponent == 0 {
+        // Subnormals are far below a thousandth.
+        return 0;
+    }
+    // value * 1000 = (mantissa * 1000) * 2^(exponent - 150), exactly. From
+    // exponent 150 on the value is at least 2^23, far beyond i32 in milli.
+    if exponent >= 150 {
+        return if negative { i32::MIN } else { i32::MAX };
+    }
+    let product = (fraction | 0x0080_0000) * 1000;
+    let down = (150 - exponent) as u32;
+    let magnitude = if down >= 64 {
+        0
+    } else {
+        (product + (1 << (down - 1))) >> down
+    };
+    if negative {
+        if magnitude >= 1 << 31 {
+            i32::MIN
+        } else {
+            -(magnitude as i32)
+        }
+    } else if magnitude > i32::MAX as u64 {
+        i32::MAX
+    } else {
+        magnitude as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CarControls, CarState, MAX_STEERING, f32_bits_to_milli, milli_to_f32_bits};
+
+    #[test]
+    fn fixed_controls_store_the_same_bits_as_float_controls() {
+        for milli in [-1000, -524, -1, 0, 1, 3, 250, 333, 500, 999, 1000, 123_456] {
+            let mut fixed_slot = [0u32; 3];
+            let mut float_slot = [0u32; 3];
+            let mut fixed = CarControls::bind(fixed_slot.as_mut_ptr() as usize);
+            let mut float = super::super::CarControls::bind(float_slot.as_mut_ptr() as usize);
+
+            fixed.set_accelerator(milli);
+            fixed.set_brake(-milli);
+            fixed.set_steering(milli);
+            let value = milli as f32 / 1000.0;
+            float.set_accelerator(value);
+            float.set_brake(-milli as f32 / 1000.0);
+            float.set_steering(value);
+
+            assert_eq!(fixed_slot, float_slot, "milli {milli}");
+            assert_eq!(fixed.steering(), milli);
+        }
+    }
+
+    #[test]
+    fn every_control_value_in_range_matches_the_float_conversion() {
+        for milli in -2000..=2000 {
+            assert_eq!(
+                milli_to_f32_bits(milli),
+                (milli as f32 / 1000.0).to_bits(),
+                "milli {milli}"
+            );
+            assert_eq!(f32_bits_to_milli(milli_to_f32_bits(milli)), milli);
+        }
+        assert_eq!(
+            f32_bits_to_milli(milli_to_f32_bits(MAX_STEERING)),
+            MAX_STEERING
+        );
+        assert_eq!(
+            f32_bits_to_milli(super::super::MAX_STEERING.to_bits()),
+            MAX_STEERING
+        );
+    }
+
+    #[test]
+    fn fixed_state_reads_float_slots_in_milli_units() {
+        let mut slot = [0u32; 0x80 / 4 + super::super::PARAM_COUNT];
+        slot[0] = 12.3456f32.to_bits();
+        slot[0x04 / 4] = (-40.0005f32).to_bits();
+        slot[0x08 / 4] = 1e9f32.to_bits();
+        slot[0x0C / 4] = 0.70710677f32.to_bits();
+        slot[0x10 / 4] = (-0.70710677f32).to_bits();
+        slot[0x20 / 4] = f32::NAN.to_bits();
+        slot[0x24 / 4] = 2;
+        slot[0x28 / 4] = 3;
+        slot[0x2C / 4] = 0.0004f32.to_bits();
+        slot[0x38 / 4] = 0.4f32.to_bits();
+        slot[0x3C / 4] = 1538.4615f32.to_bits();
+        slot[0x40 / 4] = 2307.6924f32.to_bits();
+        slot[0x80 / 4 + 1] = 1.5f32.to_bits();
+
+        let fixed = CarState::bind(slot.as_ptr() as usize);
+        let float = super::super::CarState::bind(slot.as_ptr() as usize);
+        let expected = |value: f32| (value as f64 * 1000.0).round() as i32;
+
+        assert_eq!(fixed.speed(), expected(float.speed()));
+        assert_eq!(fixed.speed(), 12346);
+        let position = float.position();
+        assert_eq!(fixed.position(), (expected(position.x), i32::MAX));
+        let forward = float.forward();
+        assert_eq!(fixed.forward(), (707, -707));
+        assert_eq!(fixed.forward().0, expected(forward.x));
+        assert_eq!(fixed.centerline_offset(), 0);
+        assert_eq!(fixed.laps_remaining(), float.laps_remaining());
+        assert_eq!(fixed.tire_wear(), 0);
+        assert_eq!(fixed.brake_bias(), 400);
+        assert_eq!(fixed.brake_forces(), (1_538_462, 2_307_692));
+        let (front, rear) = float.brake_forces();
+        assert_eq!(fixed.brake_forces(), (expected(front), expected(rear)));
+        assert_eq!(fixed.param(1), 1500);
+        assert_eq!(fixed.param(super::super::PARAM_COUNT), 0);
+    }
+}
@@ -2,8 +2,11 @@
 
 use crate::log::Log;
 
+pub mod assert;
 pub mod driving;
 pub mod log;
+pub mod math;
+pub mod prelude;
 
 pub const SLOT1: usize = 0x100;
 pub const SLOT2: usize = 0x200;
@@ -11,11 +14,61 @@ pub const SLOT3: usize = 0x300;
 pub const SLOT4: usize = 0x400;
 pub const SLOT5: usize = 0x500;
 pub const SLOT6: usize = 0x600;
+pub const SLOT7: usize = 0x700;
+
+/// Bot ABI version this SDK targets; the game refuses bots built for another
+/// one. Keep in sync with `botracers_protocol::BOT_ABI_VERSION`.
+pub const ABI_VERSION: u32 = 1;
+
+/// Records [`ABI_VERSION`] in the image; `link.x` must `KEEP` the section.
+#[cfg(target_os = "none")]
+#[used]
+#[unsafe(link_section = ".botracers.abi")]
+static ABI_VERSION_RECORD: u32 = ABI_VERSION;
+
+/// Source revision the bot was built from, taken from `BOTRACERS_BUILD_ID` at
+/// compile time. The server records it when the artifact is uploaded.
+pub const BUILD_ID: Option<&str> = option_env!("BOTRACERS_BUILD_ID");
+
+#[cfg(target_os = "none")]
+const BUILD_ID_BYTES: &str = match BUILD_ID {
+    Some(id) => id,
+    None => "",
+};
+
+/// Records [`BUILD_ID`] in the image (empty when unset); `link.x` must `KEEP`
+/// the section.
+#[cfg(target_os = "none")]
+#[used]
+#[unsafe(link_section = ".botracers.build_id")]
+static BUILD_ID_RECORD: [u8; BUILD_ID_BYTES.len()] = {
+    let mut record = [0; BUILD_ID_BYTES.len()];
+    let bytes = BUILD_ID_BYTES.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        record[i] = bytes[i];
+        i += 1;
+    }
+    record
+};
 
 pub fn log() -> Log {
     Log::bind(SLOT1)
 }
 
+/// Hands the rest of this physics tick's instruction budget back to the host.
+/// The bot resumes right after the call on the next tick, with its state
+/// intact and a fresh budget; call it once this tick's controls are written
+/// instead of spinning.
+#[inline(always)]
+pub fn yield_tick() {
+    // The host treats `ecall` as a yield and resumes at the next instruction.
+    #[cfg(target_arch = "riscv32")]
+    unsafe {
+        core::arch::asm!("ecall");
+    }
+}
+
 #[cfg(all(feature = "panic-handler", target_os = "none"))]
 mod panic_support {
     use core::{fmt::Write, panic::PanicInfo};
@@ -25,7 +78,9 @@ mod panic_support {
     #[panic_handler]
     fn panic(panic_info: &PanicInfo<'_>) -> ! {
         writeln!(log(), "{}", panic_info).ok();
-        loop {}
+        loop {
+            crate::yield_tick();
+        }
     }
 }
 
@@ -1,22 +1,317 @@
 use core::{fmt::Write, ptr};
 
+const DATA: usize = 0x00;
+const WRITTEN: usize = 0x04;
+const CONSUMED: usize = 0x08;
+const CAPACITY: usize = 0x0C;
+
+const METRIC_ID: usize = 0x00;
+const METRIC_VALUE: usize = 0x04;
+
+/// Writer for the host log slot.
+///
+/// The host buffers at most `capacity` characters and publishes how many it
+/// has accepted (`written`) and drained (`consumed`). Writes wait until the
+/// host has room, so output is never lost or overwritten.
 pub struct Log {
-    mem: *mut char,
+    base: usize,
 }
 
 impl Log {
     pub const fn bind(slot: usize) -> Self {
-        Self {
-            mem: slot as *mut char,
-        }
+        Self { base: slot }
+    }
+
+    /// Characters the host can accept right now.
+    pub fn free(&self) -> usize {
+        let written = self.read(WRITTEN);
+        let consumed = self.read(CONSUMED);
+        let capacity = self.read(CAPACITY);
+        capacity.saturating_sub(written.wrapping_sub(consumed)) as usize
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        unsafe { ptr::read_volatile((self.base + offset) as *const u32) }
     }
 }
 
 impl Write for Log {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        s.chars().for_each(|c| unsafe {
-            ptr::write_volatile(self.mem, c);
-        });
+        for c in s.chars() {
+            while self.free() == 0 {
+                core::hint::spin_loop();
+            }
+            unsafe {
+                ptr::write_volatile((self.base + DATA) as *mut char, c);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writer for the host metrics slot.
+///
+/// Each [`Metrics::record`] stores the metric id, then the value; the host
+/// files the value under the last id written, stamped with the current tick.
+pub struct Metrics {
+    base: usize,
+}
+
+impl Metrics {
+    pub const fn bind(slot: usize) -> Self {
+        Self { base: slot }
+    }
+
+    pub fn record(&mut self, id: u32, value: f32) {
+        unsafe {
+            ptr::write_volatile((self.base + METRIC_ID) as *mut u32, id);
+            ptr::write_volatile((self.base + METRIC_VALUE) as *mut f32, value);
+        }
+    }
+}
+
+/// Records `value` for metric `id` this tick, e.g. `metric(0, speed)`.
+pub fn metric(id: u32, value: f32) {
+    Metrics::bind(crate::SLOT7).record(id, value);
+}
+
+/// What a [`LogBuffer`] does when a new character does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Discard the oldest complete lines and report how many were lost with a
+    /// `(N lines dropped)` marker on the next flush. A single line longer than
+    /// the buffer is truncated but keeps its line break.
+    DropOldest,
+    /// Push buffered lines to the sink immediately, ignoring the flush budget.
+    /// With a [`Log`] sink this waits for the host to make room.
+    Block,
+}
+
+/// Fixed-capacity ring of `N` characters in front of a log sink.
+///
+/// Writes only fill the ring; [`LogBuffer::flush`] forwards complete lines to
+/// the sink, at most `budget` characters per call, so a chatty bot can bound
+/// how much it hands to the host each tick. Lines are never split on output.
+pub struct LogBuffer<W: Write, const N: usize> {
+    sink: W,
+    chars: [char; N],
+    head: usize,
+    len: usize,
+    dropped: usize,
+    overflow: Overflow,
+}
+
+impl<W: Write, const N: usize> LogBuffer<W, N> {
+    pub const fn new(sink: W, overflow: Overflow) -> Self {
+        Self {
+            sink,
+            chars: ['\0'; N],
+            head: 0,
+            len: 0,
+            dropped: 0,
+            overflow,
+        }
+    }
+
+    /// Number of characters waiting to be flushed.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Lines dropped since the last flush that reported them.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn sink(&self) -> &W {
+        &self.sink
+    }
+
+    /// Forwards complete lines to the sink while they fit in `budget`
+    /// characters, preceded by a drop marker if lines were lost. Returns the
+    /// number of buffered characters written.
+    pub fn flush(&mut self, budget: usize) -> Result<usize, core::fmt::Error> {
+        if self.dropped > 0 {
+            writeln!(self.sink, "({} lines dropped)", self.dropped)?;
+            self.dropped = 0;
+        }
+        let mut written = 0;
+        while let Some(line_len) = self.first_line_len() {
+            if written + line_len > budget {
+                break;
+            }
+            self.pop_to_sink(line_len)?;
+            written += line_len;
+        }
+        Ok(written)
+    }
+
+    fn push(&mut self, c: char) -> core::fmt::Result {
+        if self.len == N {
+            match self.overflow {
+                Overflow::DropOldest => match self.first_line_len() {
+                    Some(line_len) => {
+                        self.head = (self.head + line_len) % N;
+                        self.len -= line_len;
+                        self.dropped += 1;
+                    }
+                    None => {
+                        // Keep the truncated line terminated so it does not
+                        // run into the next one.
+                        if c == '\n' && N > 0 {
+                            self.chars[(self.head + N - 1) % N] = '\n';
+                        }
+                        return Ok(());
+                    }
+                },
+                Overflow::Block => {
+                    // With no complete line buffered, the pending line is as
+                    // long as the ring and has to go out unterminated.
+                    let out = self.complete_len().unwrap_or(self.len);
+                    self.pop_to_sink(out)?;
+                }
+            }
+        }
+        if N == 0 {
+            return Ok(());
+        }
+        self.chars[(self.head + self.len) % N] = c;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Length of the oldest buffered line including its `\n`, if complete.
+    fn first_line_len(&self) -> Option<usize> {
+        (0..self.len)
+            .find(|&i| self.chars[(self.head + i) % N] == '\n')
+            .map(|i| i + 1)
+    }
+
+    /// Length of all complete lines buffered, if there is at least one.
+    fn complete_len(&self) -> Option<usize> {
+        (0..self.len)
+            .rev()
+            .find(|&i| self.chars[(self.head + i) % N] == '\n')
+            .map(|i| i + 1)
+    }
+
+    fn pop_to_sink(&mut self, count: usize) -> core::fmt::Result {
+        for _ in 0..count {
+            self.sink.write_char(self.chars[self.head])?;
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+        }
         Ok(())
     }
 }
+
+impl<const N: usize> LogBuffer<Log, N> {
+    /// Flushes as many complete lines as the host can take without waiting.
+    /// A pending drop marker is written first and may wait for room.
+    pub fn flush_available(&mut self) -> Result<usize, core::fmt::Error> {
+        let free = self.sink.free();
+        self.flush(free)
+    }
+}
+
+impl<W: Write, const N: usize> Write for LogBuffer<W, N> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        s.chars().try_for_each(|c| self.push(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::fmt::Write;
+    use std::string::String;
+
+    use super::{CAPACITY, CONSUMED, Log, LogBuffer, Metrics, Overflow, WRITTEN};
+
+    #[test]
+    fn flush_forwards_only_complete_lines_within_budget() {
+        let mut log = LogBuffer::<String, 64>::new(String::new(), Overflow::DropOldest);
+        write!(log, "one\ntwo\nthr").unwrap();
+
+        assert_eq!(log.flush(6).unwrap(), 4);
+        assert_eq!(log.sink(), "one\n");
+        assert_eq!(log.flush(100).unwrap(), 4);
+        assert_eq!(log.sink(), "one\ntwo\n");
+
+        writeln!(log, "ee").unwrap();
+        log.flush(100).unwrap();
+        assert_eq!(log.sink(), "one\ntwo\nthree\n");
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn overflow_drops_oldest_whole_lines_and_reports_them() {
+        let mut log = LogBuffer::<String, 16>::new(String::new(), Overflow::DropOldest);
+        for i in 0..5 {
+            writeln!(log, "line {i}").unwrap();
+        }
+        assert_eq!(log.dropped(), 3);
+
+        log.flush(100).unwrap();
+        assert_eq!(log.sink(), "(3 lines dropped)\nline 3\nline 4\n");
+        assert_eq!(log.dropped(), 0);
+    }
+
+    #[test]
+    fn overflow_truncates_a_line_longer_than_the_buffer() {
+        let mut log = LogBuffer::<String, 8>::new(String::new(), Overflow::DropOldest);
+        write!(log, "abcdefghijkl").unwrap();
+        assert_eq!(log.len(), 8);
+        assert_eq!(log.flush(100).unwrap(), 0);
+
+        let mut log = LogBuffer::<String, 8>::new(String::new(), Overflow::DropOldest);
+        write!(log, "ab\ncdefghijkl\n").unwrap();
+        log.flush(100).unwrap();
+        assert_eq!(log.sink(), "(1 lines dropped)\ncdefghi\n");
+    }
+
+    #[test]
+    fn blocking_mode_never_drops() {
+        let mut log = LogBuffer::<String, 16>::new(String::new(), Overflow::Block);
+        for i in 0..5 {
+            writeln!(log, "line {i}").unwrap();
+        }
+        assert_eq!(log.dropped(), 0);
+
+        log.flush(100).unwrap();
+        assert_eq!(log.sink(), "line 0\nline 1\nline 2\nline 3\nline 4\n");
+    }
+
+    #[test]
+    fn log_free_space_follows_host_cursors() {
+        let mut slot = [0u32; 4];
+        slot[CAPACITY / 4] = 8;
+        slot[WRITTEN / 4] = 6;
+        slot[CONSUMED / 4] = 1;
+        let log = Log::bind(slot.as_mut_ptr() as usize);
+        assert_eq!(log.free(), 3);
+
+        slot[WRITTEN / 4] = 2;
+        slot[CONSUMED / 4] = u32::MAX - 3;
+        let log = Log::bind(slot.as_mut_ptr() as usize);
+        assert_eq!(log.free(), 2);
+
+        slot[CONSUMED / 4] = 0;
+        slot[WRITTEN / 4] = 12;
+        let log = Log::bind(slot.as_mut_ptr() as usize);
+        assert_eq!(log.free(), 0);
+    }
+
+    #[test]
+    fn metrics_write_the_id_before_the_value() {
+        let mut slot = [0u32; 2];
+        let mut metrics = Metrics::bind(slot.as_mut_ptr() as usize);
+        metrics.record(7, 1.5);
+        assert_eq!(slot, [7, 1.5f32.to_bits()]);
+    }
+}
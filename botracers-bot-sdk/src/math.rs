@@ -0,0 +1,82 @@
+//! `f32` vector helpers for bots. Sensor accessors return [`Vec2`], which
+//! already has `dot`, `length`, `perp_dot` and friends; the functions here
+//! cover the steps bots otherwise hand-roll.
+
+use core::f32::consts::{PI, TAU};
+
+pub use bevy_math::Vec2;
+use bevy_math::ops;
+
+/// Unit vector in the direction of `v`, or zero for a zero-length `v`
+/// (never NaN).
+pub fn normalize(v: Vec2) -> Vec2 {
+    v.normalize_or_zero()
+}
+
+/// `v` rotated counter-clockwise by `angle` radians.
+pub fn rotate(v: Vec2, angle: f32) -> Vec2 {
+    Vec2::from_angle(angle).rotate(v)
+}
+
+/// `angle` wrapped into `[-PI, PI)`.
+pub fn wrap_angle(angle: f32) -> f32 {
+    ops::rem_euclid(angle + PI, TAU) - PI
+}
+
+/// Signed angle that turns `from` onto `to`, counter-clockwise positive, in
+/// `(-PI, PI]`. Zero if either vector is zero.
+pub fn angle_between(from: Vec2, to: Vec2) -> f32 {
+    ops::atan2(from.perp_dot(to), from.dot(to))
+}
+
+/// World-space `offset` expressed in the frame of a car heading along
+/// `forward`, as `(right, forward)` — the input [`steer_toward`] expects.
+///
+/// [`steer_toward`]: crate::driving::steer_toward
+pub fn to_car_frame(offset: Vec2, forward: Vec2) -> (f32, f32) {
+    let forward = normalize(forward);
+    (offset.dot(-forward.perp()), offset.dot(forward))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f32::consts::{FRAC_PI_2, PI};
+
+    use super::{Vec2, angle_between, normalize, rotate, to_car_frame, wrap_angle};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn normalize_of_zero_is_zero() {
+        assert_eq!(normalize(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(normalize(Vec2::new(3.0, 4.0)), Vec2::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn angles_wrap_around_the_half_turn() {
+        assert!(close(wrap_angle(3.0 * FRAC_PI_2), -FRAC_PI_2));
+        assert!(close(wrap_angle(-3.0 * FRAC_PI_2), FRAC_PI_2));
+        assert!(close(wrap_angle(0.25), 0.25));
+
+        // 170° to -170° is a short 20° turn left, not 340° right.
+        let from = Vec2::from_angle(170f32.to_radians());
+        let to = Vec2::from_angle(-170f32.to_radians());
+        assert!(close(angle_between(from, to), 20f32.to_radians()));
+        assert!(close(angle_between(to, from), -20f32.to_radians()));
+        assert!(close(angle_between(Vec2::X, -Vec2::X).abs(), PI));
+        assert_eq!(angle_between(Vec2::ZERO, Vec2::X), 0.0);
+    }
+
+    #[test]
+    fn rotation_and_car_frame_agree() {
+        let v = rotate(Vec2::X, FRAC_PI_2);
+        assert!(close(v.x, 0.0) && close(v.y, 1.0));
+
+        // Car heading up (+y): a point to the east is on its right.
+        let (right, ahead) = to_car_frame(Vec2::new(2.0, 5.0), Vec2::Y);
+        assert!(close(right, 2.0) && close(ahead, 5.0));
+        assert!(crate::driving::steer_toward((right, ahead)) > 0.0);
+    }
+}
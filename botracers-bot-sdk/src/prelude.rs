@@ -0,0 +1,47 @@
+//! One-line import for bots: `use botracers_bot_sdk::prelude::*;` brings in
+//! the driving devices and helpers, `Vec2`, logging, metrics, `sdk_assert!`, `yield_tick`, the slot
+//! constants and `fmt::Write` for `writeln!(log(), ...)`.
+
+pub use core::fmt::Write;
+
+pub use crate::driving::{
+    CarControls, CarRadar, CarState, Pid, SplineQuery, TrackRadar, steer_toward, throttle_for_speed,
+};
+pub use crate::log::{Log, LogBuffer, Metrics, Overflow, metric};
+pub use crate::math::Vec2;
+pub use crate::{SLOT1, SLOT2, SLOT3, SLOT4, SLOT5, SLOT6, SLOT7, log, sdk_assert, yield_tick};
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::string::String;
+
+    // Deliberately nothing but the prelude: a bot written against it must build.
+    use crate::prelude::*;
+
+    fn step(state: &CarState, controls: &mut CarControls, radar: &TrackRadar) {
+        let steering = (-state.forward().x * 0.6).clamp(-0.5, 0.5);
+        controls.set_steering(steering);
+        let clear_ahead = radar.distances()[3] > 10.0;
+        controls.set_accelerator(if clear_ahead { 0.5 } else { 0.2 });
+        writeln!(log(), "speed {}", state.speed()).ok();
+    }
+
+    #[test]
+    fn prelude_is_enough_for_a_bot() {
+        let _step: fn(&CarState, &mut CarControls, &TrackRadar) = step;
+        let _ = (
+            CarState::bind(SLOT2),
+            CarControls::bind(SLOT3),
+            SplineQuery::bind(SLOT4),
+            TrackRadar::bind(SLOT5),
+            CarRadar::bind(SLOT6),
+        );
+
+        let mut buffered = LogBuffer::<String, 32>::new(String::new(), Overflow::Block);
+        writeln!(buffered, "lap {}", 1).unwrap();
+        buffered.flush(32).unwrap();
+        assert_eq!(buffered.sink(), "lap 1\n");
+    }
+}
@@ -1,23 +1,43 @@
-#[cfg(not(target_arch = "wasm32"))]
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 use base64::Engine;
 use bevy::prelude::*;
+use botracers_game::track_format::TrackFile;
 use botracers_protocol::{
-    ArtifactSummary, ServerCapabilities, UpdateArtifactVisibilityRequest, UploadArtifactRequest,
-    UploadArtifactResponse,
+    ArtifactId, ArtifactRaceResult, ArtifactScope, ArtifactSummary, BOT_ABI_VERSION,
+    DEFAULT_AUTH_HEADER, DEFAULT_AUTH_SCHEME, ImportArtifactRequest, RaceEntrySubmission,
+    RenameUserRequest, ReplayFile, ServerCapabilities, SubmitRaceRequest, SubmitRaceResponse,
+    UpdateArtifactVisibilityRequest, UploadArtifactRequest, UploadArtifactResponse, UserInfo,
 };
 #[cfg(not(target_arch = "wasm32"))]
-use botracers_protocol::{LoginRequest, LoginResponse};
+use botracers_protocol::{
+    LoginRequest, LoginResponse, RaceReview, RegisterRequest, RejectRaceRequest,
+};
 #[cfg(not(target_arch = "wasm32"))]
 use botracers_server::{AuthMode, ServerConfig};
 
-use crate::game_api::{DriverType, SpawnCarRequest, SpawnResolvedCarRequest, WebApiCommand};
-use crate::race_runtime::SimState;
+use crate::game_api::{
+    CancelSpawn, CarLoadOutcome, CarLoadReport, DriverType, LoadRaceSetup, RaceStartRefused,
+    SpawnCarRequest, SpawnResolvedCarRequest, WebApiCommand, local_file_name,
+};
+use crate::race_runtime::{GridLimits, SimState, replay_check};
+use crate::race_setup::RaceSetup;
+
+/// Number of artifacts requested per list page.
+const ARTIFACT_PAGE_SIZE: u32 = 20;
+/// Number of past races shown in the race history panel.
+const RACE_HISTORY_PAGE_SIZE: u32 = 10;
+/// How long fetched server capabilities are trusted before commands refetch them.
+const CAPABILITIES_TTL_SECS: f64 = 300.0;
+/// Most failed spawns `WebPortalState` keeps; older ones are dropped first.
+const MAX_SPAWN_FAILURES: usize = 8;
 
 pub struct BootstrapPlugin;
 
@@ -27,6 +47,7 @@ impl Plugin for BootstrapPlugin {
             .init_resource::<WebPortalState>()
             .init_resource::<WebApiQueue>()
             .init_resource::<ArtifactFetchPipeline>()
+            .init_resource::<LocalBotLibrary>()
             .add_systems(
                 Startup,
                 (initialize_bootstrap, trigger_initial_capability_check).chain(),
@@ -34,10 +55,18 @@ impl Plugin for BootstrapPlugin {
             .add_systems(
                 Update,
                 (
+                    send_pending_import.before(handle_web_api_commands),
+                    send_pending_rename.before(handle_web_api_commands),
+                    send_pending_race_setup,
+                    send_pending_replay.before(handle_web_api_commands),
                     handle_web_api_commands,
                     process_web_api_events,
-                    handle_spawn_car_request,
+                    handle_spawn_car_request.before(process_artifact_fetch_results),
+                    handle_cancel_spawn.before(process_artifact_fetch_results),
                     process_artifact_fetch_results,
+                    report_car_loads,
+                    report_race_start_refusals,
+                    limit_grid_to_server_field,
                 ),
             );
     }
@@ -47,19 +76,69 @@ impl Plugin for BootstrapPlugin {
 pub struct BootstrapConfig {
     pub standalone_mode: bool,
     pub standalone_bind: Option<String>,
+    /// Artifact URL passed with `--import=<url>`.
+    pub import_url: Option<String>,
+    /// Grid and race length passed with `--race=<code>`.
+    pub race_setup: Option<RaceSetup>,
+    /// New username passed with `--rename=<name>`.
+    pub rename_to: Option<String>,
+    /// Replay passed with `--replay=<id>`.
+    pub replay_id: Option<i64>,
+    /// Directory of bot ELFs passed with `--local-bots=<dir>`. When set the
+    /// game runs offline: no server, login or capabilities.
+    pub local_bots_dir: Option<PathBuf>,
+}
+
+/// The ELF files found in `BootstrapConfig::local_bots_dir`, listed in the
+/// "Local Bots" panel.
+#[derive(Resource, Default)]
+pub struct LocalBotLibrary {
+    pub dir: Option<PathBuf>,
+    pub drivers: Vec<DriverType>,
+}
+
+/// One `DriverType::LocalFile` per `.elf` file directly in `dir`, sorted by
+/// path.
+pub fn scan_local_bots(dir: &Path) -> std::io::Result<Vec<DriverType>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("elf"))
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths
+        .into_iter()
+        .map(|path| DriverType::LocalFile { path })
+        .collect())
 }
 
 pub struct CompileResult {
     pub id: u64,
     pub binary: String,
-    pub result: Result<Vec<u8>, String>,
+    pub result: Result<Vec<u8>, WebApiError>,
 }
 
 #[derive(Resource)]
 pub struct ArtifactFetchPipeline {
     pub async_results: Arc<Mutex<Vec<CompileResult>>>,
     pub pending: HashMap<u64, DriverType>,
+    /// Per-request abort flags shared with the fetch callbacks; set by `CancelSpawn`.
+    pub abort_flags: HashMap<u64, Arc<AtomicBool>>,
     pub next_request_id: u64,
+    /// ELFs already downloaded this session, so respawning an artifact (e.g.
+    /// for a rematch) skips the download. Artifacts are immutable, so entries
+    /// only go stale when the artifact is deleted.
+    pub elf_cache: HashMap<ArtifactId, Vec<u8>>,
+    /// Resolved spawns held back until no download is pending, then released
+    /// sorted by artifact id so the grid does not depend on which download
+    /// finished first.
+    pub ready: Vec<SpawnResolvedCarRequest>,
 }
 
 impl Default for ArtifactFetchPipeline {
@@ -67,27 +146,142 @@ impl Default for ArtifactFetchPipeline {
         Self {
             async_results: Arc::new(Mutex::new(Vec::<CompileResult>::new())),
             pending: HashMap::new(),
+            abort_flags: HashMap::new(),
             next_request_id: 1,
+            elf_cache: HashMap::new(),
+            ready: Vec::new(),
+        }
+    }
+}
+
+/// Why a web API request failed; `Display` gives the user-facing message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebApiError {
+    /// No response arrived (connection refused, DNS failure, ...).
+    Network(String),
+    /// The server answered with a non-success status other than 401.
+    Http { status: u16, message: String },
+    /// The server answered 401: the session or token is missing or expired.
+    Auth { message: String },
+    /// The response body did not match the expected payload.
+    Deserialize(String),
+    /// The request payload could not be encoded.
+    Serialize(String),
+    /// The response could not be saved to disk.
+    Io(String),
+}
+
+impl WebApiError {
+    /// Classifies a non-success response, keeping its status and body text.
+    /// The server answers 401 only when the credentials themselves are
+    /// missing or no longer valid; requests it refuses for a valid user,
+    /// e.g. someone else's private artifact, get 403.
+    fn from_response(resp: &ehttp::Response) -> Self {
+        let body = String::from_utf8_lossy(&resp.bytes);
+        let message = format!("HTTP {} {}: {}", resp.status, resp.status_text, body.trim());
+        if resp.status == 401 {
+            Self::Auth { message }
+        } else {
+            Self::Http {
+                status: resp.status,
+                message,
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for WebApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Network(error) => write!(f, "network error: {error}"),
+            Self::Http { message, .. } | Self::Auth { message } => f.write_str(message),
+            Self::Deserialize(message) | Self::Serialize(message) | Self::Io(message) => {
+                f.write_str(message)
+            }
         }
     }
 }
 
 #[derive(Debug, Clone)]
 enum WebApiEvent {
-    Capabilities(Result<ServerCapabilities, String>),
+    Capabilities(Result<ServerCapabilities, WebApiError>),
     #[cfg(not(target_arch = "wasm32"))]
-    Login(Result<LoginResponse, String>),
-    Artifacts(Result<Vec<ArtifactSummary>, String>),
-    UploadResult(Result<UploadArtifactResponse, String>),
+    Login(Result<LoginResponse, WebApiError>),
+    /// Carries the credentials so a successful registration can log in next.
+    #[cfg(not(target_arch = "wasm32"))]
+    Registered {
+        username: String,
+        password: String,
+        result: Result<UserInfo, WebApiError>,
+    },
+    Artifacts {
+        offset: u32,
+        result: Result<Vec<ArtifactSummary>, WebApiError>,
+    },
+    UploadResult(Result<UploadArtifactResponse, WebApiError>),
+    ImportResult {
+        url: String,
+        result: Result<UploadArtifactResponse, WebApiError>,
+    },
     DeleteResult {
-        artifact_id: i64,
-        result: Result<(), String>,
+        artifact_id: ArtifactId,
+        result: Result<(), WebApiError>,
     },
     VisibilityResult {
-        artifact_id: i64,
+        artifact_id: ArtifactId,
         is_public: bool,
-        result: Result<(), String>,
+        result: Result<(), WebApiError>,
+    },
+    CloneResult {
+        artifact_id: ArtifactId,
+        result: Result<UploadArtifactResponse, WebApiError>,
+    },
+    StarResult {
+        artifact_id: ArtifactId,
+        starred: bool,
+        result: Result<(), WebApiError>,
+    },
+    RaceHistory {
+        artifact_id: ArtifactId,
+        result: Result<Vec<ArtifactRaceResult>, WebApiError>,
+    },
+    RaceSubmitted(Result<SubmitRaceResponse, WebApiError>),
+    ReplayLoaded {
+        replay_id: i64,
+        result: Result<ReplayFile, WebApiError>,
     },
+    Renamed(Result<UserInfo, WebApiError>),
+    /// Where the artifact export tarball was saved.
+    #[cfg(not(target_arch = "wasm32"))]
+    ExportResult(Result<PathBuf, WebApiError>),
+}
+
+impl WebApiEvent {
+    /// Whether the server refused the request because the session is no
+    /// longer valid. Failed logins and registrations are about the
+    /// credentials themselves and never count.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn session_rejected(&self) -> bool {
+        let error = match self {
+            Self::Capabilities(result) => result.as_ref().err(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Login(_) | Self::Registered { .. } => None,
+            Self::Artifacts { result, .. } => result.as_ref().err(),
+            Self::UploadResult(result) => result.as_ref().err(),
+            Self::ImportResult { result, .. } | Self::CloneResult { result, .. } => {
+                result.as_ref().err()
+            }
+            Self::DeleteResult { result, .. }
+            | Self::VisibilityResult { result, .. }
+            | Self::StarResult { result, .. } => result.as_ref().err(),
+            Self::RaceHistory { result, .. } => result.as_ref().err(),
+            Self::RaceSubmitted(result) => result.as_ref().err(),
+            Self::ReplayLoaded { result, .. } => result.as_ref().err(),
+            Self::Renamed(result) => result.as_ref().err(),
+            Self::ExportResult(result) => result.as_ref().err(),
+        };
+        matches!(error, Some(WebApiError::Auth { .. }))
+    }
 }
 
 #[derive(Resource, Clone)]
@@ -103,17 +297,96 @@ impl Default for WebApiQueue {
     }
 }
 
+/// A car that could not be spawned, or whose bot trapped at init.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpawnFailure {
+    /// `DriverType::label` of the car.
+    pub driver: String,
+    pub error: String,
+}
+
 #[derive(Resource)]
 pub struct WebPortalState {
     pub server_url: String,
     pub standalone_mode: bool,
     pub auth_required: Option<bool>,
+    /// Whether the server accepts self-registration, from its capabilities.
+    pub registration_enabled: bool,
+    /// Visibility the server gives new uploads, from its capabilities.
+    pub default_artifact_public: bool,
+    /// Upload size limit reported by the server; files above it are not sent.
+    pub max_artifact_bytes: Option<u64>,
+    /// Most cars the server takes results for; `GridLimits` is capped to it.
+    pub max_field_size: Option<u32>,
+    /// Set when the server's major version differs from this client's.
+    pub version_warning: Option<String>,
+    /// Header and scheme for the session token, from the server's capabilities.
+    pub auth_header: String,
+    pub auth_scheme: String,
     #[cfg(not(target_arch = "wasm32"))]
     pub token: Option<String>,
     #[cfg(not(target_arch = "wasm32"))]
     pub cli_credentials: Option<(String, String)>,
     pub artifacts: Vec<ArtifactSummary>,
+    pub artifact_scope: ArtifactScope,
+    /// Offset of the next artifact page to request with `LoadMoreArtifacts`.
+    pub artifacts_next_offset: u32,
+    /// Set once the server returned a short page for the current scope.
+    pub artifacts_exhausted: bool,
+    /// Set when the first page for the current scope came back empty, as
+    /// opposed to no list loaded yet; the artifact list then shows
+    /// `empty_artifacts_hint` instead of rows.
+    pub artifacts_empty: bool,
+    /// Artifact whose race history is shown, most recent race first.
+    pub race_history_artifact: Option<ArtifactId>,
+    pub race_history: Vec<ArtifactRaceResult>,
+    /// URL to import as soon as the server accepts uploads from this player.
+    pub pending_import_url: Option<String>,
+    /// Username to switch to once the player is logged in.
+    pub pending_rename: Option<String>,
+    /// Shared setup to load once artifacts can be downloaded.
+    pub pending_race_setup: Option<RaceSetup>,
+    /// Replay to download and re-simulate once the player is logged in.
+    pub pending_replay: Option<i64>,
+    /// `Time::elapsed_secs_f64` when capabilities last arrived; commands
+    /// refetch them once this is `CAPABILITIES_TTL_SECS` old.
+    pub capabilities_fetched_at: Option<f64>,
+    /// Set while a capability request is in flight, so commands do not stack
+    /// duplicate fetches.
+    pub capabilities_pending: bool,
     pub status_message: Option<String>,
+    /// Failed spawns, oldest first, listed under the status until the player
+    /// dismisses them, so later status messages do not hide them.
+    pub spawn_failures: Vec<SpawnFailure>,
+}
+
+impl WebPortalState {
+    /// Shows a failed spawn in the status line and keeps it in
+    /// `spawn_failures`.
+    fn record_spawn_failure(&mut self, driver: &DriverType, error: String) {
+        let driver = driver.label();
+        self.status_message = Some(format!("[error][spawn] {driver}: {error}"));
+        if self.spawn_failures.len() == MAX_SPAWN_FAILURES {
+            self.spawn_failures.remove(0);
+        }
+        self.spawn_failures.push(SpawnFailure { driver, error });
+    }
+
+    /// What to do about an empty artifact list: star something for the
+    /// starred scope, otherwise upload a bot, and on servers where only
+    /// existing accounts can sign in, enable registration so others can too.
+    pub fn empty_artifacts_hint(&self) -> String {
+        if self.artifact_scope == ArtifactScope::Starred {
+            return "No starred bots yet: star one from the All list".to_string();
+        }
+        let mut hint = "No bots yet: build one with the bot SDK and press Upload".to_string();
+        if self.auth_required == Some(true) && !self.registration_enabled {
+            hint.push_str(
+                "; set BOTRACERS_REGISTRATION_ENABLED=true on the server to let other players sign up",
+            );
+        }
+        hint
+    }
 }
 
 impl Default for WebPortalState {
@@ -132,17 +405,63 @@ impl Default for WebPortalState {
             },
             standalone_mode: false,
             auth_required: None,
+            registration_enabled: false,
+            default_artifact_public: false,
+            max_artifact_bytes: None,
+            max_field_size: None,
+            version_warning: None,
+            auth_header: DEFAULT_AUTH_HEADER.to_string(),
+            auth_scheme: DEFAULT_AUTH_SCHEME.to_string(),
             #[cfg(not(target_arch = "wasm32"))]
             token: None,
             #[cfg(not(target_arch = "wasm32"))]
             cli_credentials: None,
             artifacts: Vec::new(),
+            artifact_scope: ArtifactScope::Mine,
+            artifacts_next_offset: 0,
+            artifacts_exhausted: false,
+            artifacts_empty: false,
+            race_history_artifact: None,
+            race_history: Vec::new(),
+            pending_import_url: None,
+            pending_rename: None,
+            pending_race_setup: None,
+            pending_replay: None,
+            capabilities_fetched_at: None,
+            capabilities_pending: false,
             status_message: None,
+            spawn_failures: Vec::new(),
         }
     }
 }
 
-fn initialize_bootstrap(config: Res<BootstrapConfig>, mut web_state: ResMut<WebPortalState>) {
+fn initialize_bootstrap(
+    config: Res<BootstrapConfig>,
+    mut web_state: ResMut<WebPortalState>,
+    mut local_bots: ResMut<LocalBotLibrary>,
+) {
+    web_state.pending_import_url = config.import_url.clone();
+    web_state.pending_rename = config.rename_to.clone();
+    web_state.pending_race_setup = config.race_setup.clone();
+    web_state.pending_replay = config.replay_id;
+
+    if let Some(dir) = &config.local_bots_dir {
+        local_bots.dir = Some(dir.clone());
+        web_state.status_message = Some(match scan_local_bots(dir) {
+            Ok(drivers) => {
+                let message = format!(
+                    "Offline: {} local bot(s) in {}",
+                    drivers.len(),
+                    dir.display()
+                );
+                local_bots.drivers = drivers;
+                message
+            }
+            Err(error) => format!("[error] Could not scan {}: {error}", dir.display()),
+        });
+        return;
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     if config.standalone_mode {
         let bind = config
@@ -172,8 +491,69 @@ fn initialize_bootstrap(config: Res<BootstrapConfig>, mut web_state: ResMut<WebP
     }
 }
 
-fn trigger_initial_capability_check(mut cmds: MessageWriter<WebApiCommand>) {
-    cmds.write(WebApiCommand::RefreshCapabilities);
+fn trigger_initial_capability_check(
+    config: Res<BootstrapConfig>,
+    mut cmds: MessageWriter<WebApiCommand>,
+) {
+    if config.local_bots_dir.is_none() {
+        cmds.write(WebApiCommand::RefreshCapabilities);
+    }
+}
+
+/// Issues the `--import` request once capabilities are known and, where the
+/// server requires it, the player is logged in.
+fn send_pending_import(
+    mut web_state: ResMut<WebPortalState>,
+    mut cmds: MessageWriter<WebApiCommand>,
+) {
+    if web_state.pending_import_url.is_none() || maybe_auth(&web_state).is_err() {
+        return;
+    }
+    if let Some(url) = web_state.pending_import_url.take() {
+        cmds.write(WebApiCommand::ImportArtifact { url });
+    }
+}
+
+/// Issues the `--rename` request under the same conditions as
+/// `send_pending_import`.
+fn send_pending_rename(
+    mut web_state: ResMut<WebPortalState>,
+    mut cmds: MessageWriter<WebApiCommand>,
+) {
+    if web_state.pending_rename.is_none() || maybe_auth(&web_state).is_err() {
+        return;
+    }
+    if let Some(username) = web_state.pending_rename.take() {
+        cmds.write(WebApiCommand::RenameUser { username });
+    }
+}
+
+/// Loads the `--race` setup once its artifacts can be downloaded, i.e. under
+/// the same conditions as `send_pending_import`.
+fn send_pending_race_setup(
+    mut web_state: ResMut<WebPortalState>,
+    mut setups: MessageWriter<LoadRaceSetup>,
+) {
+    if web_state.pending_race_setup.is_none() || maybe_auth(&web_state).is_err() {
+        return;
+    }
+    if let Some(setup) = web_state.pending_race_setup.take() {
+        setups.write(LoadRaceSetup { setup });
+    }
+}
+
+/// Issues the `--replay` request under the same conditions as
+/// `send_pending_import`.
+fn send_pending_replay(
+    mut web_state: ResMut<WebPortalState>,
+    mut cmds: MessageWriter<WebApiCommand>,
+) {
+    if web_state.pending_replay.is_none() || maybe_auth(&web_state).is_err() {
+        return;
+    }
+    if let Some(id) = web_state.pending_replay.take() {
+        cmds.write(WebApiCommand::LoadReplay { id });
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -209,6 +589,92 @@ fn prompt_cli_credentials() -> Result<Option<(String, String)>, String> {
     Ok(Some((username, password)))
 }
 
+/// Re-simulates race `race_id` from the replay it was submitted with and
+/// records on the server whether its claimed results hold, logging in as an
+/// admin from the terminal when the server requires it. The outer error
+/// means the race could not be checked; the inner one is why it was
+/// rejected.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn verify_remote_race(race_id: i64) -> Result<Result<(), String>, String> {
+    fn fetch(request: ehttp::Request, what: &str) -> Result<ehttp::Response, String> {
+        match ehttp::fetch_blocking(&request) {
+            Ok(resp) if resp.ok => Ok(resp),
+            Ok(resp) => Err(format!(
+                "{what} failed: {}",
+                WebApiError::from_response(&resp)
+            )),
+            Err(err) => Err(format!("{what} failed: {err}")),
+        }
+    }
+
+    let server_url = WebPortalState::default().server_url;
+    let capabilities = fetch(
+        ehttp::Request::get(web_api_url(&server_url, "/api/v1/capabilities")),
+        "capabilities",
+    )?;
+    let capabilities: ServerCapabilities =
+        parse_response(&capabilities, "capabilities").map_err(|e| e.to_string())?;
+    let auth = if capabilities.auth_required {
+        let Some((username, password)) = prompt_cli_credentials()? else {
+            return Err("verifying races needs an admin login".to_string());
+        };
+        let login = ehttp::Request::json(
+            web_api_url(&server_url, "/api/v1/auth/login"),
+            &LoginRequest { username, password },
+        )
+        .map_err(|e| format!("failed to serialize login request: {e}"))?;
+        let login: LoginResponse =
+            parse_response(&fetch(login, "login")?, "login").map_err(|e| e.to_string())?;
+        Some(AuthCredential::new(
+            &capabilities.auth_header,
+            &capabilities.auth_scheme,
+            &login.token,
+        ))
+    } else {
+        None
+    };
+
+    let race_path = format!("/api/v1/admin/races/{race_id}");
+    let review = fetch(
+        web_request_with_auth(web_api_url(&server_url, &race_path), auth.as_ref()),
+        "race review",
+    )?;
+    let review: RaceReview = parse_response(&review, "race review").map_err(|e| e.to_string())?;
+    if review.verified {
+        return Ok(Ok(()));
+    }
+    if let Some(rejection) = review.rejection {
+        return Ok(Err(rejection));
+    }
+
+    let verdict = match &review.replay {
+        Some(replay) => {
+            let claimed = SubmitRaceRequest {
+                entries: review.entries,
+                replay: None,
+            };
+            replay_check::verify_claims(replay, &TrackFile::load_builtin()?, &claimed)
+        }
+        None => Err("no replay was submitted with the race".to_string()),
+    };
+    let mut request = match &verdict {
+        Ok(()) => ehttp::Request::post(
+            web_api_url(&server_url, &format!("{race_path}/verify")),
+            Vec::new(),
+        ),
+        Err(reason) => ehttp::Request::json(
+            web_api_url(&server_url, &format!("{race_path}/reject")),
+            &RejectRaceRequest {
+                reason: reason.clone(),
+            },
+        )
+        .map_err(|e| format!("failed to serialize rejection: {e}"))?,
+    };
+    attach_auth(&mut request.headers, auth.as_ref());
+    fetch(request, "recording the verdict")?;
+    Ok(verdict)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn spawn_embedded_botracers(bind: String) {
     let mut config = ServerConfig::default();
@@ -240,30 +706,58 @@ fn web_api_url(base: &str, path: &str) -> String {
     }
 }
 
-fn web_request_with_auth(url: String, _token: Option<&str>) -> ehttp::Request {
+fn web_request_with_auth(url: String, auth: Option<&AuthCredential>) -> ehttp::Request {
     let mut req = ehttp::Request::get(url);
-    #[cfg(not(target_arch = "wasm32"))]
-    let token = _token;
-    #[cfg(target_arch = "wasm32")]
-    let token: Option<&str> = None;
-    if let Some(token) = token {
-        req.headers
-            .insert("Authorization", format!("Bearer {token}"));
-    }
+    attach_auth(&mut req.headers, auth);
     req
 }
 
+/// Session credential in the header and scheme the server advertised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthCredential {
+    pub header: String,
+    pub value: String,
+}
+
+impl AuthCredential {
+    pub fn new(header: &str, scheme: &str, token: &str) -> Self {
+        let value = if scheme.is_empty() {
+            token.to_string()
+        } else {
+            format!("{scheme} {token}")
+        };
+        Self {
+            header: header.to_string(),
+            value,
+        }
+    }
+}
+
+fn attach_auth(headers: &mut ehttp::Headers, auth: Option<&AuthCredential>) {
+    // The web build authenticates with the session cookie instead.
+    if cfg!(target_arch = "wasm32") {
+        return;
+    }
+    if let Some(auth) = auth {
+        headers.insert(&auth.header, &auth.value);
+    }
+}
+
+/// Decodes a successful response body, naming the payload on failure.
+fn parse_response<T: serde::de::DeserializeOwned>(
+    resp: &ehttp::Response,
+    what: &str,
+) -> Result<T, WebApiError> {
+    resp.json::<T>()
+        .map_err(|err| WebApiError::Deserialize(format!("invalid {what} response: {err}")))
+}
+
 fn push_web_event(queue: &Arc<Mutex<Vec<WebApiEvent>>>, event: WebApiEvent) {
     if let Ok(mut events) = queue.lock() {
         events.push(event);
     }
 }
 
-fn response_error(resp: &ehttp::Response) -> String {
-    let body = String::from_utf8_lossy(&resp.bytes);
-    format!("HTTP {} {}: {}", resp.status, resp.status_text, body.trim())
-}
-
 #[cfg(not(target_arch = "wasm32"))]
 fn web_fetch_login(
     server_url: &str,
@@ -283,7 +777,9 @@ fn web_fetch_login(
         Err(err) => {
             push_web_event(
                 &queue,
-                WebApiEvent::Login(Err(format!("failed to serialize login request: {err}"))),
+                WebApiEvent::Login(Err(WebApiError::Serialize(format!(
+                    "failed to serialize login request: {err}"
+                )))),
             );
             return;
         }
@@ -291,52 +787,125 @@ fn web_fetch_login(
 
     ehttp::fetch(request, move |result| {
         let event = match result {
-            Ok(resp) if resp.ok => WebApiEvent::Login(
-                resp.json::<LoginResponse>()
-                    .map_err(|err| format!("invalid login response: {err}")),
-            ),
-            Ok(resp) => WebApiEvent::Login(Err(response_error(&resp))),
-            Err(err) => WebApiEvent::Login(Err(format!("network error: {err}"))),
+            Ok(resp) if resp.ok => {
+                WebApiEvent::Login(parse_response::<LoginResponse>(&resp, "login"))
+            }
+            Ok(resp) => WebApiEvent::Login(Err(WebApiError::from_response(&resp))),
+            Err(err) => WebApiEvent::Login(Err(WebApiError::Network(err.to_string()))),
         };
         push_web_event(&queue, event);
     });
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn web_fetch_register(
+    server_url: &str,
+    username: &str,
+    password: &str,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(server_url, "/api/v1/auth/register");
+    let credentials = RegisterRequest {
+        username: username.to_string(),
+        password: password.to_string(),
+    };
+    let request = ehttp::Request::json(url, &credentials).map_err(|err| {
+        WebApiError::Serialize(format!("failed to serialize register request: {err}"))
+    });
+    let registered = move |result| WebApiEvent::Registered {
+        username: credentials.username,
+        password: credentials.password,
+        result,
+    };
+    let request = match request {
+        Ok(req) => req,
+        Err(error) => {
+            push_web_event(&queue, registered(Err(error)));
+            return;
+        }
+    };
+
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => parse_response::<UserInfo>(&resp, "register"),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(&queue, registered(result));
+    });
+}
+
 fn web_fetch_capabilities(server_url: &str, queue: Arc<Mutex<Vec<WebApiEvent>>>) {
     let url = web_api_url(server_url, "/api/v1/capabilities");
     let request = ehttp::Request::get(url);
     ehttp::fetch(request, move |result| {
         let event = match result {
-            Ok(resp) if resp.ok => WebApiEvent::Capabilities(
-                resp.json::<ServerCapabilities>()
-                    .map_err(|err| format!("invalid capabilities response: {err}")),
-            ),
-            Ok(resp) => WebApiEvent::Capabilities(Err(response_error(&resp))),
-            Err(err) => WebApiEvent::Capabilities(Err(format!("network error: {err}"))),
+            Ok(resp) if resp.ok => WebApiEvent::Capabilities(parse_response::<ServerCapabilities>(
+                &resp,
+                "capabilities",
+            )),
+            Ok(resp) => WebApiEvent::Capabilities(Err(WebApiError::from_response(&resp))),
+            Err(err) => WebApiEvent::Capabilities(Err(WebApiError::Network(err.to_string()))),
         };
         push_web_event(&queue, event);
     });
 }
 
-fn web_fetch_artifacts(server_url: &str, token: Option<&str>, queue: Arc<Mutex<Vec<WebApiEvent>>>) {
-    let url = web_api_url(server_url, "/api/v1/artifacts");
-    let request = web_request_with_auth(url, token);
+fn web_fetch_artifacts(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    scope: ArtifactScope,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    web_fetch_artifact_page(server_url, auth, scope, 0, queue);
+}
+
+fn web_fetch_artifact_page(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    scope: ArtifactScope,
+    offset: u32,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(
+        server_url,
+        &format!(
+            "/api/v1/artifacts?scope={}&limit={ARTIFACT_PAGE_SIZE}&offset={offset}",
+            scope.as_str()
+        ),
+    );
+    let request = web_request_with_auth(url, auth);
     ehttp::fetch(request, move |result| {
-        let event = match result {
-            Ok(resp) if resp.ok => WebApiEvent::Artifacts(
-                resp.json::<Vec<ArtifactSummary>>()
-                    .map_err(|err| format!("invalid artifacts response: {err}")),
-            ),
-            Ok(resp) => WebApiEvent::Artifacts(Err(response_error(&resp))),
-            Err(err) => WebApiEvent::Artifacts(Err(format!("network error: {err}"))),
+        let result = match result {
+            Ok(resp) if resp.ok => parse_response::<Vec<ArtifactSummary>>(&resp, "artifacts"),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
         };
-        push_web_event(&queue, event);
+        push_web_event(&queue, WebApiEvent::Artifacts { offset, result });
     });
 }
 
+/// Applies a fetched artifact page: offset 0 replaces the list, later offsets
+/// append while skipping ids that are already present.
+fn apply_artifact_page(web_state: &mut WebPortalState, offset: u32, page: Vec<ArtifactSummary>) {
+    let page_len = page.len() as u32;
+    if offset == 0 {
+        web_state.artifacts_empty = page.is_empty();
+        web_state.artifacts = page;
+    } else {
+        for artifact in page {
+            if !web_state.artifacts.iter().any(|a| a.id == artifact.id) {
+                web_state.artifacts.push(artifact);
+            }
+        }
+    }
+    web_state.artifacts_next_offset = offset + page_len;
+    web_state.artifacts_exhausted = page_len < ARTIFACT_PAGE_SIZE;
+}
+
 fn web_upload_artifact(
     server_url: &str,
-    _token: Option<&str>,
+    auth: Option<&AuthCredential>,
     name: String,
     note: Option<String>,
     elf: Vec<u8>,
@@ -346,65 +915,111 @@ fn web_upload_artifact(
     let mut request = match ehttp::Request::json(
         url,
         &UploadArtifactRequest {
-            name,
+            name: name.clone(),
             note,
             target: "riscv32imafc-unknown-none-elf".to_string(),
             elf_base64: base64::engine::general_purpose::STANDARD.encode(elf),
+            is_public: None,
+            build_id: None,
         },
     ) {
         Ok(req) => req,
         Err(err) => {
             push_web_event(
                 &queue,
-                WebApiEvent::UploadResult(Err(format!(
+                WebApiEvent::UploadResult(Err(WebApiError::Serialize(format!(
                     "failed to serialize upload payload: {err}"
-                ))),
+                )))),
             );
             return;
         }
     };
     request.method = "POST".to_string();
-    #[cfg(not(target_arch = "wasm32"))]
-    let token = _token;
-    #[cfg(target_arch = "wasm32")]
-    let token: Option<&str> = None;
-    if let Some(token) = token {
-        request
-            .headers
-            .insert("Authorization", format!("Bearer {token}"));
-    }
+    attach_auth(&mut request.headers, auth);
 
     ehttp::fetch(request, move |result| {
         let event = match result {
-            Ok(resp) if resp.ok => WebApiEvent::UploadResult(
-                resp.json::<UploadArtifactResponse>()
-                    .map_err(|err| format!("invalid upload response: {err}")),
-            ),
-            Ok(resp) => WebApiEvent::UploadResult(Err(response_error(&resp))),
-            Err(err) => WebApiEvent::UploadResult(Err(format!("network error: {err}"))),
+            Ok(resp) if resp.ok => {
+                WebApiEvent::UploadResult(parse_response::<UploadArtifactResponse>(&resp, "upload"))
+            }
+            Ok(resp) => WebApiEvent::UploadResult(Err(upload_error(&resp, &name))),
+            Err(err) => WebApiEvent::UploadResult(Err(WebApiError::Network(err.to_string()))),
         };
         push_web_event(&queue, event);
     });
 }
 
+/// Explains a failed upload; servers that reject duplicate names answer 409.
+fn upload_error(resp: &ehttp::Response, name: &str) -> WebApiError {
+    if resp.status == 409 {
+        WebApiError::Http {
+            status: resp.status,
+            message: format!(
+                "you already have an artifact named '{name}'; rename the file or delete the old artifact first"
+            ),
+        }
+    } else {
+        WebApiError::from_response(resp)
+    }
+}
+
+fn web_import_artifact(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    artifact_url: String,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(server_url, "/api/v1/artifacts/import");
+    let payload = ImportArtifactRequest {
+        url: artifact_url.clone(),
+        name: None,
+        note: None,
+        target: "riscv32imafc-unknown-none-elf".to_string(),
+        is_public: None,
+    };
+    let mut request = match ehttp::Request::json(url, &payload) {
+        Ok(req) => req,
+        Err(err) => {
+            push_web_event(
+                &queue,
+                WebApiEvent::ImportResult {
+                    url: artifact_url,
+                    result: Err(WebApiError::Serialize(format!(
+                        "failed to serialize import payload: {err}"
+                    ))),
+                },
+            );
+            return;
+        }
+    };
+    attach_auth(&mut request.headers, auth);
+
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => parse_response::<UploadArtifactResponse>(&resp, "import"),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(
+            &queue,
+            WebApiEvent::ImportResult {
+                url: artifact_url,
+                result,
+            },
+        );
+    });
+}
+
 fn web_delete_artifact(
     server_url: &str,
-    _token: Option<&str>,
-    artifact_id: i64,
+    auth: Option<&AuthCredential>,
+    artifact_id: ArtifactId,
     queue: Arc<Mutex<Vec<WebApiEvent>>>,
 ) {
     let url = web_api_url(server_url, &format!("/api/v1/artifacts/{artifact_id}"));
     let mut request = ehttp::Request::get(url);
     request.method = "DELETE".to_string();
-    #[cfg(not(target_arch = "wasm32"))]
-    let token = _token;
-    #[cfg(target_arch = "wasm32")]
-    let token: Option<&str> = None;
-    if let Some(token) = token {
-        request
-            .headers
-            .insert("Authorization", format!("Bearer {token}"));
-    }
+    attach_auth(&mut request.headers, auth);
 
     ehttp::fetch(request, move |result| {
         let event = match result {
@@ -414,11 +1029,11 @@ fn web_delete_artifact(
             },
             Ok(resp) => WebApiEvent::DeleteResult {
                 artifact_id,
-                result: Err(response_error(&resp)),
+                result: Err(WebApiError::from_response(&resp)),
             },
             Err(err) => WebApiEvent::DeleteResult {
                 artifact_id,
-                result: Err(format!("network error: {err}")),
+                result: Err(WebApiError::Network(err.to_string())),
             },
         };
         push_web_event(&queue, event);
@@ -427,8 +1042,8 @@ fn web_delete_artifact(
 
 fn web_set_artifact_visibility(
     server_url: &str,
-    _token: Option<&str>,
-    artifact_id: i64,
+    auth: Option<&AuthCredential>,
+    artifact_id: ArtifactId,
     is_public: bool,
     queue: Arc<Mutex<Vec<WebApiEvent>>>,
 ) {
@@ -445,22 +1060,16 @@ fn web_set_artifact_visibility(
                     WebApiEvent::VisibilityResult {
                         artifact_id,
                         is_public,
-                        result: Err(format!("failed to serialize visibility payload: {err}")),
+                        result: Err(WebApiError::Serialize(format!(
+                            "failed to serialize visibility payload: {err}"
+                        ))),
                     },
                 );
                 return;
             }
         };
     request.method = "PATCH".to_string();
-    #[cfg(not(target_arch = "wasm32"))]
-    let token = _token;
-    #[cfg(target_arch = "wasm32")]
-    let token: Option<&str> = None;
-    if let Some(token) = token {
-        request
-            .headers
-            .insert("Authorization", format!("Bearer {token}"));
-    }
+    attach_auth(&mut request.headers, auth);
 
     ehttp::fetch(request, move |result| {
         let event = match result {
@@ -472,76 +1081,308 @@ fn web_set_artifact_visibility(
             Ok(resp) => WebApiEvent::VisibilityResult {
                 artifact_id,
                 is_public,
-                result: Err(response_error(&resp)),
+                result: Err(WebApiError::from_response(&resp)),
             },
             Err(err) => WebApiEvent::VisibilityResult {
                 artifact_id,
                 is_public,
-                result: Err(format!("network error: {err}")),
+                result: Err(WebApiError::Network(err.to_string())),
             },
         };
         push_web_event(&queue, event);
     });
 }
 
-fn web_fetch_artifact_elf(
+fn web_clone_artifact(
     server_url: &str,
-    token: Option<&str>,
-    artifact_id: i64,
-    request_id: u64,
-    results_queue: Arc<Mutex<Vec<CompileResult>>>,
+    auth: Option<&AuthCredential>,
+    artifact_id: ArtifactId,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
 ) {
-    let url = web_api_url(server_url, &format!("/api/v1/artifacts/{artifact_id}"));
-    let request = web_request_with_auth(url, token);
+    let url = web_api_url(
+        server_url,
+        &format!("/api/v1/artifacts/{artifact_id}/clone"),
+    );
+    let mut request = web_request_with_auth(url, auth);
+    request.method = "POST".to_string();
+
     ehttp::fetch(request, move |result| {
-        let compile_result = match result {
-            Ok(resp) if resp.ok => CompileResult {
-                id: request_id,
-                binary: format!("artifact_{artifact_id}"),
-                result: Ok(resp.bytes),
+        let event = match result {
+            Ok(resp) if resp.ok => WebApiEvent::CloneResult {
+                artifact_id,
+                result: parse_response::<UploadArtifactResponse>(&resp, "clone"),
             },
-            Ok(resp) => CompileResult {
-                id: request_id,
-                binary: format!("artifact_{artifact_id}"),
-                result: Err(response_error(&resp)),
+            Ok(resp) => WebApiEvent::CloneResult {
+                artifact_id,
+                result: Err(WebApiError::from_response(&resp)),
             },
-            Err(err) => CompileResult {
-                id: request_id,
-                binary: format!("artifact_{artifact_id}"),
-                result: Err(format!("network error: {err}")),
+            Err(err) => WebApiEvent::CloneResult {
+                artifact_id,
+                result: Err(WebApiError::Network(err.to_string())),
             },
         };
-        if let Ok(mut pending) = results_queue.lock() {
-            pending.push(compile_result);
-        }
+        push_web_event(&queue, event);
     });
 }
 
-fn maybe_auth_token(web_state: &WebPortalState) -> Result<Option<String>, String> {
-    match web_state.auth_required {
-        Some(true) => {
-            #[cfg(target_arch = "wasm32")]
-            {
-                Ok(None)
-            }
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                web_state
-                    .token
-                    .clone()
-                    .map(Some)
-                    .ok_or_else(|| "[auth] Login required".to_string())
-            }
-        }
-        Some(false) => Ok(None),
-        None => Err("[capabilities] Server capabilities not loaded yet".to_string()),
-    }
-}
+fn web_set_artifact_star(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    artifact_id: ArtifactId,
+    starred: bool,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(server_url, &format!("/api/v1/artifacts/{artifact_id}/star"));
+    let mut request = ehttp::Request::get(url);
+    request.method = if starred { "POST" } else { "DELETE" }.to_string();
+    attach_auth(&mut request.headers, auth);
 
-#[cfg(not(target_arch = "wasm32"))]
-fn pick_artifact_for_upload_native() -> Result<Option<(String, Vec<u8>)>, String> {
-    let Some(path) = rfd::FileDialog::new().pick_file() else {
-        return Ok(None);
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => Ok(()),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(
+            &queue,
+            WebApiEvent::StarResult {
+                artifact_id,
+                starred,
+                result,
+            },
+        );
+    });
+}
+
+fn web_fetch_artifact_races(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    artifact_id: ArtifactId,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(
+        server_url,
+        &format!("/api/v1/artifacts/{artifact_id}/races?limit={RACE_HISTORY_PAGE_SIZE}"),
+    );
+    let request = web_request_with_auth(url, auth);
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => parse_response::<Vec<ArtifactRaceResult>>(&resp, "race history"),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(
+            &queue,
+            WebApiEvent::RaceHistory {
+                artifact_id,
+                result,
+            },
+        );
+    });
+}
+
+fn web_submit_race(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    payload: &SubmitRaceRequest,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(server_url, "/api/v1/races");
+    let mut request = match ehttp::Request::json(url, payload) {
+        Ok(req) => req,
+        Err(err) => {
+            push_web_event(
+                &queue,
+                WebApiEvent::RaceSubmitted(Err(WebApiError::Serialize(format!(
+                    "failed to serialize race payload: {err}"
+                )))),
+            );
+            return;
+        }
+    };
+    attach_auth(&mut request.headers, auth);
+
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => parse_response::<SubmitRaceResponse>(&resp, "race submission"),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(&queue, WebApiEvent::RaceSubmitted(result));
+    });
+}
+
+fn web_fetch_replay(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    replay_id: i64,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(server_url, &format!("/api/v1/replays/{replay_id}"));
+    let request = web_request_with_auth(url, auth);
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => parse_response::<ReplayFile>(&resp, "replay"),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(&queue, WebApiEvent::ReplayLoaded { replay_id, result });
+    });
+}
+
+/// One line of re-simulated results for the status bar, winner first.
+fn replay_summary(replay_id: i64, results: &[RaceEntrySubmission]) -> String {
+    let places: Vec<String> = results
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let best = entry
+                .best_lap_secs
+                .map(|secs| format!(", best {secs:.3} s"))
+                .unwrap_or_default();
+            format!(
+                "{}. #{} {} laps{best}",
+                index + 1,
+                entry.artifact_id,
+                entry.laps_completed
+            )
+        })
+        .collect();
+    format!("[replays] Replay #{replay_id}: {}", places.join("; "))
+}
+
+fn web_rename_user(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    username: String,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(server_url, "/api/v1/users/me");
+    let mut request = match ehttp::Request::json(url, &RenameUserRequest { username }) {
+        Ok(req) => req,
+        Err(err) => {
+            push_web_event(
+                &queue,
+                WebApiEvent::Renamed(Err(WebApiError::Serialize(format!(
+                    "failed to serialize rename payload: {err}"
+                )))),
+            );
+            return;
+        }
+    };
+    request.method = "PATCH".to_string();
+    attach_auth(&mut request.headers, auth);
+
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => parse_response::<UserInfo>(&resp, "rename"),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(&queue, WebApiEvent::Renamed(result));
+    });
+}
+
+/// Downloads `/api/v1/artifacts/export` and writes the tarball to `path`.
+#[cfg(not(target_arch = "wasm32"))]
+fn web_export_artifacts(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    path: PathBuf,
+    queue: Arc<Mutex<Vec<WebApiEvent>>>,
+) {
+    let url = web_api_url(server_url, "/api/v1/artifacts/export");
+    let request = web_request_with_auth(url, auth);
+    ehttp::fetch(request, move |result| {
+        let result = match result {
+            Ok(resp) if resp.ok => std::fs::write(&path, &resp.bytes)
+                .map(|()| path)
+                .map_err(|err| WebApiError::Io(format!("failed to save export: {err}"))),
+            Ok(resp) => Err(WebApiError::from_response(&resp)),
+            Err(err) => Err(WebApiError::Network(err.to_string())),
+        };
+        push_web_event(&queue, WebApiEvent::ExportResult(result));
+    });
+}
+
+fn web_fetch_artifact_elf(
+    server_url: &str,
+    auth: Option<&AuthCredential>,
+    artifact_id: ArtifactId,
+    request_id: u64,
+    abort: Arc<AtomicBool>,
+    results_queue: Arc<Mutex<Vec<CompileResult>>>,
+) {
+    let url = web_api_url(server_url, &format!("/api/v1/artifacts/{artifact_id}"));
+    let request = web_request_with_auth(url, auth);
+    ehttp::fetch(request, move |result| {
+        let compile_result = match result {
+            Ok(resp) if resp.ok => CompileResult {
+                id: request_id,
+                binary: format!("artifact_{artifact_id}"),
+                result: Ok(resp.bytes),
+            },
+            Ok(resp) => CompileResult {
+                id: request_id,
+                binary: format!("artifact_{artifact_id}"),
+                result: Err(WebApiError::from_response(&resp)),
+            },
+            Err(err) => CompileResult {
+                id: request_id,
+                binary: format!("artifact_{artifact_id}"),
+                result: Err(WebApiError::Network(err.to_string())),
+            },
+        };
+        deliver_fetch_result(&abort, &results_queue, compile_result);
+    });
+}
+
+/// Queues a finished download unless its request was cancelled meanwhile, in
+/// which case the downloaded bytes are dropped right here.
+fn deliver_fetch_result(
+    abort: &AtomicBool,
+    results_queue: &Mutex<Vec<CompileResult>>,
+    result: CompileResult,
+) {
+    if abort.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Ok(mut pending) = results_queue.lock() {
+        pending.push(result);
+    }
+}
+
+fn maybe_auth(web_state: &WebPortalState) -> Result<Option<AuthCredential>, String> {
+    match web_state.auth_required {
+        Some(true) => {
+            #[cfg(target_arch = "wasm32")]
+            {
+                Ok(None)
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                web_state
+                    .token
+                    .as_deref()
+                    .map(|token| {
+                        Some(AuthCredential::new(
+                            &web_state.auth_header,
+                            &web_state.auth_scheme,
+                            token,
+                        ))
+                    })
+                    .ok_or_else(|| "[auth] Login required".to_string())
+            }
+        }
+        Some(false) => Ok(None),
+        None => Err("[capabilities] Server capabilities not loaded yet".to_string()),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn pick_artifact_for_upload_native() -> Result<Option<(String, Vec<u8>)>, String> {
+    let Some(path) = rfd::FileDialog::new().pick_file() else {
+        return Ok(None);
     };
     let bytes = std::fs::read(&path).map_err(|e| format!("failed to read file: {e}"))?;
     let name = path
@@ -551,10 +1392,21 @@ fn pick_artifact_for_upload_native() -> Result<Option<(String, Vec<u8>)>, String
     Ok(Some((name, bytes)))
 }
 
+/// Rejects an upload the server would refuse for its size, before sending it.
+fn check_artifact_size(name: &str, len: usize, max_bytes: Option<u64>) -> Result<(), String> {
+    match max_bytes {
+        Some(max) if len as u64 > max => Err(format!(
+            "'{name}' is {len} bytes; the server accepts at most {max}"
+        )),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 fn pick_artifact_for_upload_web(
     server_url: String,
-    token: Option<String>,
+    auth: Option<AuthCredential>,
+    max_bytes: Option<u64>,
     queue: Arc<Mutex<Vec<WebApiEvent>>>,
 ) {
     wasm_bindgen_futures::spawn_local(async move {
@@ -563,31 +1415,84 @@ fn pick_artifact_for_upload_web(
         };
         let bytes = file.read().await;
         let name = file.file_name();
-        web_upload_artifact(&server_url, token.as_deref(), name, None, bytes, queue);
+        if let Err(error) = check_artifact_size(&name, bytes.len(), max_bytes) {
+            push_web_event(&queue, WebApiEvent::UploadResult(Err(error)));
+            return;
+        }
+        web_upload_artifact(&server_url, auth.as_ref(), name, None, bytes, queue);
     });
 }
 
+/// Starts a capability fetch unless one is already in flight.
+fn request_capabilities(web_state: &mut WebPortalState, queue: &Arc<Mutex<Vec<WebApiEvent>>>) {
+    if web_state.capabilities_pending {
+        return;
+    }
+    web_state.capabilities_pending = true;
+    web_fetch_capabilities(&web_state.server_url, queue.clone());
+}
+
+/// Whether a command can go ahead on the cached capabilities. Capabilities
+/// older than `CAPABILITIES_TTL_SECS` are refreshed in the background; until
+/// the first ones arrive the command is dropped with a status message.
+fn capabilities_ready(
+    web_state: &mut WebPortalState,
+    queue: &Arc<Mutex<Vec<WebApiEvent>>>,
+    now: f64,
+) -> bool {
+    let stale = web_state
+        .capabilities_fetched_at
+        .is_none_or(|fetched_at| now - fetched_at >= CAPABILITIES_TTL_SECS);
+    if stale {
+        request_capabilities(web_state, queue);
+    }
+    if web_state.auth_required.is_none() {
+        web_state.status_message =
+            Some("[capabilities] Checking server capabilities first...".to_string());
+        return false;
+    }
+    true
+}
+
 fn handle_web_api_commands(
     mut commands: MessageReader<WebApiCommand>,
     mut web_state: ResMut<WebPortalState>,
     web_queue: Res<WebApiQueue>,
+    time: Res<Time>,
 ) {
     for command in commands.read() {
         match command {
             WebApiCommand::RefreshCapabilities => {
                 web_state.status_message =
                     Some("[capabilities] Loading server capabilities...".to_string());
+                // An explicit refresh ignores the TTL.
+                web_state.capabilities_pending = true;
                 web_fetch_capabilities(&web_state.server_url, web_queue.events.clone());
             }
-            WebApiCommand::LoadArtifacts => {
-                if web_state.auth_required.is_none() {
+            WebApiCommand::Register { username, password } => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    web_state.status_message = Some(format!("[auth] Registering '{username}'..."));
+                    web_fetch_register(
+                        &web_state.server_url,
+                        username,
+                        password,
+                        web_queue.events.clone(),
+                    );
+                }
+                #[cfg(target_arch = "wasm32")]
+                {
+                    let _ = (username, password);
                     web_state.status_message =
-                        Some("[capabilities] Checking server capabilities first...".to_string());
-                    web_fetch_capabilities(&web_state.server_url, web_queue.events.clone());
+                        Some("[auth] Register on the web page, then reload".to_string());
+                }
+            }
+            WebApiCommand::LoadArtifacts => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
                     continue;
                 }
-                let token = match maybe_auth_token(&web_state) {
-                    Ok(token) => token,
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
                     Err(error) => {
                         web_state.status_message = Some(error);
                         continue;
@@ -596,19 +1501,62 @@ fn handle_web_api_commands(
                 web_state.status_message = Some("[load] Loading artifacts...".to_string());
                 web_fetch_artifacts(
                     &web_state.server_url,
-                    token.as_deref(),
+                    auth.as_ref(),
+                    web_state.artifact_scope,
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::LoadMoreArtifacts => {
+                if web_state.artifacts_exhausted {
+                    web_state.status_message = Some("[load] No more artifacts to load".to_string());
+                    continue;
+                }
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                web_state.status_message = Some("[load] Loading more artifacts...".to_string());
+                web_fetch_artifact_page(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    web_state.artifact_scope,
+                    web_state.artifacts_next_offset,
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::SetArtifactScope { scope } => {
+                web_state.artifact_scope = *scope;
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                web_state.status_message =
+                    Some(format!("[load] Loading {} artifacts...", scope.as_str()));
+                web_fetch_artifacts(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    web_state.artifact_scope,
                     web_queue.events.clone(),
                 );
             }
             WebApiCommand::UploadArtifact => {
-                if web_state.auth_required.is_none() {
-                    web_state.status_message =
-                        Some("[capabilities] Checking server capabilities first...".to_string());
-                    web_fetch_capabilities(&web_state.server_url, web_queue.events.clone());
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
                     continue;
                 }
-                let token = match maybe_auth_token(&web_state) {
-                    Ok(token) => token,
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
                     Err(error) => {
                         web_state.status_message = Some(error);
                         continue;
@@ -617,10 +1565,17 @@ fn handle_web_api_commands(
                 #[cfg(not(target_arch = "wasm32"))]
                 match pick_artifact_for_upload_native() {
                     Ok(Some((name, bytes))) => {
+                        if let Err(error) =
+                            check_artifact_size(&name, bytes.len(), web_state.max_artifact_bytes)
+                        {
+                            web_state.status_message =
+                                Some(format!("[error][upload] Upload failed: {error}"));
+                            continue;
+                        }
                         web_state.status_message = Some(format!("[upload] Uploading '{name}'..."));
                         web_upload_artifact(
                             &web_state.server_url,
-                            token.as_deref(),
+                            auth.as_ref(),
                             name,
                             None,
                             bytes,
@@ -638,20 +1593,18 @@ fn handle_web_api_commands(
                         Some("[upload] Pick artifact to upload...".to_string());
                     pick_artifact_for_upload_web(
                         web_state.server_url.clone(),
-                        token,
+                        auth,
+                        web_state.max_artifact_bytes,
                         web_queue.events.clone(),
                     );
                 }
             }
             WebApiCommand::DeleteArtifact { id } => {
-                if web_state.auth_required.is_none() {
-                    web_state.status_message =
-                        Some("[capabilities] Checking server capabilities first...".to_string());
-                    web_fetch_capabilities(&web_state.server_url, web_queue.events.clone());
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
                     continue;
                 }
-                let token = match maybe_auth_token(&web_state) {
-                    Ok(token) => token,
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
                     Err(error) => {
                         web_state.status_message = Some(error);
                         continue;
@@ -660,20 +1613,55 @@ fn handle_web_api_commands(
                 web_state.status_message = Some(format!("[delete] Deleting artifact #{id}..."));
                 web_delete_artifact(
                     &web_state.server_url,
-                    token.as_deref(),
+                    auth.as_ref(),
+                    *id,
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::ImportArtifact { url } => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                web_state.status_message = Some(format!("[import] Importing {url}..."));
+                web_import_artifact(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    url.clone(),
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::CloneArtifact { id } => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                web_state.status_message = Some(format!("[clone] Cloning artifact #{id}..."));
+                web_clone_artifact(
+                    &web_state.server_url,
+                    auth.as_ref(),
                     *id,
                     web_queue.events.clone(),
                 );
             }
             WebApiCommand::SetArtifactVisibility { id, is_public } => {
-                if web_state.auth_required.is_none() {
-                    web_state.status_message =
-                        Some("[capabilities] Checking server capabilities first...".to_string());
-                    web_fetch_capabilities(&web_state.server_url, web_queue.events.clone());
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
                     continue;
                 }
-                let token = match maybe_auth_token(&web_state) {
-                    Ok(token) => token,
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
                     Err(error) => {
                         web_state.status_message = Some(error);
                         continue;
@@ -685,54 +1673,264 @@ fn handle_web_api_commands(
                 ));
                 web_set_artifact_visibility(
                     &web_state.server_url,
-                    token.as_deref(),
+                    auth.as_ref(),
                     *id,
                     *is_public,
                     web_queue.events.clone(),
                 );
             }
-        }
-    }
-}
-
-fn process_web_api_events(mut web_state: ResMut<WebPortalState>, web_queue: Res<WebApiQueue>) {
-    let mut events = Vec::new();
-    if let Ok(mut queue) = web_queue.events.lock() {
-        events.append(&mut *queue);
-    }
-
-    for event in events {
-        match event {
-            WebApiEvent::Capabilities(result) => match result {
-                Ok(caps) => {
-                    web_state.auth_required = Some(caps.auth_required);
-                    web_state.status_message = Some(format!(
-                        "[capabilities] Connected: mode={}, auth_required={}, registration_enabled={}",
-                        caps.mode, caps.auth_required, caps.registration_enabled
-                    ));
-                    #[cfg(not(target_arch = "wasm32"))]
-                    if caps.auth_required && web_state.token.is_none() {
-                        if let Some((username, password)) = web_state.cli_credentials.clone() {
-                            web_state.status_message =
-                                Some(format!("[auth] Logging in as '{username}'..."));
-                            web_fetch_login(
-                                &web_state.server_url,
-                                &username,
-                                &password,
-                                web_queue.events.clone(),
-                            );
-                            continue;
-                        }
-                    }
-                    if let Ok(token) = maybe_auth_token(&web_state) {
-                        web_fetch_artifacts(
-                            &web_state.server_url,
-                            token.as_deref(),
-                            web_queue.events.clone(),
-                        );
+            WebApiCommand::SetArtifactStar { id, starred } => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                let action = if *starred { "Starring" } else { "Unstarring" };
+                web_state.status_message = Some(format!("[star] {action} artifact #{id}..."));
+                web_set_artifact_star(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    *id,
+                    *starred,
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::LoadArtifactRaces { id } => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                web_state.status_message = Some(format!(
+                    "[races] Loading race history for artifact #{id}..."
+                ));
+                web_fetch_artifact_races(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    *id,
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::SubmitRace { entries, replay } => {
+                // Results are best-effort: without a usable session the race is
+                // simply not recorded.
+                let Ok(auth) = maybe_auth(&web_state) else {
+                    continue;
+                };
+                web_state.status_message = Some("[races] Submitting race results...".to_string());
+                web_submit_race(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    &SubmitRaceRequest {
+                        entries: entries.clone(),
+                        replay: replay.as_deref().cloned(),
+                    },
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::LoadReplay { id } => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                web_state.status_message = Some(format!("[replays] Loading replay #{id}..."));
+                web_fetch_replay(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    *id,
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::RenameUser { username } => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                let auth = match maybe_auth(&web_state) {
+                    Ok(auth) => auth,
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                        continue;
+                    }
+                };
+                web_state.status_message = Some(format!("[auth] Renaming to '{username}'..."));
+                web_rename_user(
+                    &web_state.server_url,
+                    auth.as_ref(),
+                    username.clone(),
+                    web_queue.events.clone(),
+                );
+            }
+            WebApiCommand::ExportArtifacts => {
+                if !capabilities_ready(&mut web_state, &web_queue.events, time.elapsed_secs_f64()) {
+                    continue;
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                match maybe_auth(&web_state) {
+                    Ok(auth) => {
+                        let Some(path) = rfd::FileDialog::new()
+                            .set_file_name("botracers-artifacts.tar")
+                            .save_file()
+                        else {
+                            continue;
+                        };
+                        web_state.status_message = Some(format!(
+                            "[export] Exporting artifacts to {}...",
+                            path.display()
+                        ));
+                        web_export_artifacts(
+                            &web_state.server_url,
+                            auth.as_ref(),
+                            path,
+                            web_queue.events.clone(),
+                        );
+                    }
+                    Err(error) => {
+                        web_state.status_message = Some(error);
+                    }
+                }
+                #[cfg(target_arch = "wasm32")]
+                {
+                    web_state.status_message =
+                        Some("[export] Exporting artifacts needs the native game".to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Warning shown when client and server disagree on the major version, so a
+/// breaking API change is visible instead of surfacing as odd request errors.
+/// Servers that do not report a version are assumed compatible.
+fn version_mismatch_warning(client: &str, server: &str) -> Option<String> {
+    let major = |version: &str| version.split('.').next().map(str::to_string);
+    if server.is_empty() || major(client) == major(server) {
+        return None;
+    }
+    Some(format!(
+        "[warning] client/server version mismatch: client {client}, server {server}"
+    ))
+}
+
+/// Warning shown when the server lists the bot ABI versions it accepts and
+/// this game's is not among them, so uploads would build for a game the
+/// server refuses. An empty list comes from servers that do not check.
+fn abi_support_warning(supported: &[u32]) -> Option<String> {
+    if supported.is_empty() || supported.contains(&BOT_ABI_VERSION) {
+        return None;
+    }
+    Some(format!(
+        "[warning] server does not accept bot ABI version {BOT_ABI_VERSION} (supports {supported:?})"
+    ))
+}
+
+fn visibility_label(is_public: bool) -> &'static str {
+    if is_public { "public" } else { "private" }
+}
+
+/// Drops a token the server no longer accepts and logs in again with the CLI
+/// credentials, if there are any.
+#[cfg(not(target_arch = "wasm32"))]
+fn reauthenticate(web_state: &mut WebPortalState, queue: &Arc<Mutex<Vec<WebApiEvent>>>) {
+    if web_state.token.take().is_none() {
+        return;
+    }
+    if let Some((username, password)) = web_state.cli_credentials.clone() {
+        web_fetch_login(&web_state.server_url, &username, &password, queue.clone());
+    }
+}
+
+fn process_web_api_events(
+    mut web_state: ResMut<WebPortalState>,
+    web_queue: Res<WebApiQueue>,
+    mut fetch_pipeline: ResMut<ArtifactFetchPipeline>,
+    time: Res<Time>,
+) {
+    let mut events = Vec::new();
+    if let Ok(mut queue) = web_queue.events.lock() {
+        events.append(&mut *queue);
+    }
+
+    for event in events {
+        #[cfg(not(target_arch = "wasm32"))]
+        if event.session_rejected() {
+            reauthenticate(&mut web_state, &web_queue.events);
+        }
+        match event {
+            WebApiEvent::Capabilities(result) => match result {
+                Ok(caps) => {
+                    web_state.capabilities_pending = false;
+                    web_state.capabilities_fetched_at = Some(time.elapsed_secs_f64());
+                    web_state.auth_required = Some(caps.auth_required);
+                    web_state.registration_enabled = caps.registration_enabled;
+                    web_state.default_artifact_public = caps.default_artifact_public;
+                    web_state.max_artifact_bytes = caps.max_artifact_bytes;
+                    web_state.max_field_size = caps.max_field_size;
+                    web_state.auth_header = caps.auth_header.clone();
+                    web_state.auth_scheme = caps.auth_scheme.clone();
+                    web_state.version_warning =
+                        version_mismatch_warning(env!("CARGO_PKG_VERSION"), &caps.version)
+                            .or_else(|| abi_support_warning(&caps.supported_abi_versions));
+                    if let Some(warning) = &web_state.version_warning {
+                        warn!("{warning}");
+                    }
+                    let version = if caps.version.is_empty() {
+                        "unknown"
+                    } else {
+                        &caps.version
+                    };
+                    let max_upload = caps
+                        .max_artifact_bytes
+                        .map_or_else(|| "unlimited".to_string(), |max| format!("{max}B"));
+                    web_state.status_message = Some(format!(
+                        "[capabilities] Connected: version={version}, mode={}, auth_required={}, registration_enabled={}, uploads={}, max_upload={max_upload}, targets=[{}], compile={}",
+                        caps.mode,
+                        caps.auth_required,
+                        caps.registration_enabled,
+                        visibility_label(caps.default_artifact_public),
+                        caps.allowed_targets.join(", "),
+                        caps.compile_supported
+                    ));
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if caps.auth_required && web_state.token.is_none() {
+                        if let Some((username, password)) = web_state.cli_credentials.clone() {
+                            web_state.status_message =
+                                Some(format!("[auth] Logging in as '{username}'..."));
+                            web_fetch_login(
+                                &web_state.server_url,
+                                &username,
+                                &password,
+                                web_queue.events.clone(),
+                            );
+                            continue;
+                        }
+                    }
+                    if let Ok(auth) = maybe_auth(&web_state) {
+                        web_fetch_artifacts(
+                            &web_state.server_url,
+                            auth.as_ref(),
+                            web_state.artifact_scope,
+                            web_queue.events.clone(),
+                        );
                     }
                 }
                 Err(error) => {
+                    web_state.capabilities_pending = false;
                     web_state.status_message = Some(format!(
                         "[error][capabilities] Capability check failed: {error}"
                     ));
@@ -744,9 +1942,11 @@ fn process_web_api_events(mut web_state: ResMut<WebPortalState>, web_queue: Res<
                     web_state.token = Some(login.token);
                     web_state.status_message =
                         Some(format!("[auth] Logged in as {}", login.user.username));
+                    let auth = maybe_auth(&web_state).ok().flatten();
                     web_fetch_artifacts(
                         &web_state.server_url,
-                        web_state.token.as_deref(),
+                        auth.as_ref(),
+                        web_state.artifact_scope,
                         web_queue.events.clone(),
                     );
                 }
@@ -754,13 +1954,46 @@ fn process_web_api_events(mut web_state: ResMut<WebPortalState>, web_queue: Res<
                     web_state.status_message = Some(format!("[error][auth] Login failed: {error}"));
                 }
             },
-            WebApiEvent::Artifacts(result) => match result {
-                Ok(artifacts) => {
-                    web_state.artifacts = artifacts;
+            #[cfg(not(target_arch = "wasm32"))]
+            WebApiEvent::Registered {
+                username,
+                password,
+                result,
+            } => match result {
+                Ok(user) => {
                     web_state.status_message = Some(format!(
-                        "[load] Loaded {} artifacts",
-                        web_state.artifacts.len()
+                        "[auth] Registered '{}'; logging in...",
+                        user.username
                     ));
+                    web_fetch_login(
+                        &web_state.server_url,
+                        &username,
+                        &password,
+                        web_queue.events.clone(),
+                    );
+                    web_state.cli_credentials = Some((username, password));
+                }
+                Err(error) => {
+                    web_state.status_message =
+                        Some(format!("[error][auth] Registration failed: {error}"));
+                }
+            },
+            WebApiEvent::Artifacts { offset, result } => match result {
+                Ok(page) => {
+                    apply_artifact_page(&mut web_state, offset, page);
+                    web_state.status_message = Some(if web_state.artifacts_empty {
+                        format!("[load] {}", web_state.empty_artifacts_hint())
+                    } else {
+                        let more = if web_state.artifacts_exhausted {
+                            ""
+                        } else {
+                            " (more available)"
+                        };
+                        format!(
+                            "[load] Loaded {} artifacts{more}",
+                            web_state.artifacts.len()
+                        )
+                    });
                 }
                 Err(error) => {
                     web_state.status_message =
@@ -769,14 +2002,22 @@ fn process_web_api_events(mut web_state: ResMut<WebPortalState>, web_queue: Res<
             },
             WebApiEvent::UploadResult(result) => match result {
                 Ok(upload) => {
+                    // Servers that suffix duplicate names report the name they used.
+                    let stored_as = if upload.name.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" as '{}'", upload.name)
+                    };
                     web_state.status_message = Some(format!(
-                        "[upload] Uploaded artifact #{}",
-                        upload.artifact_id
+                        "[upload] Uploaded artifact #{}{stored_as} ({})",
+                        upload.artifact_id,
+                        visibility_label(web_state.default_artifact_public)
                     ));
-                    if let Ok(token) = maybe_auth_token(&web_state) {
+                    if let Ok(auth) = maybe_auth(&web_state) {
                         web_fetch_artifacts(
                             &web_state.server_url,
-                            token.as_deref(),
+                            auth.as_ref(),
+                            web_state.artifact_scope,
                             web_queue.events.clone(),
                         );
                     }
@@ -791,12 +2032,14 @@ fn process_web_api_events(mut web_state: ResMut<WebPortalState>, web_queue: Res<
                 result,
             } => match result {
                 Ok(()) => {
+                    fetch_pipeline.elf_cache.remove(&artifact_id);
                     web_state.status_message =
                         Some(format!("[delete] Deleted artifact #{artifact_id}"));
-                    if let Ok(token) = maybe_auth_token(&web_state) {
+                    if let Ok(auth) = maybe_auth(&web_state) {
                         web_fetch_artifacts(
                             &web_state.server_url,
-                            token.as_deref(),
+                            auth.as_ref(),
+                            web_state.artifact_scope,
                             web_queue.events.clone(),
                         );
                     }
@@ -817,10 +2060,11 @@ fn process_web_api_events(mut web_state: ResMut<WebPortalState>, web_queue: Res<
                     web_state.status_message = Some(format!(
                         "[visibility] Set artifact #{artifact_id} to {visibility}"
                     ));
-                    if let Ok(token) = maybe_auth_token(&web_state) {
+                    if let Ok(auth) = maybe_auth(&web_state) {
                         web_fetch_artifacts(
                             &web_state.server_url,
-                            token.as_deref(),
+                            auth.as_ref(),
+                            web_state.artifact_scope,
                             web_queue.events.clone(),
                         );
                     }
@@ -831,6 +2075,167 @@ fn process_web_api_events(mut web_state: ResMut<WebPortalState>, web_queue: Res<
                     ));
                 }
             },
+            WebApiEvent::StarResult {
+                artifact_id,
+                starred,
+                result,
+            } => match result {
+                Ok(()) => {
+                    let action = if starred { "Starred" } else { "Unstarred" };
+                    web_state.status_message =
+                        Some(format!("[star] {action} artifact #{artifact_id}"));
+                    if let Ok(auth) = maybe_auth(&web_state) {
+                        web_fetch_artifacts(
+                            &web_state.server_url,
+                            auth.as_ref(),
+                            web_state.artifact_scope,
+                            web_queue.events.clone(),
+                        );
+                    }
+                }
+                Err(error) => {
+                    web_state.status_message = Some(format!(
+                        "[error][star] Failed to update artifact #{artifact_id}: {error}"
+                    ));
+                }
+            },
+            WebApiEvent::ImportResult { url, result } => match result {
+                Ok(import) => {
+                    web_state.status_message = Some(format!(
+                        "[import] Imported {url} as artifact #{} ({})",
+                        import.artifact_id,
+                        visibility_label(web_state.default_artifact_public)
+                    ));
+                    if let Ok(auth) = maybe_auth(&web_state) {
+                        web_fetch_artifacts(
+                            &web_state.server_url,
+                            auth.as_ref(),
+                            web_state.artifact_scope,
+                            web_queue.events.clone(),
+                        );
+                    }
+                }
+                Err(error) => {
+                    web_state.status_message =
+                        Some(format!("[error][import] Importing {url} failed: {error}"));
+                }
+            },
+            WebApiEvent::CloneResult {
+                artifact_id,
+                result,
+            } => match result {
+                Ok(clone) => {
+                    web_state.status_message = Some(format!(
+                        "[clone] Cloned artifact #{artifact_id} as #{}",
+                        clone.artifact_id
+                    ));
+                    if let Ok(auth) = maybe_auth(&web_state) {
+                        web_fetch_artifacts(
+                            &web_state.server_url,
+                            auth.as_ref(),
+                            web_state.artifact_scope,
+                            web_queue.events.clone(),
+                        );
+                    }
+                }
+                Err(error) => {
+                    web_state.status_message = Some(format!(
+                        "[error][clone] Failed to clone artifact #{artifact_id}: {error}"
+                    ));
+                }
+            },
+            WebApiEvent::RaceHistory {
+                artifact_id,
+                result,
+            } => match result {
+                Ok(races) => {
+                    web_state.status_message = Some(format!(
+                        "[races] Loaded {} races for artifact #{artifact_id}",
+                        races.len()
+                    ));
+                    web_state.race_history_artifact = Some(artifact_id);
+                    web_state.race_history = races;
+                }
+                Err(error) => {
+                    web_state.status_message = Some(format!(
+                        "[error][races] Failed to load races for artifact #{artifact_id}: {error}"
+                    ));
+                }
+            },
+            WebApiEvent::RaceSubmitted(result) => match result {
+                Ok(race) => {
+                    web_state.status_message = Some(match race.replay_id {
+                        Some(replay_id) => format!(
+                            "[races] Recorded race #{} with replay #{replay_id}",
+                            race.race_id
+                        ),
+                        None => format!("[races] Recorded race #{}", race.race_id),
+                    });
+                    if let (Some(artifact_id), Ok(auth)) =
+                        (web_state.race_history_artifact, maybe_auth(&web_state))
+                    {
+                        web_fetch_artifact_races(
+                            &web_state.server_url,
+                            auth.as_ref(),
+                            artifact_id,
+                            web_queue.events.clone(),
+                        );
+                    }
+                }
+                Err(error) => {
+                    web_state.status_message = Some(format!(
+                        "[error][races] Failed to record race results: {error}"
+                    ));
+                }
+            },
+            WebApiEvent::ReplayLoaded { replay_id, result } => {
+                let results = result
+                    .map_err(|error| error.to_string())
+                    .and_then(|replay| {
+                        let track = TrackFile::load_builtin()?;
+                        replay_check::resimulated_results(&replay, &track, u32::MAX)
+                    });
+                web_state.status_message = Some(match results {
+                    Ok(results) => replay_summary(replay_id, &results),
+                    Err(error) => {
+                        format!("[error][replays] Failed to load replay #{replay_id}: {error}")
+                    }
+                });
+            }
+            WebApiEvent::Renamed(result) => match result {
+                Ok(user) => {
+                    web_state.status_message =
+                        Some(format!("[auth] Renamed to '{}'", user.username));
+                    // Later logins, e.g. after the session expires, need the new name.
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some((username, _)) = web_state.cli_credentials.as_mut() {
+                        *username = user.username;
+                    }
+                    if let Ok(auth) = maybe_auth(&web_state) {
+                        web_fetch_artifacts(
+                            &web_state.server_url,
+                            auth.as_ref(),
+                            web_state.artifact_scope,
+                            web_queue.events.clone(),
+                        );
+                    }
+                }
+                Err(error) => {
+                    web_state.status_message =
+                        Some(format!("[error][auth] Rename failed: {error}"));
+                }
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            WebApiEvent::ExportResult(result) => match result {
+                Ok(path) => {
+                    web_state.status_message =
+                        Some(format!("[export] Saved artifacts to {}", path.display()));
+                }
+                Err(error) => {
+                    web_state.status_message =
+                        Some(format!("[error][export] Export failed: {error}"));
+                }
+            },
         }
     }
 }
@@ -846,32 +2251,81 @@ fn handle_spawn_car_request(
             continue;
         }
 
+        let id = match &event.driver {
+            DriverType::RemoteArtifact { id } => *id,
+            DriverType::LocalFile { path } => {
+                let name = local_file_name(path);
+                match std::fs::read(path) {
+                    Ok(elf_bytes) => {
+                        fetch_pipeline.ready.push(SpawnResolvedCarRequest {
+                            driver: event.driver.clone(),
+                            elf_bytes,
+                            binary_name: name.clone(),
+                        });
+                        web_state.status_message = Some(format!("Spawned local bot '{name}'"));
+                    }
+                    Err(error) => {
+                        web_state.record_spawn_failure(
+                            &event.driver,
+                            format!("Could not read '{name}': {error}"),
+                        );
+                    }
+                }
+                continue;
+            }
+        };
+        if let Some(elf_bytes) = fetch_pipeline.elf_cache.get(&id).cloned() {
+            fetch_pipeline.ready.push(SpawnResolvedCarRequest {
+                driver: event.driver.clone(),
+                elf_bytes,
+                binary_name: format!("artifact_{id}"),
+            });
+            web_state.status_message = Some(format!("Spawned cached artifact #{id}"));
+            continue;
+        }
+
         let request_id = fetch_pipeline.next_request_id;
         fetch_pipeline.next_request_id += 1;
         fetch_pipeline
             .pending
             .insert(request_id, event.driver.clone());
+        let abort = Arc::new(AtomicBool::new(false));
+        fetch_pipeline.abort_flags.insert(request_id, abort.clone());
 
-        match &event.driver {
-            DriverType::RemoteArtifact { id } => {
-                let token = match maybe_auth_token(&web_state) {
-                    Ok(token) => token,
-                    Err(error) => {
-                        fetch_pipeline.pending.remove(&request_id);
-                        web_state.status_message = Some(error);
-                        continue;
-                    }
-                };
-                web_state.status_message = Some(format!("Downloading artifact #{id}..."));
-                web_fetch_artifact_elf(
-                    &web_state.server_url,
-                    token.as_deref(),
-                    *id,
-                    request_id,
-                    fetch_pipeline.async_results.clone(),
-                );
+        let auth = match maybe_auth(&web_state) {
+            Ok(auth) => auth,
+            Err(error) => {
+                fetch_pipeline.pending.remove(&request_id);
+                fetch_pipeline.abort_flags.remove(&request_id);
+                web_state.record_spawn_failure(&event.driver, error);
+                continue;
             }
+        };
+        web_state.status_message = Some(format!("Downloading artifact #{id}..."));
+        web_fetch_artifact_elf(
+            &web_state.server_url,
+            auth.as_ref(),
+            id,
+            request_id,
+            abort,
+            fetch_pipeline.async_results.clone(),
+        );
+    }
+}
+
+fn handle_cancel_spawn(
+    mut events: MessageReader<CancelSpawn>,
+    mut fetch_pipeline: ResMut<ArtifactFetchPipeline>,
+    mut web_state: ResMut<WebPortalState>,
+) {
+    for event in events.read() {
+        let Some(driver) = fetch_pipeline.pending.remove(&event.request_id) else {
+            continue;
+        };
+        if let Some(abort) = fetch_pipeline.abort_flags.remove(&event.request_id) {
+            abort.store(true, Ordering::Relaxed);
         }
+        web_state.status_message = Some(format!("Cancelled loading {}", driver.label()));
     }
 }
 
@@ -887,6 +2341,7 @@ fn process_artifact_fetch_results(
     }
 
     for result in results {
+        fetch_pipeline.abort_flags.remove(&result.id);
         let Some(driver) = fetch_pipeline.pending.remove(&result.id) else {
             continue;
         };
@@ -901,7 +2356,10 @@ fn process_artifact_fetch_results(
                     continue;
                 }
 
-                resolved_events.write(SpawnResolvedCarRequest {
+                if let DriverType::RemoteArtifact { id } = &driver {
+                    fetch_pipeline.elf_cache.insert(*id, elf_bytes.clone());
+                }
+                fetch_pipeline.ready.push(SpawnResolvedCarRequest {
                     driver,
                     elf_bytes,
                     binary_name: result.binary.clone(),
@@ -909,11 +2367,824 @@ fn process_artifact_fetch_results(
                 web_state.status_message = Some(format!("Loaded and spawned '{}'", result.binary));
             }
             Err(error) => {
-                web_state.status_message = Some(format!(
-                    "Artifact load failed for '{}': {}",
-                    result.binary, error
-                ));
+                web_state.record_spawn_failure(
+                    &driver,
+                    format!("Artifact load failed for '{}': {error}", result.binary),
+                );
             }
         }
     }
+
+    if *state.get() != SimState::PreRace {
+        fetch_pipeline.ready.clear();
+    } else if fetch_pipeline.pending.is_empty() {
+        let mut ready = std::mem::take(&mut fetch_pipeline.ready);
+        // Stable, so the same driver spawned twice keeps its request order.
+        ready.sort_by(|a, b| a.driver.cmp(&b.driver));
+        resolved_events.write_batch(ready);
+    }
+}
+
+/// Lowers `GridLimits::max_cars` to the server's field size, so a grid whose
+/// results would be refused cannot start.
+fn limit_grid_to_server_field(web_state: Res<WebPortalState>, mut limits: ResMut<GridLimits>) {
+    let Some(max_field_size) = web_state.max_field_size else {
+        return;
+    };
+    let max_field_size = max_field_size as usize;
+    if limits.max_cars > max_field_size {
+        info!(
+            "Limiting the grid to {max_field_size} cars, the most the server accepts results for"
+        );
+        limits.max_cars = max_field_size;
+    }
+}
+
+/// Tells the player whether a spawned bot actually runs, so a car that never
+/// moves is explained in the status line.
+fn report_car_loads(
+    mut reports: MessageReader<CarLoadReport>,
+    mut web_state: ResMut<WebPortalState>,
+) {
+    for report in reports.read() {
+        let label = report.driver.label();
+        match &report.outcome {
+            CarLoadOutcome::Rejected { error } => {
+                web_state.record_spawn_failure(&report.driver, format!("Not spawning: {error}"));
+            }
+            CarLoadOutcome::NotDriving { warning } => {
+                web_state.status_message =
+                    Some(format!("[warning][spawn] {label} may not drive: {warning}"));
+            }
+            CarLoadOutcome::Started => {
+                web_state.status_message = Some(format!("[spawn] {label} started"));
+            }
+            CarLoadOutcome::TrappedAtInit { trap } => {
+                web_state.record_spawn_failure(&report.driver, format!("Trapped at init: {trap}"));
+            }
+        }
+    }
+}
+
+fn report_race_start_refusals(
+    mut refusals: MessageReader<RaceStartRefused>,
+    mut web_state: ResMut<WebPortalState>,
+) {
+    for refusal in refusals.read() {
+        web_state.status_message = Some(format!("[race] {}", refusal.reason));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(id: i64) -> ArtifactSummary {
+        ArtifactSummary {
+            id: ArtifactId(id),
+            owner_user_id: 1,
+            owner_username: "local".to_string(),
+            name: format!("bot_{id}"),
+            note: None,
+            target: "riscv32imafc-unknown-none-elf".to_string(),
+            is_public: false,
+            owned_by_me: true,
+            starred: false,
+            abi_version: None,
+            build_id: None,
+            races_count: 0,
+            wins: 0,
+            best_lap_secs: None,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            deleted_at: None,
+        }
+    }
+
+    fn page(ids: std::ops::Range<i64>) -> Vec<ArtifactSummary> {
+        ids.map(artifact).collect()
+    }
+
+    fn response(status: u16, status_text: &str, body: &str) -> ehttp::Response {
+        ehttp::Response {
+            url: "http://127.0.0.1:8787/api/v1/artifacts".to_string(),
+            ok: (200..300).contains(&status),
+            status,
+            status_text: status_text.to_string(),
+            headers: ehttp::Headers::default(),
+            bytes: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn web_api_errors_classify_failed_responses() {
+        let expired = WebApiError::from_response(&response(401, "Unauthorized", "expired"));
+        assert!(matches!(expired, WebApiError::Auth { .. }));
+        assert_eq!(expired.to_string(), "HTTP 401 Unauthorized: expired");
+
+        let broken = WebApiError::from_response(&response(500, "Internal Server Error", "boom\n"));
+        assert!(matches!(broken, WebApiError::Http { status: 500, .. }));
+        assert_eq!(broken.to_string(), "HTTP 500 Internal Server Error: boom");
+
+        let duplicate = upload_error(&response(409, "Conflict", ""), "car.elf");
+        assert!(matches!(duplicate, WebApiError::Http { status: 409, .. }));
+
+        let garbled =
+            parse_response::<Vec<ArtifactSummary>>(&response(200, "OK", "<html>"), "artifacts")
+                .unwrap_err();
+        assert!(matches!(garbled, WebApiError::Deserialize(_)));
+        assert!(
+            garbled
+                .to_string()
+                .starts_with("invalid artifacts response: ")
+        );
+    }
+
+    #[test]
+    fn only_session_failures_trigger_reauthentication() {
+        let auth = || WebApiError::Auth {
+            message: "HTTP 401 Unauthorized: expired".to_string(),
+        };
+        let artifacts = |error: WebApiError| WebApiEvent::Artifacts {
+            offset: 0,
+            result: Err(error),
+        };
+        assert!(artifacts(auth()).session_rejected());
+        assert!(!artifacts(WebApiError::Network("refused".to_string())).session_rejected());
+        assert!(
+            !artifacts(WebApiError::Http {
+                status: 403,
+                message: "forbidden".to_string(),
+            })
+            .session_rejected()
+        );
+        assert!(!WebApiEvent::Login(Err(auth())).session_rejected());
+    }
+
+    #[test]
+    fn load_more_accumulates_pages_without_duplicates() {
+        let mut state = WebPortalState::default();
+        let page_size = ARTIFACT_PAGE_SIZE as i64;
+
+        apply_artifact_page(&mut state, 0, page(0..page_size));
+        assert_eq!(state.artifacts_next_offset, ARTIFACT_PAGE_SIZE);
+        assert!(!state.artifacts_exhausted);
+
+        // A row shifted by a concurrent upload reappears at the start of the next page.
+        let mut second = page(page_size..2 * page_size);
+        second.insert(0, artifact(page_size - 1));
+        second.pop();
+        let offset = state.artifacts_next_offset;
+        apply_artifact_page(&mut state, offset, second);
+        assert!(!state.artifacts_exhausted);
+
+        let offset = state.artifacts_next_offset;
+        apply_artifact_page(
+            &mut state,
+            offset,
+            page(2 * page_size - 1..2 * page_size + 3),
+        );
+        assert!(state.artifacts_exhausted);
+
+        let ids: Vec<i64> = state.artifacts.iter().map(|a| a.id.0).collect();
+        let expected: Vec<i64> = (0..2 * page_size + 3).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn initial_load_replaces_accumulated_pages() {
+        let mut state = WebPortalState::default();
+        apply_artifact_page(&mut state, 0, page(0..ARTIFACT_PAGE_SIZE as i64));
+        apply_artifact_page(&mut state, ARTIFACT_PAGE_SIZE, page(100..105));
+        apply_artifact_page(&mut state, 0, page(7..9));
+
+        let ids: Vec<i64> = state.artifacts.iter().map(|a| a.id.0).collect();
+        assert_eq!(ids, vec![7, 8]);
+        assert_eq!(state.artifacts_next_offset, 2);
+        assert!(state.artifacts_exhausted);
+    }
+
+    #[test]
+    fn uploads_above_the_server_limit_are_rejected_locally() {
+        assert!(check_artifact_size("bot.elf", 1024, Some(1024)).is_ok());
+        assert!(check_artifact_size("bot.elf", 1025, None).is_ok());
+        let error = check_artifact_size("bot.elf", 1025, Some(1024)).unwrap_err();
+        assert!(error.contains("at most 1024"), "{error}");
+    }
+
+    #[test]
+    fn mismatched_server_major_version_warns() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<WebApiQueue>()
+            .init_resource::<ArtifactFetchPipeline>()
+            .insert_resource(WebPortalState {
+                server_url: "http://127.0.0.1:1".to_string(),
+                ..default()
+            })
+            .add_systems(Update, process_web_api_events);
+
+        let client_major: u32 = env!("CARGO_PKG_VERSION")
+            .split('.')
+            .next()
+            .and_then(|major| major.parse().ok())
+            .expect("client major version");
+        let queue = app.world().resource::<WebApiQueue>().events.clone();
+        push_web_event(
+            &queue,
+            WebApiEvent::Capabilities(Ok(ServerCapabilities {
+                auth_required: false,
+                mode: "disabled".to_string(),
+                registration_enabled: false,
+                default_artifact_public: false,
+                max_artifact_bytes: None,
+                allowed_targets: Vec::new(),
+                compile_supported: false,
+                version: format!("{}.0.0", client_major + 1),
+                auth_header: DEFAULT_AUTH_HEADER.to_string(),
+                auth_scheme: DEFAULT_AUTH_SCHEME.to_string(),
+                supported_abi_versions: vec![BOT_ABI_VERSION],
+                max_field_size: None,
+            })),
+        );
+        app.update();
+
+        let warning = app
+            .world()
+            .resource::<WebPortalState>()
+            .version_warning
+            .clone()
+            .expect("version warning");
+        assert!(
+            warning.contains("client/server version mismatch"),
+            "{warning}"
+        );
+
+        assert!(version_mismatch_warning("1.2.0", "1.9.3").is_none());
+        assert!(version_mismatch_warning("1.2.0", "").is_none());
+
+        assert!(abi_support_warning(&[]).is_none());
+        assert!(abi_support_warning(&[BOT_ABI_VERSION]).is_none());
+        assert!(abi_support_warning(&[BOT_ABI_VERSION + 1]).is_some());
+    }
+
+    #[test]
+    fn requests_carry_the_advertised_auth_header_and_scheme() {
+        let mut web_state = WebPortalState {
+            auth_required: Some(true),
+            token: Some("abc123".to_string()),
+            ..Default::default()
+        };
+        let auth = maybe_auth(&web_state).expect("logged in");
+        let request = web_request_with_auth("http://server/api/v1/me".to_string(), auth.as_ref());
+        assert_eq!(request.headers.get("authorization"), Some("Bearer abc123"));
+
+        web_state.auth_header = "X-BotRacers-Auth".to_string();
+        web_state.auth_scheme = "Token".to_string();
+        let auth = maybe_auth(&web_state).expect("logged in");
+        let request = web_request_with_auth("http://server/api/v1/me".to_string(), auth.as_ref());
+        assert_eq!(
+            request.headers.get("x-botracers-auth"),
+            Some("Token abc123")
+        );
+        assert_eq!(request.headers.get("authorization"), None);
+
+        web_state.auth_scheme.clear();
+        let auth = maybe_auth(&web_state).expect("logged in");
+        let request = web_request_with_auth("http://server/api/v1/me".to_string(), auth.as_ref());
+        assert_eq!(request.headers.get("x-botracers-auth"), Some("abc123"));
+    }
+
+    #[test]
+    fn duplicate_name_conflicts_get_a_friendly_upload_error() {
+        let response = |status: u16, body: &str| ehttp::Response {
+            url: "http://server/api/v1/artifacts".to_string(),
+            ok: false,
+            status,
+            status_text: String::new(),
+            headers: ehttp::Headers::default(),
+            bytes: body.as_bytes().to_vec(),
+        };
+
+        let conflict = upload_error(&response(409, "{}"), "car.elf").to_string();
+        assert!(
+            conflict.starts_with("you already have an artifact named 'car.elf'"),
+            "{conflict}"
+        );
+        let other = upload_error(&response(400, "bad target"), "car.elf").to_string();
+        assert!(other.contains("bad target"), "{other}");
+    }
+
+    fn fetch_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .add_message::<SpawnResolvedCarRequest>()
+            .add_message::<CancelSpawn>()
+            .init_resource::<ArtifactFetchPipeline>()
+            .init_resource::<WebPortalState>()
+            .add_systems(
+                Update,
+                (handle_cancel_spawn, process_artifact_fetch_results).chain(),
+            );
+        app
+    }
+
+    fn start_fetch(app: &mut App, artifact_id: i64) -> (u64, Arc<AtomicBool>) {
+        let mut pipeline = app.world_mut().resource_mut::<ArtifactFetchPipeline>();
+        let request_id = pipeline.next_request_id;
+        pipeline.next_request_id += 1;
+        pipeline.pending.insert(
+            request_id,
+            DriverType::RemoteArtifact {
+                id: ArtifactId(artifact_id),
+            },
+        );
+        let abort = Arc::new(AtomicBool::new(false));
+        pipeline.abort_flags.insert(request_id, abort.clone());
+        (request_id, abort)
+    }
+
+    fn finish_fetch(app: &mut App, request_id: u64, abort: &AtomicBool) {
+        let queue = app
+            .world()
+            .resource::<ArtifactFetchPipeline>()
+            .async_results
+            .clone();
+        deliver_fetch_result(
+            abort,
+            &queue,
+            CompileResult {
+                id: request_id,
+                binary: format!("artifact_{request_id}"),
+                result: Ok(vec![0x7f, b'E', b'L', b'F']),
+            },
+        );
+    }
+
+    fn resolved_spawns(app: &App) -> usize {
+        app.world()
+            .resource::<Messages<SpawnResolvedCarRequest>>()
+            .len()
+    }
+
+    #[test]
+    fn cancelled_download_produces_no_resolved_spawn() {
+        let mut app = fetch_app();
+        let (cancelled, cancelled_abort) = start_fetch(&mut app, 7);
+        let (kept, kept_abort) = start_fetch(&mut app, 8);
+
+        app.world_mut().write_message(CancelSpawn {
+            request_id: cancelled,
+        });
+        app.update();
+        assert!(cancelled_abort.load(Ordering::Relaxed));
+        assert!(
+            !app.world()
+                .resource::<ArtifactFetchPipeline>()
+                .pending
+                .contains_key(&cancelled)
+        );
+
+        // Both downloads complete after the cancellation was processed.
+        finish_fetch(&mut app, cancelled, &cancelled_abort);
+        finish_fetch(&mut app, kept, &kept_abort);
+        app.update();
+
+        assert_eq!(resolved_spawns(&app), 1);
+        let pipeline = app.world().resource::<ArtifactFetchPipeline>();
+        assert!(pipeline.pending.is_empty());
+        assert!(pipeline.abort_flags.is_empty());
+    }
+
+    fn resolved_spawn_order(app: &mut App) -> Vec<ArtifactId> {
+        app.world_mut()
+            .resource_mut::<Messages<SpawnResolvedCarRequest>>()
+            .drain()
+            .map(|spawn| {
+                let DriverType::RemoteArtifact { id } = spawn.driver else {
+                    panic!("only artifacts are fetched");
+                };
+                id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scanning_a_directory_finds_its_elf_files() {
+        let dir = std::env::temp_dir().join(format!("botracers_local_bots_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested.elf")).expect("create dirs");
+        for name in ["slow.elf", "fast.ELF", "notes.txt"] {
+            std::fs::write(dir.join(name), b"\x7fELF").expect("write file");
+        }
+
+        let drivers = scan_local_bots(&dir).expect("scan");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            drivers,
+            vec![
+                DriverType::LocalFile {
+                    path: dir.join("fast.ELF")
+                },
+                DriverType::LocalFile {
+                    path: dir.join("slow.elf")
+                },
+            ]
+        );
+        assert_eq!(drivers[1].label(), "File: slow.elf");
+    }
+
+    #[test]
+    fn grid_order_does_not_depend_on_download_completion_order() {
+        let grid_for = |completion_order: [usize; 3]| {
+            let mut app = fetch_app();
+            let fetches: Vec<_> = [9, 4, 7]
+                .into_iter()
+                .map(|artifact_id| start_fetch(&mut app, artifact_id))
+                .collect();
+            let mut spawned = Vec::new();
+            for index in completion_order {
+                let (request_id, abort) = &fetches[index];
+                finish_fetch(&mut app, *request_id, abort);
+                app.update();
+                spawned.extend(resolved_spawn_order(&mut app));
+            }
+            spawned
+        };
+
+        let expected = vec![ArtifactId(4), ArtifactId(7), ArtifactId(9)];
+        assert_eq!(grid_for([0, 1, 2]), expected);
+        assert_eq!(grid_for([2, 0, 1]), expected);
+        assert_eq!(grid_for([1, 2, 0]), expected);
+    }
+
+    #[test]
+    fn cancelled_result_that_already_arrived_is_discarded() {
+        let mut app = fetch_app();
+        let (request_id, abort) = start_fetch(&mut app, 7);
+
+        // The download landed in the queue before the cancel was handled.
+        finish_fetch(&mut app, request_id, &abort);
+        app.world_mut().write_message(CancelSpawn { request_id });
+        app.update();
+
+        assert_eq!(resolved_spawns(&app), 0);
+    }
+
+    #[test]
+    fn failed_download_stays_listed_after_later_status_messages() {
+        let mut app = fetch_app();
+        let (failed, failed_abort) = start_fetch(&mut app, 7);
+        let queue = app
+            .world()
+            .resource::<ArtifactFetchPipeline>()
+            .async_results
+            .clone();
+        deliver_fetch_result(
+            &failed_abort,
+            &queue,
+            CompileResult {
+                id: failed,
+                binary: "artifact_7".to_string(),
+                result: Err(WebApiError::Http {
+                    status: 404,
+                    message: "artifact not found".to_string(),
+                }),
+            },
+        );
+        app.update();
+
+        let (loaded, loaded_abort) = start_fetch(&mut app, 8);
+        finish_fetch(&mut app, loaded, &loaded_abort);
+        app.update();
+
+        let web_state = app.world().resource::<WebPortalState>();
+        assert_eq!(
+            web_state.status_message.as_deref(),
+            Some("Loaded and spawned 'artifact_2'")
+        );
+        assert_eq!(
+            web_state.spawn_failures,
+            [SpawnFailure {
+                driver: "Artifact: #7".to_string(),
+                error: "Artifact load failed for 'artifact_7': artifact not found".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn pending_import_waits_for_capabilities() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<WebApiCommand>()
+            .insert_resource(WebPortalState {
+                pending_import_url: Some("https://example.com/bot.elf".to_string()),
+                ..default()
+            })
+            .add_systems(Update, send_pending_import);
+
+        app.update();
+        assert!(
+            app.world()
+                .resource::<WebPortalState>()
+                .pending_import_url
+                .is_some()
+        );
+
+        app.world_mut()
+            .resource_mut::<WebPortalState>()
+            .auth_required = Some(false);
+        app.update();
+        assert!(
+            app.world()
+                .resource::<WebPortalState>()
+                .pending_import_url
+                .is_none()
+        );
+        let commands: Vec<String> = app
+            .world_mut()
+            .resource_mut::<Messages<WebApiCommand>>()
+            .drain()
+            .filter_map(|cmd| match cmd {
+                WebApiCommand::ImportArtifact { url } => Some(url),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(commands, vec!["https://example.com/bot.elf".to_string()]);
+    }
+
+    fn capabilities_app(fetched_at: f64) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<WebApiCommand>()
+            .init_resource::<WebApiQueue>()
+            .insert_resource(WebPortalState {
+                server_url: "http://127.0.0.1:1".to_string(),
+                auth_required: Some(false),
+                capabilities_fetched_at: Some(fetched_at),
+                ..default()
+            })
+            .add_systems(Update, handle_web_api_commands);
+        app.world_mut().write_message(WebApiCommand::LoadArtifacts);
+        app.update();
+        app
+    }
+
+    #[test]
+    fn fresh_capabilities_are_not_refetched() {
+        let app = capabilities_app(0.0);
+        assert!(
+            !app.world()
+                .resource::<WebPortalState>()
+                .capabilities_pending
+        );
+    }
+
+    #[test]
+    fn stale_capabilities_are_refetched_by_commands() {
+        let app = capabilities_app(-CAPABILITIES_TTL_SECS);
+        assert!(
+            app.world()
+                .resource::<WebPortalState>()
+                .capabilities_pending
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn successful_registration_proceeds_to_login() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<WebApiQueue>()
+            .init_resource::<ArtifactFetchPipeline>()
+            .insert_resource(WebPortalState {
+                // Nothing listens here, so the follow-up login fails fast.
+                server_url: "http://127.0.0.1:1".to_string(),
+                auth_required: Some(true),
+                registration_enabled: true,
+                ..default()
+            })
+            .add_systems(Update, process_web_api_events);
+
+        let queue = app.world().resource::<WebApiQueue>().events.clone();
+        push_web_event(
+            &queue,
+            WebApiEvent::Registered {
+                username: "alice".to_string(),
+                password: "secret".to_string(),
+                result: Ok(UserInfo {
+                    id: 1,
+                    username: "alice".to_string(),
+                }),
+            },
+        );
+        app.update();
+
+        let state = app.world().resource::<WebPortalState>();
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("[auth] Registered 'alice'; logging in...")
+        );
+        assert_eq!(
+            state.cli_credentials,
+            Some(("alice".to_string(), "secret".to_string()))
+        );
+
+        // The login request issued after registering reports back through the queue.
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            app.update();
+            let status = app
+                .world()
+                .resource::<WebPortalState>()
+                .status_message
+                .clone();
+            if status
+                .as_deref()
+                .is_some_and(|s| s.starts_with("[error][auth] Login failed"))
+            {
+                break;
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "login never attempted: {status:?}"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn ownership_denials_keep_the_session() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<WebApiQueue>()
+            .init_resource::<ArtifactFetchPipeline>()
+            .insert_resource(WebPortalState {
+                auth_required: Some(true),
+                token: Some("session".to_string()),
+                ..default()
+            })
+            .add_systems(Update, process_web_api_events);
+
+        let denied = || {
+            WebApiError::from_response(&response(
+                403,
+                "Forbidden",
+                "artifact is not owned by current user",
+            ))
+        };
+        let queue = app.world().resource::<WebApiQueue>().events.clone();
+        push_web_event(
+            &queue,
+            WebApiEvent::CloneResult {
+                artifact_id: ArtifactId(3),
+                result: Err(denied()),
+            },
+        );
+        push_web_event(
+            &queue,
+            WebApiEvent::StarResult {
+                artifact_id: ArtifactId(3),
+                starred: true,
+                result: Err(denied()),
+            },
+        );
+        app.update();
+
+        let state = app.world().resource::<WebPortalState>();
+        assert_eq!(state.token.as_deref(), Some("session"));
+        let status = state.status_message.as_deref().unwrap_or_default();
+        assert!(status.contains("not owned"), "{status}");
+    }
+
+    #[test]
+    fn loaded_replays_are_resimulated_into_the_status() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<WebApiQueue>()
+            .init_resource::<ArtifactFetchPipeline>()
+            .insert_resource(WebPortalState {
+                auth_required: Some(false),
+                ..default()
+            })
+            .add_systems(Update, process_web_api_events);
+        let status = |app: &App| {
+            app.world()
+                .resource::<WebPortalState>()
+                .status_message
+                .clone()
+                .unwrap_or_default()
+        };
+
+        let replay = ReplayFile {
+            version: botracers_protocol::REPLAY_FORMAT_VERSION,
+            tick_hz: crate::race_runtime::FIXED_TICK_HZ,
+            track: TrackFile::load_builtin().expect("track").metadata.name,
+            car_scale: 1.0,
+            cars_collide: true,
+            tire_wear_per_m: None,
+            participants: vec![botracers_protocol::ReplayParticipant {
+                artifact_id: ArtifactId(7),
+                start_x: 0.0,
+                start_y: 0.0,
+                start_heading: 0.0,
+                brake_bias: None,
+            }],
+            ticks: vec![
+                [botracers_protocol::ReplayInput {
+                    steering: 0.0,
+                    accelerator: 0.0,
+                    brake: 1.0,
+                }];
+                3
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let queue = app.world().resource::<WebApiQueue>().events.clone();
+        push_web_event(
+            &queue,
+            WebApiEvent::ReplayLoaded {
+                replay_id: 5,
+                result: Ok(replay.clone()),
+            },
+        );
+        app.update();
+        assert_eq!(status(&app), "[replays] Replay #5: 1. #7 0 laps");
+
+        let mut elsewhere = replay;
+        elsewhere.track = "elsewhere".to_string();
+        push_web_event(
+            &queue,
+            WebApiEvent::ReplayLoaded {
+                replay_id: 6,
+                result: Ok(elsewhere),
+            },
+        );
+        app.update();
+        let rejected = status(&app);
+        assert!(
+            rejected.starts_with("[error][replays] Failed to load replay #6: "),
+            "{rejected}"
+        );
+
+        push_web_event(
+            &queue,
+            WebApiEvent::ReplayLoaded {
+                replay_id: 8,
+                result: Err(WebApiError::from_response(&response(
+                    404,
+                    "Not Found",
+                    "replay not found",
+                ))),
+            },
+        );
+        app.update();
+        let missing = status(&app);
+        assert!(missing.contains("replay not found"), "{missing}");
+    }
+
+    #[test]
+    fn empty_artifact_list_is_told_apart_from_not_loaded() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<WebApiQueue>()
+            .init_resource::<ArtifactFetchPipeline>()
+            .insert_resource(WebPortalState {
+                auth_required: Some(true),
+                ..default()
+            })
+            .add_systems(Update, process_web_api_events);
+        assert!(!app.world().resource::<WebPortalState>().artifacts_empty);
+
+        let queue = app.world().resource::<WebApiQueue>().events.clone();
+        push_web_event(
+            &queue,
+            WebApiEvent::Artifacts {
+                offset: 0,
+                result: Ok(Vec::new()),
+            },
+        );
+        app.update();
+
+        let state = app.world().resource::<WebPortalState>();
+        assert!(state.artifacts_empty);
+        let status = state.status_message.as_deref().unwrap_or_default();
+        assert!(status.contains("press Upload"), "{status}");
+        assert!(
+            status.contains("BOTRACERS_REGISTRATION_ENABLED"),
+            "{status}"
+        );
+
+        push_web_event(
+            &queue,
+            WebApiEvent::Artifacts {
+                offset: 0,
+                result: Ok(page(0..2)),
+            },
+        );
+        app.update();
+        assert!(!app.world().resource::<WebPortalState>().artifacts_empty);
+    }
 }
@@ -1,18 +1,24 @@
 mod car_controls;
 mod car_radar;
 mod car_state;
+mod metrics;
 mod spline_query;
 mod track_radar;
 
-pub use car_controls::CarControlsDevice;
+pub use car_controls::{
+    ActuatorRateLimits, CAR_CONTROLS_SLOT, CarControlsDevice, ControlsWatchdog, FirstControls,
+    driving_warning, first_controls,
+};
 pub use car_radar::CarRadarDevice;
-pub use car_state::CarStateDevice;
+pub use car_state::{CarStateDevice, PARAM_COUNT};
+pub use metrics::MetricsDevice;
 pub use spline_query::SplineDevice;
 pub use track_radar::TrackRadarDevice;
 
 pub use car_controls::update_system as car_controls_system;
 pub use car_radar::update_system as car_radar_system;
 pub use car_state::system as car_state_system;
+pub use metrics::update_system as metrics_system;
 pub use track_radar::update_system as track_radar_system;
 
 pub use track_radar::TrackRadarBorders;
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
-use emulator::cpu::Device;
+use emulator::bevy::CpuComponent;
+use emulator::cpu::{self, Device, Trap};
 
 use crate::Car;
 
@@ -12,11 +13,19 @@ use crate::Car;
 #[derive(Component)]
 pub struct CarControlsDevice {
     data: [u8; 12], // 3 × f32
+    /// Set by every store, cleared once per update by [`update_system`].
+    written: bool,
+    /// Updates in a row without a store, for [`ControlsWatchdog`].
+    silent_updates: u32,
 }
 
 impl Default for CarControlsDevice {
     fn default() -> Self {
-        Self { data: [0u8; 12] }
+        Self {
+            data: [0u8; 12],
+            written: false,
+            silent_updates: 0,
+        }
     }
 }
 
@@ -45,6 +54,111 @@ impl CarControlsDevice {
     pub fn steering(&self) -> f32 {
         self.read_f32(0x08)
     }
+
+    /// Updates in a row, up to the last one, in which the bot wrote none of
+    /// its controls.
+    pub fn silent_updates(&self) -> u32 {
+        self.silent_updates
+    }
+
+    fn record_update(&mut self) -> u32 {
+        if std::mem::take(&mut self.written) {
+            self.silent_updates = 0;
+        } else {
+            self.silent_updates = self.silent_updates.saturating_add(1);
+        }
+        self.silent_updates
+    }
+}
+
+/// Takes over from a bot that stopped writing its controls, e.g. because it
+/// halted or is stuck: after `timeout_updates` updates without a control
+/// write the accelerator falls to zero and the steering returns to center at
+/// `release_per_s`, so the car coasts instead of holding its last command.
+/// The brake is left where it is. A `timeout_updates` of 0 turns it off.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ControlsWatchdog {
+    pub timeout_updates: u32,
+    pub release_per_s: f32,
+}
+
+impl Default for ControlsWatchdog {
+    fn default() -> Self {
+        Self {
+            // One second at the 200 Hz fixed tick.
+            timeout_updates: 200,
+            release_per_s: 2.0,
+        }
+    }
+}
+
+/// Device slot racing CPUs map [`CarControlsDevice`] to.
+pub const CAR_CONTROLS_SLOT: usize = 3;
+
+/// A bot's first write to its controls, as found by [`first_controls`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FirstControls {
+    /// Instructions executed up to and including the first control store.
+    pub cycles: u32,
+    pub accelerator: f32,
+    pub brake: f32,
+    pub steering: f32,
+}
+
+/// Steps `cpu` until the bot first writes its controls, for at most
+/// `max_cycles` instructions, so validation can report "produced controls in
+/// N cycles" or, on `None`, "never controlled". `devices` are the racing
+/// slots in order, with the controls at [`CAR_CONTROLS_SLOT`].
+pub fn first_controls(
+    cpu: &mut CpuComponent,
+    max_cycles: u32,
+    devices: &mut [&mut dyn Device],
+) -> Result<Option<FirstControls>, Trap> {
+    let Some(cycles) = cpu.run_until_store(CAR_CONTROLS_SLOT, max_cycles, devices)? else {
+        return Ok(None);
+    };
+    let controls = &devices[CAR_CONTROLS_SLOT - 1];
+    let read = |offset| f32::from_bits(controls.load(offset, 32).unwrap_or(0));
+    Ok(Some(FirstControls {
+        cycles,
+        accelerator: read(0x00),
+        brake: read(0x04),
+        steering: read(0x08),
+    }))
+}
+
+/// Why `elf` does not look like a bot that drives, e.g. a log-only demo, or
+/// `None` if it does. A static heuristic for a warning at spawn, not a
+/// verdict: the bot must define `main` (when it has a symbol table) and load
+/// an address in the [`CAR_CONTROLS_SLOT`] window somewhere. Malformed images
+/// give `None`, as loading reports those.
+pub fn driving_warning(elf: &[u8]) -> Option<String> {
+    if cpu::defines_symbol(elf, "main").ok()? == Some(false) {
+        return Some("it defines no `main` entry".to_string());
+    }
+    if !cpu::referenced_slots(elf)
+        .ok()?
+        .contains(&CAR_CONTROLS_SLOT)
+    {
+        return Some(format!(
+            "it never addresses the car controls (0x{:x}), so it may not drive",
+            CAR_CONTROLS_SLOT * 0x100
+        ));
+    }
+    None
+}
+
+/// Optional per-car actuator slew limits, in units per second. Without it a
+/// car applies the bot's commands as soon as they are written.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ActuatorRateLimits {
+    pub accelerator_per_s: f32,
+    pub brake_per_s: f32,
+    pub steering_per_s: f32,
+}
+
+fn slew(current: f32, target: f32, max_step: f32) -> f32 {
+    current + (target - current).clamp(-max_step, max_step)
 }
 
 impl Device for CarControlsDevice {
@@ -86,6 +200,7 @@ impl Device for CarControlsDevice {
             8 => {
                 if addr < self.data.len() {
                     self.data[addr] = value as u8;
+                    self.written = true;
                     Ok(())
                 } else {
                     Err(())
@@ -95,6 +210,7 @@ impl Device for CarControlsDevice {
                 if addr + 1 < self.data.len() {
                     self.data[addr] = (value & 0xFF) as u8;
                     self.data[addr + 1] = ((value >> 8) & 0xFF) as u8;
+                    self.written = true;
                     Ok(())
                 } else {
                     Err(())
@@ -106,6 +222,7 @@ impl Device for CarControlsDevice {
                     self.data[addr + 1] = ((value >> 8) & 0xFF) as u8;
                     self.data[addr + 2] = ((value >> 16) & 0xFF) as u8;
                     self.data[addr + 3] = ((value >> 24) & 0xFF) as u8;
+                    self.written = true;
                     Ok(())
                 } else {
                     Err(())
@@ -116,11 +233,42 @@ impl Device for CarControlsDevice {
     }
 }
 
-/// Runs AFTER cpu_system::<RacingCpuConfig>: reads control outputs and applies them.
-pub fn update_system(mut emu_query: Query<(&mut Car, &CarControlsDevice)>) {
-    for (mut car, ctrl_dev) in &mut emu_query {
-        car.accelerator = ctrl_dev.accelerator();
-        car.brake = ctrl_dev.brake();
-        car.steer = ctrl_dev.steering();
+/// Runs AFTER cpu_system::<RacingCpuConfig>: reads control outputs and applies them,
+/// slewed by [`ActuatorRateLimits`] where present, unless the
+/// [`ControlsWatchdog`] has taken over.
+pub fn update_system(
+    time: Res<Time>,
+    watchdog: Res<ControlsWatchdog>,
+    mut emu_query: Query<(
+        &mut Car,
+        &mut CarControlsDevice,
+        Option<&ActuatorRateLimits>,
+    )>,
+) {
+    let dt = time.delta_secs();
+    for (mut car, mut ctrl_dev, limits) in &mut emu_query {
+        let silent_updates = ctrl_dev.record_update();
+        if watchdog.timeout_updates > 0 && silent_updates >= watchdog.timeout_updates {
+            let step = watchdog.release_per_s * dt;
+            car.accelerator = slew(car.accelerator, 0.0, step);
+            car.steer = slew(car.steer, 0.0, step);
+            continue;
+        }
+        match limits {
+            Some(limits) => {
+                car.accelerator = slew(
+                    car.accelerator,
+                    ctrl_dev.accelerator(),
+                    limits.accelerator_per_s * dt,
+                );
+                car.brake = slew(car.brake, ctrl_dev.brake(), limits.brake_per_s * dt);
+                car.steer = slew(car.steer, ctrl_dev.steering(), limits.steering_per_s * dt);
+            }
+            None => {
+                car.accelerator = ctrl_dev.accelerator();
+                car.brake = ctrl_dev.brake();
+                car.steer = ctrl_dev.steering();
+            }
+        }
     }
 }
@@ -2,22 +2,50 @@ use avian2d::prelude::*;
 use bevy::prelude::*;
 use emulator::cpu::Device;
 
+use crate::Car;
+use crate::track::TrackCenterline;
+
 /// Memory-mapped device that provides car state to the RISC-V bot.
 ///
-/// Layout (all f32, little-endian):
+/// Layout (f32 unless noted, little-endian):
 ///   0x00: speed
 ///   0x04: position_x
 ///   0x08: position_y
 ///   0x0C: forward_x
 ///   0x10: forward_y
+///   0x14: applied_steering
+///   0x18: applied_throttle
+///   0x1C: applied_brake
+///   0x20: centerline_offset (signed distance to the track centre line,
+///         positive to the right of the lap direction)
+///   0x24: laps_completed (u32)
+///   0x28: race_laps (u32, laps the race runs for; 0 when open-ended)
+///   0x2C: tire_wear (0 on fresh tires, 1 when worn out)
+///   0x30: finished (u32, 1 once the car has completed race_laps, else 0)
+///   0x34: cycles_per_tick (u32, instructions the bot may run per update)
+///   0x38: brake_bias (share of the brake force sent to the front axle)
+///   0x3C: brake_force_front (N, after the axle's traction cap)
+///   0x40: brake_force_rear (N, after the axle's traction cap)
+///   0x80..0xA0: params[0..PARAM_COUNT] (tuning knobs set by the host)
+///
+/// The applied values are what the car actually used last tick, after any
+/// actuator rate limiting, rather than what the bot commanded. The params are
+/// only written by [`CarStateDevice::set_params`], so they keep their values
+/// across ticks and races.
 #[derive(Component)]
 pub struct CarStateDevice {
-    data: [u8; 20], // 5 × f32
+    data: [u8; PARAMS_OFFSET + PARAM_COUNT * 4],
 }
 
+/// Number of f32 tuning params a bot can read from its car state.
+pub const PARAM_COUNT: usize = 8;
+const PARAMS_OFFSET: usize = 0x80;
+
 impl Default for CarStateDevice {
     fn default() -> Self {
-        Self { data: [0u8; 20] }
+        Self {
+            data: [0u8; PARAMS_OFFSET + PARAM_COUNT * 4],
+        }
     }
 }
 
@@ -35,6 +63,51 @@ impl CarStateDevice {
         self.write_f32(0x0C, forward.x);
         self.write_f32(0x10, forward.y);
     }
+
+    /// Write the control values the car applied after actuator limits.
+    pub fn update_applied(&mut self, steering: f32, throttle: f32, brake: f32) {
+        self.write_f32(0x14, steering);
+        self.write_f32(0x18, throttle);
+        self.write_f32(0x1C, brake);
+    }
+
+    /// Write the car's signed lateral offset from the track centre line.
+    pub fn update_centerline_offset(&mut self, offset: f32) {
+        self.write_f32(0x20, offset);
+    }
+
+    /// Write the car's completed laps and the race's lap count, and whether
+    /// that finishes the car's race (never in an open-ended one).
+    pub fn update_laps(&mut self, completed: u32, race_laps: u32) {
+        let finished = race_laps > 0 && completed >= race_laps;
+        self.data[0x24..0x28].copy_from_slice(&completed.to_le_bytes());
+        self.data[0x28..0x2C].copy_from_slice(&race_laps.to_le_bytes());
+        self.data[0x30..0x34].copy_from_slice(&u32::from(finished).to_le_bytes());
+    }
+
+    /// Write how worn the car's tires are.
+    pub fn update_tire_wear(&mut self, wear: f32) {
+        self.write_f32(0x2C, wear);
+    }
+
+    /// Write the car's brake bias and the brake force each axle applied.
+    pub fn update_brakes(&mut self, bias: f32, front: f32, rear: f32) {
+        self.write_f32(0x38, bias);
+        self.write_f32(0x3C, front);
+        self.write_f32(0x40, rear);
+    }
+
+    /// Write the instruction budget the bot gets per fixed update.
+    pub fn set_cycles_per_tick(&mut self, cycles: u32) {
+        self.data[0x34..0x38].copy_from_slice(&cycles.to_le_bytes());
+    }
+
+    /// Write the tuning params the bot reads with `CarState::param(i)`.
+    pub fn set_params(&mut self, params: &[f32; PARAM_COUNT]) {
+        for (index, value) in params.iter().enumerate() {
+            self.write_f32(PARAMS_OFFSET + index * 4, *value);
+        }
+    }
 }
 
 impl Device for CarStateDevice {
@@ -75,12 +148,21 @@ impl Device for CarStateDevice {
     }
 }
 
-/// Runs BEFORE cpu_system::<RacingCpuConfig>: writes host car kinematics into CarStateDevice.
-pub fn system(mut emu_query: Query<(&Transform, &LinearVelocity, &mut CarStateDevice)>) {
-    for (transform, velocity, mut state_dev) in &mut emu_query {
+/// Runs BEFORE cpu_system::<RacingCpuConfig>: writes host car kinematics, applied
+/// controls and the centre-line offset into CarStateDevice.
+pub fn system(
+    centerline: Option<Res<TrackCenterline>>,
+    mut emu_query: Query<(&Transform, &LinearVelocity, &Car, &mut CarStateDevice)>,
+) {
+    for (transform, velocity, car, mut state_dev) in &mut emu_query {
         let car_pos = transform.translation.xy();
         let car_forward = transform.up().xy().normalize();
         let car_speed = velocity.length();
         state_dev.update(car_speed, car_pos, car_forward);
+        state_dev.update_applied(car.steer, car.accelerator, car.brake);
+        let offset = centerline
+            .as_ref()
+            .map_or(0.0, |c| c.signed_offset(car_pos));
+        state_dev.update_centerline_offset(offset);
     }
 }
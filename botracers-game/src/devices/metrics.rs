@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use bevy::prelude::*;
+use emulator::cpu::Device;
+
+/// Memory-mapped device bots push named telemetry samples into.
+///
+/// Layout (little-endian):
+///   0x00: metric id (u32, write)
+///   0x04: value (f32, write; records `(id, value)` at the current tick)
+///   0x08: recorded (u32, read; samples kept so far)
+///   0x0C: dropped (u32, read; samples lost to the capacity)
+#[derive(Component)]
+pub struct MetricsDevice {
+    id: u32,
+    /// Fixed updates seen so far, advanced by [`update_system`].
+    tick: u32,
+    series: BTreeMap<u32, Vec<(u32, f32)>>,
+    recorded: u32,
+    dropped: u32,
+    capacity: u32,
+}
+
+impl Default for MetricsDevice {
+    fn default() -> Self {
+        // About a minute of one sample per metric per tick for eight metrics.
+        Self::with_capacity(100_000)
+    }
+}
+
+impl MetricsDevice {
+    /// A device that keeps at most `capacity` samples across all metrics and
+    /// counts the rest as dropped.
+    pub fn with_capacity(capacity: u32) -> Self {
+        Self {
+            id: 0,
+            tick: 0,
+            series: BTreeMap::new(),
+            recorded: 0,
+            dropped: 0,
+            capacity,
+        }
+    }
+
+    /// `(tick, value)` samples recorded for `id`, oldest first.
+    pub fn series(&self, id: u32) -> &[(u32, f32)] {
+        self.series.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Ids the bot has recorded at least one sample for, ascending.
+    pub fn metric_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.series.keys().copied()
+    }
+
+    /// Samples lost because the device was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// All samples as `tick,metric,value` lines under a header, ordered by
+    /// metric and then by tick.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("tick,metric,value\n");
+        for (id, samples) in &self.series {
+            for (tick, value) in samples {
+                writeln!(csv, "{tick},{id},{value}").unwrap();
+            }
+        }
+        csv
+    }
+
+    fn record(&mut self, value: f32) {
+        if self.recorded >= self.capacity {
+            self.dropped = self.dropped.saturating_add(1);
+            return;
+        }
+        self.series
+            .entry(self.id)
+            .or_default()
+            .push((self.tick, value));
+        self.recorded += 1;
+    }
+}
+
+impl Device for MetricsDevice {
+    fn load(&self, addr: u32, size: u32) -> Result<u32, ()> {
+        match (addr, size) {
+            (0x00, 32) => Ok(self.id),
+            (0x08, 32) => Ok(self.recorded),
+            (0x0C, 32) => Ok(self.dropped),
+            (_, 8 | 16 | 32) => Ok(0),
+            _ => Err(()),
+        }
+    }
+
+    fn store(&mut self, addr: u32, size: u32, value: u32) -> Result<(), ()> {
+        match (addr, size) {
+            (0x00, 32) => self.id = value,
+            (0x04, 32) => self.record(f32::from_bits(value)),
+            _ => return Err(()),
+        }
+        Ok(())
+    }
+}
+
+/// Runs AFTER cpu_system::<RacingCpuConfig>: closes the tick the bot's
+/// samples were recorded in.
+pub fn update_system(mut query: Query<&mut MetricsDevice>) {
+    for mut metrics in &mut query {
+        metrics.tick = metrics.tick.wrapping_add(1);
+    }
+}
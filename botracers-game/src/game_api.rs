@@ -1,18 +1,68 @@
+use std::path::PathBuf;
+
 use bevy::prelude::*;
+use botracers_protocol::{ArtifactId, ArtifactScope, RaceEntrySubmission, ReplayFile};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::race_setup::RaceSetup;
+
+/// Where a car's bot comes from. The ordering (artifacts by id, then local
+/// files by path) is the order a batch of spawns takes on the grid.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DriverType {
-    RemoteArtifact { id: i64 },
+    RemoteArtifact {
+        id: ArtifactId,
+    },
+    /// An ELF read from disk (`--local-bots=<dir>`); never sent to a server.
+    LocalFile {
+        path: PathBuf,
+    },
 }
 
 impl DriverType {
     pub fn label(&self) -> String {
         match self {
             DriverType::RemoteArtifact { id } => format!("Artifact: #{id}"),
+            DriverType::LocalFile { path } => format!("File: {}", local_file_name(path)),
+        }
+    }
+
+    /// Kart tint for this driver; the same artifact or file name always gets
+    /// the same color.
+    pub fn color(&self) -> Color {
+        match self {
+            DriverType::RemoteArtifact { id } => artifact_color(*id),
+            DriverType::LocalFile { path } => {
+                // FNV-1a, so the tint does not change between runs.
+                let hash = local_file_name(path)
+                    .bytes()
+                    .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+                    });
+                golden_ratio_color(hash as i64)
+            }
         }
     }
 }
 
+/// The file name of a local bot, or the whole path if it has none.
+pub fn local_file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Steps artifact ids around the hue circle by the golden ratio, so ids that
+/// are close together still get clearly different colors.
+pub fn artifact_color(id: ArtifactId) -> Color {
+    golden_ratio_color(id.0)
+}
+
+fn golden_ratio_color(seed: i64) -> Color {
+    let hue = (seed as f64 * 0.618_033_988_75).rem_euclid(1.0) as f32 * 360.0;
+    Color::hsl(hue, 0.7, 0.6)
+}
+
 #[derive(Message)]
 pub struct SpawnCarRequest {
     pub driver: DriverType,
@@ -26,13 +76,101 @@ pub struct SpawnResolvedCarRequest {
     pub binary_name: String,
 }
 
+/// How a `SpawnResolvedCarRequest` turned out, reported back by the race
+/// runtime once it is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CarLoadOutcome {
+    /// The ELF image was refused and no car was spawned.
+    Rejected { error: String },
+    /// The car was spawned, but a static look at the ELF suggests the bot
+    /// will not drive it (see `devices::driving_warning`).
+    NotDriving { warning: String },
+    /// The bot got through its first updates without trapping.
+    Started,
+    /// The bot trapped during its first updates, so the car will not move.
+    TrappedAtInit { trap: String },
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct CarLoadReport {
+    pub driver: DriverType,
+    pub outcome: CarLoadOutcome,
+}
+
+/// Abandons an in-flight artifact download started for a `SpawnCarRequest`.
+#[derive(Message)]
+pub struct CancelSpawn {
+    pub request_id: u64,
+}
+
+/// Asks to start the race from `PreRace`; refused with a `RaceStartRefused`
+/// while the grid is outside the configured `GridLimits`.
+#[derive(Message)]
+pub struct StartRaceRequest;
+
+#[derive(Message, Debug, Clone)]
+pub struct RaceStartRefused {
+    pub reason: String,
+}
+
+/// Clears the track and respawns the drivers of the last race's grid.
+#[derive(Message)]
+pub struct RematchRequest;
+
+/// Replaces the `PreRace` grid and race length with a shared setup.
+#[derive(Message)]
+pub struct LoadRaceSetup {
+    pub setup: RaceSetup,
+}
+
 #[derive(Message)]
 pub enum WebApiCommand {
     RefreshCapabilities,
+    Register {
+        username: String,
+        password: String,
+    },
     LoadArtifacts,
+    LoadMoreArtifacts,
+    SetArtifactScope {
+        scope: ArtifactScope,
+    },
     UploadArtifact,
-    DeleteArtifact { id: i64 },
-    SetArtifactVisibility { id: i64, is_public: bool },
+    ImportArtifact {
+        url: String,
+    },
+    DeleteArtifact {
+        id: ArtifactId,
+    },
+    CloneArtifact {
+        id: ArtifactId,
+    },
+    SetArtifactVisibility {
+        id: ArtifactId,
+        is_public: bool,
+    },
+    SetArtifactStar {
+        id: ArtifactId,
+        starred: bool,
+    },
+    LoadArtifactRaces {
+        id: ArtifactId,
+    },
+    /// Records a race's results together with its replay, if one was kept.
+    SubmitRace {
+        entries: Vec<RaceEntrySubmission>,
+        replay: Option<Box<ReplayFile>>,
+    },
+    /// Downloads a replay and re-simulates it on the built-in track.
+    LoadReplay {
+        id: i64,
+    },
+    /// Saves a tarball of the player's artifacts; native only.
+    ExportArtifacts,
+    /// Changes the logged-in player's username.
+    RenameUser {
+        username: String,
+    },
 }
 
 pub struct GameApiPlugin;
@@ -41,6 +179,12 @@ impl Plugin for GameApiPlugin {
     fn build(&self, app: &mut App) {
         app.add_message::<SpawnCarRequest>()
             .add_message::<SpawnResolvedCarRequest>()
+            .add_message::<CarLoadReport>()
+            .add_message::<CancelSpawn>()
+            .add_message::<RematchRequest>()
+            .add_message::<LoadRaceSetup>()
+            .add_message::<StartRaceRequest>()
+            .add_message::<RaceStartRefused>()
             .add_message::<WebApiCommand>();
     }
 }
@@ -3,17 +3,208 @@ use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*};
 
 mod bootstrap;
 mod game_api;
+mod race_results;
 mod race_runtime;
+mod race_setup;
 mod ui;
 
 fn main() {
     #[cfg(not(target_arch = "wasm32"))]
     let mut standalone_mode = false;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut import_url = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut local_bots_dir = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut rename_to = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut replay_id = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut verify_replay = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut claimed_results = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut verify_race = None;
+    let mut race_setup = None;
+    let mut practice_mode = false;
+    let mut bot_params = race_runtime::BotParams::default();
+    let mut grid_limits = race_runtime::GridLimits::default();
+    let mut car_collisions = race_runtime::CarCollisions::default();
+    let mut race_config = race_runtime::RaceConfig::default();
+    let mut tire_wear = race_runtime::TireWearModel::default();
+    let mut car_dimensions = race_runtime::CarDimensions::default();
+    let mut brake_bias = race_runtime::BrakeBias::default();
+    let mut controls_watchdog = botracers_game::devices::ControlsWatchdog::default();
     for arg in std::env::args().skip(1) {
+        if arg == "--practice" {
+            practice_mode = true;
+        }
+        if arg == "--ghost-cars" {
+            car_collisions.between_cars = false;
+        }
+        match arg.strip_prefix("--param=") {
+            Some(param) if !set_bot_param(&mut bot_params, param) => eprintln!(
+                "ignoring --param={param}: expected <index>=<f32> with index below {}",
+                botracers_game::devices::PARAM_COUNT
+            ),
+            _ => {}
+        }
+        if let Some(count) = arg.strip_prefix("--min-cars=") {
+            match count.parse() {
+                Ok(count) => grid_limits.min_cars = count,
+                Err(_) => eprintln!("ignoring --min-cars={count}: expected a car count"),
+            }
+        }
+        if let Some(count) = arg.strip_prefix("--max-cars=") {
+            match count.parse() {
+                Ok(count) => grid_limits.max_cars = count,
+                Err(_) => eprintln!("ignoring --max-cars={count}: expected a car count"),
+            }
+        }
+        if let Some(laps) = arg.strip_prefix("--laps=") {
+            match laps.parse() {
+                Ok(laps) => {
+                    let candidate = race_runtime::RaceConfig {
+                        laps,
+                        ..race_config
+                    };
+                    set_race_config(&mut race_config, candidate, &arg);
+                }
+                Err(_) => eprintln!("ignoring {arg}: expected a lap count"),
+            }
+        }
+        if let Some(secs) = arg.strip_prefix("--time-limit=") {
+            match secs.parse() {
+                Ok(secs) => {
+                    let candidate = race_runtime::RaceConfig {
+                        time_limit_secs: Some(secs),
+                        ..race_config
+                    };
+                    set_race_config(&mut race_config, candidate, &arg);
+                }
+                Err(_) => eprintln!("ignoring {arg}: expected a number of seconds"),
+            }
+        }
+        if let Some(cycles) = arg.strip_prefix("--cycles-per-tick=") {
+            match cycles.parse() {
+                Ok(cycles) => {
+                    let candidate = race_runtime::RaceConfig {
+                        cycles_per_tick: Some(cycles),
+                        ..race_config
+                    };
+                    set_race_config(&mut race_config, candidate, &arg);
+                }
+                Err(_) => eprintln!("ignoring {arg}: expected a number of cycles"),
+            }
+        }
+        if let Some(rate) = arg.strip_prefix("--tire-wear=") {
+            match rate.parse::<f32>() {
+                Ok(rate) if rate.is_finite() && rate >= 0.0 => tire_wear.wear_per_m = rate,
+                _ => eprintln!("ignoring {arg}: expected a non-negative wear per metre"),
+            }
+        }
+        if let Some(factor) = arg.strip_prefix("--car-scale=") {
+            match factor.parse::<f32>() {
+                Ok(factor) if race_runtime::CAR_SCALES.contains(&factor) => {
+                    car_dimensions = race_runtime::CarDimensions::default().scaled(factor);
+                }
+                _ => eprintln!(
+                    "ignoring {arg}: expected a factor from {} to {}",
+                    race_runtime::CAR_SCALES.start(),
+                    race_runtime::CAR_SCALES.end()
+                ),
+            }
+        }
+        if let Some(share) = arg.strip_prefix("--brake-bias=") {
+            match share.parse::<f32>() {
+                Ok(front) if (0.0..=1.0).contains(&front) => {
+                    brake_bias = race_runtime::BrakeBias { front };
+                }
+                _ => eprintln!("ignoring {arg}: expected a front share from 0 to 1"),
+            }
+        }
+        if let Some(updates) = arg.strip_prefix("--controls-timeout=") {
+            match updates.parse() {
+                Ok(updates) => controls_watchdog.timeout_updates = updates,
+                Err(_) => eprintln!("ignoring {arg}: expected a number of updates"),
+            }
+        }
+        if let Some(code) = arg.strip_prefix("--race=") {
+            match race_setup::RaceSetup::decode(code) {
+                Ok(setup) => race_setup = Some(setup),
+                Err(reason) => eprintln!("ignoring --race: {reason}"),
+            }
+        }
         #[cfg(not(target_arch = "wasm32"))]
         if arg == "--standalone" {
             standalone_mode = true;
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(url) = arg.strip_prefix("--import=") {
+            import_url = Some(url.to_string());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(name) = arg.strip_prefix("--rename=") {
+            rename_to = Some(name.to_string());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dir) = arg.strip_prefix("--local-bots=") {
+            local_bots_dir = Some(std::path::PathBuf::from(dir));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(id) = arg.strip_prefix("--replay=") {
+            match id.parse() {
+                Ok(id) => replay_id = Some(id),
+                Err(_) => eprintln!("ignoring {arg}: expected a replay id"),
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = arg.strip_prefix("--verify-replay=") {
+            verify_replay = Some(std::path::PathBuf::from(path));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = arg.strip_prefix("--claimed-results=") {
+            claimed_results = Some(std::path::PathBuf::from(path));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(id) = arg.strip_prefix("--verify-race=") {
+            match id.parse() {
+                Ok(id) => verify_race = Some(id),
+                Err(_) => eprintln!("ignoring {arg}: expected a race id"),
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(race_id) = verify_race {
+        match bootstrap::verify_remote_race(race_id) {
+            Ok(Ok(())) => println!("race #{race_id} verified: the claimed results match"),
+            Ok(Err(reason)) => {
+                println!("race #{race_id} rejected: {reason}");
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("could not verify race #{race_id}: {error}");
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(replay) = verify_replay {
+        let Some(claimed) = claimed_results else {
+            eprintln!("--verify-replay needs --claimed-results=<race.json>");
+            std::process::exit(2);
+        };
+        match verify_replay_files(&replay, &claimed) {
+            Ok(()) => println!("replay verified: the claimed results match"),
+            Err(reason) => {
+                println!("replay rejected: {reason}");
+                std::process::exit(1);
+            }
+        }
+        return;
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -23,16 +214,42 @@ fn main() {
         bootstrap::BootstrapConfig {
             standalone_mode: true,
             standalone_bind: Some(bind),
+            import_url,
+            race_setup,
+            rename_to,
+            replay_id,
+            local_bots_dir,
         }
     } else {
-        bootstrap::BootstrapConfig::default()
+        bootstrap::BootstrapConfig {
+            import_url,
+            race_setup,
+            rename_to,
+            replay_id,
+            local_bots_dir,
+            ..default()
+        }
     };
 
     #[cfg(target_arch = "wasm32")]
-    let bootstrap_config = bootstrap::BootstrapConfig::default();
+    let bootstrap_config = bootstrap::BootstrapConfig {
+        race_setup,
+        ..default()
+    };
 
     App::new()
         .insert_resource(bootstrap_config)
+        .insert_resource(race_runtime::PracticeMode {
+            enabled: practice_mode,
+        })
+        .insert_resource(bot_params)
+        .insert_resource(grid_limits)
+        .insert_resource(car_collisions)
+        .insert_resource(race_config)
+        .insert_resource(tire_wear)
+        .insert_resource(car_dimensions)
+        .insert_resource(brake_bias)
+        .insert_resource(controls_watchdog)
         .add_plugins((
             DefaultPlugins.set(WindowPlugin {
                 primary_window: Some(Window {
@@ -52,3 +269,46 @@ fn main() {
         ))
         .run();
 }
+
+/// Re-simulates the replay at `replay` on the built-in track and checks the
+/// `SubmitRaceRequest` at `claimed` against it, without opening a window.
+#[cfg(not(target_arch = "wasm32"))]
+fn verify_replay_files(replay: &std::path::Path, claimed: &std::path::Path) -> Result<(), String> {
+    fn read_json<T: serde::de::DeserializeOwned>(path: &std::path::Path) -> Result<T, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        serde_json::from_str(&text).map_err(|e| format!("failed to parse {}: {e}", path.display()))
+    }
+
+    let replay: botracers_protocol::ReplayFile = read_json(replay)?;
+    let claimed: botracers_protocol::SubmitRaceRequest = read_json(claimed)?;
+    let track = botracers_game::track_format::TrackFile::load_builtin()?;
+    race_runtime::replay_check::verify_claims(&replay, &track, &claimed)
+}
+
+/// Takes `candidate` if it is a sane race, otherwise keeps `config`.
+fn set_race_config(
+    config: &mut race_runtime::RaceConfig,
+    candidate: race_runtime::RaceConfig,
+    arg: &str,
+) {
+    match candidate.validate() {
+        Ok(()) => *config = candidate,
+        Err(reason) => eprintln!("ignoring {arg}: {reason}"),
+    }
+}
+
+/// Applies one `<index>=<value>` from `--param`; false if it does not parse.
+fn set_bot_param(params: &mut race_runtime::BotParams, param: &str) -> bool {
+    let Some((index, value)) = param.split_once('=') else {
+        return false;
+    };
+    let (Ok(index), Ok(value)) = (index.parse::<usize>(), value.parse::<f32>()) else {
+        return false;
+    };
+    let Some(slot) = params.values.get_mut(index) else {
+        return false;
+    };
+    *slot = value;
+    true
+}
@@ -0,0 +1,219 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use emulator::cpu::Trap;
+
+use crate::game_api::DriverType;
+
+/// Fraction window a car must pass through before its next start/finish line
+/// crossing counts, so wiggling back and forth over the line scores nothing.
+const ARM_WINDOW: std::ops::Range<f32> = 0.4..0.6;
+/// A fraction drop from above `1 - LINE_BAND` to below `LINE_BAND` (or the
+/// reverse) is treated as crossing the start/finish line.
+const LINE_BAND: f32 = 0.25;
+
+/// A start/finish line crossing that completed a lap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineCrossing {
+    /// Race time of the crossing in seconds.
+    pub time: f32,
+    /// Race-wide crossing sequence number; lower values crossed first.
+    pub seq: u64,
+}
+
+/// Per-car lap tracking, fed every fixed tick from the car's position.
+#[derive(Component, Debug, Clone)]
+pub struct LapProgress {
+    /// Order in which the car was spawned (`RaceManager::next_car_id`).
+    pub spawn_index: u32,
+    pub laps_completed: u32,
+    /// Progress into the current lap in `[0, 1)`.
+    pub fraction: f32,
+    pub lap_started_at: f32,
+    pub best_lap: Option<f32>,
+    pub last_crossing: Option<LineCrossing>,
+    armed: bool,
+}
+
+impl LapProgress {
+    pub fn new(spawn_index: u32) -> Self {
+        Self {
+            spawn_index,
+            laps_completed: 0,
+            fraction: 0.0,
+            lap_started_at: 0.0,
+            best_lap: None,
+            last_crossing: None,
+            armed: false,
+        }
+    }
+
+    /// Feeds the car's current lap fraction at race time `now`.
+    ///
+    /// Returns `true` when this update completed a lap; the crossing takes the
+    /// next value of `next_seq`.
+    pub fn update(&mut self, fraction: f32, now: f32, next_seq: &mut u64) -> bool {
+        let previous = self.fraction;
+        self.fraction = fraction;
+
+        if ARM_WINDOW.contains(&fraction) {
+            self.armed = true;
+        }
+
+        if previous > 1.0 - LINE_BAND && fraction < LINE_BAND {
+            if !self.armed {
+                return false;
+            }
+            let lap_time = now - self.lap_started_at;
+            self.best_lap = Some(self.best_lap.map_or(lap_time, |best| best.min(lap_time)));
+            self.laps_completed += 1;
+            self.lap_started_at = now;
+            self.last_crossing = Some(LineCrossing {
+                time: now,
+                seq: *next_seq,
+            });
+            *next_seq += 1;
+            self.armed = false;
+            return true;
+        }
+
+        if previous < LINE_BAND && fraction > 1.0 - LINE_BAND {
+            // Reversed over the line: the lap has to be driven again.
+            self.armed = false;
+        }
+        false
+    }
+}
+
+/// Race time and line-crossing counter, reset whenever the race returns to `PreRace`.
+#[derive(Resource, Debug, Default)]
+pub struct RaceClock {
+    pub elapsed: f32,
+    pub next_crossing_seq: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Standing {
+    /// The car, e.g. for the spectator camera to follow the leader.
+    pub entity: Entity,
+    pub name: String,
+    pub driver: DriverType,
+    pub progress: LapProgress,
+    /// The trap that halted the car's bot; it cannot complete further laps.
+    pub trap: Option<Trap>,
+}
+
+/// Current race order, best first. Rebuilt every fixed tick while racing.
+#[derive(Resource, Debug, Default)]
+pub struct RaceStandings {
+    pub rows: Vec<Standing>,
+}
+
+/// Ranking rule for standings and final results, best first:
+///
+/// 1. more laps completed;
+/// 2. earlier race time of the last completed lap;
+/// 3. earlier start/finish line crossing order (resolves equal coarse timestamps);
+/// 4. further into the current lap;
+/// 5. lower spawn order.
+///
+/// Every car has a distinct spawn order, so the result is a total order and
+/// rankings are reproducible.
+pub fn compare_progress(a: &LapProgress, b: &LapProgress) -> Ordering {
+    b.laps_completed
+        .cmp(&a.laps_completed)
+        .then_with(|| match (a.last_crossing, b.last_crossing) {
+            (Some(a), Some(b)) => a.time.total_cmp(&b.time).then(a.seq.cmp(&b.seq)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        })
+        .then_with(|| b.fraction.total_cmp(&a.fraction))
+        .then_with(|| a.spawn_index.cmp(&b.spawn_index))
+}
+
+pub fn sort_standings(rows: &mut [Standing]) {
+    rows.sort_by(|a, b| compare_progress(&a.progress, &b.progress));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use botracers_protocol::ArtifactId;
+
+    fn finished(spawn_index: u32, laps: u32, time: f32, seq: u64) -> Standing {
+        let mut progress = LapProgress::new(spawn_index);
+        progress.laps_completed = laps;
+        progress.last_crossing = Some(LineCrossing { time, seq });
+        Standing {
+            entity: Entity::PLACEHOLDER,
+            name: format!("Car {spawn_index}"),
+            driver: DriverType::RemoteArtifact {
+                id: ArtifactId(spawn_index as i64),
+            },
+            progress,
+            trap: None,
+        }
+    }
+
+    fn names(rows: &[Standing]) -> Vec<&str> {
+        rows.iter().map(|row| row.name.as_str()).collect()
+    }
+
+    #[test]
+    fn equal_lap_times_are_ranked_by_crossing_order_then_spawn_order() {
+        // Cars 3 and 1 finish in the same tick; car 3 was processed first.
+        let mut rows = vec![
+            finished(1, 3, 42.5, 8),
+            finished(2, 2, 30.0, 5),
+            finished(3, 3, 42.5, 7),
+            finished(4, 3, 40.0, 6),
+        ];
+        sort_standings(&mut rows);
+        assert_eq!(names(&rows), ["Car 4", "Car 3", "Car 1", "Car 2"]);
+
+        // Identical records differ only in spawn order.
+        let mut rows = vec![
+            Standing {
+                entity: Entity::PLACEHOLDER,
+                name: "Car 6".to_string(),
+                driver: DriverType::RemoteArtifact { id: ArtifactId(6) },
+                progress: LapProgress::new(6),
+                trap: None,
+            },
+            Standing {
+                entity: Entity::PLACEHOLDER,
+                name: "Car 5".to_string(),
+                driver: DriverType::RemoteArtifact { id: ArtifactId(5) },
+                progress: LapProgress::new(5),
+                trap: None,
+            },
+        ];
+        sort_standings(&mut rows);
+        assert_eq!(names(&rows), ["Car 5", "Car 6"]);
+    }
+
+    #[test]
+    fn lap_counts_only_after_passing_half_distance() {
+        let mut seq = 0;
+        let mut lap = LapProgress::new(1);
+
+        // Grid slot just behind the line: the first crossing is not a lap.
+        assert!(!lap.update(0.98, 0.1, &mut seq));
+        assert!(!lap.update(0.02, 0.2, &mut seq));
+
+        for (fraction, now) in [(0.3, 10.0), (0.5, 20.0), (0.9, 30.0)] {
+            assert!(!lap.update(fraction, now, &mut seq));
+        }
+        assert!(lap.update(0.01, 40.0, &mut seq));
+        assert_eq!(lap.laps_completed, 1);
+        assert_eq!(lap.best_lap, Some(40.0));
+        assert_eq!(lap.last_crossing, Some(LineCrossing { time: 40.0, seq: 0 }));
+        assert_eq!(seq, 1);
+
+        // Backing over the line and forward again does not count.
+        assert!(!lap.update(0.95, 41.0, &mut seq));
+        assert!(!lap.update(0.05, 42.0, &mut seq));
+        assert_eq!(lap.laps_completed, 1);
+    }
+}
@@ -4,21 +4,35 @@ use avian2d::prelude::{forces::ForcesItem, *};
 use bevy::{
     color::palettes::css::{GREEN, RED, WHITE, YELLOW},
     diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::system::SystemParam,
     input::mouse::{MouseMotion, MouseWheel},
     prelude::*,
 };
+use emulator::CpuBuilder;
 use emulator::bevy::{CpuComponent, cpu_system};
-use emulator::cpu::LogDevice;
+use emulator::cpu::{LogDevice, Trap};
 
 use botracers_game::Car;
 use botracers_game::devices::TrackRadarBorders;
 use botracers_game::devices::{
-    self, CarControlsDevice, CarRadarDevice, CarStateDevice, SplineDevice, TrackRadarDevice,
+    self, CarControlsDevice, CarRadarDevice, CarStateDevice, MetricsDevice, PARAM_COUNT,
+    SplineDevice, TrackRadarDevice,
 };
 use botracers_game::track;
-use botracers_game::track_format::TrackFile;
+use botracers_game::track_format::{Barrier, TrackFile};
+use botracers_protocol::{
+    BOT_ABI_SECTION, BOT_ABI_VERSION, FinishReason, MAX_RACE_TIME_LIMIT_SECS, MAX_REPLAY_TICKS,
+    REPLAY_FORMAT_VERSION, RaceEntrySubmission, ReplayFile, ReplayInput, ReplayParticipant,
+    ReplayTicks, SIM_TICK_HZ,
+};
+
+use crate::game_api::{
+    CarLoadOutcome, CarLoadReport, DriverType, LoadRaceSetup, RaceStartRefused, RematchRequest,
+    SpawnCarRequest, SpawnResolvedCarRequest, StartRaceRequest, WebApiCommand,
+};
+use crate::race_results::{LapProgress, RaceClock, RaceStandings, Standing, sort_standings};
 
-use crate::game_api::{DriverType, SpawnResolvedCarRequest};
+pub mod replay_check;
 
 pub struct RaceRuntimePlugin;
 
@@ -31,19 +45,61 @@ impl Plugin for RaceRuntimePlugin {
             ))
             .insert_resource(RaceManager::default())
             .insert_resource(FollowCar::default())
+            .init_resource::<InspectedCar>()
             .insert_resource(KartLongitudinalParams::default())
             .insert_resource(CpuFrequencySetting::default())
+            .init_resource::<TimeScale>()
+            .init_resource::<PracticeMode>()
+            .init_resource::<BotParams>()
+            .init_resource::<LastGrid>()
+            .init_resource::<GridLimits>()
+            .init_resource::<RaceConfig>()
+            .init_resource::<CarCollisions>()
+            .init_resource::<TireWearModel>()
+            .init_resource::<CarDimensions>()
+            .init_resource::<BrakeBias>()
+            .init_resource::<devices::ControlsWatchdog>()
+            .init_resource::<RaceClock>()
+            .init_resource::<RaceStandings>()
+            .init_resource::<ReplayRecorder>()
             .add_systems(Startup, (setup_track, setup.after(setup_track)))
             .add_systems(Startup, set_default_zoom.after(setup))
             .add_systems(Startup, pause_physics)
+            .add_systems(
+                OnExit(SimState::PreRace),
+                (remember_grid, start_replay_recording),
+            )
             .add_systems(OnEnter(SimState::Racing), unpause_physics)
             .add_systems(OnEnter(SimState::Paused), pause_physics)
-            .add_systems(OnEnter(SimState::PreRace), pause_physics)
+            .add_systems(OnEnter(SimState::Finished), pause_physics)
+            .add_systems(
+                OnEnter(SimState::PreRace),
+                (
+                    pause_physics,
+                    reset_race_progress,
+                    reset_cpus,
+                    reset_tire_wear,
+                ),
+            )
             .add_systems(
                 Update,
-                (handle_spawn_resolved_event, apply_cpu_frequency_setting),
+                (
+                    handle_spawn_resolved_event,
+                    apply_cpu_frequency_setting,
+                    apply_bot_params,
+                    (handle_rematch_request, spawn_rematch_grid).chain(),
+                    handle_load_race_setup,
+                    handle_start_race_request,
+                ),
+            )
+            .add_systems(Update, (handle_car_input, toggle_pause))
+            .add_systems(Update, (step_time_scale, apply_time_scale).chain())
+            .add_systems(
+                Update,
+                (start_practice, respawn_practice_car)
+                    .after(handle_spawn_resolved_event)
+                    .run_if(practice_enabled),
             )
-            .add_systems(Update, handle_car_input)
             .configure_sets(
                 FixedUpdate,
                 (CpuSystems::PreCpu, CpuSystems::Cpu, CpuSystems::PostCpu).chain(),
@@ -54,14 +110,34 @@ impl Plugin for RaceRuntimePlugin {
                     devices::car_state_system.in_set(CpuSystems::PreCpu),
                     devices::car_radar_system.in_set(CpuSystems::PreCpu),
                     devices::track_radar_system.in_set(CpuSystems::PreCpu),
+                    update_lap_sensors.in_set(CpuSystems::PreCpu),
+                    update_tire_wear_sensors.in_set(CpuSystems::PreCpu),
+                    update_brake_sensors.in_set(CpuSystems::PreCpu),
                     cpu_system::<RacingCpuConfig>.in_set(CpuSystems::Cpu),
                     devices::car_controls_system.in_set(CpuSystems::PostCpu),
+                    report_cpu_traps.in_set(CpuSystems::PostCpu),
+                    report_car_starts.in_set(CpuSystems::PostCpu),
+                    report_budget_hogs.in_set(CpuSystems::PostCpu),
+                    devices::metrics_system.in_set(CpuSystems::PostCpu),
                 )
                     .run_if(in_state(SimState::Racing)),
             )
             .add_systems(
                 FixedUpdate,
-                apply_car_forces.run_if(in_state(SimState::Racing)),
+                (
+                    update_slipstream.before(apply_car_forces),
+                    record_replay_tick.before(apply_car_forces),
+                    apply_car_forces,
+                    (track_lap_progress, finish_race).chain(),
+                )
+                    .after(CpuSystems::PostCpu)
+                    .run_if(in_state(SimState::Racing)),
+            )
+            .add_systems(
+                Update,
+                (cycle_follow_target, follow_race_leader)
+                    .chain()
+                    .before(update_camera),
             )
             .add_systems(Update, (update_fps_counter, update_camera, draw_gizmos));
     }
@@ -73,6 +149,61 @@ pub enum SimState {
     PreRace,
     Racing,
     Paused,
+    /// Every car completed `RaceConfig::laps` or the time limit ran out;
+    /// standings stay up until a reset or rematch.
+    Finished,
+}
+
+/// Single-car practice for iterating on one bot: spawning replaces the current
+/// car, the sim goes straight to `Racing`, results are never submitted, and
+/// `R` puts the car back on the start line.
+#[derive(Resource, Default, Clone, Copy)]
+pub struct PracticeMode {
+    pub enabled: bool,
+}
+
+/// Tuning knobs handed to every bot through its car state (`--param=<i>=<v>`),
+/// so constants can be tuned without rebuilding the bot.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq)]
+pub struct BotParams {
+    pub values: [f32; PARAM_COUNT],
+}
+
+/// Collision layers of the race world.
+#[derive(PhysicsLayer, Clone, Copy, Debug, Default)]
+pub enum GameLayer {
+    /// Static track geometry; cars always collide with it.
+    #[default]
+    Wall,
+    Car,
+}
+
+/// Whether cars bounce off each other (`--ghost-cars` turns it off for
+/// training or ghost races). Walls stop cars either way.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CarCollisions {
+    pub between_cars: bool,
+}
+
+impl Default for CarCollisions {
+    fn default() -> Self {
+        Self { between_cars: true }
+    }
+}
+
+impl CarCollisions {
+    /// Layers for a car's collider under this setting.
+    pub fn layers(self) -> CollisionLayers {
+        if self.between_cars {
+            CollisionLayers::new(GameLayer::Car, [GameLayer::Car, GameLayer::Wall])
+        } else {
+            CollisionLayers::new(GameLayer::Car, GameLayer::Wall)
+        }
+    }
+}
+
+fn practice_enabled(practice: Res<PracticeMode>) -> bool {
+    practice.enabled
 }
 
 #[derive(Resource)]
@@ -90,19 +221,248 @@ impl Default for RaceManager {
     }
 }
 
+/// How many cars a race may start with (`--min-cars=<n>`, `--max-cars=<n>`).
+/// Practice mode starts with its single car regardless.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridLimits {
+    pub min_cars: usize,
+    pub max_cars: usize,
+}
+
+impl Default for GridLimits {
+    fn default() -> Self {
+        Self {
+            min_cars: 1,
+            max_cars: 16,
+        }
+    }
+}
+
+impl GridLimits {
+    /// Why a grid of `cars` may not start, if it may not.
+    pub fn refusal(&self, cars: usize) -> Option<String> {
+        if cars < self.min_cars {
+            Some(format!(
+                "Need at least {} car(s) to start, {cars} on the grid",
+                self.min_cars
+            ))
+        } else if cars > self.max_cars {
+            Some(format!(
+                "At most {} cars may race, remove {}",
+                self.max_cars,
+                cars - self.max_cars
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Longest race that can be configured, in laps.
+pub const MAX_RACE_LAPS: u32 = 50;
+/// Shortest and longest time limit a race can have, in seconds.
+pub const RACE_TIME_LIMIT_SECS: std::ops::RangeInclusive<f32> =
+    30.0..=MAX_RACE_TIME_LIMIT_SECS as f32;
+const TIME_LIMIT_PRESETS_SECS: [f32; 5] = [60.0, 180.0, 300.0, 600.0, 1800.0];
+/// Smallest and largest per-tick cycle budget a race can cap bots at.
+pub const RACE_CYCLES_PER_TICK: std::ops::RangeInclusive<u32> = 100..=100_000;
+
+/// Length of the next race (`--laps=<n>`, `--time-limit=<secs>`, or the race
+/// panel before the start). The race finishes once every car has completed
+/// `laps` laps, or when `time_limit_secs` of race time have passed. Practice
+/// has no finish. `cycles_per_tick` (`--cycles-per-tick=<n>`) caps every
+/// bot's instruction budget per fixed update below what the CPU frequency
+/// setting allows, so a shared setup gives all bots the same compute.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct RaceConfig {
+    pub laps: u32,
+    pub time_limit_secs: Option<f32>,
+    pub cycles_per_tick: Option<u32>,
+}
+
+impl Default for RaceConfig {
+    fn default() -> Self {
+        Self {
+            laps: 5,
+            time_limit_secs: None,
+            cycles_per_tick: None,
+        }
+    }
+}
+
+impl RaceConfig {
+    /// Why this configuration is not a sane race, if it is not.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_RACE_LAPS).contains(&self.laps) {
+            return Err(format!(
+                "a race runs for 1 to {MAX_RACE_LAPS} laps, not {}",
+                self.laps
+            ));
+        }
+        match self.time_limit_secs {
+            Some(limit) if !RACE_TIME_LIMIT_SECS.contains(&limit) => Err(format!(
+                "a time limit is {} to {} seconds, not {limit}",
+                RACE_TIME_LIMIT_SECS.start(),
+                RACE_TIME_LIMIT_SECS.end()
+            )),
+            _ => match self.cycles_per_tick {
+                Some(cycles) if !RACE_CYCLES_PER_TICK.contains(&cycles) => Err(format!(
+                    "a cycle budget is {} to {} cycles per tick, not {cycles}",
+                    RACE_CYCLES_PER_TICK.start(),
+                    RACE_CYCLES_PER_TICK.end()
+                )),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Instructions each bot may run per fixed update: the CPU frequency
+    /// setting's budget, capped at `cycles_per_tick`.
+    pub fn cycle_budget(&self, cpu_frequency: &CpuFrequencySetting) -> u32 {
+        let budget = cpu_frequency.instructions_per_update();
+        self.cycles_per_tick.map_or(budget, |cap| budget.min(cap))
+    }
+
+    pub fn step_laps(&mut self, up: bool) {
+        self.laps = if up {
+            (self.laps + 1).min(MAX_RACE_LAPS)
+        } else {
+            self.laps.saturating_sub(1).max(1)
+        };
+    }
+
+    /// Steps through no limit and the preset limits.
+    pub fn step_time_limit(&mut self, up: bool) {
+        let current = self.time_limit_secs.unwrap_or(0.0);
+        self.time_limit_secs = if up {
+            TIME_LIMIT_PRESETS_SECS
+                .into_iter()
+                .find(|&preset| preset > current)
+                .or(self.time_limit_secs)
+        } else {
+            TIME_LIMIT_PRESETS_SECS
+                .into_iter()
+                .rev()
+                .find(|&preset| preset < current)
+        };
+    }
+
+    pub fn format_time_limit_label(&self) -> String {
+        match self.time_limit_secs {
+            Some(limit) => format!("Limit: {}:{:02}", limit as u32 / 60, limit as u32 % 60),
+            None => "Limit: off".to_string(),
+        }
+    }
+
+    /// Whether a race at race time `elapsed` with these standings is over.
+    /// Cars whose bot trapped cannot finish and are not waited for.
+    pub fn is_finished(&self, elapsed: f32, rows: &[Standing]) -> bool {
+        let all_done = !rows.is_empty()
+            && rows
+                .iter()
+                .all(|row| row.progress.laps_completed >= self.laps || row.trap.is_some());
+        all_done || self.timed_out(elapsed)
+    }
+
+    fn timed_out(&self, elapsed: f32) -> bool {
+        self.time_limit_secs.is_some_and(|limit| elapsed >= limit)
+    }
+
+    /// Why `row`'s race ended when the race stopped at race time `elapsed`.
+    /// A car that completed the distance keeps its finish even if its bot
+    /// trapped afterwards.
+    pub fn finish_reason(&self, elapsed: f32, row: &Standing) -> FinishReason {
+        if row.progress.laps_completed >= self.laps {
+            FinishReason::Finished
+        } else if let Some(trap) = row.trap {
+            FinishReason::CrashedTrap {
+                kind: trap.kind().to_string(),
+                pc: trap.pc(),
+            }
+        } else if self.timed_out(elapsed) {
+            FinishReason::DnfTimeout
+        } else {
+            FinishReason::Halted
+        }
+    }
+}
+
+/// Drivers of the most recent race in grid order, respawned by a
+/// `RematchRequest`.
+#[derive(Resource, Default)]
+pub struct LastGrid {
+    pub drivers: Vec<DriverType>,
+    /// Set by a rematch until the grid has been requested again in `PreRace`.
+    respawn_pending: bool,
+}
+
 pub struct CarEntry {
     pub entity: Entity,
     pub name: String,
     pub driver: DriverType,
+    /// Kart tint from `DriverType::color`, also used for the car's UI label.
+    pub color: Color,
     pub console_output: String,
 }
 
 #[derive(Resource, Default)]
 pub struct FollowCar {
     pub target: Option<Entity>,
+    /// Spectator mode: `target` is kept on the race leader.
+    pub leader: bool,
+}
+
+/// Car whose bot state is shown in the inspector panel.
+#[derive(Resource, Default)]
+pub struct InspectedCar {
+    pub target: Option<Entity>,
+}
+
+/// Console lines the bot inspector shows, newest last.
+const INSPECTOR_LOG_LINES: usize = 8;
+
+/// What the inspector panel shows for one car.
+pub struct BotInspection {
+    pub name: String,
+    pub telemetry: LongitudinalDebugData,
+    pub log_tail: Vec<String>,
+    /// The trap that halted the bot, if it stopped executing.
+    pub trap: Option<Trap>,
+}
+
+/// Gathers the inspected car's console, telemetry and CPU state from what the
+/// race runtime already keeps per car.
+#[derive(SystemParam)]
+pub struct BotInspector<'w, 's> {
+    inspected: Res<'w, InspectedCar>,
+    manager: Res<'w, RaceManager>,
+    cars: Query<
+        'w,
+        's,
+        (
+            &'static LongitudinalDebugData,
+            Option<&'static CpuComponent>,
+        ),
+    >,
+}
+
+impl BotInspector<'_, '_> {
+    pub fn inspect(&self) -> Option<BotInspection> {
+        let entity = self.inspected.target?;
+        let entry = self.manager.cars.iter().find(|car| car.entity == entity)?;
+        let (telemetry, cpu) = self.cars.get(entity).ok()?;
+        let lines: Vec<&str> = entry.console_output.lines().collect();
+        let start = lines.len().saturating_sub(INSPECTOR_LOG_LINES);
+        Some(BotInspection {
+            name: entry.name.clone(),
+            telemetry: telemetry.clone(),
+            log_tail: lines[start..].iter().map(|line| line.to_string()).collect(),
+            trap: cpu.and_then(CpuComponent::trap),
+        })
+    }
 }
 
-pub const FIXED_TICK_HZ: u32 = 200;
+pub const FIXED_TICK_HZ: u32 = SIM_TICK_HZ;
 const CPU_FREQUENCY_PRESETS_HZ: [u32; 10] = [
     1_000, 5_000, 10_000, 20_000, 50_000, 100_000, 200_000, 500_000, 1_000_000, 2_000_000,
 ];
@@ -151,6 +511,49 @@ impl CpuFrequencySetting {
     }
 }
 
+const TIME_SCALE_PRESETS: [f32; 4] = [0.1, 0.25, 0.5, 1.0];
+
+/// Debug slow motion, stepped with `[` and `]`. It scales virtual time, so
+/// fixed updates (bots, devices, physics) and everything rendered slow down
+/// together while every fixed step keeps its length: a race plays out the
+/// same at any scale, only slower. Ranked races (anything but practice, whose
+/// results are submitted) always run at 1.0.
+#[derive(Resource, Clone, Copy)]
+pub struct TimeScale {
+    preset_index: usize,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            preset_index: TIME_SCALE_PRESETS.len() - 1,
+        }
+    }
+}
+
+impl TimeScale {
+    pub fn scale(&self) -> f32 {
+        TIME_SCALE_PRESETS[self.preset_index]
+    }
+
+    /// The scale actually applied: ranked races force 1.0.
+    pub fn effective(&self, practice: PracticeMode) -> f32 {
+        if practice.enabled { self.scale() } else { 1.0 }
+    }
+
+    pub fn step_up(&mut self) {
+        if self.preset_index + 1 < TIME_SCALE_PRESETS.len() {
+            self.preset_index += 1;
+        }
+    }
+
+    pub fn step_down(&mut self) {
+        if self.preset_index > 0 {
+            self.preset_index -= 1;
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct CarLabel {
     pub name: String,
@@ -170,6 +573,8 @@ pub struct LongitudinalDebugData {
     pub t_brake_axle: f32,
     pub f_drive: f32,
     pub f_brake: f32,
+    pub f_brake_front: f32,
+    pub f_brake_rear: f32,
     pub f_rr: f32,
     pub f_drag: f32,
     pub f_raw: f32,
@@ -180,6 +585,164 @@ pub struct LongitudinalDebugData {
     pub brake: f32,
 }
 
+/// Multiplier on a car's aerodynamic drag, below 1 while it drafts behind
+/// another car. Recomputed every tick by [`update_slipstream`].
+#[derive(Component, Clone, Copy)]
+pub struct Slipstream {
+    pub drag_factor: f32,
+}
+
+impl Default for Slipstream {
+    fn default() -> Self {
+        Self { drag_factor: 1.0 }
+    }
+}
+
+/// How quickly tires wear and how much grip worn-out tires lose. Set from
+/// `--tire-wear=<rate>`; a rate of 0 turns wear off.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct TireWearModel {
+    /// Wear added per metre driven with the tires fully loaded.
+    pub wear_per_m: f32,
+    /// Share of the tires' grip lost at full wear.
+    pub max_grip_loss: f32,
+}
+
+impl Default for TireWearModel {
+    fn default() -> Self {
+        Self {
+            wear_per_m: 3.0e-4,
+            max_grip_loss: 0.35,
+        }
+    }
+}
+
+/// The race being recorded for upload with its results: the cars in spawn
+/// order, their starting poses and the controls each applied on every tick.
+/// Practice runs and races with local-file bots are not recorded, as their
+/// results are not submitted either.
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    /// Name of the loaded track, written into every replay.
+    pub track: String,
+    cars: Vec<Entity>,
+    replay: Option<ReplayFile>,
+}
+
+/// Tire wear over the current stint, from 0 (fresh) to 1 (worn out). Cleared
+/// when the race returns to `PreRace`.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct TireWear {
+    pub wear: f32,
+}
+
+impl TireWear {
+    /// Adds the wear of driving `distance` metres at `load`, the share of the
+    /// available grip in use. Wear grows with the square of the load, so
+    /// sliding wears the tires far more than smooth driving over the same
+    /// distance.
+    pub fn accumulate(&mut self, model: &TireWearModel, load: f32, distance: f32) {
+        let load = load.clamp(0.0, 1.0);
+        self.wear = (self.wear + model.wear_per_m * load * load * distance).min(1.0);
+    }
+
+    /// Multiplier on tire grip, 1 on fresh tires.
+    pub fn grip_factor(&self, model: &TireWearModel) -> f32 {
+        1.0 - model.max_grip_loss * self.wear
+    }
+}
+
+/// Smallest and largest factor `--car-scale=<factor>` scales the kart by.
+pub const CAR_SCALES: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+/// A car's size: where its wheels sit and the box its collider covers, in
+/// metres. The race's resource is copied onto each car when it spawns; the
+/// default is the kart, and `--car-scale=<factor>` scales every length for
+/// bigger or smaller classes.
+#[derive(Resource, Component, Debug, Clone, Copy, PartialEq)]
+pub struct CarDimensions {
+    /// From the rear axle, the car's origin, to the front axle.
+    pub wheel_base: f32,
+    /// Between the left and right wheels.
+    pub wheel_track: f32,
+    pub body_width: f32,
+    pub body_length: f32,
+    /// How far ahead of the rear axle the body's centre sits.
+    pub body_offset: f32,
+}
+
+impl Default for CarDimensions {
+    fn default() -> Self {
+        Self {
+            wheel_base: 1.18,
+            wheel_track: 0.95,
+            body_width: 1.25,
+            body_length: 2.0,
+            body_offset: 0.66,
+        }
+    }
+}
+
+impl CarDimensions {
+    /// These dimensions with every length multiplied by `factor`.
+    pub fn scaled(self, factor: f32) -> Self {
+        Self {
+            wheel_base: self.wheel_base * factor,
+            wheel_track: self.wheel_track * factor,
+            body_width: self.body_width * factor,
+            body_length: self.body_length * factor,
+            body_offset: self.body_offset * factor,
+        }
+    }
+
+    /// Length relative to the default kart, for sprites and grid spacing.
+    pub fn scale(&self) -> f32 {
+        self.body_length / Self::default().body_length
+    }
+
+    pub fn wheel_offsets(&self) -> WheelOffsets {
+        let half_track = self.wheel_track / 2.0;
+        WheelOffsets {
+            front_left: Vec2::new(-half_track, self.wheel_base),
+            front_right: Vec2::new(half_track, self.wheel_base),
+            rear_left: Vec2::new(-half_track, 0.0),
+            rear_right: Vec2::new(half_track, 0.0),
+        }
+    }
+}
+
+/// Where a car's wheels touch the ground in its own frame: x to the right,
+/// y forward, origin on the rear axle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelOffsets {
+    pub front_left: Vec2,
+    pub front_right: Vec2,
+    pub rear_left: Vec2,
+    pub rear_right: Vec2,
+}
+
+/// Share of a car's brake force sent to the front axle, 0..1. The race's
+/// resource (`--brake-bias=<share>`) is copied onto each car when it spawns,
+/// so cars can be set up differently.
+#[derive(Resource, Component, Debug, Clone, Copy, PartialEq)]
+pub struct BrakeBias {
+    pub front: f32,
+}
+
+impl Default for BrakeBias {
+    fn default() -> Self {
+        Self { front: 0.4 }
+    }
+}
+
+/// How far behind a leading car the draft reaches, in metres.
+const SLIPSTREAM_RANGE: f32 = 12.0;
+/// Cosine of the half-angle of the draft cone behind a leading car (~15°).
+const SLIPSTREAM_CONE_COS: f32 = 0.966;
+/// Drag reduction right behind a leading car; fades linearly to zero at
+/// [`SLIPSTREAM_RANGE`].
+const SLIPSTREAM_MAX_REDUCTION: f32 = 0.4;
+
 #[derive(Resource, Clone, Copy)]
 struct KartLongitudinalParams {
     mass_kg: f32,
@@ -238,9 +801,121 @@ enum CpuSystems {
 
 #[cfg(test)]
 mod tests {
+    use bevy::color::palettes::css::{GREEN, RED};
+    use bevy::prelude::*;
+
     use super::{
-        CpuFrequencySetting, KartLongitudinalParams, engine_torque_full, governor_scale, smoothstep,
+        BotInspector, BotParams, BrakeBias, CarCollisions, CarDimensions, CarEntry, CarLabel,
+        CpuFrequencySetting, FollowCar, GameLayer, GridLimits, InspectedCar,
+        KartLongitudinalParams, LastGrid, LongitudinalDebugData, PracticeMode, RaceConfig,
+        RaceManager, RacingCpuConfig, ReplayRecorder, SLIP_BAR_MAX_LEN, STARTUP_WATCH_UPDATES,
+        SimState, StartupWatch, TimeScale, TireWear, TireWearModel, aero_drag, apply_bot_params,
+        apply_car_forces, apply_cpu_frequency_setting, apply_time_scale, axle_brake_forces,
+        barrier_collider, car_body, car_collider, cycle_follow, engine_torque, engine_torque_full,
+        finish_race, follow_race_leader, governor_scale, handle_load_race_setup,
+        handle_rematch_request, handle_start_race_request, pause_physics, record_replay_tick,
+        remember_grid, report_car_starts, reset_race_progress, slip_gizmo, slipstream_drag_factor,
+        smoothstep, spawn_rematch_grid, start_practice, start_replay_recording, toggle_pause,
+        track_lap_progress, traction_limit, unpause_physics, update_brake_sensors,
+        update_lap_sensors,
+    };
+    use crate::game_api::{
+        CarLoadOutcome, CarLoadReport, DriverType, LoadRaceSetup, RaceStartRefused, RematchRequest,
+        SpawnCarRequest, StartRaceRequest, WebApiCommand, artifact_color,
+    };
+    use crate::race_results::{LapProgress, RaceClock, RaceStandings, Standing, sort_standings};
+    use crate::race_setup::RaceSetup;
+    use avian2d::prelude::LinearVelocity;
+    use bevy::ecs::system::RunSystemOnce;
+    use botracers_game::Car;
+    use botracers_game::devices::{
+        self, ActuatorRateLimits, CarControlsDevice, CarRadarDevice, CarStateDevice, MetricsDevice,
+        SplineDevice, TrackRadarDevice,
     };
+    use botracers_game::track::{self, TrackCenterline, TrackSurface};
+    use botracers_game::track_format::{Barrier, SurfaceRegion};
+    use botracers_protocol::{ArtifactId, FinishReason};
+    use emulator::bevy::{CpuComponent, cpu_system};
+    use emulator::cpu::{Device, LogDevice};
+    use emulator::test_elf::elf_with_code;
+
+    #[test]
+    fn bot_inspector_reports_the_selected_cars_telemetry_and_log() {
+        let mut world = World::new();
+        let mut cars = Vec::new();
+        for (index, speed) in [4.0, 9.5].into_iter().enumerate() {
+            let entity = world
+                .spawn(LongitudinalDebugData {
+                    speed_mps: speed,
+                    ..Default::default()
+                })
+                .id();
+            let log: String = (0..12)
+                .map(|line| format!("car{index} line {line}\n"))
+                .collect();
+            cars.push(CarEntry {
+                entity,
+                name: format!("Car {}", index + 1),
+                driver: DriverType::RemoteArtifact {
+                    id: ArtifactId(index as i64),
+                },
+                color: Color::WHITE,
+                console_output: log,
+            });
+        }
+        let selected = cars[1].entity;
+        world.insert_resource(RaceManager {
+            cars,
+            next_car_id: 3,
+        });
+        world.insert_resource(InspectedCar { target: None });
+
+        let inspect = |world: &mut World| {
+            world
+                .run_system_once(|inspector: BotInspector| inspector.inspect())
+                .unwrap()
+        };
+        assert!(inspect(&mut world).is_none());
+
+        world.resource_mut::<InspectedCar>().target = Some(selected);
+        let inspection = inspect(&mut world).expect("selected car is inspected");
+        assert_eq!(inspection.name, "Car 2");
+        assert_eq!(inspection.telemetry.speed_mps, 9.5);
+        assert_eq!(inspection.log_tail.len(), 8);
+        assert_eq!(inspection.log_tail.first().unwrap(), "car1 line 4");
+        assert_eq!(inspection.log_tail.last().unwrap(), "car1 line 11");
+        assert!(inspection.trap.is_none());
+    }
+
+    #[test]
+    fn artifact_colors_are_stable_and_distinct() {
+        assert_eq!(
+            artifact_color(ArtifactId(42)),
+            artifact_color(ArtifactId(42))
+        );
+        assert_eq!(
+            DriverType::RemoteArtifact { id: ArtifactId(42) }.color(),
+            artifact_color(ArtifactId(42))
+        );
+
+        // Negative ids still land on the hue circle.
+        let negative = Hsla::from(artifact_color(ArtifactId(-3))).hue;
+        assert!((0.0..360.0).contains(&negative), "{negative}");
+
+        let hues: Vec<f32> = (0..32)
+            .map(|id| Hsla::from(artifact_color(ArtifactId(id))).hue)
+            .collect();
+        for (i, a) in hues.iter().enumerate() {
+            for b in &hues[i + 1..] {
+                assert!((a - b).abs() > 1.0, "hues {a} and {b} too close");
+            }
+        }
+        // Neighbouring ids, e.g. consecutive uploads, are far apart on the wheel.
+        for pair in hues.windows(2) {
+            let gap = (pair[0] - pair[1]).abs();
+            assert!(gap.min(360.0 - gap) > 90.0, "{pair:?}");
+        }
+    }
 
     #[test]
     fn cpu_frequency_setting_clamps_at_boundaries() {
@@ -282,6 +957,197 @@ mod tests {
         assert_eq!(setting.format_hz_label(), "20 kHz");
     }
 
+    /// Simulated seconds that pass in one real second at `time_scale`.
+    fn simulated_per_real_second(time_scale: TimeScale, practice: bool) -> f32 {
+        use bevy::time::TimeUpdateStrategy;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(time_scale)
+            .insert_resource(PracticeMode { enabled: practice })
+            .insert_resource(Time::<Fixed>::from_hz(super::FIXED_TICK_HZ as f64))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(
+                std::time::Duration::from_secs_f64(1.0 / 64.0),
+            ))
+            .add_systems(Update, apply_time_scale);
+        // Let the scale take effect before measuring.
+        for _ in 0..4 {
+            app.update();
+        }
+        let start = app.world().resource::<Time<Fixed>>().elapsed_secs();
+        for _ in 0..64 {
+            app.update();
+        }
+        app.world().resource::<Time<Fixed>>().elapsed_secs() - start
+    }
+
+    /// Headless app with gravity-free physics stepping 1/64 s per update.
+    /// Callers add their systems, then `finish` and `cleanup` it: physics
+    /// registers some of its resources in `Plugin::finish`.
+    fn physics_test_app() -> App {
+        use avian2d::prelude::*;
+        use bevy::time::TimeUpdateStrategy;
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            PhysicsPlugins::default(),
+        ))
+        // Colliders can be built from meshes and scenes, so physics expects
+        // both to be set up.
+        .init_asset::<Mesh>()
+        .insert_resource(Gravity(Vec2::ZERO))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            std::time::Duration::from_secs_f64(1.0 / 64.0),
+        ));
+        app
+    }
+
+    /// Speed a car-sized body keeps after driving straight into `barrier`.
+    fn rebound_speed(barrier: &Barrier) -> f32 {
+        use avian2d::prelude::*;
+
+        let mut app = physics_test_app();
+        app.finish();
+        app.cleanup();
+        app.world_mut().spawn(barrier_collider(barrier));
+        let car = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(-3.0, 0.0, 0.0),
+                RigidBody::Dynamic,
+                Collider::rectangle(1.25, 2.0),
+                Friction::new(0.1),
+                Restitution::new(0.2),
+                CollisionLayers::new(GameLayer::Car, GameLayer::Wall),
+                LinearVelocity(Vec2::new(10.0, 0.0)),
+            ))
+            .id();
+        for _ in 0..64 {
+            app.update();
+        }
+        let velocity = app.world().get::<LinearVelocity>(car).unwrap().0;
+        assert!(velocity.x <= 0.0, "car went through the barrier");
+        -velocity.x
+    }
+
+    #[test]
+    fn tire_barrier_returns_less_of_an_impact_than_a_concrete_wall() {
+        let wall = |restitution| Barrier {
+            name: String::new(),
+            restitution,
+            friction: 0.1,
+            points: vec![[0.0, -10.0], [0.0, 10.0]],
+        };
+        let tire_stack = rebound_speed(&wall(0.0));
+        let concrete = rebound_speed(&wall(0.9));
+        assert!(
+            tire_stack < concrete,
+            "tire stack {tire_stack} m/s vs concrete {concrete} m/s"
+        );
+        assert!(
+            concrete > 3.0,
+            "concrete wall barely bounced: {concrete} m/s"
+        );
+    }
+
+    #[test]
+    fn pausing_freezes_fixed_updates_and_physics_until_resumed() {
+        use avian2d::prelude::*;
+
+        #[derive(Resource, Default)]
+        struct RacingSteps(u32);
+
+        let mut app = physics_test_app();
+        app.add_plugins(bevy::state::app::StatesPlugin)
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<RacingSteps>()
+            .init_state::<SimState>()
+            .add_systems(OnEnter(SimState::Racing), unpause_physics)
+            .add_systems(OnEnter(SimState::Paused), pause_physics)
+            .add_systems(Update, toggle_pause)
+            .add_systems(
+                FixedUpdate,
+                (|mut steps: ResMut<RacingSteps>| steps.0 += 1).run_if(in_state(SimState::Racing)),
+            );
+        app.finish();
+        app.cleanup();
+        let body = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                RigidBody::Dynamic,
+                Collider::circle(0.5),
+                LinearVelocity(Vec2::new(5.0, 0.0)),
+            ))
+            .id();
+        let press_pause = |app: &mut App| {
+            app.world_mut()
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .press(KeyCode::KeyP);
+            app.update();
+            let mut keyboard = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keyboard.release(KeyCode::KeyP);
+            keyboard.clear();
+            // The state changes at the start of the next frame.
+            app.update();
+        };
+        let snapshot = |app: &App| {
+            (
+                app.world().resource::<RacingSteps>().0,
+                app.world().get::<Transform>(body).unwrap().translation.x,
+            )
+        };
+
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::Racing);
+        for _ in 0..16 {
+            app.update();
+        }
+        press_pause(&mut app);
+        assert_eq!(
+            *app.world().resource::<State<SimState>>().get(),
+            SimState::Paused
+        );
+        let paused = snapshot(&app);
+        assert!(paused.0 > 0 && paused.1 > 0.0);
+        for _ in 0..32 {
+            app.update();
+        }
+        assert_eq!(snapshot(&app), paused);
+
+        press_pause(&mut app);
+        assert_eq!(
+            *app.world().resource::<State<SimState>>().get(),
+            SimState::Racing
+        );
+        for _ in 0..16 {
+            app.update();
+        }
+        let resumed = snapshot(&app);
+        assert!(resumed.0 > paused.0 && resumed.1 > paused.1);
+    }
+
+    #[test]
+    fn half_time_scale_halves_simulated_progress() {
+        let mut half = TimeScale::default();
+        half.step_down();
+        assert_eq!(half.scale(), 0.5);
+
+        let tick = 1.0 / super::FIXED_TICK_HZ as f32;
+        let full = simulated_per_real_second(TimeScale::default(), true);
+        let slowed = simulated_per_real_second(half, true);
+        assert!((full - 1.0).abs() <= tick, "full speed ran {full}s");
+        assert!((slowed - 0.5).abs() <= tick, "half speed ran {slowed}s");
+
+        let ranked = simulated_per_real_second(half, false);
+        assert!((ranked - 1.0).abs() <= tick, "ranked race ran {ranked}s");
+    }
+
     #[test]
     fn smoothstep_clamps_and_is_monotonic() {
         assert_eq!(smoothstep(2.0, 4.0, 1.0), 0.0);
@@ -315,6 +1181,22 @@ mod tests {
         assert_eq!(governor_scale(params.redline_rpm + 1000.0, &params), 0.0);
     }
 
+    #[test]
+    fn slip_bar_grows_and_reddens_with_slip_angle() {
+        let forward = Vec2::Y;
+        let (rolling_len, rolling_color) = slip_gizmo(forward * 10.0, forward);
+        assert_eq!(rolling_len, 0.0);
+        assert_eq!(rolling_color, Color::from(GREEN));
+
+        let (drift_len, _) = slip_gizmo(Vec2::new(3.0, 10.0), forward);
+        let (sideways_len, sideways_color) = slip_gizmo(Vec2::new(-10.0, 0.0), forward);
+        assert!(drift_len > 0.0 && drift_len < sideways_len);
+        assert!((sideways_len - SLIP_BAR_MAX_LEN).abs() < 1e-5);
+        assert_eq!(sideways_color, Color::from(RED));
+
+        assert_eq!(slip_gizmo(Vec2::new(0.05, 0.0), forward).0, 0.0);
+    }
+
     #[test]
     fn traction_clamp_enforces_limit() {
         let params = KartLongitudinalParams::default();
@@ -323,10 +1205,1383 @@ mod tests {
         assert!(clamped <= limit);
         assert!(clamped >= -limit);
     }
+
+    #[test]
+    fn applied_controls_trail_commands_under_rate_limits() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<devices::ControlsWatchdog>();
+        let car = world
+            .spawn((
+                Car {
+                    steer: 0.0,
+                    accelerator: 0.0,
+                    brake: 0.0,
+                    engine_rpm: 0.0,
+                    wheel_omega: 0.0,
+                },
+                Transform::default(),
+                LinearVelocity::default(),
+                CarControlsDevice::default(),
+                CarStateDevice::default(),
+                ActuatorRateLimits {
+                    accelerator_per_s: 2.0,
+                    brake_per_s: 2.0,
+                    steering_per_s: 1.0,
+                },
+            ))
+            .id();
+
+        let mut controls = world.get_mut::<CarControlsDevice>(car).unwrap();
+        controls.store(0x00, 32, 1.0f32.to_bits()).unwrap();
+        controls.store(0x08, 32, (-0.5f32).to_bits()).unwrap();
+
+        let tick = |world: &mut World| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_millis(100));
+            world.run_system_once(devices::car_controls_system).unwrap();
+            world.run_system_once(devices::car_state_system).unwrap();
+        };
+        let read = |world: &World, offset: u32| {
+            let state = world.get::<CarStateDevice>(car).unwrap();
+            f32::from_bits(state.load(offset, 32).unwrap())
+        };
+
+        tick(&mut world);
+        let commanded = world.get::<CarControlsDevice>(car).unwrap();
+        assert_eq!(commanded.accelerator(), 1.0);
+        assert_eq!(commanded.steering(), -0.5);
+        assert!((read(&world, 0x18) - 0.2).abs() < 1e-5);
+        assert!((read(&world, 0x14) + 0.1).abs() < 1e-5);
+        assert_eq!(read(&world, 0x1C), 0.0);
+
+        for _ in 0..10 {
+            tick(&mut world);
+        }
+        assert_eq!(read(&world, 0x18), 1.0);
+        assert_eq!(read(&world, 0x14), -0.5);
+    }
+
+    #[test]
+    fn silent_bot_coasts_to_zero_throttle_and_center_steering() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(devices::ControlsWatchdog {
+            timeout_updates: 3,
+            release_per_s: 2.0,
+        });
+        let car = world
+            .spawn((
+                Car {
+                    steer: 0.0,
+                    accelerator: 0.0,
+                    brake: 0.0,
+                    engine_rpm: 0.0,
+                    wheel_omega: 0.0,
+                },
+                CarControlsDevice::default(),
+            ))
+            .id();
+        let write = |world: &mut World, offset: u32, value: f32| {
+            let mut controls = world.get_mut::<CarControlsDevice>(car).unwrap();
+            controls.store(offset, 32, value.to_bits()).unwrap();
+        };
+        let tick = |world: &mut World| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_millis(100));
+            world.run_system_once(devices::car_controls_system).unwrap();
+            let car = world.get::<Car>(car).unwrap();
+            (car.accelerator, car.steer)
+        };
+
+        write(&mut world, 0x00, 1.0);
+        write(&mut world, 0x08, 0.5);
+        assert_eq!(tick(&mut world), (1.0, 0.5));
+        // The bot goes quiet: the last command holds until the timeout.
+        assert_eq!(tick(&mut world), (1.0, 0.5));
+        assert_eq!(tick(&mut world), (1.0, 0.5));
+        let (accelerator, steer) = tick(&mut world);
+        assert!((accelerator - 0.8).abs() < 1e-5, "{accelerator}");
+        assert!((steer - 0.3).abs() < 1e-5, "{steer}");
+        for _ in 0..5 {
+            tick(&mut world);
+        }
+        assert_eq!(tick(&mut world), (0.0, 0.0));
+        assert_eq!(
+            world.get::<CarControlsDevice>(car).unwrap().accelerator(),
+            1.0
+        );
+
+        // A write hands control back at once.
+        write(&mut world, 0x00, 0.7);
+        assert_eq!(tick(&mut world), (0.7, 0.5));
+    }
+
+    #[test]
+    fn centerline_offset_reports_lateral_displacement() {
+        let mut world = World::new();
+        // Straight track along +y, driven in the +y direction.
+        world.insert_resource(TrackCenterline {
+            points: (0..40).map(|i| Vec2::new(0.0, i as f32)).collect(),
+        });
+        let spawn = |world: &mut World, x: f32| {
+            world
+                .spawn((
+                    Car {
+                        steer: 0.0,
+                        accelerator: 0.0,
+                        brake: 0.0,
+                        engine_rpm: 0.0,
+                        wheel_omega: 0.0,
+                    },
+                    Transform::from_xyz(x, 10.0, 0.0),
+                    LinearVelocity::default(),
+                    CarStateDevice::default(),
+                ))
+                .id()
+        };
+        let right = spawn(&mut world, 1.5);
+        let left = spawn(&mut world, -2.25);
+        world.run_system_once(devices::car_state_system).unwrap();
+
+        let offset = |world: &World, car| {
+            let state = world.get::<CarStateDevice>(car).unwrap();
+            f32::from_bits(state.load(0x20, 32).unwrap())
+        };
+        assert!((offset(&world, right) - 1.5).abs() < 1e-5);
+        assert!((offset(&world, left) + 2.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn lifting_throttle_at_speed_brakes_gently() {
+        let params = KartLongitudinalParams::default();
+        let rpm = 4000.0;
+        assert!(engine_torque(1.0, rpm, &params) > 0.0);
+
+        // Clutch locked, zero brake: only engine braking acts on the axle.
+        let t_axle =
+            params.drivetrain_efficiency * params.gear_ratio * engine_torque(0.0, rpm, &params);
+        let decel = -t_axle / params.wheel_radius_m / params.mass_kg;
+        assert!(decel > 0.0);
+        assert!(
+            decel < 1.0,
+            "engine braking should be mild, got {decel} m/s^2"
+        );
+
+        // Less RPM, less engine braking.
+        assert!(engine_torque(0.0, 2500.0, &params) > engine_torque(0.0, rpm, &params));
+    }
+
+    #[test]
+    fn forward_brake_bias_moves_force_to_the_front_axle() {
+        let loose = [f32::MAX, f32::MAX];
+        let [rear_biased_front, rear_biased_rear] = axle_brake_forces(1000.0, 0.3, loose);
+        let [front_biased_front, front_biased_rear] = axle_brake_forces(1000.0, 0.7, loose);
+        assert!(front_biased_front > rear_biased_front);
+        assert!(front_biased_rear < rear_biased_rear);
+        assert!((front_biased_front + front_biased_rear - 1000.0).abs() < 1e-3);
+
+        // An axle asked for more than its tires hold locks at the limit.
+        let [front, rear] = axle_brake_forces(1000.0, 0.9, [500.0, 500.0]);
+        assert_eq!(front, 500.0);
+        assert!((rear - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn low_grip_region_reduces_wheel_traction() {
+        let params = KartLongitudinalParams::default();
+        let centerline = TrackCenterline {
+            points: (0..40).map(|i| Vec2::new(i as f32, 0.0)).collect(),
+        };
+        let puddle = SurfaceRegion {
+            name: "puddle".to_string(),
+            grip: 0.4,
+            polygon: vec![[10.0, -2.0], [14.0, -2.0], [14.0, 2.0], [10.0, 2.0]],
+        };
+        let surface = TrackSurface::new(centerline, 4.0, 0.5, 0.6, &[puddle]);
+
+        let on_track = surface.grip_at(Vec2::new(5.0, 0.5));
+        let in_puddle = surface.grip_at(Vec2::new(12.0, 0.5));
+        let on_grass = surface.grip_at(Vec2::new(5.0, 6.0));
+        assert_eq!(on_track, 1.0);
+        assert_eq!(in_puddle, 0.4);
+        assert_eq!(on_grass, 0.6);
+
+        // Full braking from 8 m/s along the track, one car on tarmac and one
+        // with all four wheels in the puddle.
+        let mut app = physics_test_app();
+        app.add_plugins(bevy::gizmos::GizmoPlugin)
+            .insert_resource(Time::<Fixed>::from_hz(super::FIXED_TICK_HZ as f64))
+            .insert_resource(params)
+            .insert_resource(surface)
+            .init_resource::<TireWearModel>()
+            .add_systems(FixedUpdate, apply_car_forces);
+        app.finish();
+        app.cleanup();
+        let layers = CarCollisions::default().layers();
+        let dimensions = CarDimensions::default();
+        let [tarmac, wet] = [1.0, 10.1].map(|x| {
+            let transform = Transform::from_xyz(x, 0.0, 1.0)
+                .with_rotation(Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2));
+            let car = app
+                .world_mut()
+                .spawn((
+                    car_body(transform, dimensions),
+                    children![car_collider(layers, &dimensions)],
+                    LinearVelocity(Vec2::new(8.0, 0.0)),
+                ))
+                .id();
+            app.world_mut().get_mut::<Car>(car).unwrap().brake = 1.0;
+            car
+        });
+        for _ in 0..16 {
+            app.update();
+        }
+
+        let debug = |car| {
+            app.world()
+                .get::<LongitudinalDebugData>(car)
+                .unwrap()
+                .clone()
+        };
+        let (tarmac, wet) = (debug(tarmac), debug(wet));
+        for car in [&tarmac, &wet] {
+            assert!(car.f_raw < -car.traction_limit, "braking fits the grip");
+            assert_eq!(car.f_clamped, -car.traction_limit);
+        }
+        // Tire wear takes a little off both limits, so compare their ratio.
+        let ratio = wet.traction_limit / tarmac.traction_limit;
+        assert!((ratio - in_puddle / on_track).abs() < 1e-3, "ratio {ratio}");
+        assert!(
+            wet.speed_mps > tarmac.speed_mps,
+            "{} m/s in the puddle, {} m/s on tarmac",
+            wet.speed_mps,
+            tarmac.speed_mps
+        );
+    }
+
+    #[test]
+    fn each_car_brakes_with_its_own_bias_and_reports_it() {
+        let centerline = TrackCenterline {
+            points: (0..40).map(|i| Vec2::new(i as f32, 0.0)).collect(),
+        };
+        let mut app = physics_test_app();
+        app.add_plugins(bevy::gizmos::GizmoPlugin)
+            .insert_resource(Time::<Fixed>::from_hz(super::FIXED_TICK_HZ as f64))
+            .insert_resource(KartLongitudinalParams::default())
+            .insert_resource(TrackSurface::new(centerline, 4.0, 0.5, 0.6, &[]))
+            .init_resource::<TireWearModel>()
+            .add_systems(FixedUpdate, apply_car_forces);
+        app.finish();
+        app.cleanup();
+        let layers = CarCollisions::default().layers();
+        let dimensions = CarDimensions::default();
+        // Light braking, so neither axle reaches its traction cap.
+        let [rear_biased, front_biased] = [(1.0, 0.2), (20.0, 0.8)].map(|(x, front)| {
+            let transform = Transform::from_xyz(x, 0.0, 1.0)
+                .with_rotation(Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2));
+            let car = app
+                .world_mut()
+                .spawn((
+                    car_body(transform, dimensions),
+                    children![car_collider(layers, &dimensions)],
+                    LinearVelocity(Vec2::new(8.0, 0.0)),
+                    CarStateDevice::default(),
+                ))
+                .insert(BrakeBias { front })
+                .id();
+            app.world_mut().get_mut::<Car>(car).unwrap().brake = 0.3;
+            car
+        });
+        for _ in 0..4 {
+            app.update();
+        }
+        app.world_mut()
+            .run_system_once(update_brake_sensors)
+            .unwrap();
+
+        for (car, front) in [(rear_biased, 0.2), (front_biased, 0.8)] {
+            let debug = app.world().get::<LongitudinalDebugData>(car).unwrap();
+            assert!(debug.f_brake > 0.0);
+            assert!((debug.f_brake_front - front * debug.f_brake).abs() < 1e-2);
+            assert!((debug.f_brake_rear - (1.0 - front) * debug.f_brake).abs() < 1e-2);
+
+            let state = app.world().get::<CarStateDevice>(car).unwrap();
+            let read = |addr| f32::from_bits(state.load(addr, 32).unwrap());
+            assert_eq!(read(0x38), front);
+            assert_eq!(read(0x3C), debug.f_brake_front);
+            assert_eq!(read(0x40), debug.f_brake_rear);
+        }
+    }
+
+    #[test]
+    fn sliding_wears_tires_faster_than_smooth_driving() {
+        let model = TireWearModel::default();
+        let params = KartLongitudinalParams::default();
+        let mut smooth = TireWear::default();
+        let mut sliding = TireWear::default();
+        // The same kilometre in 10 cm steps.
+        for _ in 0..10_000 {
+            smooth.accumulate(&model, 0.3, 0.1);
+            sliding.accumulate(&model, 1.2, 0.1);
+        }
+
+        let smooth_grip = smooth.grip_factor(&model);
+        let sliding_grip = sliding.grip_factor(&model);
+        assert!(smooth_grip > 0.98, "smooth driving wore {smooth:?}");
+        assert!(
+            sliding_grip < smooth_grip - 0.05,
+            "sliding kept {sliding_grip} grip, smooth driving {smooth_grip}"
+        );
+        assert!(traction_limit(&params, sliding_grip) < traction_limit(&params, smooth_grip));
+
+        let mut worn = TireWear::default();
+        worn.accumulate(&model, 1.0, 1.0e9);
+        assert_eq!(worn.wear, 1.0);
+        assert!((worn.grip_factor(&model) - (1.0 - model.max_grip_loss)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn larger_cars_have_proportionally_wider_spaced_wheels() {
+        let kart = CarDimensions::default();
+        let formula = kart.scaled(2.5);
+        let (small, large) = (kart.wheel_offsets(), formula.wheel_offsets());
+        for (small, large) in [
+            (small.front_left, large.front_left),
+            (small.front_right, large.front_right),
+            (small.rear_left, large.rear_left),
+            (small.rear_right, large.rear_right),
+        ] {
+            assert!((large - small * 2.5).length() < 1e-5, "{small} -> {large}");
+        }
+        assert!(large.front_left.x < 0.0 && large.front_right.x > 0.0);
+        assert_eq!(large.front_left.y, formula.wheel_base);
+        assert!((formula.scale() - 2.5).abs() < 1e-6);
+
+        let mut world = World::new();
+        let collider = world
+            .spawn(car_collider(CarCollisions::default().layers(), &formula))
+            .id();
+        let half_extents = world
+            .get::<avian2d::prelude::Collider>(collider)
+            .unwrap()
+            .shape()
+            .as_cuboid()
+            .unwrap()
+            .half_extents;
+        assert!((half_extents.x * 2.0 - kart.body_width * 2.5).abs() < 1e-5);
+        assert!((half_extents.y * 2.0 - kart.body_length * 2.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn trailing_car_in_draft_has_less_drag_than_in_clean_air() {
+        let params = KartLongitudinalParams::default();
+        let leader = Vec2::new(0.0, 10.0);
+        let leader_forward = Vec2::Y;
+        let v_long = 15.0;
+
+        let clean_air = aero_drag(&params, v_long, 1.0);
+        let close = slipstream_drag_factor(Vec2::new(0.0, 6.0), leader, leader_forward);
+        let far = slipstream_drag_factor(Vec2::new(0.0, 0.0), leader, leader_forward);
+        assert!(aero_drag(&params, v_long, close) < clean_air);
+        assert!(close < far && far < 1.0);
+
+        let alongside = slipstream_drag_factor(Vec2::new(3.0, 10.0), leader, leader_forward);
+        let ahead = slipstream_drag_factor(Vec2::new(0.0, 14.0), leader, leader_forward);
+        let out_of_range = slipstream_drag_factor(Vec2::new(0.0, -5.0), leader, leader_forward);
+        assert_eq!(alongside, 1.0);
+        assert_eq!(ahead, 1.0);
+        assert_eq!(out_of_range, 1.0);
+    }
+
+    #[test]
+    fn practice_mode_races_a_single_car_without_waiting() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .insert_resource(RaceManager::default())
+            .add_systems(Update, start_practice);
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<SimState>>().get(),
+            SimState::PreRace
+        );
+
+        let entity = app.world_mut().spawn_empty().id();
+        app.world_mut()
+            .resource_mut::<RaceManager>()
+            .cars
+            .push(CarEntry {
+                entity,
+                name: "Car 1".to_string(),
+                driver: DriverType::RemoteArtifact { id: ArtifactId(1) },
+                color: Color::WHITE,
+                console_output: String::new(),
+            });
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<SimState>>().get(),
+            SimState::Racing
+        );
+    }
+
+    #[test]
+    fn race_start_is_blocked_below_the_minimum_grid() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .add_message::<StartRaceRequest>()
+            .add_message::<RaceStartRefused>()
+            .insert_resource(RaceManager::default())
+            .insert_resource(GridLimits {
+                min_cars: 2,
+                max_cars: 4,
+            })
+            .add_systems(Update, handle_start_race_request);
+        let add_car = |app: &mut App| {
+            let entity = app.world_mut().spawn_empty().id();
+            let mut manager = app.world_mut().resource_mut::<RaceManager>();
+            let id = manager.cars.len() as i64 + 1;
+            manager.cars.push(CarEntry {
+                entity,
+                name: format!("Car {id}"),
+                driver: DriverType::RemoteArtifact { id: ArtifactId(id) },
+                color: Color::WHITE,
+                console_output: String::new(),
+            });
+        };
+        let state = |app: &App| *app.world().resource::<State<SimState>>().get();
+
+        add_car(&mut app);
+        app.world_mut().write_message(StartRaceRequest);
+        app.update();
+        app.update();
+        assert_eq!(state(&app), SimState::PreRace);
+        let refusals: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<RaceStartRefused>>()
+            .drain()
+            .collect();
+        assert_eq!(refusals.len(), 1);
+        assert!(refusals[0].reason.contains("at least 2"), "{refusals:?}");
+
+        add_car(&mut app);
+        app.world_mut().write_message(StartRaceRequest);
+        app.update();
+        app.update();
+        assert_eq!(state(&app), SimState::Racing);
+        assert!(
+            app.world()
+                .resource::<Messages<RaceStartRefused>>()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn grid_limits_refuse_oversized_grids() {
+        let limits = GridLimits {
+            min_cars: 1,
+            max_cars: 2,
+        };
+        assert!(limits.refusal(0).is_some());
+        assert_eq!(limits.refusal(2), None);
+        assert_eq!(
+            limits.refusal(3).as_deref(),
+            Some("At most 2 cars may race, remove 1")
+        );
+    }
+
+    #[test]
+    fn race_config_rejects_insane_lengths() {
+        assert!(RaceConfig::default().validate().is_ok());
+        for config in [
+            RaceConfig {
+                laps: 0,
+                time_limit_secs: None,
+                cycles_per_tick: None,
+            },
+            RaceConfig {
+                laps: 51,
+                time_limit_secs: None,
+                cycles_per_tick: None,
+            },
+            RaceConfig {
+                laps: 3,
+                time_limit_secs: Some(5.0),
+                cycles_per_tick: None,
+            },
+            RaceConfig {
+                laps: 3,
+                time_limit_secs: None,
+                cycles_per_tick: Some(10),
+            },
+        ] {
+            assert!(config.validate().is_err(), "{config:?} was accepted");
+        }
+
+        let mut config = RaceConfig::default();
+        config.step_time_limit(false);
+        assert_eq!(config.time_limit_secs, None);
+        config.step_time_limit(true);
+        assert_eq!(config.format_time_limit_label(), "Limit: 1:00");
+    }
+
+    #[test]
+    fn three_lap_race_finishes_after_three_laps_per_car() {
+        const LAP_STEPS: usize = 16;
+        let ring = |t: f32| Vec2::from_angle(t * std::f32::consts::TAU) * 20.0;
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .insert_resource(RaceConfig {
+                laps: 3,
+                time_limit_secs: None,
+                cycles_per_tick: None,
+            })
+            .init_resource::<PracticeMode>()
+            .init_resource::<RaceClock>()
+            .init_resource::<RaceStandings>()
+            .insert_resource(RaceManager::default())
+            .insert_resource(TrackCenterline {
+                points: (0..64).map(|i| ring(i as f32 / 64.0)).collect(),
+            })
+            .add_systems(
+                Update,
+                (track_lap_progress, finish_race)
+                    .chain()
+                    .run_if(in_state(SimState::Racing)),
+            );
+        let cars = [1, 2].map(|id| {
+            let entity = app
+                .world_mut()
+                .spawn((
+                    CarLabel {
+                        name: format!("Car {id}"),
+                    },
+                    Transform::from_translation(ring(0.0).extend(0.0)),
+                    LapProgress::new(id as u32),
+                ))
+                .id();
+            app.world_mut()
+                .resource_mut::<RaceManager>()
+                .cars
+                .push(car_entry(entity, id));
+            entity
+        });
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::Racing);
+        app.update();
+
+        let drive_laps = |app: &mut App, car: Entity, laps: usize| {
+            for step in 1..=laps * LAP_STEPS {
+                let position = ring(step as f32 / LAP_STEPS as f32);
+                app.world_mut()
+                    .get_mut::<Transform>(car)
+                    .unwrap()
+                    .translation = position.extend(0.0);
+                app.update();
+            }
+        };
+        let state = |app: &App| *app.world().resource::<State<SimState>>().get();
+        let laps =
+            |app: &App, car: Entity| app.world().get::<LapProgress>(car).unwrap().laps_completed;
+
+        // The leader's extra lap does not count, and the race waits for the
+        // other car.
+        drive_laps(&mut app, cars[0], 4);
+        assert_eq!(laps(&app, cars[0]), 3);
+        assert_eq!(state(&app), SimState::Racing);
+
+        drive_laps(&mut app, cars[1], 2);
+        assert_eq!(state(&app), SimState::Racing);
+        drive_laps(&mut app, cars[1], 1);
+        app.update();
+        assert_eq!(laps(&app, cars[1]), 3);
+        assert_eq!(state(&app), SimState::Finished);
+    }
+
+    #[test]
+    fn crossing_the_final_line_sets_the_finished_sensor() {
+        const LAP_STEPS: usize = 16;
+        let ring = |t: f32| Vec2::from_angle(t * std::f32::consts::TAU) * 20.0;
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .insert_resource(RaceConfig {
+                laps: 2,
+                time_limit_secs: None,
+                cycles_per_tick: None,
+            })
+            .init_resource::<PracticeMode>()
+            .init_resource::<RaceClock>()
+            .init_resource::<RaceStandings>()
+            .insert_resource(RaceManager::default())
+            .insert_resource(TrackCenterline {
+                points: (0..64).map(|i| ring(i as f32 / 64.0)).collect(),
+            })
+            .add_systems(
+                Update,
+                (track_lap_progress, update_lap_sensors)
+                    .chain()
+                    .run_if(in_state(SimState::Racing)),
+            );
+        let cars = [1, 2].map(|id| {
+            let entity = app
+                .world_mut()
+                .spawn((
+                    CarLabel {
+                        name: format!("Car {id}"),
+                    },
+                    Transform::from_translation(ring(0.0).extend(0.0)),
+                    LapProgress::new(id as u32),
+                    CarStateDevice::default(),
+                ))
+                .id();
+            app.world_mut()
+                .resource_mut::<RaceManager>()
+                .cars
+                .push(car_entry(entity, id));
+            entity
+        });
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::Racing);
+        app.update();
+
+        let sensors = |app: &App, car: Entity| {
+            let device = app.world().get::<CarStateDevice>(car).unwrap();
+            [0x24, 0x28, 0x30].map(|offset| device.load(offset, 32).unwrap())
+        };
+        for step in 1..=2 * LAP_STEPS {
+            app.world_mut()
+                .get_mut::<Transform>(cars[0])
+                .unwrap()
+                .translation = ring(step as f32 / LAP_STEPS as f32).extend(0.0);
+            app.update();
+            if step == LAP_STEPS {
+                assert_eq!(sensors(&app, cars[0]), [1, 2, 0]);
+            }
+        }
+        // laps_completed, race_laps, finished.
+        assert_eq!(sensors(&app, cars[0]), [2, 2, 1]);
+        assert_eq!(sensors(&app, cars[1]), [0, 2, 0]);
+    }
+
+    #[test]
+    fn trapped_car_result_carries_the_trap_kind_and_pc() {
+        const LAP_STEPS: usize = 16;
+        let ring = |t: f32| Vec2::from_angle(t * std::f32::consts::TAU) * 20.0;
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .add_message::<WebApiCommand>()
+            .insert_resource(RaceConfig {
+                laps: 3,
+                time_limit_secs: None,
+                cycles_per_tick: None,
+            })
+            .init_resource::<PracticeMode>()
+            .init_resource::<RaceClock>()
+            .init_resource::<RaceStandings>()
+            .init_resource::<ReplayRecorder>()
+            .insert_resource(RaceManager::default())
+            .insert_resource(TrackCenterline {
+                points: (0..64).map(|i| ring(i as f32 / 64.0)).collect(),
+            })
+            .add_systems(OnEnter(SimState::PreRace), reset_race_progress)
+            .add_systems(
+                Update,
+                track_lap_progress.run_if(in_state(SimState::Racing)),
+            );
+
+        let mut trapping = CpuComponent::new(&elf_with_code(&[0x1234_b00b]), 100).expect("load");
+        let trap = trapping.run_n_cycles(1, &mut []).unwrap_err();
+        let spinning = CpuComponent::new(&elf_with_code(&[0x0000_006f]), 100).expect("load");
+        let cars = [(1, trapping), (2, spinning)].map(|(id, cpu)| {
+            let entity = app
+                .world_mut()
+                .spawn((
+                    CarLabel {
+                        name: format!("Car {id}"),
+                    },
+                    Transform::from_translation(ring(0.0).extend(0.0)),
+                    LapProgress::new(id as u32),
+                    cpu,
+                ))
+                .id();
+            app.world_mut()
+                .resource_mut::<RaceManager>()
+                .cars
+                .push(car_entry(entity, id));
+            entity
+        });
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::Racing);
+        app.update();
+        for step in 1..=LAP_STEPS {
+            app.world_mut()
+                .get_mut::<Transform>(cars[1])
+                .unwrap()
+                .translation = ring(step as f32 / LAP_STEPS as f32).extend(0.0);
+            app.update();
+        }
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::PreRace);
+        app.update();
+
+        let commands: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<WebApiCommand>>()
+            .drain()
+            .collect();
+        let [WebApiCommand::SubmitRace { entries, .. }] = commands.as_slice() else {
+            panic!(
+                "expected one race submission, got {} commands",
+                commands.len()
+            );
+        };
+        let reasons: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.artifact_id, entry.finish_reason.clone()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (ArtifactId(2), Some(FinishReason::Halted)),
+                (
+                    ArtifactId(1),
+                    Some(FinishReason::CrashedTrap {
+                        kind: "illegal_instruction".to_string(),
+                        pc: trap.pc(),
+                    })
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn races_are_recorded_and_uploaded_with_their_results() {
+        const LAP_STEPS: usize = 16;
+        let ring = |t: f32| Vec2::from_angle(t * std::f32::consts::TAU) * 20.0;
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .add_message::<WebApiCommand>()
+            .init_resource::<RaceConfig>()
+            .init_resource::<PracticeMode>()
+            .init_resource::<RaceClock>()
+            .init_resource::<RaceStandings>()
+            .insert_resource(CarDimensions::default().scaled(1.5))
+            .insert_resource(CarCollisions {
+                between_cars: false,
+            })
+            .insert_resource(TireWearModel {
+                wear_per_m: 0.001,
+                ..default()
+            })
+            .insert_resource(ReplayRecorder {
+                track: "ring".to_string(),
+                ..default()
+            })
+            .insert_resource(RaceManager::default())
+            .insert_resource(TrackCenterline {
+                points: (0..64).map(|i| ring(i as f32 / 64.0)).collect(),
+            })
+            .add_systems(OnExit(SimState::PreRace), start_replay_recording)
+            .add_systems(OnEnter(SimState::PreRace), reset_race_progress)
+            .add_systems(
+                Update,
+                (record_replay_tick, track_lap_progress)
+                    .chain()
+                    .run_if(in_state(SimState::Racing)),
+            );
+
+        let cars = [1, 2].map(|id| {
+            let entity = app
+                .world_mut()
+                .spawn((
+                    CarLabel {
+                        name: format!("Car {id}"),
+                    },
+                    Car {
+                        steer: 0.0,
+                        accelerator: 0.0,
+                        brake: 0.0,
+                        engine_rpm: 0.0,
+                        wheel_omega: 0.0,
+                    },
+                    Transform::from_translation(ring(0.0).extend(0.0))
+                        .with_rotation(Quat::from_rotation_z(id as f32)),
+                    BrakeBias {
+                        front: 0.25 * id as f32,
+                    },
+                    LapProgress::new(id as u32),
+                ))
+                .id();
+            app.world_mut()
+                .resource_mut::<RaceManager>()
+                .cars
+                .push(car_entry(entity, id));
+            entity
+        });
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::Racing);
+        app.update();
+        for step in 1..=LAP_STEPS {
+            let mut car = app.world_mut().entity_mut(cars[1]);
+            car.get_mut::<Car>().unwrap().accelerator = 1.0;
+            car.get_mut::<Transform>().unwrap().translation =
+                ring(step as f32 / LAP_STEPS as f32).extend(0.0);
+            app.update();
+        }
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::PreRace);
+        app.update();
+
+        let commands: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<WebApiCommand>>()
+            .drain()
+            .collect();
+        let [
+            WebApiCommand::SubmitRace {
+                entries,
+                replay: Some(replay),
+            },
+        ] = commands.as_slice()
+        else {
+            panic!(
+                "expected results with a replay, got {} commands",
+                commands.len()
+            );
+        };
+        assert_eq!(entries.len(), 2);
+        assert_eq!(replay.track, "ring");
+        assert_eq!(replay.tick_hz, super::FIXED_TICK_HZ);
+        assert!(
+            (replay.car_scale - 1.5).abs() < 1e-6,
+            "{}",
+            replay.car_scale
+        );
+        let grid: Vec<_> = replay
+            .participants
+            .iter()
+            .map(|participant| (participant.artifact_id, participant.start_x))
+            .collect();
+        assert_eq!(grid, [(ArtifactId(1), 20.0), (ArtifactId(2), 20.0)]);
+        let biases: Vec<_> = replay
+            .participants
+            .iter()
+            .map(|participant| participant.brake_bias)
+            .collect();
+        assert_eq!(biases, [Some(0.25), Some(0.5)]);
+        assert!(!replay.cars_collide);
+        assert_eq!(replay.tire_wear_per_m, Some(0.001));
+        // Headings are measured from +x, the car's rotation from +y.
+        let heading = replay.participants[1].start_heading;
+        let expected = Vec2::from_angle(2.0 + std::f32::consts::FRAC_PI_2);
+        assert!(
+            Vec2::from_angle(heading).angle_to(expected).abs() < 1e-5,
+            "{heading}"
+        );
+        let ticks: Vec<_> = replay.ticks.iter().collect();
+        assert_eq!(ticks.len(), LAP_STEPS + 1);
+        assert_eq!(ticks[0][1].accelerator, 0.0);
+        assert!(ticks[1..].iter().all(|tick| tick[1].accelerator == 1.0));
+        assert!(app.world().resource::<ReplayRecorder>().replay.is_none());
+    }
+
+    #[test]
+    fn rematch_requests_the_last_grid_again() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .add_message::<RematchRequest>()
+            .add_message::<SpawnCarRequest>()
+            .insert_resource(RaceManager::default())
+            .init_resource::<LastGrid>()
+            .add_systems(OnExit(SimState::PreRace), remember_grid)
+            .add_systems(Update, (handle_rematch_request, spawn_rematch_grid).chain());
+        let drivers: Vec<_> = [3, 1, 3]
+            .map(|id| DriverType::RemoteArtifact { id: ArtifactId(id) })
+            .into();
+        for (index, driver) in drivers.iter().enumerate() {
+            let entity = app.world_mut().spawn_empty().id();
+            app.world_mut()
+                .resource_mut::<RaceManager>()
+                .cars
+                .push(CarEntry {
+                    entity,
+                    name: format!("Car {}", index + 1),
+                    driver: driver.clone(),
+                    color: Color::WHITE,
+                    console_output: String::new(),
+                });
+        }
+        app.world_mut()
+            .resource_mut::<NextState<SimState>>()
+            .set(SimState::Racing);
+        app.update();
+
+        app.world_mut().write_message(RematchRequest);
+        app.update();
+        app.update();
+
+        assert!(app.world().resource::<RaceManager>().cars.is_empty());
+        assert_eq!(
+            *app.world().resource::<State<SimState>>().get(),
+            SimState::PreRace
+        );
+        let requested: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<SpawnCarRequest>>()
+            .drain()
+            .map(|request| request.driver)
+            .collect();
+        assert_eq!(requested, drivers);
+    }
+
+    #[test]
+    fn shared_setup_replaces_the_grid_and_race_length() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+            .init_state::<SimState>()
+            .add_message::<LoadRaceSetup>()
+            .add_message::<SpawnCarRequest>()
+            .insert_resource(RaceManager::default())
+            .init_resource::<RaceConfig>()
+            .init_resource::<CarDimensions>()
+            .init_resource::<CarCollisions>()
+            .init_resource::<TireWearModel>()
+            .add_systems(Update, handle_load_race_setup);
+        let old_car = app.world_mut().spawn_empty().id();
+        app.world_mut()
+            .resource_mut::<RaceManager>()
+            .cars
+            .push(car_entry(old_car, 9));
+
+        let setup =
+            RaceSetup::decode("botracers-race:1:laps=3;limit=300;scale=2;ghost=1;wear=0;grid=4,2")
+                .unwrap();
+        app.world_mut().write_message(LoadRaceSetup {
+            setup: setup.clone(),
+        });
+        app.update();
+
+        assert!(app.world().resource::<RaceManager>().cars.is_empty());
+        assert!(app.world().get_entity(old_car).is_err());
+        assert_eq!(*app.world().resource::<RaceConfig>(), setup.config);
+        assert_eq!(
+            *app.world().resource::<CarDimensions>(),
+            CarDimensions::default().scaled(2.0)
+        );
+        assert!(!app.world().resource::<CarCollisions>().between_cars);
+        assert_eq!(app.world().resource::<TireWearModel>().wear_per_m, 0.0);
+        let requested: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<SpawnCarRequest>>()
+            .drain()
+            .map(|request| request.driver)
+            .collect();
+        assert_eq!(requested, setup.drivers);
+    }
+
+    #[test]
+    fn bot_trapping_at_init_is_reported() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<CarLoadReport>()
+            .add_systems(Update, report_car_starts);
+        let watch = |id| StartupWatch {
+            driver: DriverType::RemoteArtifact { id: ArtifactId(id) },
+            updates: 0,
+        };
+        // A custom-0 opcode traps on the very first instruction; `j .` spins.
+        let mut trapping = CpuComponent::new(&elf_with_code(&[0x1234_b00b]), 100).expect("load");
+        assert!(trapping.run_n_cycles(1, &mut []).is_err());
+        let spinning = CpuComponent::new(&elf_with_code(&[0x0000_006f]), 100).expect("load");
+        app.world_mut().spawn((trapping, watch(1)));
+        app.world_mut().spawn((spinning, watch(2)));
+
+        let mut reports = Vec::new();
+        for _ in 0..STARTUP_WATCH_UPDATES + 2 {
+            app.update();
+            reports.extend(
+                app.world_mut()
+                    .resource_mut::<Messages<CarLoadReport>>()
+                    .drain(),
+            );
+        }
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].driver, watch(1).driver);
+        assert!(matches!(
+            reports[0].outcome,
+            CarLoadOutcome::TrappedAtInit { .. }
+        ));
+        assert_eq!(reports[1].driver, watch(2).driver);
+        assert_eq!(reports[1].outcome, CarLoadOutcome::Started);
+    }
+
+    #[test]
+    fn bot_reads_host_set_params() {
+        let mut world = World::new();
+        let mut params = BotParams::default();
+        params.values[1] = 0.75;
+        world.insert_resource(params);
+        let car = world
+            .spawn((CarStateDevice::default(), CarControlsDevice::default()))
+            .id();
+        world.run_system_once(apply_bot_params).unwrap();
+
+        // Copy param 1 (slot 2 + 0x84) into the accelerator (slot 3 + 0x00).
+        let code = [
+            0x2000_0293, // addi x5, x0, 0x200
+            0x0842_a383, // lw   x7, 0x84(x5)
+            0x3000_0313, // addi x6, x0, 0x300
+            0x0073_2023, // sw   x7, 0(x6)
+        ];
+        let mut cpu = CpuComponent::new(&elf_with_code(&code), 100).expect("load");
+        let mut log = LogDevice::default();
+        let mut entity = world.entity_mut(car);
+        let mut state = entity.take::<CarStateDevice>().unwrap();
+        let mut controls = entity.take::<CarControlsDevice>().unwrap();
+        cpu.run_n_cycles(
+            code.len() as u32,
+            &mut [&mut log, &mut state, &mut controls],
+        )
+        .expect("bot runs");
+
+        assert_eq!(controls.accelerator(), 0.75);
+    }
+
+    #[test]
+    fn starter_bot_produces_controls_within_the_cap() {
+        // Shaped like the SDK starter: clear 1 KiB of statics, read the car
+        // state, then floor the accelerator.
+        let starter = [
+            0x0000_22b7, // lui  t0, 0x2
+            0x4002_8313, // addi t1, t0, 0x400
+            0x0002_a023, // loop: sw zero, 0(t0)
+            0x0042_8293, // addi t0, t0, 4
+            0xfe62_9ce3, // bne  t0, t1, loop
+            0x2000_0513, // addi a0, x0, 0x200
+            0x0005_2583, // lw   a1, 0(a0)
+            0x3000_0613, // addi a2, x0, 0x300
+            0x3f80_06b7, // lui  a3, 0x3f800 (1.0f32)
+            0x00d6_2023, // sw   a3, 0(a2)
+            0x0000_006f, // j .
+        ];
+        const CAP: u32 = 10_000;
+        let mut cpu = CpuComponent::new(&elf_with_code(&starter), 100).expect("load");
+        let mut log = LogDevice::default();
+        let mut state = CarStateDevice::default();
+        let mut controls = CarControlsDevice::default();
+        let first =
+            devices::first_controls(&mut cpu, CAP, &mut [&mut log, &mut state, &mut controls])
+                .expect("bot runs")
+                .expect("bot produced controls");
+        assert_eq!(first.cycles, 2 + 3 * 256 + 5);
+        assert_eq!(first.accelerator, 1.0);
+        assert_eq!((first.brake, first.steering), (0.0, 0.0));
+
+        // A bot that only spins never controls the car.
+        let mut idle = CpuComponent::new(&elf_with_code(&[0x0000_006f]), 100).expect("load");
+        let mut controls = CarControlsDevice::default();
+        assert_eq!(
+            devices::first_controls(&mut idle, CAP, &mut [&mut log, &mut state, &mut controls]),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn log_only_bot_is_flagged_as_not_driving() {
+        let log_only = [
+            0x1000_0513, // addi a0, x0, 0x100
+            0x0480_0593, // addi a1, x0, 'H'
+            0x00b5_2023, // sw   a1, 0(a0)
+            0x0000_006f, // j .
+        ];
+        let warning = devices::driving_warning(&elf_with_code(&log_only)).expect("warned");
+        assert!(warning.contains("car controls (0x300)"), "{warning}");
+
+        let driving = [
+            0x3000_0613, // addi a2, x0, 0x300
+            0x3f80_06b7, // lui  a3, 0x3f800 (1.0f32)
+            0x00d6_2023, // sw   a3, 0(a2)
+            0x0000_006f, // j .
+        ];
+        assert_eq!(devices::driving_warning(&elf_with_code(&driving)), None);
+    }
+
+    #[test]
+    fn bot_metrics_are_recorded_per_tick_through_the_racing_slots() {
+        let program = [
+            0x7000_0513, // addi a0, x0, 0x700
+            0x0030_0593, // addi a1, x0, 3
+            0x00b5_2023, // sw   a1, 0(a0)     (metric 3)
+            0x3fc0_0637, // lui  a2, 0x3fc00   (1.5f32)
+            0x00c5_2223, // sw   a2, 4(a0)
+            0x0000_0073, // ecall
+            0x4000_0637, // lui  a2, 0x40000   (2.0f32)
+            0x00c5_2223, // sw   a2, 4(a0)     (still metric 3)
+            0x0040_0593, // addi a1, x0, 4
+            0x00b5_2023, // sw   a1, 0(a0)     (metric 4)
+            0x00c5_2223, // sw   a2, 4(a0)
+            0x0000_006f, // j .
+        ];
+        let spline = track::build_spline(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            Vec2::new(100.0, 100.0),
+            Vec2::new(0.0, 100.0),
+        ]);
+        bevy::tasks::ComputeTaskPool::get_or_init(bevy::tasks::TaskPool::default);
+        let mut world = World::new();
+        let car = world
+            .spawn((
+                CpuComponent::new(&elf_with_code(&program), 100).expect("load"),
+                LogDevice::default(),
+                CarStateDevice::default(),
+                CarControlsDevice::default(),
+                SplineDevice::new(&track::TrackSpline { spline }),
+                TrackRadarDevice::default(),
+                CarRadarDevice::default(),
+                MetricsDevice::default(),
+            ))
+            .id();
+
+        for _ in 0..3 {
+            world
+                .run_system_once(cpu_system::<RacingCpuConfig>)
+                .unwrap();
+            world.run_system_once(devices::metrics_system).unwrap();
+        }
+
+        let metrics = world.get::<MetricsDevice>(car).unwrap();
+        assert_eq!(world.get::<CpuComponent>(car).unwrap().trap(), None);
+        assert_eq!(metrics.metric_ids().collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(metrics.series(3), [(0, 1.5), (1, 2.0)]);
+        assert_eq!(metrics.series(4), [(1, 2.0)]);
+        assert_eq!(metrics.load(0x08, 32), Ok(3));
+        assert_eq!(
+            metrics.to_csv(),
+            "tick,metric,value\n0,3,1.5\n1,3,2\n1,4,2\n"
+        );
+    }
+
+    #[test]
+    fn busy_bot_is_capped_at_the_race_cycle_budget() {
+        let program = [
+            0x0012_8293, // loop: addi t0, t0, 1
+            0xffdf_f06f, // j loop
+        ];
+        let spline = track::build_spline(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(100.0, 0.0),
+            Vec2::new(100.0, 100.0),
+            Vec2::new(0.0, 100.0),
+        ]);
+        bevy::tasks::ComputeTaskPool::get_or_init(bevy::tasks::TaskPool::default);
+        let mut world = World::new();
+        world.insert_resource(CpuFrequencySetting::default());
+        world.insert_resource(RaceConfig {
+            cycles_per_tick: Some(300),
+            ..RaceConfig::default()
+        });
+        let car = world
+            .spawn((
+                CpuComponent::new(&elf_with_code(&program), 10_000).expect("load"),
+                LogDevice::default(),
+                CarStateDevice::default(),
+                CarControlsDevice::default(),
+                SplineDevice::new(&track::TrackSpline { spline }),
+                TrackRadarDevice::default(),
+                CarRadarDevice::default(),
+                MetricsDevice::default(),
+            ))
+            .id();
+        world.run_system_once(apply_cpu_frequency_setting).unwrap();
+        assert_eq!(
+            world.get::<CarStateDevice>(car).unwrap().load(0x34, 32),
+            Ok(300)
+        );
+
+        for tick in 1..=3 {
+            world
+                .run_system_once(cpu_system::<RacingCpuConfig>)
+                .unwrap();
+            let cpu = world.get::<CpuComponent>(car).unwrap();
+            assert_eq!(cpu.last_run_cycles(), 300);
+            assert_eq!(cpu.exhausted_runs(), tick);
+        }
+
+        // Without a cap the CPU frequency setting decides again.
+        world.resource_mut::<RaceConfig>().cycles_per_tick = None;
+        world.run_system_once(apply_cpu_frequency_setting).unwrap();
+        world
+            .run_system_once(cpu_system::<RacingCpuConfig>)
+            .unwrap();
+        assert_eq!(
+            world.get::<CpuComponent>(car).unwrap().last_run_cycles(),
+            CpuFrequencySetting::default().instructions_per_update()
+        );
+        assert_eq!(
+            world.get::<CarStateDevice>(car).unwrap().load(0x34, 32),
+            Ok(10_000)
+        );
+    }
+
+    /// Steps two overlapping cars resting on a wall for a second and returns
+    /// their final positions.
+    fn settle_overlapping_cars(collisions: CarCollisions) -> [Vec2; 2] {
+        use avian2d::prelude::{Collider, Gravity, PhysicsPlugins, RigidBody};
+        use bevy::time::TimeUpdateStrategy;
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            bevy::asset::AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            PhysicsPlugins::default(),
+        ))
+        .insert_resource(Gravity::ZERO)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            std::time::Duration::from_secs_f64(1.0 / 64.0),
+        ));
+        app.finish();
+        // The car colliders span y -0.34..1.66; the wall pokes into both.
+        app.world_mut().spawn((
+            RigidBody::Static,
+            Collider::rectangle(10.0, 1.0),
+            Transform::from_xyz(0.0, -0.7, 0.0),
+        ));
+        let cars = [0.0, 0.6].map(|x| {
+            app.world_mut()
+                .spawn((
+                    RigidBody::Dynamic,
+                    Transform::from_xyz(x, 0.0, 0.0),
+                    children![car_collider(collisions.layers(), &CarDimensions::default())],
+                ))
+                .id()
+        });
+        for _ in 0..64 {
+            app.update();
+        }
+        cars.map(|car| {
+            app.world()
+                .get::<Transform>(car)
+                .unwrap()
+                .translation
+                .truncate()
+        })
+    }
+
+    #[test]
+    fn ghost_cars_overlap_but_still_hit_walls() {
+        let [a, b] = settle_overlapping_cars(CarCollisions {
+            between_cars: false,
+        });
+        assert!(
+            ((b.x - a.x) - 0.6).abs() < 1e-3,
+            "ghost cars were pushed apart: {a} {b}"
+        );
+        assert!(
+            a.y > 0.1 && b.y > 0.1,
+            "the wall did not push back: {a} {b}"
+        );
+
+        let [a, b] = settle_overlapping_cars(CarCollisions::default());
+        assert!(b.x - a.x > 1.0, "colliding cars still overlap: {a} {b}");
+    }
+
+    fn car_entry(entity: Entity, id: i64) -> CarEntry {
+        CarEntry {
+            entity,
+            name: format!("Car {id}"),
+            driver: DriverType::RemoteArtifact { id: ArtifactId(id) },
+            color: Color::WHITE,
+            console_output: String::new(),
+        }
+    }
+
+    fn standing(entity: Entity, spawn_index: u32, laps_completed: u32) -> Standing {
+        let mut progress = LapProgress::new(spawn_index);
+        progress.laps_completed = laps_completed;
+        Standing {
+            entity,
+            name: format!("Car {spawn_index}"),
+            driver: DriverType::RemoteArtifact {
+                id: ArtifactId(spawn_index as i64),
+            },
+            progress,
+            trap: None,
+        }
+    }
+
+    #[test]
+    fn spectator_camera_follows_the_new_leader() {
+        let mut world = World::new();
+        let first = world.spawn_empty().id();
+        let second = world.spawn_empty().id();
+        world.insert_resource(FollowCar {
+            target: None,
+            leader: true,
+        });
+        let mut standings = vec![standing(first, 1, 2), standing(second, 2, 1)];
+        sort_standings(&mut standings);
+        world.insert_resource(RaceStandings { rows: standings });
+
+        world.run_system_once(follow_race_leader).unwrap();
+        assert_eq!(world.resource::<FollowCar>().target, Some(first));
+
+        // The second car completes two more laps and takes the lead.
+        let mut rows = std::mem::take(&mut world.resource_mut::<RaceStandings>().rows);
+        rows[1].progress.laps_completed = 3;
+        sort_standings(&mut rows);
+        world.resource_mut::<RaceStandings>().rows = rows;
+
+        world.run_system_once(follow_race_leader).unwrap();
+        assert_eq!(world.resource::<FollowCar>().target, Some(second));
+    }
+
+    #[test]
+    fn follow_cycles_leader_then_cars_then_free() {
+        let mut world = World::new();
+        let cars = [
+            car_entry(world.spawn_empty().id(), 1),
+            car_entry(world.spawn_empty().id(), 2),
+        ];
+        let mut follow = FollowCar::default();
+
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            cycle_follow(&mut follow, &cars);
+            seen.push((follow.leader, follow.target));
+        }
+        assert_eq!(
+            seen,
+            [
+                (true, None),
+                (false, Some(cars[0].entity)),
+                (false, Some(cars[1].entity)),
+                (false, None),
+            ]
+        );
+    }
 }
 
-const WHEEL_BASE: f32 = 1.18;
-const WHEEL_TRACK: f32 = 0.95;
+const GRAVITY: f32 = 9.81;
 
 fn setup_track(
     mut commands: Commands,
@@ -335,6 +2590,10 @@ fn setup_track(
 ) {
     let track_file =
         TrackFile::load_builtin().unwrap_or_else(|_| panic!("Failed to load track file"));
+    commands.insert_resource(ReplayRecorder {
+        track: track_file.metadata.name.clone(),
+        ..default()
+    });
 
     let control_points = track_file.control_points_vec2();
     let track_width = track_file.metadata.track_width;
@@ -351,6 +2610,9 @@ fn setup_track(
     commands.insert_resource(track::TrackSpline {
         spline: spline.clone(),
     });
+    let centerline = track::TrackCenterline::from_spline(&spline, 1000);
+    commands.insert_resource(track_surface(&track_file, &centerline));
+    commands.insert_resource(centerline);
     let (inner_border, outer_border) = track::sample_track_borders(&spline, track_width, 1000);
     commands.insert_resource(TrackRadarBorders {
         inner: inner_border,
@@ -376,6 +2638,40 @@ fn setup_track(
         MeshMaterial2d(materials.add(ColorMaterial::default())),
         Transform::from_xyz(0.0, 0.0, 0.1),
     ));
+
+    for barrier in &track_file.barriers {
+        commands.spawn(barrier_collider(barrier));
+    }
+}
+
+/// Grip over `track_file`'s track, kerbs, off-track area and surface patches.
+fn track_surface(
+    track_file: &TrackFile,
+    centerline: &track::TrackCenterline,
+) -> track::TrackSurface {
+    track::TrackSurface::new(
+        centerline.clone(),
+        track_file.metadata.track_width,
+        track_file.metadata.kerb_width,
+        track_file.metadata.off_track_grip,
+        &track_file.surfaces,
+    )
+}
+
+/// Static collider for a track barrier, carrying its own bounce and friction.
+fn barrier_collider(barrier: &Barrier) -> impl Bundle {
+    let points = barrier
+        .points
+        .iter()
+        .map(|&[x, y]| Vec2::new(x, y))
+        .collect();
+    (
+        RigidBody::Static,
+        Collider::polyline(points, None),
+        Restitution::new(barrier.restitution),
+        Friction::new(barrier.friction),
+        CollisionLayers::new(GameLayer::Wall, GameLayer::Car),
+    )
 }
 
 fn rpm_to_rad_per_sec(rpm: f32) -> f32 {
@@ -394,9 +2690,54 @@ fn smoothstep(edge0: f32, edge1: f32, value: f32) -> f32 {
     x * x * (3.0 - 2.0 * x)
 }
 
-fn engine_torque_full(rpm: f32, params: &KartLongitudinalParams) -> f32 {
-    let x = ((rpm - params.torque_peak_rpm) / (params.redline_rpm - params.torque_peak_rpm)).clamp(0.0, 1.0);
-    params.torque_peak_nm * (1.0 - (1.0 - params.redline_torque_fraction) * x * x)
+fn engine_torque_full(rpm: f32, params: &KartLongitudinalParams) -> f32 {
+    let x = ((rpm - params.torque_peak_rpm) / (params.redline_rpm - params.torque_peak_rpm))
+        .clamp(0.0, 1.0);
+    params.torque_peak_nm * (1.0 - (1.0 - params.redline_torque_fraction) * x * x)
+}
+
+/// Net crankshaft torque: governed drive torque for the throttle share, minus
+/// engine braking for the closed share. Engine braking grows with RPM and only
+/// reaches the wheels through the clutch, so it fades out near idle.
+fn engine_torque(throttle: f32, rpm: f32, params: &KartLongitudinalParams) -> f32 {
+    let drive = throttle * engine_torque_full(rpm, params) * governor_scale(rpm, params);
+    let engine_brake = (1.0 - throttle) * params.engine_brake_nm * (rpm / params.redline_rpm);
+    drive - engine_brake
+}
+
+/// Longitudinal force the tires can transmit on a surface with the given grip multiplier.
+fn traction_limit(params: &KartLongitudinalParams, grip: f32) -> f32 {
+    params.tire_mu * grip * params.mass_kg * GRAVITY
+}
+
+/// Splits the brake force between front and rear axle, `brake_bias` being the
+/// front share (the car's [`BrakeBias`]), capping each axle at what its tires
+/// can hold; braking past that locks the axle and adds no further retardation.
+fn axle_brake_forces(f_brake: f32, brake_bias: f32, axle_limits: [f32; 2]) -> [f32; 2] {
+    let bias = brake_bias.clamp(0.0, 1.0);
+    [
+        (f_brake * bias).min(axle_limits[0]),
+        (f_brake * (1.0 - bias)).min(axle_limits[1]),
+    ]
+}
+
+fn aero_drag(params: &KartLongitudinalParams, v_long: f32, drag_factor: f32) -> f32 {
+    0.5 * params.air_density * params.drag_area * drag_factor * v_long * v_long
+}
+
+/// Drag multiplier for a car at `follower` due to a car at `leader` heading
+/// along `leader_forward`: 1 in clean air, lower the closer the follower sits
+/// inside the cone behind the leader.
+fn slipstream_drag_factor(follower: Vec2, leader: Vec2, leader_forward: Vec2) -> f32 {
+    let to_leader = leader - follower;
+    let distance = to_leader.length();
+    if distance <= f32::EPSILON || distance > SLIPSTREAM_RANGE {
+        return 1.0;
+    }
+    if to_leader.dot(leader_forward) / distance < SLIPSTREAM_CONE_COS {
+        return 1.0;
+    }
+    1.0 - SLIPSTREAM_MAX_REDUCTION * (1.0 - distance / SLIPSTREAM_RANGE)
 }
 
 fn governor_scale(rpm: f32, params: &KartLongitudinalParams) -> f32 {
@@ -434,6 +2775,7 @@ struct FpsCounterText;
 
 fn update_fps_counter(
     diagnostics: Res<DiagnosticsStore>,
+    time: Res<Time<Virtual>>,
     mut query: Query<&mut Text, With<FpsCounterText>>,
 ) {
     let Ok(mut text) = query.single_mut() else {
@@ -445,6 +2787,9 @@ fn update_fps_counter(
         .and_then(|value| value.smoothed())
     {
         text.0 = format!("FPS: {fps:>3.0}");
+        if time.relative_speed() != 1.0 {
+            text.0 += &format!("  x{:.2}", time.relative_speed());
+        }
     }
 }
 
@@ -458,6 +2803,44 @@ fn set_default_zoom(mut camera_query: Query<&mut Projection, With<Camera2d>>) {
     }
 }
 
+/// `[` slows the simulation down, `]` speeds it back up.
+fn step_time_scale(keyboard: Res<ButtonInput<KeyCode>>, mut time_scale: ResMut<TimeScale>) {
+    if keyboard.just_pressed(KeyCode::BracketLeft) {
+        time_scale.step_down();
+    }
+    if keyboard.just_pressed(KeyCode::BracketRight) {
+        time_scale.step_up();
+    }
+}
+
+/// `P` pauses a running race and resumes a paused one, like the start
+/// button. Resuming continues from the same fixed step.
+fn toggle_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    state: Res<State<SimState>>,
+    mut next_state: ResMut<NextState<SimState>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+    match state.get() {
+        SimState::Racing => next_state.set(SimState::Paused),
+        SimState::Paused => next_state.set(SimState::Racing),
+        SimState::PreRace | SimState::Finished => {}
+    }
+}
+
+fn apply_time_scale(
+    time_scale: Res<TimeScale>,
+    practice: Res<PracticeMode>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let scale = time_scale.effective(*practice);
+    if time.relative_speed() != scale {
+        time.set_relative_speed(scale);
+    }
+}
+
 fn pause_physics(mut physics_time: ResMut<Time<Physics>>) {
     physics_time.pause();
 }
@@ -472,6 +2855,7 @@ fn grid_offset(index: usize) -> Vec2 {
     Vec2::new(row * 2.0, side * 2.0)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_spawn_resolved_event(
     mut events: MessageReader<SpawnResolvedCarRequest>,
     mut commands: Commands,
@@ -479,36 +2863,127 @@ fn handle_spawn_resolved_event(
     track_spline: Res<track::TrackSpline>,
     mut manager: ResMut<RaceManager>,
     cpu_frequency: Res<CpuFrequencySetting>,
+    race_config: Res<RaceConfig>,
     state: Res<State<SimState>>,
+    practice: Res<PracticeMode>,
+    collisions: Res<CarCollisions>,
+    dimensions: Res<CarDimensions>,
+    brake_bias: Res<BrakeBias>,
+    mut reports: MessageWriter<CarLoadReport>,
 ) {
     for event in events.read() {
         if *state.get() != SimState::PreRace {
             continue;
         }
+        if practice.enabled {
+            for car in manager.cars.drain(..) {
+                commands.entity(car.entity).despawn();
+            }
+        }
 
+        let builder = CpuBuilder::default().abi_version(BOT_ABI_SECTION, BOT_ABI_VERSION);
+        let cpu = match CpuComponent::with_builder(
+            builder,
+            &event.elf_bytes,
+            race_config.cycle_budget(&cpu_frequency),
+        ) {
+            Ok(cpu) => cpu,
+            Err(error) => {
+                warn!("Not spawning {}: {error}", event.driver.label());
+                reports.write(CarLoadReport {
+                    driver: event.driver.clone(),
+                    outcome: CarLoadOutcome::Rejected {
+                        error: error.to_string(),
+                    },
+                });
+                continue;
+            }
+        };
+        if let Some(warning) = devices::driving_warning(&event.elf_bytes) {
+            warn!("{} may not drive: {warning}", event.driver.label());
+            reports.write(CarLoadReport {
+                driver: event.driver.clone(),
+                outcome: CarLoadOutcome::NotDriving { warning },
+            });
+        }
         spawn_car_entry(
             &mut commands,
             &asset_server,
             &track_spline,
             &mut manager,
-            &cpu_frequency,
             event.driver.clone(),
-            &event.elf_bytes,
+            cpu,
+            collisions.layers(),
+            *dimensions,
+            *brake_bias,
         );
     }
 }
 
+/// Starts practice as soon as a car is on track; there is no grid to fill.
+fn start_practice(
+    manager: Res<RaceManager>,
+    state: Res<State<SimState>>,
+    mut next_state: ResMut<NextState<SimState>>,
+) {
+    if *state.get() == SimState::PreRace && !manager.cars.is_empty() {
+        next_state.set(SimState::Racing);
+    }
+}
+
+fn respawn_practice_car(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut car_query: Query<(
+        &mut Transform,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+        &mut Car,
+        &mut LapProgress,
+        &CarDimensions,
+    )>,
+    mut cpu_query: Query<(Entity, &mut CpuComponent)>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+    let track_file =
+        TrackFile::load_builtin().unwrap_or_else(|_| panic!("Failed to load track file"));
+    let start_point = track::first_point_from_file(&track_file);
+    for (mut transform, mut linear, mut angular, mut car, mut progress, dimensions) in
+        &mut car_query
+    {
+        let position = start_point + grid_offset(0) * dimensions.scale();
+        *transform = Transform::from_xyz(position.x, position.y, 1.0)
+            .with_rotation(Quat::from_axis_angle(Vec3::Z, PI / 2.0));
+        linear.0 = Vec2::ZERO;
+        angular.0 = 0.0;
+        car.steer = 0.0;
+        car.wheel_omega = 0.0;
+        *progress = LapProgress::new(progress.spawn_index);
+    }
+    // Restart the bot's code but keep what it stored in DRAM, e.g. a learned
+    // racing line.
+    for (entity, mut cpu) in &mut cpu_query {
+        cpu.soft_restart();
+        commands.entity(entity).remove::<CpuTrapReported>();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_car_entry(
     commands: &mut Commands,
     asset_server: &AssetServer,
     track_spline: &track::TrackSpline,
     manager: &mut RaceManager,
-    cpu_frequency: &CpuFrequencySetting,
     driver: DriverType,
-    elf_bytes: &[u8],
+    cpu: CpuComponent,
+    layers: CollisionLayers,
+    dimensions: CarDimensions,
+    brake_bias: BrakeBias,
 ) {
     let car_index = manager.cars.len();
-    let offset = grid_offset(car_index);
+    let offset = grid_offset(car_index) * dimensions.scale();
 
     let track_file =
         TrackFile::load_builtin().unwrap_or_else(|_| panic!("Failed to load track file"));
@@ -516,39 +2991,52 @@ fn spawn_car_entry(
 
     let position = start_point + offset;
     let car_name = format!("Car {}", manager.next_car_id);
+    let color = driver.color();
     let entity = spawn_car(
         commands,
         asset_server,
         position,
         track_spline,
         &car_name,
-        elf_bytes,
-        cpu_frequency.instructions_per_update(),
+        color,
+        cpu,
+        layers,
+        dimensions,
     );
+    commands.entity(entity).insert((
+        brake_bias,
+        LapProgress::new(manager.next_car_id),
+        StartupWatch {
+            driver: driver.clone(),
+            updates: 0,
+        },
+    ));
     manager.cars.push(CarEntry {
         entity,
         name: car_name,
         driver,
+        color,
         console_output: String::new(),
     });
     manager.next_car_id += 1;
 }
 
-fn spawn_car(
-    commands: &mut Commands,
-    asset_server: &AssetServer,
-    position: Vec2,
-    track_spline: &track::TrackSpline,
-    name: &str,
-    bot_elf: &[u8],
-    instructions_per_update: u32,
-) -> Entity {
-    let sprite_scale = Vec3::splat(0.008);
+/// The car body's collider, a child of the car entity.
+fn car_collider(layers: CollisionLayers, dimensions: &CarDimensions) -> impl Bundle {
+    (
+        Collider::rectangle(dimensions.body_width, dimensions.body_length),
+        Transform::from_xyz(0.0, dimensions.body_offset, 0.0),
+        layers,
+    )
+}
 
-    let mut entity = commands.spawn((
-        Transform::from_xyz(position.x, position.y, 1.0)
-            .with_rotation(Quat::from_axis_angle(Vec3::Z, PI / 2.0)),
-        Visibility::default(),
+/// The physics side of a car at `transform`: its rigid body and the state
+/// [`apply_car_forces`] drives. The collider goes on a child
+/// ([`car_collider`]).
+fn car_body(transform: Transform, dimensions: CarDimensions) -> impl Bundle {
+    (
+        transform,
+        dimensions,
         RigidBody::Dynamic,
         //LinearDamping(0.1),
         Friction::new(0.1),
@@ -560,13 +3048,40 @@ fn spawn_car(
             engine_rpm: 1800.0,
             wheel_omega: 0.0,
         },
+        LongitudinalDebugData::default(),
+        Slipstream::default(),
+        TireWear::default(),
+        BrakeBias::default(),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_car(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    position: Vec2,
+    track_spline: &track::TrackSpline,
+    name: &str,
+    color: Color,
+    cpu: CpuComponent,
+    layers: CollisionLayers,
+    dimensions: CarDimensions,
+) -> Entity {
+    let sprite_scale = Vec3::splat(0.008 * dimensions.scale());
+    let wheels = dimensions.wheel_offsets();
+
+    let mut entity = commands.spawn((
+        car_body(
+            Transform::from_xyz(position.x, position.y, 1.0)
+                .with_rotation(Quat::from_axis_angle(Vec3::Z, PI / 2.0)),
+            dimensions,
+        ),
+        Visibility::default(),
         CarLabel {
             name: name.to_string(),
         },
-        LongitudinalDebugData::default(),
     ));
 
-    let cpu = CpuComponent::new(bot_elf, instructions_per_update);
     entity.insert((
         EmulatorDriver,
         cpu,
@@ -576,24 +3091,25 @@ fn spawn_car(
         SplineDevice::new(track_spline),
         TrackRadarDevice::default(),
         CarRadarDevice::default(),
+        MetricsDevice::default(),
     ));
 
     let entity_id = entity.id();
 
     entity.with_children(|parent| {
-        parent.spawn((
-            Collider::rectangle(1.25, 2.0),
-            Transform::from_xyz(0.0, 0.66, 0.0),
-        ));
+        parent.spawn(car_collider(layers, &dimensions));
 
         parent.spawn((
-            Sprite::from_image(asset_server.load("kart.png")),
-            Transform::from_xyz(0.0, 0.66, 0.1).with_scale(sprite_scale),
+            Sprite {
+                color,
+                ..Sprite::from_image(asset_server.load("kart.png"))
+            },
+            Transform::from_xyz(0.0, dimensions.body_offset, 0.1).with_scale(sprite_scale),
         ));
 
         parent
             .spawn((
-                Transform::from_xyz(-WHEEL_TRACK / 2.0, WHEEL_BASE, 0.1),
+                Transform::from_translation(wheels.front_left.extend(0.1)),
                 Visibility::default(),
                 FrontWheel,
             ))
@@ -608,7 +3124,7 @@ fn spawn_car(
 
         parent
             .spawn((
-                Transform::from_xyz(WHEEL_TRACK / 2.0, WHEEL_BASE, 0.1),
+                Transform::from_translation(wheels.front_right.extend(0.1)),
                 Visibility::default(),
                 FrontWheel,
             ))
@@ -625,23 +3141,461 @@ fn spawn_car(
     entity_id
 }
 
+/// Applies the cycle budget to every CPU when the CPU frequency setting or
+/// the race's cap changes, and tells new cars' bots what it is.
 fn apply_cpu_frequency_setting(
     cpu_frequency: Res<CpuFrequencySetting>,
-    mut cpu_query: Query<&mut CpuComponent>,
+    race_config: Res<RaceConfig>,
+    mut cpu_query: Query<(&mut CpuComponent, Option<&mut CarStateDevice>)>,
+) {
+    let changed = cpu_frequency.is_changed() || race_config.is_changed();
+    let instructions_per_update = race_config.cycle_budget(&cpu_frequency);
+    for (mut cpu, state) in &mut cpu_query {
+        if changed {
+            cpu.set_instructions_per_update(instructions_per_update);
+        }
+        if let Some(mut state) = state
+            && (changed || state.is_added())
+        {
+            state.set_cycles_per_tick(cpu.instructions_per_update());
+        }
+    }
+}
+
+/// Writes [`BotParams`] into new cars and, when the params change, into all.
+fn apply_bot_params(params: Res<BotParams>, mut device_query: Query<&mut CarStateDevice>) {
+    for mut device in &mut device_query {
+        if params.is_changed() || device.is_added() {
+            device.set_params(&params.values);
+        }
+    }
+}
+
+/// Advances the race clock and lap tracking, then rebuilds [`RaceStandings`].
+///
+/// Cars are updated in spawn order so that crossings within the same tick get
+/// deterministic sequence numbers.
+/// Cars that completed `RaceConfig::laps` keep their result; further laps do
+/// not count.
+#[allow(clippy::too_many_arguments)]
+fn track_lap_progress(
+    time: Res<Time>,
+    centerline: Res<track::TrackCenterline>,
+    manager: Res<RaceManager>,
+    config: Res<RaceConfig>,
+    practice: Res<PracticeMode>,
+    mut clock: ResMut<RaceClock>,
+    mut standings: ResMut<RaceStandings>,
+    mut car_query: Query<(
+        Entity,
+        &CarLabel,
+        &Transform,
+        &mut LapProgress,
+        Option<&CpuComponent>,
+    )>,
+) {
+    clock.elapsed += time.delta_secs();
+    let now = clock.elapsed;
+
+    let mut cars: Vec<_> = car_query.iter_mut().collect();
+    cars.sort_by_key(|(_, _, _, progress, _)| progress.spawn_index);
+
+    let mut rows = Vec::with_capacity(cars.len());
+    for (entity, label, transform, mut progress, cpu) in cars {
+        if practice.enabled || progress.laps_completed < config.laps {
+            let fraction = centerline.lap_fraction(transform.translation.truncate());
+            progress.update(fraction, now, &mut clock.next_crossing_seq);
+        }
+        let Some(entry) = manager.cars.iter().find(|entry| entry.entity == entity) else {
+            continue;
+        };
+        rows.push(Standing {
+            entity,
+            name: label.name.clone(),
+            driver: entry.driver.clone(),
+            progress: progress.clone(),
+            trap: cpu.and_then(CpuComponent::trap),
+        });
+    }
+    sort_standings(&mut rows);
+    standings.rows = rows;
+}
+
+/// Ends a race that has run its configured laps or time.
+fn finish_race(
+    config: Res<RaceConfig>,
+    practice: Res<PracticeMode>,
+    clock: Res<RaceClock>,
+    standings: Res<RaceStandings>,
+    mut next_state: ResMut<NextState<SimState>>,
+) {
+    if !practice.enabled && config.is_finished(clock.elapsed, &standings.rows) {
+        next_state.set(SimState::Finished);
+    }
+}
+
+/// Tells every bot how many laps it has completed and how long the race is.
+fn update_lap_sensors(
+    config: Res<RaceConfig>,
+    practice: Res<PracticeMode>,
+    mut car_query: Query<(&LapProgress, &mut CarStateDevice)>,
+) {
+    let race_laps = if practice.enabled { 0 } else { config.laps };
+    for (progress, mut state_dev) in &mut car_query {
+        state_dev.update_laps(progress.laps_completed, race_laps);
+    }
+}
+
+/// Tells every bot how worn its tires are.
+fn update_tire_wear_sensors(mut car_query: Query<(&TireWear, &mut CarStateDevice)>) {
+    for (tire_wear, mut state_dev) in &mut car_query {
+        state_dev.update_tire_wear(tire_wear.wear);
+    }
+}
+
+/// Tells every bot its brake bias and the brake force each axle took last
+/// tick.
+fn update_brake_sensors(
+    mut car_query: Query<(&BrakeBias, &LongitudinalDebugData, &mut CarStateDevice)>,
+) {
+    for (bias, debug_data, mut state_dev) in &mut car_query {
+        state_dev.update_brakes(
+            bias.front,
+            debug_data.f_brake_front,
+            debug_data.f_brake_rear,
+        );
+    }
+}
+
+/// Fits fresh tires for the next race.
+fn reset_tire_wear(mut wear_query: Query<&mut TireWear>) {
+    for mut tire_wear in &mut wear_query {
+        *tire_wear = TireWear::default();
+    }
+}
+
+/// Clears race progress on returning to `PreRace`. A race in which at least
+/// one lap was completed is submitted to the server first, unless it was
+/// practice.
+fn reset_race_progress(
+    mut clock: ResMut<RaceClock>,
+    mut standings: ResMut<RaceStandings>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut web_commands: MessageWriter<WebApiCommand>,
+    practice: Res<PracticeMode>,
+    config: Res<RaceConfig>,
+) {
+    let replay = recorder.replay.take();
+    recorder.cars.clear();
+    if !practice.enabled
+        && standings
+            .rows
+            .iter()
+            .any(|row| row.progress.laps_completed > 0)
+    {
+        let entries: Option<Vec<_>> = standings
+            .rows
+            .iter()
+            .map(|row| match row.driver {
+                DriverType::RemoteArtifact { id } => Some(RaceEntrySubmission {
+                    artifact_id: id,
+                    laps_completed: row.progress.laps_completed,
+                    best_lap_secs: row.progress.best_lap,
+                    finish_reason: Some(config.finish_reason(clock.elapsed, row)),
+                }),
+                DriverType::LocalFile { .. } => None,
+            })
+            .collect();
+        match entries {
+            Some(entries) => {
+                web_commands.write(WebApiCommand::SubmitRace {
+                    entries,
+                    replay: replay.map(Box::new),
+                });
+            }
+            None => info!("Not submitting the race: local-file bots have no artifact id"),
+        }
+    }
+
+    *clock = RaceClock::default();
+    standings.rows.clear();
+}
+
+/// Starts the race unless the grid is outside `GridLimits`.
+fn handle_start_race_request(
+    mut requests: MessageReader<StartRaceRequest>,
+    manager: Res<RaceManager>,
+    limits: Res<GridLimits>,
+    state: Res<State<SimState>>,
+    mut next_state: ResMut<NextState<SimState>>,
+    mut refusals: MessageWriter<RaceStartRefused>,
+) {
+    if requests.read().count() == 0 || *state.get() != SimState::PreRace {
+        return;
+    }
+    match limits.refusal(manager.cars.len()) {
+        Some(reason) => {
+            refusals.write(RaceStartRefused { reason });
+        }
+        None => next_state.set(SimState::Racing),
+    }
+}
+
+/// Starts recording the race's replay from the grid's starting poses.
+fn start_replay_recording(
+    manager: Res<RaceManager>,
+    practice: Res<PracticeMode>,
+    dimensions: Res<CarDimensions>,
+    collisions: Res<CarCollisions>,
+    tire_wear: Res<TireWearModel>,
+    cars: Query<(&Transform, &BrakeBias)>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    recorder.cars.clear();
+    recorder.replay = None;
+    if practice.enabled || manager.cars.is_empty() {
+        return;
+    }
+    let mut participants = Vec::with_capacity(manager.cars.len());
+    for car in &manager.cars {
+        let (DriverType::RemoteArtifact { id }, Ok((transform, brake_bias))) =
+            (&car.driver, cars.get(car.entity))
+        else {
+            return;
+        };
+        let forward = transform.up().truncate();
+        participants.push(ReplayParticipant {
+            artifact_id: *id,
+            start_x: transform.translation.x,
+            start_y: transform.translation.y,
+            start_heading: forward.y.atan2(forward.x),
+            brake_bias: Some(brake_bias.front),
+        });
+    }
+    recorder.cars = manager.cars.iter().map(|car| car.entity).collect();
+    recorder.replay = Some(ReplayFile {
+        version: REPLAY_FORMAT_VERSION,
+        tick_hz: FIXED_TICK_HZ,
+        track: recorder.track.clone(),
+        car_scale: dimensions.scale(),
+        cars_collide: collisions.between_cars,
+        tire_wear_per_m: Some(tire_wear.wear_per_m),
+        ticks: ReplayTicks::new(participants.len()),
+        participants,
+    });
+}
+
+/// Appends the controls every recorded car applies this tick. A car leaving
+/// mid-race ends the recording, since the replay could no longer reproduce
+/// the race, and so does a race without a time limit outlasting the longest
+/// one, whose replay no reader would accept.
+fn record_replay_tick(mut recorder: ResMut<ReplayRecorder>, cars: Query<&Car>) {
+    let recorder = &mut *recorder;
+    let Some(replay) = &mut recorder.replay else {
+        return;
+    };
+    if replay.ticks.len() == MAX_REPLAY_TICKS {
+        recorder.replay = None;
+        return;
+    }
+    let inputs: Option<Vec<_>> = recorder
+        .cars
+        .iter()
+        .map(|&entity| {
+            cars.get(entity).ok().map(|car| ReplayInput {
+                steering: car.steer,
+                accelerator: car.accelerator,
+                brake: car.brake,
+            })
+        })
+        .collect();
+    match inputs {
+        Some(inputs) => replay.ticks.push(&inputs),
+        None => recorder.replay = None,
+    }
+}
+
+/// Records the grid a race starts with; an empty track keeps the old grid.
+fn remember_grid(manager: Res<RaceManager>, mut grid: ResMut<LastGrid>) {
+    if manager.cars.is_empty() {
+        return;
+    }
+    grid.drivers = manager.cars.iter().map(|car| car.driver.clone()).collect();
+}
+
+/// Clears the track like `Reset` and queues the last grid for respawning.
+fn handle_rematch_request(
+    mut requests: MessageReader<RematchRequest>,
+    mut commands: Commands,
+    mut manager: ResMut<RaceManager>,
+    mut grid: ResMut<LastGrid>,
+    mut next_state: ResMut<NextState<SimState>>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    if grid.drivers.is_empty() {
+        warn!("No previous race to rematch");
+        return;
+    }
+    for car in manager.cars.drain(..) {
+        commands.entity(car.entity).despawn();
+    }
+    manager.next_car_id = 1;
+    grid.respawn_pending = true;
+    next_state.set(SimState::PreRace);
+}
+
+/// Replaces the `PreRace` grid, race length, car size, collisions and tire
+/// wear with a shared setup. Drivers
+/// whose artifact is unknown or has been deleted fail to download and are
+/// reported like any other failed spawn.
+#[allow(clippy::too_many_arguments)]
+fn handle_load_race_setup(
+    mut setups: MessageReader<LoadRaceSetup>,
+    state: Res<State<SimState>>,
+    mut commands: Commands,
+    mut manager: ResMut<RaceManager>,
+    mut config: ResMut<RaceConfig>,
+    mut dimensions: ResMut<CarDimensions>,
+    mut collisions: ResMut<CarCollisions>,
+    mut tire_wear: ResMut<TireWearModel>,
+    mut spawn_events: MessageWriter<SpawnCarRequest>,
+) {
+    for LoadRaceSetup { setup } in setups.read() {
+        if *state.get() != SimState::PreRace {
+            warn!("Ignoring race setup: a race is already running");
+            continue;
+        }
+        for car in manager.cars.drain(..) {
+            commands.entity(car.entity).despawn();
+        }
+        manager.next_car_id = 1;
+        *config = setup.config;
+        *dimensions = CarDimensions::default().scaled(setup.car_scale);
+        *collisions = setup.collisions;
+        *tire_wear = setup.tire_wear;
+        for driver in &setup.drivers {
+            spawn_events.write(SpawnCarRequest {
+                driver: driver.clone(),
+            });
+        }
+    }
+}
+
+/// Requests the remembered grid once the rematch is back in `PreRace`. Cached
+/// ELFs spawn immediately, the rest are downloaded again; drivers whose
+/// artifact has since been deleted fail to load and are reported like any
+/// other failed spawn.
+fn spawn_rematch_grid(
+    mut grid: ResMut<LastGrid>,
+    state: Res<State<SimState>>,
+    mut spawn_events: MessageWriter<SpawnCarRequest>,
 ) {
-    if !cpu_frequency.is_changed() {
+    if !grid.respawn_pending || *state.get() != SimState::PreRace {
         return;
     }
+    grid.respawn_pending = false;
+    for driver in &grid.drivers {
+        spawn_events.write(SpawnCarRequest {
+            driver: driver.clone(),
+        });
+    }
+}
 
-    let instructions_per_update = cpu_frequency.instructions_per_update();
-    for mut cpu in &mut cpu_query {
-        cpu.set_instructions_per_update(instructions_per_update);
+/// Returns every bot to the state it was loaded in on entering `PreRace`, so
+/// nothing a previous race left in registers or memory carries over.
+fn reset_cpus(mut commands: Commands, mut cpu_query: Query<(Entity, &mut CpuComponent)>) {
+    for (entity, mut cpu) in &mut cpu_query {
+        cpu.reset();
+        commands.entity(entity).remove::<CpuTrapReported>();
     }
 }
 
 #[derive(Component)]
 struct EmulatorDriver;
 
+/// Marks a car whose CPU trap has already been written to its console.
+#[derive(Component)]
+struct CpuTrapReported;
+
+/// Surfaces a halted bot's trap once in its console instead of letting it coast silently.
+fn report_cpu_traps(
+    mut commands: Commands,
+    mut cpu_query: Query<
+        (Entity, &CarLabel, &CpuComponent, &mut LogDevice),
+        Without<CpuTrapReported>,
+    >,
+) {
+    for (entity, label, cpu, mut log) in &mut cpu_query {
+        let Some(trap) = cpu.trap() else {
+            continue;
+        };
+        warn!("{} halted: {trap}", label.name);
+        log.push_str(&format!("\n[trap] bot hit {trap}\n"));
+        commands.entity(entity).insert(CpuTrapReported);
+    }
+}
+
+/// Fixed updates in a row a bot may spend its whole cycle budget, never
+/// yielding, before it is flagged (one second).
+const BUDGET_HOG_UPDATES: u32 = FIXED_TICK_HZ;
+
+/// Flags a bot once per streak when it has used up its cycle budget for
+/// `BUDGET_HOG_UPDATES` updates in a row: it is being cut off mid-work
+/// rather than finishing with `yield_tick()`.
+fn report_budget_hogs(mut cpu_query: Query<(&CarLabel, &CpuComponent, &mut LogDevice)>) {
+    for (label, cpu, mut log) in &mut cpu_query {
+        if cpu.exhausted_runs() != BUDGET_HOG_UPDATES {
+            continue;
+        }
+        let budget = cpu.instructions_per_update();
+        warn!(
+            "{} used its whole {budget}-cycle budget for {BUDGET_HOG_UPDATES} updates in a row",
+            label.name
+        );
+        log.push_str(&format!(
+            "\n[budget] bot ran out of its {budget} cycles every tick for a second; finish each tick with yield_tick()\n"
+        ));
+    }
+}
+
+/// Fixed updates a freshly spawned bot must run without trapping before it
+/// is reported as started.
+const STARTUP_WATCH_UPDATES: u32 = 10;
+
+/// Watches a newly spawned car's first updates so its load outcome can be
+/// reported back to the bootstrap layer.
+#[derive(Component)]
+struct StartupWatch {
+    driver: DriverType,
+    updates: u32,
+}
+
+/// Reports each new bot once: trapped if it halts within its first
+/// `STARTUP_WATCH_UPDATES` updates, started otherwise.
+fn report_car_starts(
+    mut commands: Commands,
+    mut watch_query: Query<(Entity, &CpuComponent, &mut StartupWatch)>,
+    mut reports: MessageWriter<CarLoadReport>,
+) {
+    for (entity, cpu, mut watch) in &mut watch_query {
+        watch.updates += 1;
+        let outcome = match cpu.trap() {
+            Some(trap) => CarLoadOutcome::TrappedAtInit {
+                trap: trap.to_string(),
+            },
+            None if watch.updates >= STARTUP_WATCH_UPDATES => CarLoadOutcome::Started,
+            None => continue,
+        };
+        reports.write(CarLoadReport {
+            driver: watch.driver.clone(),
+            outcome,
+        });
+        commands.entity(entity).remove::<StartupWatch>();
+    }
+}
+
 #[derive(Component)]
 struct FrontWheel;
 
@@ -653,6 +3607,7 @@ emulator::define_cpu_config! {
         4 => SplineDevice,
         5 => TrackRadarDevice,
         6 => CarRadarDevice,
+        7 => MetricsDevice,
     }
 }
 
@@ -688,12 +3643,41 @@ fn handle_car_input(
     }
 }
 
+/// Gives every car the strongest draft it gets from any car ahead of it.
+fn update_slipstream(mut car_query: Query<(Entity, &Transform, &mut Slipstream), With<Car>>) {
+    let cars: Vec<_> = car_query
+        .iter()
+        .map(|(entity, transform, _)| {
+            (
+                entity,
+                transform.translation.xy(),
+                transform.up().xy().normalize(),
+            )
+        })
+        .collect();
+
+    for (entity, transform, mut slipstream) in &mut car_query {
+        let position = transform.translation.xy();
+        slipstream.drag_factor = cars
+            .iter()
+            .filter(|(other, _, _)| *other != entity)
+            .map(|&(_, leader, leader_forward)| {
+                slipstream_drag_factor(position, leader, leader_forward)
+            })
+            .fold(1.0, f32::min);
+    }
+}
+
 fn apply_car_forces(
     mut car_query: Query<(
         Entity,
         &Transform,
         &mut Car,
         &mut LongitudinalDebugData,
+        &Slipstream,
+        &mut TireWear,
+        &CarDimensions,
+        &BrakeBias,
         &Children,
         Forces,
         Has<DebugGizmos>,
@@ -701,13 +3685,26 @@ fn apply_car_forces(
     mut wheel_query: Query<&mut Transform, (With<FrontWheel>, Without<Car>)>,
     mut gizmos: Gizmos,
     params: Res<KartLongitudinalParams>,
+    wear_model: Res<TireWearModel>,
+    surface: Res<track::TrackSurface>,
     time: Res<Time<Fixed>>,
 ) {
     let dt = time.delta_secs();
-    let g = 9.81_f32;
-
-    for (_entity, transform, mut car, mut debug_data, children, mut forces, show_gizmos) in
-        &mut car_query
+    let g = GRAVITY;
+
+    for (
+        _entity,
+        transform,
+        mut car,
+        mut debug_data,
+        slipstream,
+        mut tire_wear,
+        dimensions,
+        brake_bias,
+        children,
+        mut forces,
+        show_gizmos,
+    ) in &mut car_query
     {
         let position = transform.translation.xy();
         let forward = transform.up().xy().normalize();
@@ -716,27 +3713,47 @@ fn apply_car_forces(
         let brake = car.brake.clamp(0.0, 1.0);
         let v_long = forces.linear_velocity().dot(forward);
 
+        let wheels = dimensions.wheel_offsets();
+        let [front_left, front_right, rear_left, rear_right] = [
+            wheels.front_left,
+            wheels.front_right,
+            wheels.rear_left,
+            wheels.rear_right,
+        ]
+        .map(|offset| forward * offset.y - left * offset.x);
+        let tire_grip = tire_wear.grip_factor(&wear_model);
+        let wheel_grip = [front_left, front_right, rear_left, rear_right]
+            .map(|offset| surface.grip_at(position + offset) * tire_grip);
+        let mean_grip = wheel_grip.iter().sum::<f32>() / wheel_grip.len() as f32;
+
         car.wheel_omega = v_long / params.wheel_radius_m;
         let wheel_rpm = rad_per_sec_to_rpm(car.wheel_omega.abs());
 
         let engine_rpm_prev = car.engine_rpm.max(params.idle_rpm);
-        let engine_torque_full = engine_torque_full(engine_rpm_prev, &params);
-        let mut t_eng = throttle * engine_torque_full - (1.0 - throttle) * params.engine_brake_nm;
-        t_eng *= governor_scale(engine_rpm_prev, &params);
+        let t_eng = engine_torque(throttle, engine_rpm_prev, &params);
 
         let clutch_s = smoothstep(
             params.clutch_on_rpm,
             params.clutch_lock_rpm,
             engine_rpm_prev,
         );
-        let t_drive_axle =
-            params.drivetrain_efficiency * params.gear_ratio * clutch_s * t_eng.max(0.0);
+        let t_drive_axle = params.drivetrain_efficiency * params.gear_ratio * clutch_s * t_eng;
         let t_brake_axle = brake * params.brake_max_axle_nm;
 
         let f_drive = t_drive_axle / params.wheel_radius_m;
-        let f_brake = t_brake_axle / params.wheel_radius_m;
+        // Static load is taken as evenly split, so each axle holds half the
+        // traction its own wheels' grip allows.
+        let [f_brake_front, f_brake_rear] = axle_brake_forces(
+            t_brake_axle / params.wheel_radius_m,
+            brake_bias.front,
+            [
+                traction_limit(&params, (wheel_grip[0] + wheel_grip[1]) / 2.0) / 2.0,
+                traction_limit(&params, (wheel_grip[2] + wheel_grip[3]) / 2.0) / 2.0,
+            ],
+        );
+        let f_brake = f_brake_front + f_brake_rear;
         let f_rr = params.rolling_resistance * params.mass_kg * g;
-        let f_drag_mag = 0.5 * params.air_density * params.drag_area * v_long * v_long;
+        let f_drag_mag = aero_drag(&params, v_long, slipstream.drag_factor);
         let v_sign = if v_long.abs() < 0.05 {
             0.0
         } else {
@@ -749,7 +3766,7 @@ fn apply_car_forces(
             v_long.signum()
         };
         let f_raw = f_drive - f_brake - rr_sign * f_rr - v_sign * f_drag_mag;
-        let traction_limit = params.tire_mu * params.mass_kg * g;
+        let traction_limit = traction_limit(&params, mean_grip);
         let mut f_clamped = f_raw.clamp(-traction_limit, traction_limit);
 
         // Prevent low-speed sign-flip jitter while braking/coasting to a stop.
@@ -760,6 +3777,16 @@ fn apply_car_forces(
         let a_long = f_clamped / params.mass_kg;
         forces.apply_linear_acceleration(forward * a_long);
 
+        // Tire load: longitudinal plus cornering acceleration against what the
+        // tires can hold.
+        let a_lat = forces.angular_velocity() * v_long;
+        let grip_accel = (traction_limit / params.mass_kg).max(f32::EPSILON);
+        tire_wear.accumulate(
+            &wear_model,
+            Vec2::new(a_long, a_lat).length() / grip_accel,
+            forces.linear_velocity().length() * dt,
+        );
+
         let omega_lock = params.gear_ratio * car.wheel_omega;
         let omega_idle = rpm_to_rad_per_sec(params.idle_rpm);
         let omega_max = rpm_to_rad_per_sec(params.redline_rpm);
@@ -781,6 +3808,8 @@ fn apply_car_forces(
         debug_data.t_brake_axle = t_brake_axle;
         debug_data.f_drive = f_drive;
         debug_data.f_brake = f_brake;
+        debug_data.f_brake_front = f_brake_front;
+        debug_data.f_brake_rear = f_brake_rear;
         debug_data.f_rr = f_rr;
         debug_data.f_drag = f_drag_mag;
         debug_data.f_raw = f_raw;
@@ -794,38 +3823,23 @@ fn apply_car_forces(
             gizmos.arrow_2d(position, position + forward * a_long * 0.3, WHITE);
         }
 
-        apply_wheel_force(
-            position,
-            forward * WHEEL_BASE + left * -WHEEL_TRACK / 2.0,
-            Vec2::from_angle(-car.steer).rotate(forward),
-            &mut forces,
-            &mut gizmos,
-            show_gizmos,
-        );
-        apply_wheel_force(
-            position,
-            forward * WHEEL_BASE + left * WHEEL_TRACK / 2.0,
-            Vec2::from_angle(-car.steer).rotate(forward),
-            &mut forces,
-            &mut gizmos,
-            show_gizmos,
-        );
-        apply_wheel_force(
-            position,
-            left * -WHEEL_TRACK / 2.0,
-            forward,
-            &mut forces,
-            &mut gizmos,
-            show_gizmos,
-        );
-        apply_wheel_force(
-            position,
-            left * WHEEL_TRACK / 2.0,
-            forward,
-            &mut forces,
-            &mut gizmos,
-            show_gizmos,
-        );
+        let front_forward = Vec2::from_angle(-car.steer).rotate(forward);
+        for (offset, wheel_forward, grip) in [
+            (front_left, front_forward, wheel_grip[0]),
+            (front_right, front_forward, wheel_grip[1]),
+            (rear_left, forward, wheel_grip[2]),
+            (rear_right, forward, wheel_grip[3]),
+        ] {
+            apply_wheel_force(
+                position,
+                offset,
+                wheel_forward,
+                grip,
+                &mut forces,
+                &mut gizmos,
+                show_gizmos,
+            );
+        }
 
         for child in children.iter() {
             if let Ok(mut wheel_transform) = wheel_query.get_mut(child) {
@@ -839,6 +3853,7 @@ fn apply_wheel_force(
     car_position: Vec2,
     wheel_offset: Vec2,
     wheel_forward: Vec2,
+    grip: f32,
     forces: &mut ForcesItem<'_, '_>,
     gizmos: &mut Gizmos,
     show_gizmos: bool,
@@ -858,12 +3873,16 @@ fn apply_wheel_force(
 
     if show_gizmos {
         gizmos.arrow_2d(wheel_pos, wheel_pos + wheel_velocity * 0.1, GREEN);
+        let (length, color) = slip_gizmo(wheel_velocity, wheel_forward);
+        if length > 0.0 {
+            gizmos.line_2d(wheel_pos, wheel_pos - wheel_forward * length, color);
+        }
     }
 
     if wheel_velocity.length() > 0.1 {
         let force = -wheel_velocity.normalize().dot(wheel_left)
             * wheel_left
-            * 10.0_f32.min(wheel_velocity.length() * 5.0);
+            * (10.0 * grip).min(wheel_velocity.length() * 5.0);
         if show_gizmos {
             gizmos.arrow_2d(wheel_pos, wheel_pos + force, RED);
         }
@@ -871,12 +3890,33 @@ fn apply_wheel_force(
     }
 }
 
-fn draw_gizmos(car_query: Query<(&Transform, &Car), With<DebugGizmos>>, mut gizmos: Gizmos) {
-    for (transform, _car) in &car_query {
+/// Longest slip bar (m), drawn for a wheel sliding fully sideways.
+const SLIP_BAR_MAX_LEN: f32 = 1.5;
+
+/// Skid-mark style bar for one wheel: the sine of its slip angle scales the
+/// bar trailing behind it and shades it from green (rolling) to red (sliding).
+/// Wheels slower than the lateral force cut-off draw nothing.
+fn slip_gizmo(wheel_velocity: Vec2, wheel_forward: Vec2) -> (f32, Color) {
+    if wheel_velocity.length() <= 0.1 {
+        return (0.0, GREEN.into());
+    }
+    let slip = wheel_velocity
+        .normalize()
+        .dot(wheel_forward.perp())
+        .abs()
+        .min(1.0);
+    (slip * SLIP_BAR_MAX_LEN, GREEN.mix(&RED, slip).into())
+}
+
+fn draw_gizmos(
+    car_query: Query<(&Transform, &CarDimensions), With<DebugGizmos>>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, dimensions) in &car_query {
         gizmos.cross(transform.to_isometry(), 0.2, RED);
         gizmos.cross(
             Isometry3d::new(
-                transform.translation + transform.up() * WHEEL_BASE,
+                transform.translation + transform.up() * dimensions.wheel_base,
                 transform.rotation,
             ),
             0.2,
@@ -885,7 +3925,55 @@ fn draw_gizmos(car_query: Query<(&Transform, &Car), With<DebugGizmos>>, mut gizm
     }
 }
 
+/// Rate (1/s) at which the camera closes the gap to a followed car.
+const CAMERA_FOLLOW_RATE: f32 = 6.0;
+
+/// `C` cycles the camera: race leader, then each car in spawn order, then
+/// free.
+fn cycle_follow_target(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    manager: Res<RaceManager>,
+    mut follow: ResMut<FollowCar>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyC) {
+        cycle_follow(&mut follow, &manager.cars);
+    }
+}
+
+fn cycle_follow(follow: &mut FollowCar, cars: &[CarEntry]) {
+    if follow.leader {
+        follow.leader = false;
+        follow.target = cars.first().map(|car| car.entity);
+        return;
+    }
+    let current = follow
+        .target
+        .and_then(|target| cars.iter().position(|car| car.entity == target));
+    match current {
+        Some(index) => follow.target = cars.get(index + 1).map(|car| car.entity),
+        None => {
+            follow.target = None;
+            follow.leader = true;
+        }
+    }
+}
+
+/// Keeps a spectating camera on whoever leads [`RaceStandings`]. Only writes
+/// on a lead change so the car list is not rebuilt every frame.
+fn follow_race_leader(standings: Res<RaceStandings>, mut follow: ResMut<FollowCar>) {
+    if !follow.leader {
+        return;
+    }
+    let Some(leader) = standings.rows.first().map(|row| row.entity) else {
+        return;
+    };
+    if follow.target != Some(leader) {
+        follow.target = Some(leader);
+    }
+}
+
 fn update_camera(
+    time: Res<Time>,
     car_query: Query<&Transform, With<Car>>,
     mut camera_query: Query<(&mut Transform, &mut Projection), (With<Camera2d>, Without<Car>)>,
     mut scroll_events: MessageReader<MouseWheel>,
@@ -913,8 +4001,14 @@ fn update_camera(
 
     if let Some(follow_entity) = follow.target {
         if let Ok(car_tf) = car_query.get(follow_entity) {
-            camera_transform.translation.x = car_tf.translation.x;
-            camera_transform.translation.y = car_tf.translation.y;
+            // Ease towards the car so switching targets pans instead of jumping.
+            let blend = 1.0 - (-CAMERA_FOLLOW_RATE * time.delta_secs()).exp();
+            let position = camera_transform
+                .translation
+                .truncate()
+                .lerp(car_tf.translation.truncate(), blend);
+            camera_transform.translation.x = position.x;
+            camera_transform.translation.y = position.y;
             return;
         }
     }
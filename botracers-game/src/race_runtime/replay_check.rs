@@ -0,0 +1,603 @@
+//! Headless re-simulation of recorded races, so claimed results can be
+//! checked against what the recorded controls actually produce before they
+//! reach the leaderboard (`--verify-replay=<replay.json>`).
+//!
+//! A replay holds the controls each car applied on every tick. Feeding them
+//! back through the same car physics on the same track reproduces the race,
+//! since the simulation is deterministic for a fixed tick rate. Cars are
+//! sized by the replay's `car_scale` and set up with its collision, tire
+//! wear and per-car brake bias settings.
+
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+
+use avian2d::prelude::*;
+use bevy::ecs::schedule::ExecutorKind;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use botracers_game::Car;
+use botracers_game::track::{self, TrackCenterline};
+use botracers_game::track_format::TrackFile;
+use botracers_protocol::{
+    REPLAY_FORMAT_VERSION, RaceEntrySubmission, ReplayFile, ReplayInput, ReplayParticipant,
+    SubmitRaceRequest,
+};
+
+use super::{
+    BrakeBias, CAR_SCALES, CarCollisions, CarDimensions, FIXED_TICK_HZ, KartLongitudinalParams,
+    TireWearModel, apply_car_forces, barrier_collider, car_body, car_collider, track_surface,
+    update_slipstream,
+};
+use crate::race_results::{LapProgress, compare_progress};
+
+/// How far a claimed best lap may be from the re-simulated one, in seconds.
+pub const BEST_LAP_TOLERANCE_SECS: f32 = 0.05;
+
+/// Controls applied on the next tick, one per car in spawn order.
+#[derive(Resource, Default)]
+struct TickInputs(Vec<ReplayInput>);
+
+/// Replay participants' cars, in spawn order.
+#[derive(Resource, Default)]
+struct ReplayCars(Vec<Entity>);
+
+/// The race-wide physics a replay was recorded with.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplayPhysics {
+    pub dimensions: CarDimensions,
+    pub collisions: CarCollisions,
+    pub tire_wear: TireWearModel,
+}
+
+impl ReplayPhysics {
+    /// The settings `replay` records, with the game's defaults for those it
+    /// predates.
+    pub fn of(replay: &ReplayFile) -> Self {
+        let mut tire_wear = TireWearModel::default();
+        if let Some(wear_per_m) = replay.tire_wear_per_m {
+            tire_wear.wear_per_m = wear_per_m;
+        }
+        Self {
+            dimensions: CarDimensions::default().scaled(replay.car_scale),
+            collisions: CarCollisions {
+                between_cars: replay.cars_collide,
+            },
+            tire_wear,
+        }
+    }
+}
+
+/// A race world without rendering, stepped one fixed tick per
+/// [`step`](Self::step).
+pub struct ReplaySim {
+    app: App,
+    centerline: TrackCenterline,
+    progress: Vec<LapProgress>,
+    lap_cap: u32,
+    tick: u32,
+    next_crossing_seq: u64,
+}
+
+impl ReplaySim {
+    /// Puts `participants` on `track` at their starting poses with their
+    /// brake bias, under `physics`. Laps stop counting once a car has
+    /// `lap_cap`, as they do after the flag.
+    pub fn new(
+        track: &TrackFile,
+        participants: &[ReplayParticipant],
+        physics: ReplayPhysics,
+        lap_cap: u32,
+    ) -> Self {
+        let dimensions = physics.dimensions;
+        let tick = Duration::from_nanos(1_000_000_000 / u64::from(FIXED_TICK_HZ));
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            bevy::gizmos::GizmoPlugin,
+            PhysicsPlugins::default(),
+        ))
+        .init_asset::<Mesh>()
+        .insert_resource(Gravity::ZERO)
+        .insert_resource(Time::<Fixed>::from_duration(tick))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(tick))
+        .insert_resource(KartLongitudinalParams::default())
+        .insert_resource(physics.tire_wear)
+        .init_resource::<TickInputs>()
+        .init_resource::<ReplayCars>()
+        .add_systems(
+            FixedUpdate,
+            (
+                apply_tick_inputs,
+                update_slipstream,
+                apply_car_forces.after(update_slipstream),
+            )
+                .chain(),
+        );
+        app.finish();
+        app.cleanup();
+        // Replays loaded in the game are re-run from one of its systems, where
+        // a multi-threaded executor would wait on the task pool threads busy
+        // running that system.
+        for (_, schedule) in app.world_mut().resource_mut::<Schedules>().iter_mut() {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        }
+
+        let spline = track::build_spline(&track.control_points_vec2());
+        let centerline = TrackCenterline::from_spline(&spline, 1000);
+        let world = app.world_mut();
+        world.insert_resource(track_surface(track, &centerline));
+        for barrier in &track.barriers {
+            world.spawn(barrier_collider(barrier));
+        }
+        let layers = physics.collisions.layers();
+        let cars = participants
+            .iter()
+            .map(|participant| {
+                let transform = Transform::from_xyz(participant.start_x, participant.start_y, 1.0)
+                    .with_rotation(Quat::from_rotation_z(participant.start_heading - FRAC_PI_2));
+                let mut car = world.spawn((
+                    car_body(transform, dimensions),
+                    children![car_collider(layers, &dimensions)],
+                ));
+                if let Some(front) = participant.brake_bias {
+                    car.insert(BrakeBias { front });
+                }
+                car.id()
+            })
+            .collect();
+        world.insert_resource(ReplayCars(cars));
+
+        Self {
+            app,
+            centerline,
+            progress: (1..=participants.len() as u32)
+                .map(LapProgress::new)
+                .collect(),
+            lap_cap,
+            tick: 0,
+            next_crossing_seq: 0,
+        }
+    }
+
+    /// Runs one tick with `inputs` applied, one per car in spawn order.
+    pub fn step(&mut self, inputs: &[ReplayInput]) {
+        self.app.world_mut().resource_mut::<TickInputs>().0 = inputs.to_vec();
+        self.app.update();
+        self.tick += 1;
+
+        let now = self.tick as f32 / FIXED_TICK_HZ as f32;
+        for (index, (position, _)) in self.poses().into_iter().enumerate() {
+            let progress = &mut self.progress[index];
+            if progress.laps_completed < self.lap_cap {
+                let fraction = self.centerline.lap_fraction(position);
+                progress.update(fraction, now, &mut self.next_crossing_seq);
+            }
+        }
+    }
+
+    /// Each car's position and unit forward vector, in spawn order.
+    pub fn poses(&self) -> Vec<(Vec2, Vec2)> {
+        let world = self.app.world();
+        world
+            .resource::<ReplayCars>()
+            .0
+            .iter()
+            .map(|&car| {
+                let transform = world.get::<Transform>(car).expect("replay car");
+                (
+                    transform.translation.truncate(),
+                    transform.up().truncate().normalize(),
+                )
+            })
+            .collect()
+    }
+
+    /// Each car's lap progress so far, in spawn order.
+    pub fn progress(&self) -> &[LapProgress] {
+        &self.progress
+    }
+}
+
+fn apply_tick_inputs(
+    inputs: Res<TickInputs>,
+    cars: Res<ReplayCars>,
+    mut car_query: Query<&mut Car>,
+) {
+    for (&entity, input) in cars.0.iter().zip(&inputs.0) {
+        if let Ok(mut car) = car_query.get_mut(entity) {
+            car.steer = input.steering;
+            car.accelerator = input.accelerator;
+            car.brake = input.brake;
+        }
+    }
+}
+
+/// Re-runs every tick of `replay` on `track` and returns each participant's
+/// lap progress, in spawn order.
+pub fn resimulate(
+    replay: &ReplayFile,
+    track: &TrackFile,
+    lap_cap: u32,
+) -> Result<Vec<LapProgress>, String> {
+    if replay.version != REPLAY_FORMAT_VERSION {
+        return Err(format!(
+            "unsupported replay version {} (expected {REPLAY_FORMAT_VERSION})",
+            replay.version
+        ));
+    }
+    if replay.tick_hz != FIXED_TICK_HZ {
+        return Err(format!(
+            "replay was recorded at {} Hz, the simulation runs at {FIXED_TICK_HZ} Hz",
+            replay.tick_hz
+        ));
+    }
+    if replay.track != track.metadata.name {
+        return Err(format!(
+            "replay was recorded on '{}', not '{}'",
+            replay.track, track.metadata.name
+        ));
+    }
+    if !CAR_SCALES.contains(&replay.car_scale) {
+        return Err(format!(
+            "replay cars are scaled by {}, expected {} to {}",
+            replay.car_scale,
+            CAR_SCALES.start(),
+            CAR_SCALES.end()
+        ));
+    }
+    if let Some(wear_per_m) = replay
+        .tire_wear_per_m
+        .filter(|wear_per_m| !(wear_per_m.is_finite() && *wear_per_m >= 0.0))
+    {
+        return Err(format!(
+            "replay tires wear by {wear_per_m} per metre, expected a non-negative rate"
+        ));
+    }
+    if let Some((participant, front)) = replay.participants.iter().find_map(|participant| {
+        participant
+            .brake_bias
+            .filter(|front| !(0.0..=1.0).contains(front))
+            .map(|front| (participant, front))
+    }) {
+        return Err(format!(
+            "#{} has a front brake bias of {front}, expected 0 to 1",
+            participant.artifact_id
+        ));
+    }
+    if replay.ticks.cars() != replay.participants.len() {
+        return Err(format!(
+            "ticks have {} inputs for {} participants",
+            replay.ticks.cars(),
+            replay.participants.len()
+        ));
+    }
+
+    let mut sim = ReplaySim::new(
+        track,
+        &replay.participants,
+        ReplayPhysics::of(replay),
+        lap_cap,
+    );
+    for inputs in replay.ticks.iter() {
+        sim.step(&inputs);
+    }
+    Ok(sim.progress().to_vec())
+}
+
+/// Re-runs `replay` on `track` and returns the results it produces, winner
+/// first, the way the game submits them. Laps stop counting at `lap_cap`.
+pub fn resimulated_results(
+    replay: &ReplayFile,
+    track: &TrackFile,
+    lap_cap: u32,
+) -> Result<Vec<RaceEntrySubmission>, String> {
+    let simulated = resimulate(replay, track, lap_cap)?;
+    let mut order: Vec<usize> = (0..simulated.len()).collect();
+    order.sort_by(|&a, &b| compare_progress(&simulated[a], &simulated[b]));
+    Ok(order
+        .into_iter()
+        .map(|index| RaceEntrySubmission {
+            artifact_id: replay.participants[index].artifact_id,
+            laps_completed: simulated[index].laps_completed,
+            best_lap_secs: simulated[index].best_lap,
+            finish_reason: None,
+        })
+        .collect())
+}
+
+/// Checks `claimed` results against a re-simulation of `replay`: the same
+/// cars in the same finishing order, the same laps, and best laps within
+/// [`BEST_LAP_TOLERANCE_SECS`]. Returns why the claim does not hold, if it
+/// does not.
+pub fn verify_claims(
+    replay: &ReplayFile,
+    track: &TrackFile,
+    claimed: &SubmitRaceRequest,
+) -> Result<(), String> {
+    if claimed.entries.len() != replay.participants.len() {
+        return Err(format!(
+            "{} results claimed for {} participants",
+            claimed.entries.len(),
+            replay.participants.len()
+        ));
+    }
+    let lap_cap = claimed
+        .entries
+        .iter()
+        .map(|entry| entry.laps_completed)
+        .max()
+        .unwrap_or(0);
+    let simulated = resimulated_results(replay, track, lap_cap)?;
+    for (position, (entry, actual)) in claimed.entries.iter().zip(&simulated).enumerate() {
+        let place = position + 1;
+        if entry.artifact_id != actual.artifact_id {
+            return Err(format!(
+                "#{} claims place {place}, the replay puts #{} there",
+                entry.artifact_id, actual.artifact_id
+            ));
+        }
+        if entry.laps_completed != actual.laps_completed {
+            return Err(format!(
+                "#{} claims {} laps, the replay completes {}",
+                entry.artifact_id, entry.laps_completed, actual.laps_completed
+            ));
+        }
+        match (entry.best_lap_secs, actual.best_lap_secs) {
+            (None, None) => {}
+            (Some(claim), Some(actual)) if (claim - actual).abs() <= BEST_LAP_TOLERANCE_SECS => {}
+            (claim, actual) => {
+                return Err(format!(
+                    "#{} claims a best lap of {}, the replay gives {}",
+                    entry.artifact_id,
+                    format_lap(claim),
+                    format_lap(actual)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn format_lap(lap: Option<f32>) -> String {
+    lap.map_or_else(|| "none".to_string(), |secs| format!("{secs:.3} s"))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use botracers_protocol::{
+        ArtifactId, REPLAY_FORMAT_VERSION, RaceEntrySubmission, ReplayFile, ReplayInput,
+        ReplayParticipant, ReplayTicks, SubmitRaceRequest,
+    };
+
+    use super::{
+        CarCollisions, CarDimensions, ReplayPhysics, ReplaySim, resimulate, resimulated_results,
+        verify_claims,
+    };
+    use botracers_game::track_format::TrackFile;
+
+    const RING_RADIUS: f32 = 15.0;
+
+    /// A small ring with no barriers, lapped counter-clockwise.
+    fn ring_track() -> TrackFile {
+        let mut track = TrackFile::new_empty("ring");
+        track.control_points = (0..16)
+            .map(|i| {
+                let angle = i as f32 / 16.0 * std::f32::consts::TAU;
+                [angle.cos() * RING_RADIUS, angle.sin() * RING_RADIUS]
+            })
+            .collect();
+        track
+    }
+
+    /// Starts a car on the ring, heading counter-clockwise.
+    fn participant(artifact_id: i64, brake_bias: Option<f32>) -> ReplayParticipant {
+        ReplayParticipant {
+            artifact_id: ArtifactId(artifact_id),
+            start_x: RING_RADIUS,
+            start_y: 0.0,
+            start_heading: std::f32::consts::FRAC_PI_2,
+            brake_bias,
+        }
+    }
+
+    /// Drives `participants` around `track` under `physics` with a
+    /// pure-pursuit controller for `ticks` ticks, with `braking` hard for a
+    /// moment every two seconds, and records what they applied.
+    fn record_laps(
+        track: &TrackFile,
+        physics: ReplayPhysics,
+        participants: Vec<ReplayParticipant>,
+        ticks: usize,
+        braking: bool,
+    ) -> ReplayFile {
+        let mut sim = ReplaySim::new(track, &participants, physics, u32::MAX);
+        let hz = super::FIXED_TICK_HZ as usize;
+        let mut recorded = ReplayTicks::new(participants.len());
+        for tick in 0..ticks {
+            let braking = braking && tick % (2 * hz) >= 2 * hz - hz / 10;
+            let inputs: Vec<_> = sim
+                .poses()
+                .into_iter()
+                .map(|(position, forward)| {
+                    // Aim a quarter-radian further along the ring.
+                    let angle = position.y.atan2(position.x) + 0.25;
+                    let target = Vec2::from_angle(angle) * RING_RADIUS - position;
+                    let right = target.dot(-forward.perp());
+                    let ahead = target.dot(forward);
+                    ReplayInput {
+                        steering: right
+                            .atan2(ahead)
+                            .clamp(-std::f32::consts::FRAC_PI_6, std::f32::consts::FRAC_PI_6),
+                        accelerator: if braking { 0.0 } else { 1.0 },
+                        brake: if braking { 1.0 } else { 0.0 },
+                    }
+                })
+                .collect();
+            sim.step(&inputs);
+            recorded.push(&inputs);
+        }
+        ReplayFile {
+            version: REPLAY_FORMAT_VERSION,
+            tick_hz: super::FIXED_TICK_HZ,
+            track: track.metadata.name.clone(),
+            car_scale: physics.dimensions.scale(),
+            cars_collide: physics.collisions.between_cars,
+            tire_wear_per_m: Some(physics.tire_wear.wear_per_m),
+            participants,
+            ticks: recorded,
+        }
+    }
+
+    /// What the re-simulation of `replay` produces, as a claim.
+    fn honest_claim(replay: &ReplayFile, track: &TrackFile) -> SubmitRaceRequest {
+        SubmitRaceRequest {
+            entries: resimulated_results(replay, track, u32::MAX).expect("resimulated"),
+            replay: None,
+        }
+    }
+
+    #[test]
+    fn tampered_best_lap_is_rejected_and_the_true_one_accepted() {
+        let track = ring_track();
+        let replay = record_laps(
+            &track,
+            ReplayPhysics::default(),
+            vec![participant(7, None)],
+            13 * super::FIXED_TICK_HZ as usize,
+            false,
+        );
+        let mut sim = ReplaySim::new(
+            &track,
+            &replay.participants,
+            ReplayPhysics::default(),
+            u32::MAX,
+        );
+        for inputs in replay.ticks.iter() {
+            sim.step(&inputs);
+        }
+        let progress = sim.progress()[0].clone();
+        assert!(progress.laps_completed >= 1, "{progress:?}");
+        let best_lap = progress.best_lap.expect("a timed lap");
+
+        let claim = |laps_completed, best_lap_secs| SubmitRaceRequest {
+            entries: vec![RaceEntrySubmission {
+                artifact_id: ArtifactId(7),
+                laps_completed,
+                best_lap_secs,
+                finish_reason: None,
+            }],
+            replay: None,
+        };
+        let laps = progress.laps_completed;
+        assert_eq!(
+            verify_claims(&replay, &track, &claim(laps, Some(best_lap))),
+            Ok(())
+        );
+
+        let faster = verify_claims(&replay, &track, &claim(laps, Some(best_lap - 1.0)));
+        assert!(
+            faster
+                .as_ref()
+                .is_err_and(|reason| reason.contains("best lap")),
+            "{faster:?}"
+        );
+        assert!(verify_claims(&replay, &track, &claim(laps + 1, Some(best_lap))).is_err());
+        assert!(verify_claims(&replay, &track, &claim(laps, None)).is_err());
+
+        let mut other_track = replay.clone();
+        other_track.track = "elsewhere".to_string();
+        assert!(verify_claims(&other_track, &track, &claim(laps, Some(best_lap))).is_err());
+    }
+
+    #[test]
+    fn replays_are_resimulated_with_their_car_scale() {
+        let track = ring_track();
+        let physics = ReplayPhysics {
+            dimensions: CarDimensions::default().scaled(1.5),
+            ..default()
+        };
+        let replay = record_laps(
+            &track,
+            physics,
+            vec![participant(7, None)],
+            13 * super::FIXED_TICK_HZ as usize,
+            false,
+        );
+        let progress = resimulate(&replay, &track, u32::MAX).expect("resimulated")[0].clone();
+        assert!(progress.laps_completed >= 1, "{progress:?}");
+        let claim = SubmitRaceRequest {
+            entries: vec![RaceEntrySubmission {
+                artifact_id: ArtifactId(7),
+                laps_completed: progress.laps_completed,
+                best_lap_secs: progress.best_lap,
+                finish_reason: None,
+            }],
+            replay: None,
+        };
+        assert_eq!(verify_claims(&replay, &track, &claim), Ok(()));
+
+        let mut kart = replay.clone();
+        kart.car_scale = 1.0;
+        assert!(verify_claims(&kart, &track, &claim).is_err());
+
+        let mut oversized = replay.clone();
+        oversized.car_scale = 10.0;
+        let refused = verify_claims(&oversized, &track, &claim);
+        assert!(
+            refused
+                .as_ref()
+                .is_err_and(|reason| reason.contains("scaled")),
+            "{refused:?}"
+        );
+    }
+
+    #[test]
+    fn replays_are_resimulated_with_their_ghost_cars_and_brake_bias() {
+        let track = ring_track();
+        // Both cars start on the same spot, which only works as ghosts.
+        let replay = record_laps(
+            &track,
+            ReplayPhysics {
+                collisions: CarCollisions {
+                    between_cars: false,
+                },
+                ..default()
+            },
+            vec![participant(7, Some(0.95)), participant(8, Some(0.1))],
+            13 * super::FIXED_TICK_HZ as usize,
+            true,
+        );
+        let claim = honest_claim(&replay, &track);
+        assert!(
+            claim.entries.iter().all(|entry| entry.laps_completed >= 1),
+            "{:?}",
+            claim.entries
+        );
+        assert_eq!(verify_claims(&replay, &track, &claim), Ok(()));
+
+        let mut colliding = replay.clone();
+        colliding.cars_collide = true;
+        assert!(verify_claims(&colliding, &track, &claim).is_err());
+
+        let mut default_bias = replay.clone();
+        for participant in &mut default_bias.participants {
+            participant.brake_bias = None;
+        }
+        assert!(verify_claims(&default_bias, &track, &claim).is_err());
+
+        let mut broken_bias = replay.clone();
+        broken_bias.participants[1].brake_bias = Some(1.5);
+        let refused = verify_claims(&broken_bias, &track, &claim);
+        assert!(
+            refused
+                .as_ref()
+                .is_err_and(|reason| reason.contains("brake bias")),
+            "{refused:?}"
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use botracers_protocol::ArtifactId;
+
+use crate::game_api::DriverType;
+use crate::race_runtime::{CAR_SCALES, CarCollisions, RaceConfig, TireWearModel};
+
+/// Marks a shared race setup and its format version.
+pub const RACE_SETUP_PREFIX: &str = "botracers-race:1:";
+
+/// A grid plus its race length, car size, collisions and tire wear, shareable
+/// as one line of text so a matchup can be reproduced elsewhere
+/// (`--race=<code>`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaceSetup {
+    pub drivers: Vec<DriverType>,
+    pub config: RaceConfig,
+    /// `--car-scale` factor of the cars; 1 is the default kart.
+    pub car_scale: f32,
+    /// Whether cars bounce off each other; off for `--ghost-cars` races.
+    pub collisions: CarCollisions,
+    /// `--tire-wear` model the cars race on.
+    pub tire_wear: TireWearModel,
+}
+
+impl RaceSetup {
+    /// `botracers-race:1:laps=3;limit=300;cycles=5000;scale=1.5;ghost=1;wear=0.001;grid=12,7,45`;
+    /// `limit` and `cycles` are left out when the race has none, `scale` when
+    /// the cars are default karts, `ghost` when cars collide, `wear` at the
+    /// default tire wear, and local-file drivers are left off the grid.
+    pub fn encode(&self) -> String {
+        let grid = self
+            .drivers
+            .iter()
+            .filter_map(|driver| match driver {
+                DriverType::RemoteArtifact { id } => Some(id.to_string()),
+                // Only this machine can load it.
+                DriverType::LocalFile { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let limit = self
+            .config
+            .time_limit_secs
+            .map_or_else(String::new, |limit| format!("limit={limit};"));
+        let cycles = self
+            .config
+            .cycles_per_tick
+            .map_or_else(String::new, |cycles| format!("cycles={cycles};"));
+        let scale = if self.car_scale == 1.0 {
+            String::new()
+        } else {
+            format!("scale={};", self.car_scale)
+        };
+        let ghost = if self.collisions.between_cars {
+            ""
+        } else {
+            "ghost=1;"
+        };
+        let wear = if self.tire_wear.wear_per_m == TireWearModel::default().wear_per_m {
+            String::new()
+        } else {
+            format!("wear={};", self.tire_wear.wear_per_m)
+        };
+        format!(
+            "{RACE_SETUP_PREFIX}laps={};{limit}{cycles}{scale}{ghost}{wear}grid={grid}",
+            self.config.laps
+        )
+    }
+
+    /// Parses an [`encode`](Self::encode)d setup. Unknown fields are skipped,
+    /// so setups from newer games still load what this one understands.
+    pub fn decode(code: &str) -> Result<Self, String> {
+        let body = code
+            .trim()
+            .strip_prefix(RACE_SETUP_PREFIX)
+            .ok_or_else(|| format!("not a race setup (expected {RACE_SETUP_PREFIX}...)"))?;
+        let mut config = RaceConfig::default();
+        let mut car_scale = 1.0;
+        let mut collisions = CarCollisions::default();
+        let mut tire_wear = TireWearModel::default();
+        let mut drivers = None;
+        for field in body.split(';').filter(|field| !field.is_empty()) {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| format!("malformed field '{field}'"))?;
+            match key {
+                "laps" => {
+                    config.laps = value
+                        .parse()
+                        .map_err(|_| format!("invalid lap count '{value}'"))?;
+                }
+                "limit" => {
+                    config.time_limit_secs = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid time limit '{value}'"))?,
+                    );
+                }
+                "cycles" => {
+                    config.cycles_per_tick = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid cycle budget '{value}'"))?,
+                    );
+                }
+                "scale" => {
+                    car_scale = value
+                        .parse()
+                        .ok()
+                        .filter(|scale| CAR_SCALES.contains(scale))
+                        .ok_or_else(|| format!("invalid car scale '{value}'"))?;
+                }
+                "ghost" => {
+                    collisions.between_cars = match value {
+                        "0" => true,
+                        "1" => false,
+                        _ => return Err(format!("invalid ghost flag '{value}'")),
+                    };
+                }
+                "wear" => {
+                    tire_wear.wear_per_m = value
+                        .parse()
+                        .ok()
+                        .filter(|rate: &f32| rate.is_finite() && *rate >= 0.0)
+                        .ok_or_else(|| format!("invalid tire wear '{value}'"))?;
+                }
+                "grid" => {
+                    drivers = Some(
+                        value
+                            .split(',')
+                            .map(|id| {
+                                id.parse::<ArtifactId>()
+                                    .map(|id| DriverType::RemoteArtifact { id })
+                                    .map_err(|_| format!("invalid artifact id '{id}'"))
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        config.validate()?;
+        let drivers = drivers
+            .filter(|drivers| !drivers.is_empty())
+            .ok_or_else(|| "race setup has no cars".to_string())?;
+        Ok(Self {
+            drivers,
+            config,
+            car_scale,
+            collisions,
+            tire_wear,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use botracers_protocol::ArtifactId;
+
+    use super::RaceSetup;
+    use crate::game_api::DriverType;
+    use crate::race_runtime::{CarCollisions, RaceConfig, TireWearModel};
+
+    fn artifact(id: i64) -> DriverType {
+        DriverType::RemoteArtifact { id: ArtifactId(id) }
+    }
+
+    #[test]
+    fn encoded_setup_decodes_to_the_same_grid_and_config() {
+        for (config, car_scale, between_cars, wear_per_m) in [
+            (
+                RaceConfig {
+                    laps: 3,
+                    time_limit_secs: Some(300.0),
+                    cycles_per_tick: None,
+                },
+                1.0,
+                true,
+                TireWearModel::default().wear_per_m,
+            ),
+            (
+                RaceConfig {
+                    laps: 12,
+                    time_limit_secs: None,
+                    cycles_per_tick: None,
+                },
+                2.5,
+                false,
+                0.0,
+            ),
+            (
+                RaceConfig {
+                    laps: 1,
+                    time_limit_secs: None,
+                    cycles_per_tick: Some(5_000),
+                },
+                0.75,
+                false,
+                1.25e-3,
+            ),
+        ] {
+            let setup = RaceSetup {
+                drivers: vec![artifact(12), artifact(7), artifact(45), artifact(7)],
+                config,
+                car_scale,
+                collisions: CarCollisions { between_cars },
+                tire_wear: TireWearModel {
+                    wear_per_m,
+                    ..TireWearModel::default()
+                },
+            };
+            let code = setup.encode();
+            assert_eq!(RaceSetup::decode(&code), Ok(setup), "{code}");
+        }
+        assert_eq!(
+            RaceSetup {
+                drivers: vec![artifact(12), artifact(7)],
+                config: RaceConfig {
+                    laps: 3,
+                    time_limit_secs: Some(300.0),
+                    cycles_per_tick: None,
+                },
+                car_scale: 1.5,
+                collisions: CarCollisions::default(),
+                tire_wear: TireWearModel::default(),
+            }
+            .encode(),
+            "botracers-race:1:laps=3;limit=300;scale=1.5;grid=12,7"
+        );
+        assert_eq!(
+            RaceSetup {
+                drivers: vec![artifact(3)],
+                config: RaceConfig::default(),
+                car_scale: 1.0,
+                collisions: CarCollisions {
+                    between_cars: false
+                },
+                tire_wear: TireWearModel {
+                    wear_per_m: 0.0,
+                    ..TireWearModel::default()
+                },
+            }
+            .encode(),
+            format!(
+                "botracers-race:1:laps={};ghost=1;wear=0;grid=3",
+                RaceConfig::default().laps
+            )
+        );
+    }
+
+    #[test]
+    fn malformed_setups_are_refused() {
+        for code in [
+            "laps=3;grid=1",
+            "botracers-race:1:laps=3",
+            "botracers-race:1:laps=0;grid=1",
+            "botracers-race:1:laps=3;grid=1,x",
+            "botracers-race:1:laps=3;limit=1;grid=1",
+            "botracers-race:1:laps=3;cycles=10;grid=1",
+            "botracers-race:1:laps=3;cycles=-5;grid=1",
+            "botracers-race:1:laps=3;scale=10;grid=1",
+            "botracers-race:1:laps=3;scale=big;grid=1",
+            "botracers-race:1:laps=3;ghost=yes;grid=1",
+            "botracers-race:1:laps=3;wear=-1;grid=1",
+            "botracers-race:1:laps=3;wear=NaN;grid=1",
+            "botracers-race:1:laps",
+        ] {
+            assert!(RaceSetup::decode(code).is_err(), "{code} was accepted");
+        }
+        assert_eq!(
+            RaceSetup::decode(" botracers-race:1:grid=4;weather=rain ").map(|s| s.drivers),
+            Ok(vec![artifact(4)])
+        );
+    }
+}
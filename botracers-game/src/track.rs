@@ -1,11 +1,170 @@
 use bevy::prelude::*;
 
+use crate::track_format::SurfaceRegion;
+
 /// The computed cubic spline for the track centre line.
 #[derive(Resource)]
 pub struct TrackSpline {
     pub spline: CubicCurve<Vec2>,
 }
 
+/// Closed centre line sampled at evenly spaced spline parameters, used for
+/// lap-progress and nearest-point queries.
+#[derive(Resource, Clone)]
+pub struct TrackCenterline {
+    pub points: Vec<Vec2>,
+}
+
+impl TrackCenterline {
+    pub fn from_spline(spline: &CubicCurve<Vec2>, samples: usize) -> Self {
+        let t_max = spline.domain().end();
+        let points = (0..samples)
+            .map(|i| spline.position((i as f32 / samples as f32) * t_max))
+            .collect();
+        Self { points }
+    }
+
+    /// Index of the centre-line sample closest to `position`.
+    pub fn nearest_index(&self, position: Vec2) -> usize {
+        self.points
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    /// Progress of `position` around the lap in `[0, 1)`, where 0 is the
+    /// start/finish line at spline parameter `t = 0`.
+    pub fn lap_fraction(&self, position: Vec2) -> f32 {
+        if self.points.is_empty() {
+            return 0.0;
+        }
+        self.nearest_index(position) as f32 / self.points.len() as f32
+    }
+
+    /// Distance from `position` to the closed centre line polyline.
+    pub fn distance_to(&self, position: Vec2) -> f32 {
+        let n = self.points.len();
+        if n == 0 {
+            return f32::INFINITY;
+        }
+        let nearest = self.nearest_index(position);
+        // The closest segment always touches the closest sample.
+        [(nearest + n - 1) % n, nearest]
+            .into_iter()
+            .map(|i| distance_to_segment(position, self.points[i], self.points[(i + 1) % n]))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Signed lateral offset of `position` from the centre line: its distance
+    /// to the polyline, positive to the right and negative to the left when
+    /// facing the lap direction (increasing spline parameter).
+    pub fn signed_offset(&self, position: Vec2) -> f32 {
+        let n = self.points.len();
+        if n < 2 {
+            return 0.0;
+        }
+        let nearest = self.nearest_index(position);
+        let (distance, a, b) = [(nearest + n - 1) % n, nearest]
+            .into_iter()
+            .map(|i| {
+                let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+                (distance_to_segment(position, a, b), a, b)
+            })
+            .min_by(|x, y| x.0.total_cmp(&y.0))
+            .expect("two candidate segments");
+        if (position - a).perp_dot(b - a) < 0.0 {
+            -distance
+        } else {
+            distance
+        }
+    }
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_squared();
+    if len_sq == 0.0 {
+        return point.distance(a);
+    }
+    let t = ((point - a).dot(ab) / len_sq).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+/// Tire grip multiplier lookup for any point on or around the track.
+///
+/// Explicit surface regions win (the first one containing the point), then
+/// everything beyond the kerbs gets the track's off-track grip; the racing
+/// surface and kerbs have grip 1.
+#[derive(Resource, Clone)]
+pub struct TrackSurface {
+    centerline: TrackCenterline,
+    off_track_distance: f32,
+    off_track_grip: f32,
+    regions: Vec<(Vec<Vec2>, f32)>,
+}
+
+impl TrackSurface {
+    pub fn new(
+        centerline: TrackCenterline,
+        track_width: f32,
+        kerb_width: f32,
+        off_track_grip: f32,
+        regions: &[SurfaceRegion],
+    ) -> Self {
+        Self {
+            centerline,
+            off_track_distance: track_width / 2.0 + kerb_width,
+            off_track_grip,
+            regions: regions
+                .iter()
+                .map(|region| {
+                    let polygon = region
+                        .polygon
+                        .iter()
+                        .map(|&[x, y]| Vec2::new(x, y))
+                        .collect();
+                    (polygon, region.grip)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn grip_at(&self, position: Vec2) -> f32 {
+        if let Some((_, grip)) = self
+            .regions
+            .iter()
+            .find(|(polygon, _)| point_in_polygon(position, polygon))
+        {
+            return *grip;
+        }
+        if self.centerline.distance_to(position) > self.off_track_distance {
+            return self.off_track_grip;
+        }
+        1.0
+    }
+}
+
+/// Even-odd rule point-in-polygon test.
+fn point_in_polygon(point: Vec2, polygon: &[Vec2]) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[j];
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
 /// Build a closed cubic B-spline from control points.
 pub fn build_spline(control_points: &[Vec2]) -> CubicCurve<Vec2> {
     CubicBSpline::new(control_points.to_vec())
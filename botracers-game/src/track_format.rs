@@ -7,6 +7,37 @@ pub struct TrackFile {
     #[serde(default)]
     pub metadata: TrackMetadata,
     pub control_points: Vec<[f32; 2]>,
+    /// Areas with non-default tire grip, checked before the off-track rule.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub surfaces: Vec<SurfaceRegion>,
+    /// Solid obstacles cars bounce off, e.g. walls and tire stacks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub barriers: Vec<Barrier>,
+}
+
+/// A polygon on the ground with its own tire grip, e.g. a gravel trap or
+/// rumble strip.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SurfaceRegion {
+    #[serde(default)]
+    pub name: String,
+    /// Multiplier on tire grip for wheels inside `polygon`.
+    pub grip: f32,
+    pub polygon: Vec<[f32; 2]>,
+}
+
+/// A wall along a polyline. `restitution` and `friction` set how a car
+/// bounces off and scrapes along it: a tire barrier absorbs an impact that a
+/// concrete wall would throw back.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Barrier {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_barrier_restitution")]
+    pub restitution: f32,
+    #[serde(default = "default_barrier_friction")]
+    pub friction: f32,
+    pub points: Vec<[f32; 2]>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -19,6 +50,9 @@ pub struct TrackMetadata {
     pub track_width: f32,
     #[serde(default = "default_kerb_width")]
     pub kerb_width: f32,
+    /// Multiplier on tire grip for wheels beyond the kerbs (grass).
+    #[serde(default = "default_off_track_grip")]
+    pub off_track_grip: f32,
 }
 
 impl Default for TrackMetadata {
@@ -28,6 +62,7 @@ impl Default for TrackMetadata {
             author: String::new(),
             track_width: default_track_width(),
             kerb_width: default_kerb_width(),
+            off_track_grip: default_off_track_grip(),
         }
     }
 }
@@ -44,6 +79,18 @@ fn default_kerb_width() -> f32 {
     0.5
 }
 
+fn default_off_track_grip() -> f32 {
+    1.0
+}
+
+fn default_barrier_restitution() -> f32 {
+    0.2
+}
+
+fn default_barrier_friction() -> f32 {
+    0.1
+}
+
 impl TrackFile {
     /// Create a new empty track with default metadata.
     pub fn new_empty(name: &str) -> Self {
@@ -53,8 +100,11 @@ impl TrackFile {
                 author: String::new(),
                 track_width: default_track_width(),
                 kerb_width: default_kerb_width(),
+                off_track_grip: default_off_track_grip(),
             },
             control_points: Vec::new(),
+            surfaces: Vec::new(),
+            barriers: Vec::new(),
         }
     }
 
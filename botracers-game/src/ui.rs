@@ -1,27 +1,51 @@
+use std::cmp::{Ordering, Reverse};
+use std::path::PathBuf;
+
 use bevy::prelude::*;
+use botracers_protocol::{ArtifactId, ArtifactScope, ArtifactSummary};
 
-use crate::bootstrap::WebPortalState;
-use crate::game_api::{DriverType, SpawnCarRequest, WebApiCommand};
+use crate::bootstrap::{ArtifactFetchPipeline, LocalBotLibrary, WebPortalState};
+use crate::game_api::{
+    CancelSpawn, DriverType, RematchRequest, SpawnCarRequest, StartRaceRequest, WebApiCommand,
+};
+use crate::race_results::RaceStandings;
 use crate::race_runtime::{
-    CarLabel, CpuFrequencySetting, DebugGizmos, FollowCar, LongitudinalDebugData, RaceManager,
-    SimState,
+    BotInspector, CarCollisions, CarDimensions, CarLabel, CpuFrequencySetting, DebugGizmos,
+    FollowCar, InspectedCar, LongitudinalDebugData, RaceConfig, RaceManager, SimState,
+    TireWearModel,
 };
+use crate::race_setup::RaceSetup;
 
 pub struct BootstrapUiPlugin;
 
 impl Plugin for BootstrapUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_ui).add_systems(
-            Update,
-            (
-                handle_web_buttons,
-                update_web_status_dialog,
-                update_artifact_list_ui,
-                handle_artifact_spawn_button,
-                handle_artifact_delete_button,
-                handle_artifact_visibility_button,
-            ),
-        );
+        app.init_resource::<ArtifactSort>()
+            .add_systems(Startup, setup_ui)
+            .add_systems(
+                Update,
+                (
+                    handle_web_buttons,
+                    update_register_button_visibility,
+                    handle_dismiss_spawn_failures_button,
+                    update_web_status_dialog,
+                    update_artifact_scope_text,
+                    update_artifact_sort_text,
+                    update_artifact_list_ui,
+                    update_empty_artifacts_text,
+                    handle_artifact_spawn_button,
+                    handle_artifact_delete_button,
+                    handle_artifact_visibility_button,
+                    handle_artifact_clone_button,
+                    handle_artifact_star_button,
+                    handle_artifact_races_button,
+                    update_race_history_text,
+                    update_pending_downloads_ui,
+                    handle_cancel_spawn_button,
+                    update_local_bot_list_ui,
+                    handle_local_bot_spawn_button,
+                ),
+            );
     }
 }
 
@@ -36,13 +60,20 @@ impl Plugin for RaceRuntimeUiPlugin {
                 handle_remove_car_button,
                 handle_toggle_gizmos_button,
                 handle_follow_car_button,
+                handle_inspect_car_button,
                 handle_cpu_frequency_buttons,
+                handle_race_config_buttons,
                 handle_start_button,
                 handle_reset_button,
+                handle_rematch_button,
+                handle_share_setup_button,
                 update_console_output,
                 update_debug_telemetry_ui,
+                update_bot_inspector_ui,
                 update_cpu_frequency_text,
+                update_race_config_text,
                 update_start_button_text,
+                update_standings_text,
             ),
         );
     }
@@ -53,19 +84,58 @@ struct UiRoot;
 #[derive(Component)]
 struct StatusDialogText;
 #[derive(Component)]
+struct DismissSpawnFailuresButton;
+#[derive(Component)]
 struct ArtifactListContainer;
 #[derive(Component)]
-struct ArtifactListRow(#[allow(dead_code)] i64);
+struct ArtifactListRow(#[allow(dead_code)] ArtifactId);
+#[derive(Component)]
+struct EmptyArtifactsText;
 #[derive(Component)]
 struct RefreshArtifactsButton;
 #[derive(Component)]
 struct UploadArtifactButton;
 #[derive(Component)]
-struct SpawnArtifactButton(i64);
+struct ExportArtifactsButton;
+#[derive(Component)]
+struct LoadMoreArtifactsButton;
+#[derive(Component)]
+struct RegisterButton;
+#[derive(Component)]
+struct ArtifactScopeButton;
+#[derive(Component)]
+struct ArtifactScopeText;
+#[derive(Component)]
+struct ArtifactSortButton;
+#[derive(Component)]
+struct ArtifactSortText;
+#[derive(Component)]
+struct SpawnArtifactButton(ArtifactId);
 #[derive(Component)]
-struct DeleteArtifactButton(i64);
+struct DeleteArtifactButton(ArtifactId);
 #[derive(Component)]
-struct ToggleArtifactVisibilityButton(i64, bool);
+struct ToggleArtifactVisibilityButton(ArtifactId, bool);
+#[derive(Component)]
+struct CloneArtifactButton(ArtifactId);
+/// Stars (`true`) or unstars (`false`) the artifact for the current player.
+#[derive(Component)]
+struct StarArtifactButton(ArtifactId, bool);
+#[derive(Component)]
+struct ArtifactRacesButton(ArtifactId);
+#[derive(Component)]
+struct RaceHistoryText;
+#[derive(Component)]
+struct LocalBotListContainer;
+#[derive(Component)]
+struct LocalBotListRow;
+#[derive(Component)]
+struct SpawnLocalBotButton(PathBuf);
+#[derive(Component)]
+struct PendingDownloadsContainer;
+#[derive(Component)]
+struct PendingDownloadRow;
+#[derive(Component)]
+struct CancelSpawnButton(u64);
 #[derive(Component)]
 struct CpuFrequencyMinusButton;
 #[derive(Component)]
@@ -73,10 +143,22 @@ struct CpuFrequencyPlusButton;
 #[derive(Component)]
 struct CpuFrequencyText;
 #[derive(Component)]
+struct RaceLapsButton(bool);
+#[derive(Component)]
+struct RaceLapsText;
+#[derive(Component)]
+struct RaceTimeLimitButton(bool);
+#[derive(Component)]
+struct RaceTimeLimitText;
+#[derive(Component)]
 struct StartButton;
 #[derive(Component)]
 struct ResetButton;
 #[derive(Component)]
+struct RematchButton;
+#[derive(Component)]
+struct ShareSetupButton;
+#[derive(Component)]
 struct CarListContainer;
 #[derive(Component)]
 struct RemoveCarButton(Entity);
@@ -85,6 +167,8 @@ struct ToggleGizmosButton(Entity);
 #[derive(Component)]
 struct FollowCarButton(Entity);
 #[derive(Component)]
+struct InspectCarButton(Entity);
+#[derive(Component)]
 struct CarListRow(#[allow(dead_code)] Entity);
 #[derive(Component)]
 struct ConsoleTextContainer;
@@ -92,6 +176,12 @@ struct ConsoleTextContainer;
 struct ConsoleText;
 #[derive(Component)]
 struct DebugTelemetryText;
+#[derive(Component)]
+struct BotInspectorText;
+#[derive(Component)]
+struct StandingsText;
+
+const INSPECTOR_HINT: &str = "Inspect a car to view its bot state";
 
 const PANEL_BG: Color = Color::srgba(0.08, 0.08, 0.12, 0.92);
 const BTN_BG: Color = Color::srgb(0.25, 0.25, 0.35);
@@ -121,7 +211,11 @@ fn button_style() -> Node {
     }
 }
 
-fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
+fn setup_ui(
+    mut commands: Commands,
+    cpu_frequency: Res<CpuFrequencySetting>,
+    race_config: Res<RaceConfig>,
+) {
     commands
         .spawn((
             UiRoot,
@@ -169,6 +263,24 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
                     ));
                 });
 
+            panel
+                .spawn((
+                    Button,
+                    DismissSpawnFailuresButton,
+                    Node {
+                        display: Display::None,
+                        ..button_style()
+                    },
+                    BackgroundColor(BTN_BG),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        Text::new("Dismiss spawn errors"),
+                        text_font(14.0),
+                        TextColor(TEXT_COLOR),
+                    ));
+                });
+
             panel.spawn((
                 Text::new("Artifacts"),
                 text_font(16.0),
@@ -201,6 +313,64 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
                     .with_children(|btn| {
                         btn.spawn((Text::new("Upload"), text_font(14.0), TextColor(TEXT_COLOR)));
                     });
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    row.spawn((
+                        Button,
+                        ExportArtifactsButton,
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("Export"), text_font(14.0), TextColor(TEXT_COLOR)));
+                    });
+
+                    row.spawn((
+                        Button,
+                        RegisterButton,
+                        Node {
+                            display: Display::None,
+                            ..button_style()
+                        },
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((
+                            Text::new("Register"),
+                            text_font(14.0),
+                            TextColor(TEXT_COLOR),
+                        ));
+                    });
+
+                    row.spawn((
+                        Button,
+                        ArtifactScopeButton,
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((
+                            Text::new(artifact_scope_label(ArtifactScope::Mine)),
+                            ArtifactScopeText,
+                            text_font(14.0),
+                            TextColor(TEXT_COLOR),
+                        ));
+                    });
+
+                    row.spawn((
+                        Button,
+                        ArtifactSortButton,
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((
+                            Text::new(ArtifactSort::default().label()),
+                            ArtifactSortText,
+                            text_font(14.0),
+                            TextColor(TEXT_COLOR),
+                        ));
+                    });
                 });
 
             panel
@@ -216,6 +386,68 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
                 ))
                 .with_children(|_| {});
 
+            panel.spawn((
+                Text::new(""),
+                EmptyArtifactsText,
+                text_font(13.0),
+                TextColor(LABEL_COLOR),
+                Node {
+                    display: Display::None,
+                    ..default()
+                },
+            ));
+
+            panel
+                .spawn((
+                    Button,
+                    LoadMoreArtifactsButton,
+                    button_style(),
+                    BackgroundColor(BTN_BG),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        Text::new("Load More"),
+                        text_font(14.0),
+                        TextColor(TEXT_COLOR),
+                    ));
+                });
+
+            panel
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: px(4.0),
+                        ..default()
+                    },
+                    PendingDownloadsContainer,
+                ))
+                .with_children(|_| {});
+
+            panel
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: px(4.0),
+                        overflow: Overflow::scroll_y(),
+                        max_height: px(220.0),
+                        ..default()
+                    },
+                    LocalBotListContainer,
+                ))
+                .with_children(|_| {});
+
+            panel.spawn((
+                Text::new("Race History"),
+                text_font(16.0),
+                TextColor(LABEL_COLOR),
+            ));
+            panel.spawn((
+                Text::new("Pick \"Races\" on an artifact"),
+                RaceHistoryText,
+                text_font(12.0),
+                TextColor(TEXT_COLOR),
+            ));
+
             panel.spawn((Text::new("Race"), text_font(16.0), TextColor(LABEL_COLOR)));
 
             panel
@@ -254,6 +486,65 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
                     });
                 });
 
+            panel
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: px(6.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        Button,
+                        RaceLapsButton(false),
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("-"), text_font(16.0), TextColor(TEXT_COLOR)));
+                    });
+                    row.spawn((
+                        Text::new(format!("Laps: {}", race_config.laps)),
+                        RaceLapsText,
+                        text_font(14.0),
+                        TextColor(TEXT_COLOR),
+                    ));
+                    row.spawn((
+                        Button,
+                        RaceLapsButton(true),
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("+"), text_font(16.0), TextColor(TEXT_COLOR)));
+                    });
+
+                    row.spawn((
+                        Button,
+                        RaceTimeLimitButton(false),
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("-"), text_font(16.0), TextColor(TEXT_COLOR)));
+                    });
+                    row.spawn((
+                        Text::new(race_config.format_time_limit_label()),
+                        RaceTimeLimitText,
+                        text_font(14.0),
+                        TextColor(TEXT_COLOR),
+                    ));
+                    row.spawn((
+                        Button,
+                        RaceTimeLimitButton(true),
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("+"), text_font(16.0), TextColor(TEXT_COLOR)));
+                    });
+                });
+
             panel
                 .spawn(Node {
                     flex_direction: FlexDirection::Row,
@@ -280,6 +571,26 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
                     .with_children(|btn| {
                         btn.spawn((Text::new("Reset"), text_font(14.0), TextColor(TEXT_COLOR)));
                     });
+
+                    row.spawn((
+                        Button,
+                        RematchButton,
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("Rematch"), text_font(14.0), TextColor(TEXT_COLOR)));
+                    });
+
+                    row.spawn((
+                        Button,
+                        ShareSetupButton,
+                        button_style(),
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("Share"), text_font(14.0), TextColor(TEXT_COLOR)));
+                    });
                 });
 
             panel.spawn((Text::new("Cars"), text_font(16.0), TextColor(LABEL_COLOR)));
@@ -297,6 +608,18 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
                 ))
                 .with_children(|_| {});
 
+            panel.spawn((
+                Text::new("Standings"),
+                text_font(16.0),
+                TextColor(LABEL_COLOR),
+            ));
+            panel.spawn((
+                Text::new("No laps yet"),
+                StandingsText,
+                text_font(12.0),
+                TextColor(TEXT_COLOR),
+            ));
+
             panel.spawn((
                 Text::new("Debug Telemetry"),
                 text_font(16.0),
@@ -322,6 +645,30 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
                     ));
                 });
 
+            panel.spawn((
+                Text::new("Bot Inspector"),
+                text_font(16.0),
+                TextColor(LABEL_COLOR),
+            ));
+
+            panel
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.55)),
+                ))
+                .with_children(|container| {
+                    container.spawn((
+                        Text::new(INSPECTOR_HINT),
+                        BotInspectorText,
+                        text_font(12.0),
+                        TextColor(TEXT_COLOR),
+                    ));
+                });
+
             panel.spawn((
                 Text::new("Console"),
                 text_font(16.0),
@@ -342,9 +689,84 @@ fn setup_ui(mut commands: Commands, cpu_frequency: Res<CpuFrequencySetting>) {
         });
 }
 
+fn artifact_scope_label(scope: ArtifactScope) -> String {
+    let name = match scope {
+        ArtifactScope::Mine => "Mine",
+        ArtifactScope::Public => "Public",
+        ArtifactScope::All => "All",
+        ArtifactScope::Starred => "Starred",
+    };
+    format!("Show: {name}")
+}
+
+fn next_artifact_scope(scope: ArtifactScope) -> ArtifactScope {
+    match scope {
+        ArtifactScope::Mine => ArtifactScope::Public,
+        ArtifactScope::Public => ArtifactScope::All,
+        ArtifactScope::All => ArtifactScope::Starred,
+        ArtifactScope::Starred => ArtifactScope::Mine,
+    }
+}
+
+/// Order of the loaded artifact list. Sorting happens in the game over the
+/// pages loaded so far; the server always pages newest first.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ArtifactSort {
+    #[default]
+    Newest,
+    Wins,
+    Races,
+    BestLap,
+}
+
+impl ArtifactSort {
+    fn next(self) -> Self {
+        match self {
+            Self::Newest => Self::Wins,
+            Self::Wins => Self::Races,
+            Self::Races => Self::BestLap,
+            Self::BestLap => Self::Newest,
+        }
+    }
+
+    fn label(self) -> String {
+        let name = match self {
+            Self::Newest => "Newest",
+            Self::Wins => "Wins",
+            Self::Races => "Races",
+            Self::BestLap => "Best lap",
+        };
+        format!("Sort: {name}")
+    }
+
+    /// Stable, so ties keep the server's newest-first order; artifacts that
+    /// never completed a lap sort after those that did.
+    fn apply(self, artifacts: &mut [&ArtifactSummary]) {
+        match self {
+            Self::Newest => {}
+            Self::Wins => artifacts.sort_by_key(|a| Reverse(a.wins)),
+            Self::Races => artifacts.sort_by_key(|a| Reverse(a.races_count)),
+            Self::BestLap => artifacts.sort_by(|a, b| match (a.best_lap_secs, b.best_lap_secs) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_web_buttons(
     refresh_query: Query<&Interaction, (Changed<Interaction>, With<RefreshArtifactsButton>)>,
     upload_query: Query<&Interaction, (Changed<Interaction>, With<UploadArtifactButton>)>,
+    export_query: Query<&Interaction, (Changed<Interaction>, With<ExportArtifactsButton>)>,
+    scope_query: Query<&Interaction, (Changed<Interaction>, With<ArtifactScopeButton>)>,
+    load_more_query: Query<&Interaction, (Changed<Interaction>, With<LoadMoreArtifactsButton>)>,
+    register_query: Query<&Interaction, (Changed<Interaction>, With<RegisterButton>)>,
+    sort_query: Query<&Interaction, (Changed<Interaction>, With<ArtifactSortButton>)>,
+    web_state: Res<WebPortalState>,
+    mut sort: ResMut<ArtifactSort>,
     mut web_commands: MessageWriter<WebApiCommand>,
 ) {
     for interaction in &refresh_query {
@@ -353,38 +775,413 @@ fn handle_web_buttons(
         }
     }
 
+    for interaction in &load_more_query {
+        if *interaction == Interaction::Pressed {
+            web_commands.write(WebApiCommand::LoadMoreArtifacts);
+        }
+    }
+
     for interaction in &upload_query {
         if *interaction == Interaction::Pressed {
             web_commands.write(WebApiCommand::UploadArtifact);
         }
     }
+
+    for interaction in &export_query {
+        if *interaction == Interaction::Pressed {
+            web_commands.write(WebApiCommand::ExportArtifacts);
+        }
+    }
+
+    for interaction in &scope_query {
+        if *interaction == Interaction::Pressed {
+            web_commands.write(WebApiCommand::SetArtifactScope {
+                scope: next_artifact_scope(web_state.artifact_scope),
+            });
+        }
+    }
+
+    for interaction in &sort_query {
+        if *interaction == Interaction::Pressed {
+            *sort = sort.next();
+        }
+    }
+
+    for interaction in &register_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some((username, password)) = register_credentials(&web_state) {
+            web_commands.write(WebApiCommand::Register { username, password });
+        }
+    }
+}
+
+/// Credentials to register with, when the server allows self-registration and
+/// the player is not logged in yet. Only the native game prompts for them.
+fn register_credentials(web_state: &WebPortalState) -> Option<(String, String)> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if web_state.registration_enabled
+        && web_state.auth_required == Some(true)
+        && web_state.token.is_none()
+    {
+        return web_state.cli_credentials.clone();
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = web_state;
+    None
+}
+
+fn update_register_button_visibility(
+    web_state: Res<WebPortalState>,
+    mut button_query: Query<&mut Node, With<RegisterButton>>,
+) {
+    if !web_state.is_changed() {
+        return;
+    }
+
+    let display = if register_credentials(&web_state).is_some() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for mut node in &mut button_query {
+        node.display = display;
+    }
+}
+
+/// Clears the failed spawns listed under the status and hides the button
+/// until the next failure.
+fn handle_dismiss_spawn_failures_button(
+    interaction_query: Query<
+        &Interaction,
+        (Changed<Interaction>, With<DismissSpawnFailuresButton>),
+    >,
+    mut button_query: Query<&mut Node, With<DismissSpawnFailuresButton>>,
+    mut web_state: ResMut<WebPortalState>,
+) {
+    if interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        web_state.spawn_failures.clear();
+    }
+    if !web_state.is_changed() {
+        return;
+    }
+
+    let display = if web_state.spawn_failures.is_empty() {
+        Display::None
+    } else {
+        Display::Flex
+    };
+    for mut node in &mut button_query {
+        node.display = display;
+    }
+}
+
+fn update_artifact_scope_text(
+    web_state: Res<WebPortalState>,
+    mut text_query: Query<&mut Text, With<ArtifactScopeText>>,
+) {
+    if !web_state.is_changed() {
+        return;
+    }
+
+    for mut text in &mut text_query {
+        text.0 = artifact_scope_label(web_state.artifact_scope);
+    }
+}
+
+fn update_artifact_sort_text(
+    sort: Res<ArtifactSort>,
+    mut text_query: Query<&mut Text, With<ArtifactSortText>>,
+) {
+    if !sort.is_changed() {
+        return;
+    }
+
+    for mut text in &mut text_query {
+        text.0 = sort.label();
+    }
+}
+
+fn update_web_status_dialog(
+    web_state: Res<WebPortalState>,
+    mut text_query: Query<&mut Text, With<StatusDialogText>>,
+) {
+    if !web_state.is_changed() {
+        return;
+    }
+
+    let mut status = web_state
+        .status_message
+        .clone()
+        .unwrap_or_else(|| "[status] idle".to_string());
+    if let Some(warning) = &web_state.version_warning {
+        status = format!("{warning}\n{status}");
+    }
+    for failure in &web_state.spawn_failures {
+        status.push_str(&format!(
+            "\n[error][spawn] {}: {}",
+            failure.driver, failure.error
+        ));
+    }
+
+    for mut text in &mut text_query {
+        text.0 = status.clone();
+    }
+}
+
+/// Shows what to do instead of an empty artifact list, once the server has
+/// answered that there is nothing to list.
+fn update_empty_artifacts_text(
+    web_state: Res<WebPortalState>,
+    mut text_query: Query<(&mut Text, &mut Node), With<EmptyArtifactsText>>,
+) {
+    if !web_state.is_changed() {
+        return;
+    }
+
+    for (mut text, mut node) in &mut text_query {
+        if web_state.artifacts_empty {
+            text.0 = web_state.empty_artifacts_hint();
+            node.display = Display::Flex;
+        } else {
+            node.display = Display::None;
+        }
+    }
+}
+
+fn update_artifact_list_ui(
+    web_state: Res<WebPortalState>,
+    sort: Res<ArtifactSort>,
+    mut commands: Commands,
+    container_query: Query<Entity, With<ArtifactListContainer>>,
+    existing_rows: Query<Entity, With<ArtifactListRow>>,
+) {
+    if !web_state.is_changed() && !sort.is_changed() {
+        return;
+    }
+
+    let Ok(container) = container_query.single() else {
+        return;
+    };
+
+    for row_entity in &existing_rows {
+        commands.entity(row_entity).despawn();
+    }
+
+    let mut artifacts: Vec<&ArtifactSummary> = web_state.artifacts.iter().collect();
+    sort.apply(&mut artifacts);
+
+    for artifact in artifacts {
+        let artifact_id = artifact.id;
+        let visibility = if artifact.is_public {
+            "public"
+        } else {
+            "private"
+        };
+        let mut label = format!(
+            "{} [#{}] by {} ({})",
+            artifact.name, artifact.id, artifact.owner_username, visibility
+        );
+        if let Some(build_id) = &artifact.build_id {
+            label.push_str(&format!(" @ {build_id}"));
+        }
+        if artifact.races_count > 0 {
+            label.push_str(&format!(
+                " - {} wins / {} races",
+                artifact.wins, artifact.races_count
+            ));
+        }
+        if let Some(best_lap) = artifact.best_lap_secs {
+            label.push_str(&format!(", best lap {best_lap:.2}s"));
+        }
+
+        commands.entity(container).with_children(|list| {
+            list.spawn((
+                ArtifactListRow(artifact_id),
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: px(4.0),
+                    padding: UiRect::axes(px(4.0), px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.15, 0.15, 0.2, 0.8)),
+            ))
+            .with_children(|row| {
+                row.spawn((
+                    Text::new(label),
+                    text_font(13.0),
+                    TextColor(TEXT_COLOR),
+                    Node {
+                        flex_grow: 1.0,
+                        ..default()
+                    },
+                ));
+
+                row.spawn((
+                    Button,
+                    SpawnArtifactButton(artifact_id),
+                    Node {
+                        padding: UiRect::axes(px(6.0), px(2.0)),
+                        ..default()
+                    },
+                    BackgroundColor(BTN_BG),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((Text::new("Spawn"), text_font(12.0), TextColor(TEXT_COLOR)));
+                });
+
+                row.spawn((
+                    Button,
+                    ArtifactRacesButton(artifact_id),
+                    Node {
+                        padding: UiRect::axes(px(6.0), px(2.0)),
+                        ..default()
+                    },
+                    BackgroundColor(BTN_BG),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((Text::new("Races"), text_font(12.0), TextColor(TEXT_COLOR)));
+                });
+
+                row.spawn((
+                    Button,
+                    StarArtifactButton(artifact_id, !artifact.starred),
+                    Node {
+                        padding: UiRect::axes(px(6.0), px(2.0)),
+                        ..default()
+                    },
+                    BackgroundColor(BTN_BG),
+                ))
+                .with_children(|btn| {
+                    let text = if artifact.starred { "Unstar" } else { "Star" };
+                    btn.spawn((Text::new(text), text_font(12.0), TextColor(TEXT_COLOR)));
+                });
+
+                if artifact.owned_by_me {
+                    row.spawn((
+                        Button,
+                        ToggleArtifactVisibilityButton(artifact_id, !artifact.is_public),
+                        Node {
+                            padding: UiRect::axes(px(6.0), px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        let text = if artifact.is_public {
+                            "Make Private"
+                        } else {
+                            "Make Public"
+                        };
+                        btn.spawn((Text::new(text), text_font(12.0), TextColor(TEXT_COLOR)));
+                    });
+
+                    row.spawn((
+                        Button,
+                        DeleteArtifactButton(artifact_id),
+                        Node {
+                            padding: UiRect::axes(px(6.0), px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(RESET_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("Delete"), text_font(12.0), TextColor(TEXT_COLOR)));
+                    });
+                } else {
+                    row.spawn((
+                        Button,
+                        CloneArtifactButton(artifact_id),
+                        Node {
+                            padding: UiRect::axes(px(6.0), px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(BTN_BG),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new("Clone"), text_font(12.0), TextColor(TEXT_COLOR)));
+                    });
+                }
+            });
+        });
+    }
 }
 
-fn update_web_status_dialog(
-    web_state: Res<WebPortalState>,
-    mut text_query: Query<&mut Text, With<StatusDialogText>>,
+fn update_pending_downloads_ui(
+    fetch_pipeline: Res<ArtifactFetchPipeline>,
+    mut commands: Commands,
+    container_query: Query<Entity, With<PendingDownloadsContainer>>,
+    existing_rows: Query<Entity, With<PendingDownloadRow>>,
 ) {
-    if !web_state.is_changed() {
+    if !fetch_pipeline.is_changed() {
         return;
     }
 
-    let status = web_state
-        .status_message
-        .as_deref()
-        .unwrap_or("[status] idle");
+    let Ok(container) = container_query.single() else {
+        return;
+    };
 
-    for mut text in &mut text_query {
-        text.0 = status.to_string();
+    for row_entity in &existing_rows {
+        commands.entity(row_entity).despawn();
+    }
+
+    let mut pending: Vec<_> = fetch_pipeline.pending.iter().collect();
+    pending.sort_by_key(|(request_id, _)| **request_id);
+    for (&request_id, driver) in pending {
+        let label = format!("Downloading {}...", driver.label());
+        commands.entity(container).with_children(|list| {
+            list.spawn((
+                PendingDownloadRow,
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: px(4.0),
+                    padding: UiRect::axes(px(4.0), px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.15, 0.15, 0.2, 0.8)),
+            ))
+            .with_children(|row| {
+                row.spawn((
+                    Text::new(label),
+                    text_font(13.0),
+                    TextColor(TEXT_COLOR),
+                    Node {
+                        flex_grow: 1.0,
+                        ..default()
+                    },
+                ));
+
+                row.spawn((
+                    Button,
+                    CancelSpawnButton(request_id),
+                    Node {
+                        padding: UiRect::axes(px(6.0), px(2.0)),
+                        ..default()
+                    },
+                    BackgroundColor(RESET_BG),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((Text::new("Cancel"), text_font(12.0), TextColor(TEXT_COLOR)));
+                });
+            });
+        });
     }
 }
 
-fn update_artifact_list_ui(
-    web_state: Res<WebPortalState>,
+/// Lists the `--local-bots` directory; stays empty when playing online.
+fn update_local_bot_list_ui(
+    local_bots: Res<LocalBotLibrary>,
     mut commands: Commands,
-    container_query: Query<Entity, With<ArtifactListContainer>>,
-    existing_rows: Query<Entity, With<ArtifactListRow>>,
+    container_query: Query<Entity, With<LocalBotListContainer>>,
+    existing_rows: Query<Entity, With<LocalBotListRow>>,
 ) {
-    if !web_state.is_changed() {
+    if !local_bots.is_changed() {
         return;
     }
 
@@ -396,21 +1193,27 @@ fn update_artifact_list_ui(
         commands.entity(row_entity).despawn();
     }
 
-    for artifact in &web_state.artifacts {
-        let artifact_id = artifact.id;
-        let visibility = if artifact.is_public {
-            "public"
-        } else {
-            "private"
-        };
-        let label = format!(
-            "{} [#{}] by {} ({})",
-            artifact.name, artifact.id, artifact.owner_username, visibility
-        );
+    let Some(dir) = &local_bots.dir else {
+        return;
+    };
+    commands.entity(container).with_children(|list| {
+        list.spawn((
+            LocalBotListRow,
+            Text::new(format!("Local Bots ({})", dir.display())),
+            text_font(16.0),
+            TextColor(LABEL_COLOR),
+        ));
+    });
 
+    for driver in &local_bots.drivers {
+        let DriverType::LocalFile { path } = driver else {
+            continue;
+        };
+        let path = path.clone();
+        let label = driver.label();
         commands.entity(container).with_children(|list| {
             list.spawn((
-                ArtifactListRow(artifact_id),
+                LocalBotListRow,
                 Node {
                     flex_direction: FlexDirection::Row,
                     align_items: AlignItems::Center,
@@ -433,7 +1236,7 @@ fn update_artifact_list_ui(
 
                 row.spawn((
                     Button,
-                    SpawnArtifactButton(artifact_id),
+                    SpawnLocalBotButton(path),
                     Node {
                         padding: UiRect::axes(px(6.0), px(2.0)),
                         ..default()
@@ -443,41 +1246,41 @@ fn update_artifact_list_ui(
                 .with_children(|btn| {
                     btn.spawn((Text::new("Spawn"), text_font(12.0), TextColor(TEXT_COLOR)));
                 });
+            });
+        });
+    }
+}
 
-                if artifact.owned_by_me {
-                    row.spawn((
-                        Button,
-                        ToggleArtifactVisibilityButton(artifact_id, !artifact.is_public),
-                        Node {
-                            padding: UiRect::axes(px(6.0), px(2.0)),
-                            ..default()
-                        },
-                        BackgroundColor(BTN_BG),
-                    ))
-                    .with_children(|btn| {
-                        let text = if artifact.is_public {
-                            "Make Private"
-                        } else {
-                            "Make Public"
-                        };
-                        btn.spawn((Text::new(text), text_font(12.0), TextColor(TEXT_COLOR)));
-                    });
+fn handle_local_bot_spawn_button(
+    query: Query<(&Interaction, &SpawnLocalBotButton), Changed<Interaction>>,
+    mut spawn_events: MessageWriter<SpawnCarRequest>,
+    state: Res<State<SimState>>,
+) {
+    if *state.get() != SimState::PreRace {
+        return;
+    }
 
-                    row.spawn((
-                        Button,
-                        DeleteArtifactButton(artifact_id),
-                        Node {
-                            padding: UiRect::axes(px(6.0), px(2.0)),
-                            ..default()
-                        },
-                        BackgroundColor(RESET_BG),
-                    ))
-                    .with_children(|btn| {
-                        btn.spawn((Text::new("Delete"), text_font(12.0), TextColor(TEXT_COLOR)));
-                    });
-                }
+    for (interaction, spawn_btn) in &query {
+        if *interaction == Interaction::Pressed {
+            spawn_events.write(SpawnCarRequest {
+                driver: DriverType::LocalFile {
+                    path: spawn_btn.0.clone(),
+                },
             });
-        });
+        }
+    }
+}
+
+fn handle_cancel_spawn_button(
+    query: Query<(&Interaction, &CancelSpawnButton), Changed<Interaction>>,
+    mut cancel_events: MessageWriter<CancelSpawn>,
+) {
+    for (interaction, cancel_btn) in &query {
+        if *interaction == Interaction::Pressed {
+            cancel_events.write(CancelSpawn {
+                request_id: cancel_btn.0,
+            });
+        }
     }
 }
 
@@ -534,16 +1337,95 @@ fn handle_artifact_visibility_button(
     }
 }
 
+fn handle_artifact_clone_button(
+    query: Query<(&Interaction, &CloneArtifactButton), Changed<Interaction>>,
+    mut web_commands: MessageWriter<WebApiCommand>,
+    state: Res<State<SimState>>,
+) {
+    if *state.get() != SimState::PreRace {
+        return;
+    }
+
+    for (interaction, clone_btn) in &query {
+        if *interaction == Interaction::Pressed {
+            web_commands.write(WebApiCommand::CloneArtifact { id: clone_btn.0 });
+        }
+    }
+}
+
+fn handle_artifact_star_button(
+    query: Query<(&Interaction, &StarArtifactButton), Changed<Interaction>>,
+    mut web_commands: MessageWriter<WebApiCommand>,
+    state: Res<State<SimState>>,
+) {
+    if *state.get() != SimState::PreRace {
+        return;
+    }
+
+    for (interaction, star_btn) in &query {
+        if *interaction == Interaction::Pressed {
+            web_commands.write(WebApiCommand::SetArtifactStar {
+                id: star_btn.0,
+                starred: star_btn.1,
+            });
+        }
+    }
+}
+
+fn handle_artifact_races_button(
+    query: Query<(&Interaction, &ArtifactRacesButton), Changed<Interaction>>,
+    mut web_commands: MessageWriter<WebApiCommand>,
+) {
+    for (interaction, races_btn) in &query {
+        if *interaction == Interaction::Pressed {
+            web_commands.write(WebApiCommand::LoadArtifactRaces { id: races_btn.0 });
+        }
+    }
+}
+
+fn update_race_history_text(
+    web_state: Res<WebPortalState>,
+    mut text_query: Query<&mut Text, With<RaceHistoryText>>,
+) {
+    if !web_state.is_changed() {
+        return;
+    }
+
+    let Some(artifact_id) = web_state.race_history_artifact else {
+        return;
+    };
+    let content = if web_state.race_history.is_empty() {
+        format!("Artifact #{artifact_id} has not raced yet")
+    } else {
+        let mut lines = vec![format!("Artifact #{artifact_id}, most recent first:")];
+        for race in &web_state.race_history {
+            let best = race
+                .best_lap_secs
+                .map_or_else(|| "-".to_string(), |lap| format!("{lap:.2}s"));
+            lines.push(format!(
+                "Race #{}: P{}/{}  L{}  best {best}",
+                race.race_id, race.position, race.car_count, race.laps_completed
+            ));
+        }
+        lines.join("\n")
+    };
+
+    for mut text in &mut text_query {
+        text.0 = content.clone();
+    }
+}
+
 fn handle_start_button(
     query: Query<&Interaction, (Changed<Interaction>, With<StartButton>)>,
     current_state: Res<State<SimState>>,
     mut next_state: ResMut<NextState<SimState>>,
+    mut start_events: MessageWriter<StartRaceRequest>,
 ) {
     for interaction in &query {
         if *interaction == Interaction::Pressed {
             match current_state.get() {
                 SimState::PreRace => {
-                    next_state.set(SimState::Racing);
+                    start_events.write(StartRaceRequest);
                 }
                 SimState::Racing => {
                     next_state.set(SimState::Paused);
@@ -551,6 +1433,7 @@ fn handle_start_button(
                 SimState::Paused => {
                     next_state.set(SimState::Racing);
                 }
+                SimState::Finished => {}
             }
         }
     }
@@ -571,6 +1454,7 @@ fn update_start_button_text(
                     SimState::PreRace => "Start".into(),
                     SimState::Racing => "Pause".into(),
                     SimState::Paused => "Resume".into(),
+                    SimState::Finished => "Finished".into(),
                 };
             }
         }
@@ -596,6 +1480,49 @@ fn handle_reset_button(
     }
 }
 
+fn handle_rematch_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<RematchButton>)>,
+    mut rematch_events: MessageWriter<RematchRequest>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            rematch_events.write(RematchRequest);
+        }
+    }
+}
+
+/// Shows the current grid, race length, car size, collisions and tire wear
+/// as a `--race=<code>` setup in the status line (and the log, for copying).
+fn handle_share_setup_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<ShareSetupButton>)>,
+    manager: Res<RaceManager>,
+    race_config: Res<RaceConfig>,
+    dimensions: Res<CarDimensions>,
+    collisions: Res<CarCollisions>,
+    tire_wear: Res<TireWearModel>,
+    mut web_state: ResMut<WebPortalState>,
+) {
+    for interaction in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if manager.cars.is_empty() {
+            web_state.status_message = Some("Nothing to share: the grid is empty".to_string());
+            continue;
+        }
+        let code = RaceSetup {
+            drivers: manager.cars.iter().map(|car| car.driver.clone()).collect(),
+            config: *race_config,
+            car_scale: dimensions.scale(),
+            collisions: *collisions,
+            tire_wear: *tire_wear,
+        }
+        .encode();
+        info!("Race setup: --race={code}");
+        web_state.status_message = Some(format!("Race setup: {code}"));
+    }
+}
+
 fn handle_remove_car_button(
     query: Query<(&Interaction, &RemoveCarButton), Changed<Interaction>>,
     mut manager: ResMut<RaceManager>,
@@ -637,6 +1564,7 @@ fn handle_follow_car_button(
 ) {
     for (interaction, follow_btn) in &query {
         if *interaction == Interaction::Pressed {
+            follow.leader = false;
             if follow.target == Some(follow_btn.0) {
                 follow.target = None;
             } else {
@@ -646,6 +1574,21 @@ fn handle_follow_car_button(
     }
 }
 
+fn handle_inspect_car_button(
+    query: Query<(&Interaction, &InspectCarButton), Changed<Interaction>>,
+    mut inspected: ResMut<InspectedCar>,
+) {
+    for (interaction, inspect_btn) in &query {
+        if *interaction == Interaction::Pressed {
+            if inspected.target == Some(inspect_btn.0) {
+                inspected.target = None;
+            } else {
+                inspected.target = Some(inspect_btn.0);
+            }
+        }
+    }
+}
+
 fn handle_cpu_frequency_buttons(
     minus_query: Query<&Interaction, (Changed<Interaction>, With<CpuFrequencyMinusButton>)>,
     plus_query: Query<&Interaction, (Changed<Interaction>, With<CpuFrequencyPlusButton>)>,
@@ -664,6 +1607,44 @@ fn handle_cpu_frequency_buttons(
     }
 }
 
+/// The race length can only change before the start.
+fn handle_race_config_buttons(
+    laps_query: Query<(&Interaction, &RaceLapsButton), Changed<Interaction>>,
+    limit_query: Query<(&Interaction, &RaceTimeLimitButton), Changed<Interaction>>,
+    state: Res<State<SimState>>,
+    mut race_config: ResMut<RaceConfig>,
+) {
+    if *state.get() != SimState::PreRace {
+        return;
+    }
+    for (interaction, button) in &laps_query {
+        if *interaction == Interaction::Pressed {
+            race_config.step_laps(button.0);
+        }
+    }
+    for (interaction, button) in &limit_query {
+        if *interaction == Interaction::Pressed {
+            race_config.step_time_limit(button.0);
+        }
+    }
+}
+
+fn update_race_config_text(
+    race_config: Res<RaceConfig>,
+    mut laps_query: Query<&mut Text, (With<RaceLapsText>, Without<RaceTimeLimitText>)>,
+    mut limit_query: Query<&mut Text, (With<RaceTimeLimitText>, Without<RaceLapsText>)>,
+) {
+    if !race_config.is_changed() {
+        return;
+    }
+    for mut text in &mut laps_query {
+        text.0 = format!("Laps: {}", race_config.laps);
+    }
+    for mut text in &mut limit_query {
+        text.0 = race_config.format_time_limit_label();
+    }
+}
+
 fn update_cpu_frequency_text(
     cpu_frequency: Res<CpuFrequencySetting>,
     mut text_query: Query<&mut Text, With<CpuFrequencyText>>,
@@ -677,6 +1658,46 @@ fn update_cpu_frequency_text(
     }
 }
 
+fn update_standings_text(
+    standings: Res<RaceStandings>,
+    mut text_query: Query<&mut Text, With<StandingsText>>,
+) {
+    if !standings.is_changed() {
+        return;
+    }
+
+    let content = if standings.rows.is_empty() {
+        "No laps yet".to_string()
+    } else {
+        standings
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let best = row
+                    .progress
+                    .best_lap
+                    .map_or_else(|| "-".to_string(), |lap| format!("{lap:.2}s"));
+                let trap = row.trap.map_or_else(String::new, |trap| {
+                    format!("  DNF {} @0x{:08x}", trap.kind(), trap.pc())
+                });
+                format!(
+                    "P{}  {}  L{}  best {best}{trap}",
+                    index + 1,
+                    row.name,
+                    row.progress.laps_completed
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    for mut text in &mut text_query {
+        text.0 = content.clone();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn update_car_list_ui(
     manager: Res<RaceManager>,
     mut commands: Commands,
@@ -686,9 +1707,10 @@ fn update_car_list_ui(
     added_gizmos: Query<(), Added<DebugGizmos>>,
     mut removed_gizmos: RemovedComponents<DebugGizmos>,
     follow: Res<FollowCar>,
+    inspected: Res<InspectedCar>,
 ) {
     let gizmos_changed = !added_gizmos.is_empty() || removed_gizmos.read().next().is_some();
-    if !manager.is_changed() && !follow.is_changed() && !gizmos_changed {
+    if !manager.is_changed() && !follow.is_changed() && !inspected.is_changed() && !gizmos_changed {
         return;
     }
 
@@ -704,6 +1726,7 @@ fn update_car_list_ui(
         let entity = entry.entity;
         let has_gizmos = gizmo_query.get(entity).is_ok();
         let is_followed = follow.target == Some(entity);
+        let is_inspected = inspected.target == Some(entity);
         let driver_label = entry.driver.label();
 
         commands.entity(container).with_children(|list| {
@@ -722,7 +1745,7 @@ fn update_car_list_ui(
                 row.spawn((
                     Text::new(format!("{} [{}]", entry.name, driver_label)),
                     text_font(13.0),
-                    TextColor(TEXT_COLOR),
+                    TextColor(entry.color),
                     Node {
                         flex_grow: 1.0,
                         ..default()
@@ -747,6 +1770,24 @@ fn update_car_list_ui(
                     btn.spawn((Text::new("Follow"), text_font(12.0), TextColor(TEXT_COLOR)));
                 });
 
+                let inspect_bg = if is_inspected {
+                    Color::srgb(0.2, 0.5, 0.7)
+                } else {
+                    BTN_BG
+                };
+                row.spawn((
+                    Button,
+                    InspectCarButton(entity),
+                    Node {
+                        padding: UiRect::axes(px(6.0), px(2.0)),
+                        ..default()
+                    },
+                    BackgroundColor(inspect_bg),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((Text::new("Inspect"), text_font(12.0), TextColor(TEXT_COLOR)));
+                });
+
                 let gizmo_bg = if has_gizmos {
                     Color::srgb(0.2, 0.6, 0.3)
                 } else {
@@ -801,7 +1842,7 @@ fn update_debug_telemetry_ui(
                         "engine: {:.0} rpm | wheel: {:.0} rpm | clutch: {:.2}\n",
                         "throttle: {:.2} | brake: {:.2}\n",
                         "Teng: {:.1} Nm | Tdrive: {:.1} Nm | Tbrake: {:.1} Nm\n",
-                        "Fdrive: {:.1} N | Fbrake: {:.1} N | Frr: {:.1} N | Fdrag: {:.1} N\n",
+                        "Fdrive: {:.1} N | Fbrake: {:.1} N (F {:.1} / R {:.1}) | Frr: {:.1} N | Fdrag: {:.1} N\n",
                         "Fraw: {:.1} N | Fclamp: {:.1} N | Fmax: {:.1} N\n",
                         "a: {:.2} m/s^2"
                     ),
@@ -818,6 +1859,8 @@ fn update_debug_telemetry_ui(
                     telemetry.t_brake_axle,
                     telemetry.f_drive,
                     telemetry.f_brake,
+                    telemetry.f_brake_front,
+                    telemetry.f_brake_rear,
                     telemetry.f_rr,
                     telemetry.f_drag,
                     telemetry.f_raw,
@@ -835,6 +1878,38 @@ fn update_debug_telemetry_ui(
     text.0 = message;
 }
 
+fn update_bot_inspector_ui(
+    inspector: BotInspector,
+    mut text_query: Query<&mut Text, With<BotInspectorText>>,
+) {
+    let Ok(mut text) = text_query.single_mut() else {
+        return;
+    };
+
+    let Some(inspection) = inspector.inspect() else {
+        text.0 = INSPECTOR_HINT.to_string();
+        return;
+    };
+    let status = match inspection.trap {
+        Some(trap) => format!("HALTED: {trap}"),
+        None => "running".to_string(),
+    };
+    let telemetry = &inspection.telemetry;
+    let log = if inspection.log_tail.is_empty() {
+        "(no output)".to_string()
+    } else {
+        inspection.log_tail.join("\n")
+    };
+    text.0 = format!(
+        "{} [{status}]\nv: {:.2} m/s | engine: {:.0} rpm | throttle: {:.2} | brake: {:.2}\n{log}",
+        inspection.name,
+        telemetry.speed_mps,
+        telemetry.engine_rpm,
+        telemetry.throttle,
+        telemetry.brake,
+    );
+}
+
 fn update_console_output(
     mut manager: ResMut<RaceManager>,
     mut cpu_query: Query<(&CarLabel, &mut emulator::cpu::LogDevice)>,
@@ -849,7 +1924,9 @@ fn update_console_output(
             if let Some(entry) = manager.cars.iter_mut().find(|c| c.name == label.name) {
                 entry.console_output.push_str(&output);
                 if entry.console_output.len() > 8192 {
-                    let start = entry.console_output.len() - 4096;
+                    let start = entry
+                        .console_output
+                        .ceil_char_boundary(entry.console_output.len() - 4096);
                     let trimmed = entry.console_output[start..].to_string();
                     entry.console_output = trimmed;
                 }
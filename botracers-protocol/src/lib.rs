@@ -1,7 +1,65 @@
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+mod replay_ticks;
+
+pub use replay_ticks::ReplayTicks;
+
 pub const API_VERSION: &str = "v1";
 
+/// Version of the bot ABI (slot layout and register semantics) this game
+/// provides. Bump it together with `botracers_bot_sdk::ABI_VERSION` whenever a
+/// change would make an older bot misbehave.
+pub const BOT_ABI_VERSION: u32 = 1;
+/// Bot ABI versions this game runs; the server refuses uploads built for any
+/// other.
+pub const SUPPORTED_BOT_ABI_VERSIONS: &[u32] = &[BOT_ABI_VERSION];
+/// ELF section in which the SDK stores the ABI version a bot was built for,
+/// as a little-endian `u32`.
+pub const BOT_ABI_SECTION: &str = ".botracers.abi";
+/// ELF section in which `botracers_bot_sdk::embed_build_id!` stores the
+/// bot's build id (e.g. a git commit) as UTF-8.
+pub const BOT_BUILD_ID_SECTION: &str = ".botracers.build_id";
+/// Longest build id the server stores.
+pub const MAX_BUILD_ID_LEN: usize = 128;
+
+/// Server-assigned artifact id, serialized as a bare integer.
+///
+/// Kept apart from the game's download request ids so the two cannot be
+/// mixed up when a fetch resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ArtifactId(pub i64);
+
+impl From<i64> for ArtifactId {
+    fn from(id: i64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<ArtifactId> for i64 {
+    fn from(id: ArtifactId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for ArtifactId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for ArtifactId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -13,12 +71,39 @@ pub struct UserInfo {
     pub username: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+}
+
+/// A minted API key. `key` is only ever returned here; listings omit it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApiKeyResponse {
+    pub id: i64,
+    pub name: String,
+    pub key: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeySummary {
+    pub id: i64,
+    pub name: String,
+    pub created_at: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegisterRequest {
     pub username: String,
     pub password: String,
 }
 
+/// Body of `PATCH /api/v1/users/me`; answered with the updated `UserInfo`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameUserRequest {
+    pub username: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginRequest {
     pub username: String,
@@ -37,15 +122,93 @@ pub struct ServerCapabilities {
     pub mode: String,
     #[serde(default = "default_registration_enabled")]
     pub registration_enabled: bool,
+    /// Visibility given to uploads that do not set `is_public`.
+    #[serde(default)]
+    pub default_artifact_public: bool,
+    /// Largest ELF accepted on upload, in bytes. `None` from servers that do
+    /// not report a limit.
+    #[serde(default)]
+    pub max_artifact_bytes: Option<u64>,
+    /// Artifact targets accepted on upload.
+    #[serde(default)]
+    pub allowed_targets: Vec<String>,
+    /// Whether the server can build bots from source.
+    #[serde(default)]
+    pub compile_supported: bool,
+    /// Server version, e.g. `0.1.0`; empty from servers that predate it.
+    #[serde(default)]
+    pub version: String,
+    /// Request header that carries the session token.
+    #[serde(default = "default_auth_header")]
+    pub auth_header: String,
+    /// Scheme placed before the token in `auth_header`; empty sends the bare
+    /// token.
+    #[serde(default = "default_auth_scheme")]
+    pub auth_scheme: String,
+    /// Bot ABI versions accepted on upload ([`SUPPORTED_BOT_ABI_VERSIONS`]);
+    /// empty from servers that predate the check.
+    #[serde(default)]
+    pub supported_abi_versions: Vec<u32>,
+    /// Most entries a race submission may carry. `None` from servers that do
+    /// not report a limit.
+    #[serde(default)]
+    pub max_field_size: Option<u32>,
 }
 
+/// Header used for session tokens unless the server says otherwise.
+pub const DEFAULT_AUTH_HEADER: &str = "Authorization";
+/// Token scheme used unless the server says otherwise.
+pub const DEFAULT_AUTH_SCHEME: &str = "Bearer";
+
 fn default_registration_enabled() -> bool {
     true
 }
 
+fn default_auth_header() -> String {
+    DEFAULT_AUTH_HEADER.to_string()
+}
+
+fn default_auth_scheme() -> String {
+    DEFAULT_AUTH_SCHEME.to_string()
+}
+
+fn default_car_scale() -> f32 {
+    1.0
+}
+
+fn default_cars_collide() -> bool {
+    true
+}
+
+/// Which artifacts a list request should return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactScope {
+    /// Only artifacts owned by the caller.
+    Mine,
+    /// Only public artifacts, regardless of owner.
+    Public,
+    /// Own artifacts plus other users' public artifacts.
+    #[default]
+    All,
+    /// Artifacts the caller starred and can still download.
+    Starred,
+}
+
+impl ArtifactScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Mine => "mine",
+            Self::Public => "public",
+            Self::All => "all",
+            Self::Starred => "starred",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArtifactSummary {
-    pub id: i64,
+    pub id: ArtifactId,
     pub owner_user_id: i64,
     pub owner_username: String,
     pub name: String,
@@ -53,7 +216,33 @@ pub struct ArtifactSummary {
     pub target: String,
     pub is_public: bool,
     pub owned_by_me: bool,
+    /// Whether the caller starred this artifact.
+    #[serde(default)]
+    pub starred: bool,
+    /// Bot ABI version found in the uploaded ELF ([`BOT_ABI_SECTION`]);
+    /// `None` for images built before the SDK recorded it.
+    #[serde(default)]
+    pub abi_version: Option<u32>,
+    /// Which source produced the ELF (e.g. a git commit), from the upload
+    /// request or else [`BOT_BUILD_ID_SECTION`]; `None` when unknown.
+    #[serde(default)]
+    pub build_id: Option<String>,
+    /// Verified races this artifact took part in; unverified submissions
+    /// are left out of all three stats.
+    #[serde(default)]
+    pub races_count: u32,
+    /// Of those, the races it finished first in.
+    #[serde(default)]
+    pub wins: u32,
+    /// Fastest lap across its verified races; `None` until it completes one.
+    #[serde(default)]
+    pub best_lap_secs: Option<f32>,
     pub created_at: String,
+    /// When the artifact was moved to the trash; only set in
+    /// `?deleted=true` listings. Trashed artifacts are purged after the
+    /// server's retention period.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,14 +251,308 @@ pub struct UploadArtifactRequest {
     pub note: Option<String>,
     pub target: String,
     pub elf_base64: String,
+    /// Omit to use the server's default visibility.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_public: Option<bool>,
+    /// Source provenance such as a git commit; when omitted the server falls
+    /// back to the id embedded in the ELF, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+}
+
+/// Asks the server to download an ELF from `url` and store it as a new
+/// artifact; answered with an `UploadArtifactResponse`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportArtifactRequest {
+    pub url: String,
+    /// Omit to name the artifact after the last path segment of `url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub note: Option<String>,
+    pub target: String,
+    /// Omit to use the server's default visibility.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_public: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UploadArtifactResponse {
-    pub artifact_id: i64,
+    pub artifact_id: ArtifactId,
+    /// Name the artifact was stored under; differs from the requested name
+    /// when the server suffixed a duplicate.
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Path of the manifest inside the `/api/v1/artifacts/export` tarball. It is
+/// the last entry, after every ELF it describes.
+pub const ARTIFACT_EXPORT_MANIFEST: &str = "manifest.json";
+
+/// Contents of [`ARTIFACT_EXPORT_MANIFEST`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactExportManifest {
+    pub exported_at: String,
+    pub artifacts: Vec<ArtifactExportEntry>,
+}
+
+/// One exported artifact and where its ELF sits in the tarball.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactExportEntry {
+    pub id: ArtifactId,
+    pub name: String,
+    pub note: Option<String>,
+    pub target: String,
+    pub is_public: bool,
+    pub abi_version: Option<u32>,
+    pub build_id: Option<String>,
+    pub created_at: String,
+    /// Path of the ELF inside the tarball.
+    pub file: String,
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the ELF.
+    pub sha256: String,
+}
+
+/// Outcome of `POST /api/v1/artifacts/import-archive`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportArchiveResponse {
+    /// Artifacts created from the archive, in manifest order.
+    pub imported: Vec<UploadArtifactResponse>,
+    /// Manifest names left alone because the caller already had them.
+    pub skipped: Vec<String>,
+}
+
+/// Outcome of `POST /api/v1/admin/gc`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GarbageCollectResponse {
+    /// Files in the artifact store that no artifact referenced.
+    pub removed_files: u32,
+    pub reclaimed_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateArtifactVisibilityRequest {
     pub is_public: bool,
 }
+
+/// Why a car's race ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum FinishReason {
+    /// Completed the race distance.
+    Finished,
+    /// Still running when the race's time limit ran out.
+    DnfTimeout,
+    /// The bot's CPU trapped, e.g. `kind` `illegal_instruction` at `pc`.
+    CrashedTrap { kind: String, pc: u32 },
+    /// Still running when the race was stopped before its end.
+    Halted,
+}
+
+/// One car's result in a submitted race.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceEntrySubmission {
+    pub artifact_id: ArtifactId,
+    pub laps_completed: u32,
+    pub best_lap_secs: Option<f32>,
+    /// Missing from older games.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitRaceRequest {
+    /// Finishing order, winner first; positions are assigned from this order.
+    pub entries: Vec<RaceEntrySubmission>,
+    /// Recording of the race, stored with it so an admin can re-simulate
+    /// the claimed results. Without one the race can never be verified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<ReplayFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitRaceResponse {
+    pub race_id: i64,
+    /// When the server recorded the race (RFC 3339). Races are ordered by
+    /// this server timestamp, never by the submitting client's clock; empty
+    /// from servers that predate it.
+    #[serde(default)]
+    pub raced_at: String,
+    /// Whether the results are confirmed. Submissions are stored unverified
+    /// and only count towards artifact stats once the game's `--verify-race`
+    /// re-simulated the race's replay to the same results.
+    #[serde(default)]
+    pub verified: bool,
+    /// Id of the replay stored with the race, if one was submitted.
+    #[serde(default)]
+    pub replay_id: Option<i64>,
+}
+
+/// Response of `GET /api/v1/admin/races/{id}`: what a race claimed and the
+/// replay it was submitted with, for re-simulating it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceReview {
+    pub race_id: i64,
+    pub submitted_by: String,
+    /// The stored results, winner first.
+    pub entries: Vec<RaceEntrySubmission>,
+    pub replay: Option<ReplayFile>,
+    pub verified: bool,
+    /// Why the race was rejected, once it has been.
+    pub rejection: Option<String>,
+}
+
+/// Body of `POST /api/v1/admin/races/{id}/reject`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectRaceRequest {
+    /// Why the claimed results do not hold, e.g. `verify_claims`' error.
+    pub reason: String,
+}
+
+/// Response of `GET /api/v1/time`: the server's clock, so clients can
+/// estimate their offset from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTime {
+    /// Milliseconds since the Unix epoch.
+    pub unix_millis: i64,
+    /// The same instant as RFC 3339.
+    pub rfc3339: String,
+}
+
+/// Payload of the `race_finished` server-sent event, pushed to
+/// `/api/v1/events` subscribers whenever a race is submitted, and again with
+/// `verified` set once an admin confirms it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceFinishedEvent {
+    pub race_id: i64,
+    pub submitted_by: String,
+    /// Finishing order, winner first. Entries for artifacts the subscriber
+    /// may not download are left out.
+    pub entries: Vec<RaceEntrySubmission>,
+    /// Whether the results are confirmed; unverified results are only
+    /// claims and may still be rejected.
+    #[serde(default)]
+    pub verified: bool,
+}
+
+/// `ReplayFile::version` written by this crate. Readers reject other versions
+/// rather than guess at a layout they do not know.
+pub const REPLAY_FORMAT_VERSION: u32 = 2;
+/// Rate the game's simulation steps at, and so the rate of the ticks in
+/// every replay it records.
+pub const SIM_TICK_HZ: u32 = 200;
+/// Longest time limit a race can be given, in seconds.
+pub const MAX_RACE_TIME_LIMIT_SECS: u32 = 3600;
+/// Most ticks a replay holds: the game stops recording a race that runs
+/// past the longest time limit, and readers reject longer replays.
+pub const MAX_REPLAY_TICKS: usize = (MAX_RACE_TIME_LIMIT_SECS * SIM_TICK_HZ) as usize;
+
+/// A recorded race: putting the participants' cars on the same starting
+/// grid and feeding them `ticks` in order reproduces it. The simulation has
+/// no randomness, so there is no seed to record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub version: u32,
+    /// Simulation rate the ticks were recorded at.
+    pub tick_hz: u32,
+    /// Track the race ran on, e.g. the track file name.
+    pub track: String,
+    /// Factor every car's dimensions were scaled by relative to the default
+    /// kart (the game's `--car-scale`); 1 in replays that predate it.
+    #[serde(default = "default_car_scale")]
+    pub car_scale: f32,
+    /// Whether cars collided with each other (off for the game's
+    /// `--ghost-cars`); on in replays that predate it.
+    #[serde(default = "default_cars_collide")]
+    pub cars_collide: bool,
+    /// Tire wear per metre at full load (the game's `--tire-wear`); `None`,
+    /// as in replays that predate it, stands for the game's default.
+    #[serde(default)]
+    pub tire_wear_per_m: Option<f32>,
+    /// Cars in spawn order; every tick has one input per participant in this order.
+    pub participants: Vec<ReplayParticipant>,
+    pub ticks: ReplayTicks,
+}
+
+/// One car's artifact and starting pose in a replay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayParticipant {
+    pub artifact_id: ArtifactId,
+    pub start_x: f32,
+    pub start_y: f32,
+    /// Radians, counter-clockwise from +x.
+    pub start_heading: f32,
+    /// Share of the car's brake force on the front axle, 0..1; `None`, as
+    /// in replays that predate it, stands for the game's default.
+    #[serde(default)]
+    pub brake_bias: Option<f32>,
+}
+
+/// Controls one car applied during one tick.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub steering: f32,
+    pub accelerator: f32,
+    pub brake: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadReplayResponse {
+    pub replay_id: i64,
+}
+
+/// An artifact's placement in one past race.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactRaceResult {
+    pub race_id: i64,
+    /// 1-based finishing position.
+    pub position: u32,
+    pub car_count: u32,
+    pub laps_completed: u32,
+    pub best_lap_secs: Option<f32>,
+    pub raced_at: String,
+    /// Whether the race's results were confirmed.
+    #[serde(default)]
+    pub verified: bool,
+    /// Whether the race's replay contradicted its results; rejected races
+    /// never count towards artifact stats.
+    #[serde(default)]
+    pub rejected: bool,
+}
+
+/// Console output one car's bot wrote during a race.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceLogTranscript {
+    pub artifact_id: ArtifactId,
+    pub log: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadRaceLogsRequest {
+    pub transcripts: Vec<RaceLogTranscript>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_ids_round_trip_including_negative_values() {
+        for raw in [0, 42, -7, i64::MIN, i64::MAX] {
+            let id = ArtifactId::from(raw);
+            assert_eq!(i64::from(id), raw);
+            assert_eq!(id.to_string().parse::<ArtifactId>(), Ok(id));
+
+            let json = serde_json::to_string(&id).expect("serialize id");
+            assert_eq!(json, raw.to_string());
+            assert_eq!(
+                serde_json::from_str::<ArtifactId>(&json).expect("id json"),
+                id
+            );
+        }
+
+        // Ids are signed on the wire; a value past i64 is rejected, not wrapped.
+        assert!(serde_json::from_str::<ArtifactId>("9223372036854775808").is_err());
+        assert!("9223372036854775808".parse::<ArtifactId>().is_err());
+    }
+}
@@ -0,0 +1,382 @@
+//! Compact storage for a replay's per-tick controls.
+//!
+//! Bots usually hold a control for a while, so every tick stores only what
+//! changed since the tick before: a bitmask with one bit per car and channel
+//! (steering, accelerator, brake, in that order), followed by the changed
+//! values as little-endian `f32` bits. The stream starts with the car count
+//! as a LEB128 varint and is sent as one base64 string. Values are kept
+//! bit-exact, since re-simulating a race needs exactly the controls it ran
+//! with.
+
+use std::fmt;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::{MAX_REPLAY_TICKS, ReplayInput};
+
+const CHANNELS: usize = 3;
+/// Bytes a changed channel value takes.
+const VALUE_BYTES: usize = 4;
+/// Longest LEB128 encoding of a `u64`.
+const MAX_VARINT_BYTES: usize = 10;
+
+/// The controls every car applied on every tick of a replay, one
+/// [`ReplayInput`] per car and tick.
+#[derive(Clone, Default)]
+pub struct ReplayTicks {
+    cars: usize,
+    len: usize,
+    /// Encoded ticks, without the car count.
+    bytes: Vec<u8>,
+    /// Each car's channel bits as of the last tick, for the next `push`.
+    last: Vec<[u32; CHANNELS]>,
+}
+
+impl ReplayTicks {
+    /// No ticks yet for a race of `cars` cars.
+    pub fn new(cars: usize) -> Self {
+        Self {
+            cars,
+            ..Self::default()
+        }
+    }
+
+    /// Cars every tick has an input for.
+    pub fn cars(&self) -> usize {
+        self.cars
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends one tick.
+    ///
+    /// # Panics
+    ///
+    /// If `inputs` does not hold one input per car.
+    pub fn push(&mut self, inputs: &[ReplayInput]) {
+        assert_eq!(
+            inputs.len(),
+            self.cars,
+            "a replay tick needs one input per car"
+        );
+        if self.last.is_empty() {
+            self.last = vec![[0; CHANNELS]; self.cars];
+        }
+        let mask_at = self.bytes.len();
+        self.bytes.resize(mask_at + mask_len(self.cars), 0);
+        for (car, (input, last)) in inputs.iter().zip(&mut self.last).enumerate() {
+            for (channel, value) in channels(input).into_iter().enumerate() {
+                let bits = value.to_bits();
+                if bits != last[channel] {
+                    let bit = car * CHANNELS + channel;
+                    self.bytes[mask_at + bit / 8] |= 1 << (bit % 8);
+                    self.bytes.extend_from_slice(&bits.to_le_bytes());
+                    last[channel] = bits;
+                }
+            }
+        }
+        self.len += 1;
+    }
+
+    /// The ticks in order, each with one input per car.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            ticks: self,
+            offset: 0,
+            // A parsed replay without ticks may name any car count.
+            last: if self.is_empty() {
+                Vec::new()
+            } else {
+                vec![[0; CHANNELS]; self.cars]
+            },
+        }
+    }
+
+    /// Length of the base64 string `ticks` ticks of `cars` cars serialize
+    /// to at most, reached when every value changes on every tick.
+    pub const fn max_encoded_len(cars: usize, ticks: usize) -> usize {
+        let bytes = MAX_VARINT_BYTES + ticks * (mask_len(cars) + cars * CHANNELS * VALUE_BYTES);
+        bytes.div_ceil(3) * 4
+    }
+
+    /// Checks an encoded stream and counts its ticks without expanding them.
+    fn decode(mut encoded: Vec<u8>) -> Result<Self, String> {
+        let (cars, header_len) = read_varint(&encoded)?;
+        let cars = usize::try_from(cars).map_err(|_| "replay car count is too large")?;
+        encoded.drain(..header_len);
+        let mut ticks = Self {
+            cars,
+            len: 0,
+            bytes: encoded,
+            last: Vec::new(),
+        };
+        if ticks.bytes.is_empty() {
+            return Ok(ticks);
+        }
+        if cars == 0 {
+            return Err("replay ticks have no cars".to_string());
+        }
+        let mask_len = cars
+            .checked_mul(CHANNELS)
+            .map(|bits| bits.div_ceil(8))
+            .filter(|&mask_len| mask_len <= ticks.bytes.len())
+            .ok_or("replay ticks are truncated")?;
+        let mut last = vec![[0; CHANNELS]; cars];
+        let mut rest = &ticks.bytes[..];
+        while !rest.is_empty() {
+            if ticks.len == MAX_REPLAY_TICKS {
+                return Err(format!("replay has more than {MAX_REPLAY_TICKS} ticks"));
+            }
+            let (mask, tail) = rest
+                .split_at_checked(mask_len)
+                .ok_or("replay ticks are truncated")?;
+            rest = tail;
+            let used_bits = cars * CHANNELS;
+            if !used_bits.is_multiple_of(8) && mask[mask_len - 1] >> (used_bits % 8) != 0 {
+                return Err(format!("replay tick {} sets unused mask bits", ticks.len));
+            }
+            for (car, last) in last.iter_mut().enumerate() {
+                for (channel, last) in last.iter_mut().enumerate() {
+                    let bit = car * CHANNELS + channel;
+                    if mask[bit / 8] & (1 << (bit % 8)) != 0 {
+                        let (value, tail) = rest
+                            .split_first_chunk::<VALUE_BYTES>()
+                            .ok_or("replay ticks are truncated")?;
+                        *last = u32::from_le_bytes(*value);
+                        rest = tail;
+                    }
+                }
+            }
+            ticks.len += 1;
+        }
+        ticks.last = last;
+        Ok(ticks)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(MAX_VARINT_BYTES + self.bytes.len());
+        write_varint(&mut encoded, self.cars as u64);
+        encoded.extend_from_slice(&self.bytes);
+        encoded
+    }
+}
+
+impl fmt::Debug for ReplayTicks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplayTicks")
+            .field("cars", &self.cars)
+            .field("len", &self.len)
+            .field("encoded_bytes", &self.bytes.len())
+            .finish()
+    }
+}
+
+impl PartialEq for ReplayTicks {
+    fn eq(&self, other: &Self) -> bool {
+        self.cars == other.cars && self.len == other.len && self.bytes == other.bytes
+    }
+}
+
+/// Collects ticks of equal length; the first one sets the car count.
+///
+/// # Panics
+///
+/// If a tick has a different number of inputs than the first.
+impl<T: AsRef<[ReplayInput]>> FromIterator<T> for ReplayTicks {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut iter = iter.into_iter().peekable();
+        let cars = iter.peek().map_or(0, |inputs| inputs.as_ref().len());
+        let mut ticks = Self::new(cars);
+        for inputs in iter {
+            ticks.push(inputs.as_ref());
+        }
+        ticks
+    }
+}
+
+impl Serialize for ReplayTicks {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(self.encode()))
+    }
+}
+
+impl<'de> Deserialize<'de> for ReplayTicks {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TicksVisitor;
+
+        impl de::Visitor<'_> for TicksVisitor {
+            type Value = ReplayTicks;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("base64-encoded replay ticks")
+            }
+
+            // Taking the string by reference spares a copy of what can be
+            // hundreds of megabytes.
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<ReplayTicks, E> {
+                let encoded = STANDARD
+                    .decode(value)
+                    .map_err(|e| E::custom(format!("invalid replay ticks: {e}")))?;
+                ReplayTicks::decode(encoded).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(TicksVisitor)
+    }
+}
+
+/// Iterator over [`ReplayTicks`], from [`ReplayTicks::iter`].
+pub struct Iter<'a> {
+    ticks: &'a ReplayTicks,
+    offset: usize,
+    last: Vec<[u32; CHANNELS]>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Vec<ReplayInput>;
+
+    fn next(&mut self) -> Option<Vec<ReplayInput>> {
+        let bytes = &self.ticks.bytes;
+        if self.offset == bytes.len() {
+            return None;
+        }
+        // `push` and `decode` only leave whole ticks behind.
+        let mask = self.offset;
+        self.offset += mask_len(self.ticks.cars);
+        let mut inputs = Vec::with_capacity(self.ticks.cars);
+        for (car, last) in self.last.iter_mut().enumerate() {
+            for (channel, last) in last.iter_mut().enumerate() {
+                let bit = car * CHANNELS + channel;
+                if bytes[mask + bit / 8] & (1 << (bit % 8)) != 0 {
+                    let value = &bytes[self.offset..self.offset + VALUE_BYTES];
+                    *last = u32::from_le_bytes(value.try_into().expect("four bytes"));
+                    self.offset += VALUE_BYTES;
+                }
+            }
+            let [steering, accelerator, brake] = last.map(f32::from_bits);
+            inputs.push(ReplayInput {
+                steering,
+                accelerator,
+                brake,
+            });
+        }
+        Some(inputs)
+    }
+}
+
+const fn mask_len(cars: usize) -> usize {
+    (cars * CHANNELS).div_ceil(8)
+}
+
+fn channels(input: &ReplayInput) -> [f32; CHANNELS] {
+    [input.steering, input.accelerator, input.brake]
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// The value at the start of `bytes` and how many bytes it took.
+fn read_varint(bytes: &[u8]) -> Result<(u64, usize), String> {
+    let mut value = 0u64;
+    for (index, &byte) in bytes.iter().enumerate().take(MAX_VARINT_BYTES) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    Err("replay car count is malformed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(steering: f32, accelerator: f32, brake: f32) -> ReplayInput {
+        ReplayInput {
+            steering,
+            accelerator,
+            brake,
+        }
+    }
+
+    #[test]
+    fn ticks_round_trip_bit_exactly() {
+        let recorded = [
+            vec![input(0.0, 1.0, 0.0), input(-0.0, 0.5, 0.25)],
+            vec![input(0.1, 1.0, 0.0), input(-0.0, 0.5, 0.25)],
+            vec![input(0.1, 1.0, 0.0), input(-0.0, 0.5, 0.25)],
+            vec![input(f32::MIN_POSITIVE, 0.0, 1.0), input(1.0, 0.0, 0.0)],
+        ];
+        let ticks: ReplayTicks = recorded.iter().collect();
+        assert_eq!(ticks.cars(), 2);
+        assert_eq!(ticks.len(), 4);
+
+        let json = serde_json::to_string(&ticks).expect("serialize ticks");
+        let parsed: ReplayTicks = serde_json::from_str(&json).expect("ticks json");
+        assert_eq!(parsed, ticks);
+        let replayed: Vec<_> = parsed.iter().collect();
+        for (replayed, recorded) in replayed.iter().flatten().zip(recorded.iter().flatten()) {
+            for (a, b) in channels(replayed).into_iter().zip(channels(recorded)) {
+                assert_eq!(a.to_bits(), b.to_bits());
+            }
+        }
+        assert_eq!(replayed.len(), recorded.len());
+
+        // Appending to a parsed replay continues from its last tick.
+        let mut resumed = parsed;
+        resumed.push(&recorded[0]);
+        let mut expected = ticks;
+        expected.push(&recorded[0]);
+        assert_eq!(resumed, expected);
+    }
+
+    #[test]
+    fn held_controls_cost_only_the_mask() {
+        let held: ReplayTicks = (0..1000).map(|_| [input(0.2, 1.0, 0.0)]).collect();
+        // One byte of mask per tick, plus the two values that left zero once.
+        assert_eq!(held.bytes.len(), 1000 + 2 * VALUE_BYTES);
+    }
+
+    #[test]
+    fn worst_case_fits_max_encoded_len() {
+        let cars = 5;
+        let ticks: ReplayTicks = (0..100)
+            .map(|tick| {
+                let value = if tick % 2 == 0 { 0.5 } else { -0.5 };
+                vec![input(value, value, value); cars]
+            })
+            .collect();
+        let json = serde_json::to_string(&ticks).expect("serialize ticks");
+        // The JSON adds only the quotes.
+        assert!(json.len() - 2 <= ReplayTicks::max_encoded_len(cars, 100));
+        assert!(json.len() - 2 > ReplayTicks::max_encoded_len(cars, 100) - 16);
+    }
+
+    #[test]
+    fn malformed_ticks_are_rejected() {
+        let ticks: ReplayTicks = [[input(0.5, 1.0, 0.0)]].into_iter().collect();
+        let mut encoded = ticks.encode();
+        encoded.pop();
+        assert!(ReplayTicks::decode(encoded).is_err());
+
+        // A mask bit past the last car's brake.
+        assert!(ReplayTicks::decode(vec![1, 0b1000]).is_err());
+        // Ticks for no cars.
+        assert!(ReplayTicks::decode(vec![0, 0]).is_err());
+        // A car count the stream cannot hold a mask for.
+        assert!(ReplayTicks::decode(vec![0xff, 0xff, 0x03, 0]).is_err());
+        assert!(serde_json::from_str::<ReplayTicks>("\"not base64!\"").is_err());
+    }
+}
@@ -1,7 +1,9 @@
 use std::{
-    net::SocketAddr,
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use argon2::{
@@ -10,52 +12,168 @@ use argon2::{
 };
 use axum::{
     Form, Json, Router,
-    extract::{OriginalUri, Path as AxumPath, Query, State},
-    http::{HeaderMap, HeaderValue, StatusCode, header},
-    response::{Html, IntoResponse, Redirect, Response},
-    routing::{get, patch, post},
+    body::Bytes,
+    extract::{DefaultBodyLimit, OriginalUri, Path as AxumPath, Query, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    response::{
+        Html, IntoResponse, Redirect, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{delete, get, patch, post},
 };
 use base64::Engine;
 use botracers_protocol::{
-    ArtifactSummary, ErrorResponse, LoginRequest, LoginResponse, RegisterRequest,
-    ServerCapabilities, UpdateArtifactVisibilityRequest, UploadArtifactRequest,
-    UploadArtifactResponse, UserInfo,
+    ARTIFACT_EXPORT_MANIFEST, ApiKeySummary, ArtifactExportEntry, ArtifactExportManifest,
+    ArtifactId, ArtifactRaceResult, ArtifactScope, ArtifactSummary, BOT_ABI_SECTION,
+    BOT_BUILD_ID_SECTION, CreateApiKeyRequest, CreateApiKeyResponse, DEFAULT_AUTH_HEADER,
+    DEFAULT_AUTH_SCHEME, ErrorResponse, GarbageCollectResponse, ImportArchiveResponse,
+    ImportArtifactRequest, LoginRequest, LoginResponse, MAX_BUILD_ID_LEN, MAX_REPLAY_TICKS,
+    REPLAY_FORMAT_VERSION, RaceEntrySubmission, RaceFinishedEvent, RaceLogTranscript, RaceReview,
+    RegisterRequest, RejectRaceRequest, RenameUserRequest, ReplayFile, ReplayParticipant,
+    ReplayTicks, SUPPORTED_BOT_ABI_VERSIONS, ServerCapabilities, ServerTime, SubmitRaceRequest,
+    SubmitRaceResponse, UpdateArtifactVisibilityRequest, UploadArtifactRequest,
+    UploadArtifactResponse, UploadRaceLogsRequest, UploadReplayResponse, UserInfo,
 };
-use chrono::Utc;
+use chrono::{DateTime, TimeDelta, Utc};
+use elf::{
+    ElfBytes,
+    abi::{PF_X, PT_LOAD},
+    endian::LittleEndian,
+};
+use futures_util::Stream;
 use rand::Rng;
 use rusqlite::{Connection, OptionalExtension, params};
 use serde::Deserialize;
-use tokio::sync::Mutex;
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, broadcast};
 use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
 use tracing::{debug, info, warn};
 
 const LOCAL_USER_ID: i64 = 1;
 const LOCAL_USERNAME: &str = "local";
 const COOKIE_NAME: &str = "botracers_session";
+/// Marks API keys so `authenticate` can tell them apart from session tokens.
+const API_KEY_PREFIX: &str = "brk_";
+const MAX_ARTIFACT_PAGE_SIZE: u32 = 100;
+const MAX_RACE_PAGE_SIZE: u32 = 100;
+const MAX_RACE_LOG_BYTES: usize = 64 * 1024;
+/// Most cars a submitted race may have, advertised as
+/// `ServerCapabilities::max_field_size`.
+const MAX_FIELD_SIZE: usize = 32;
+/// Room in a replay upload for everything but its ticks: the grid of up to
+/// `MAX_FIELD_SIZE` participants and the race setup.
+const REPLAY_HEADER_BYTES: usize = 64 * 1024;
+/// Largest replay upload: a full grid racing the longest time limit with
+/// every control changing on every tick.
+const MAX_REPLAY_BYTES: usize =
+    ReplayTicks::max_encoded_len(MAX_FIELD_SIZE, MAX_REPLAY_TICKS) + REPLAY_HEADER_BYTES;
+/// Largest race submission: the results of a full grid plus the replay they
+/// came from.
+const MAX_RACE_SUBMISSION_BYTES: usize = MAX_REPLAY_BYTES + 16 * 1024;
+/// Largest decoded ELF accepted on upload; its base64 JSON body stays under
+/// axum's default 2 MB request limit.
+const MAX_ARTIFACT_BYTES: usize = 1024 * 1024;
+/// Largest total of ELF bytes one `/api/v1/artifacts/export` tarball may hold.
+const MAX_EXPORT_BYTES: u64 = 256 * 1024 * 1024;
+/// Most artifacts one `/api/v1/artifacts/import-archive` upload may create.
+const MAX_IMPORT_ARTIFACTS: usize = 64;
+/// Largest archive accepted by `/api/v1/artifacts/import-archive`:
+/// [`MAX_IMPORT_ARTIFACTS`] full-size ELFs with their tar headers and
+/// padding, plus room for the manifest.
+const MAX_IMPORT_ARCHIVE_BYTES: usize =
+    MAX_IMPORT_ARTIFACTS * (MAX_ARTIFACT_BYTES + 1024) + 1024 * 1024;
+/// Longest artifact name kept in an exported ELF's tarball path, which ustar
+/// caps at 100 bytes.
+const MAX_EXPORT_NAME_LEN: usize = 64;
+const ALLOWED_ARTIFACT_TARGETS: &[&str] = &["riscv32imafc-unknown-none-elf"];
+const IMPORT_TIMEOUT: Duration = Duration::from_secs(15);
+const IMPORT_MAX_REDIRECTS: u32 = 3;
+/// Race events buffered per `/api/v1/events` subscriber; a client that falls
+/// further behind skips the oldest ones.
+const RACE_EVENT_BUFFER: usize = 64;
+/// How often trashed artifacts past their retention period are purged.
+const ARTIFACT_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AuthMode {
     Required,
+    /// Like `Required`, but users can also mint long-lived API keys and send
+    /// them in place of a session token.
+    ApiKeys,
     Disabled,
 }
 
 impl AuthMode {
+    /// Reads `BOTRACERS_AUTH_MODE`. An unknown value falls back to
+    /// `Required` with a warning, so a typo never turns authentication off.
     pub fn from_env(value: &str) -> Self {
+        Self::parse(value).unwrap_or_else(|error| {
+            warn!("{error}, using required");
+            Self::Required
+        })
+    }
+
+    /// Strict form of [`from_env`](Self::from_env) for callers that should
+    /// refuse a typo instead of falling back.
+    pub fn parse(value: &str) -> Result<Self, String> {
         match value {
-            "disabled" => Self::Disabled,
-            _ => Self::Required,
+            "required" => Ok(Self::Required),
+            "api_keys" => Ok(Self::ApiKeys),
+            "disabled" => Ok(Self::Disabled),
+            other => Err(format!(
+                "unknown auth mode '{other}' (expected required, api_keys or disabled)"
+            )),
         }
     }
 
     pub fn as_str(self) -> &'static str {
         match self {
-            Self::Required => "server",
+            Self::Required | Self::ApiKeys => "server",
             Self::Disabled => "standalone",
         }
     }
 
     pub fn auth_required(self) -> bool {
-        matches!(self, Self::Required)
+        matches!(self, Self::Required | Self::ApiKeys)
+    }
+}
+
+/// What happens when a user stores an artifact under a name they already use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateNamePolicy {
+    /// Refuse with `409 Conflict`.
+    Reject,
+    /// Store it as `name (2)`, `name (3)`, ... keeping any extension last.
+    Suffix,
+}
+
+impl DuplicateNamePolicy {
+    /// Reads `BOTRACERS_DUPLICATE_ARTIFACT_NAMES`. An unknown value falls
+    /// back to `Reject` with a warning, so a typo never lets duplicates in.
+    pub fn from_env(value: &str) -> Self {
+        Self::parse(value).unwrap_or_else(|error| {
+            warn!("{error}, using reject");
+            Self::Reject
+        })
+    }
+
+    /// Strict form of [`from_env`](Self::from_env) for callers that should
+    /// refuse a typo instead of falling back.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "reject" => Ok(Self::Reject),
+            "suffix" => Ok(Self::Suffix),
+            other => Err(format!(
+                "unknown duplicate name policy '{other}' (expected suffix or reject)"
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reject => "reject",
+            Self::Suffix => "suffix",
+        }
     }
 }
 
@@ -68,6 +186,22 @@ pub struct ServerConfig {
     pub auth_mode: AuthMode,
     pub cookie_secure: bool,
     pub registration_enabled: bool,
+    pub default_artifact_public: bool,
+    pub events_public: bool,
+    /// Lets artifact imports fetch from loopback and private networks.
+    pub import_allow_private_hosts: bool,
+    /// Header clients put their session token in, for proxies that claim
+    /// `Authorization` for themselves.
+    pub auth_header: String,
+    /// Scheme expected before the token in `auth_header`; empty for a bare
+    /// token.
+    pub auth_scheme: String,
+    pub duplicate_names: DuplicateNamePolicy,
+    /// Days a deleted artifact stays restorable before it is purged.
+    pub artifact_retention_days: u32,
+    /// Usernames allowed to call `/api/v1/admin/*`. Ignored when auth is
+    /// disabled, where the local user is the admin.
+    pub admin_users: Vec<String>,
 }
 
 impl Default for ServerConfig {
@@ -80,6 +214,14 @@ impl Default for ServerConfig {
             auth_mode: AuthMode::Required,
             cookie_secure: false,
             registration_enabled: true,
+            default_artifact_public: false,
+            events_public: false,
+            import_allow_private_hosts: false,
+            auth_header: DEFAULT_AUTH_HEADER.to_string(),
+            auth_scheme: DEFAULT_AUTH_SCHEME.to_string(),
+            duplicate_names: DuplicateNamePolicy::Suffix,
+            artifact_retention_days: 30,
+            admin_users: Vec::new(),
         }
     }
 }
@@ -92,6 +234,16 @@ struct AppState {
     auth_mode: AuthMode,
     cookie_secure: bool,
     registration_enabled: bool,
+    default_artifact_public: bool,
+    events_public: bool,
+    race_events: broadcast::Sender<RaceFinishedEvent>,
+    import_allow_private_hosts: bool,
+    auth_header: HeaderName,
+    auth_scheme: String,
+    duplicate_names: DuplicateNamePolicy,
+    artifact_retention_days: u32,
+    max_export_bytes: u64,
+    admin_users: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -119,6 +271,55 @@ struct WebRegisterForm {
     next: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ArtifactListQuery {
+    #[serde(default)]
+    scope: ArtifactScope,
+    limit: Option<u32>,
+    #[serde(default)]
+    offset: u32,
+    /// Lists the caller's trashed artifacts instead.
+    #[serde(default)]
+    deleted: bool,
+}
+
+/// What `/api/v1/artifacts/import-archive` does with a manifest entry whose
+/// name the caller already uses.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ArchiveDuplicates {
+    #[default]
+    Skip,
+    /// Moves the existing artifact to the trash and imports the new one.
+    Overwrite,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportArchiveQuery {
+    #[serde(default)]
+    on_duplicate: ArchiveDuplicates,
+}
+
+#[derive(Debug, Deserialize)]
+struct RaceHistoryQuery {
+    limit: Option<u32>,
+    #[serde(default)]
+    offset: u32,
+}
+
+/// The only field read from an uploaded replay before its version is known.
+#[derive(Debug, Deserialize)]
+struct ReplayVersion {
+    version: u32,
+}
+
+/// The part of a stored replay `get_replay` checks access against, read
+/// without decoding the ticks.
+#[derive(Debug, Deserialize)]
+struct ReplayParticipants {
+    participants: Vec<ReplayParticipant>,
+}
+
 #[derive(Debug)]
 struct ApiError {
     status: StatusCode,
@@ -147,6 +348,13 @@ impl ApiError {
         }
     }
 
+    fn conflict(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::CONFLICT,
+            message: message.into(),
+        }
+    }
+
     fn not_found(message: impl Into<String>) -> Self {
         Self {
             status: StatusCode::NOT_FOUND,
@@ -154,6 +362,13 @@ impl ApiError {
         }
     }
 
+    fn payload_too_large(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            message: message.into(),
+        }
+    }
+
     fn internal(message: impl Into<String>) -> Self {
         Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
@@ -182,9 +397,18 @@ pub async fn run_server(config: ServerConfig) -> Result<(), Box<dyn std::error::
         artifacts_dir = %config.artifacts_dir.display(),
         static_dir = ?config.static_dir.as_ref().map(|p| p.display().to_string()),
         registration_enabled = config.registration_enabled,
+        default_artifact_public = config.default_artifact_public,
+        events_public = config.events_public,
+        import_allow_private_hosts = config.import_allow_private_hosts,
+        auth_header = %config.auth_header,
+        auth_scheme = %config.auth_scheme,
+        duplicate_names = config.duplicate_names.as_str(),
+        artifact_retention_days = config.artifact_retention_days,
         "starting botracers server"
     );
 
+    let auth_header = HeaderName::try_from(config.auth_header.as_str())?;
+
     std::fs::create_dir_all(&config.artifacts_dir)?;
     let conn = Connection::open(&config.db_path)?;
     run_migrations(&conn)?;
@@ -197,8 +421,27 @@ pub async fn run_server(config: ServerConfig) -> Result<(), Box<dyn std::error::
         auth_mode: config.auth_mode,
         cookie_secure: config.cookie_secure,
         registration_enabled: config.registration_enabled,
+        default_artifact_public: config.default_artifact_public,
+        events_public: config.events_public,
+        race_events: broadcast::channel(RACE_EVENT_BUFFER).0,
+        import_allow_private_hosts: config.import_allow_private_hosts,
+        auth_header,
+        auth_scheme: config.auth_scheme,
+        duplicate_names: config.duplicate_names,
+        artifact_retention_days: config.artifact_retention_days,
+        max_export_bytes: MAX_EXPORT_BYTES,
+        admin_users: config.admin_users,
     };
 
+    let purge_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ARTIFACT_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            purge_expired_artifacts(&purge_state, Utc::now()).await;
+        }
+    });
+
     let app = build_app(state, config.static_dir);
 
     let addr: SocketAddr = config.bind.parse()?;
@@ -241,14 +484,24 @@ fn build_app(state: AppState, static_dir: Option<PathBuf>) -> Router {
         .route("/register", get(web_register_get).post(web_register_post))
         .route("/healthz", get(healthz))
         .route("/api/v1/capabilities", get(capabilities))
+        .route("/api/v1/time", get(server_time))
         .route("/api/v1/auth/register", post(register))
         .route("/api/v1/auth/login", post(login))
         .route("/api/v1/auth/logout", post(logout))
         .route("/api/v1/me", get(me))
+        .route("/api/v1/users/me", patch(rename_user))
+        .route("/api/v1/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api/v1/api-keys/{id}", delete(revoke_api_key))
         .route(
             "/api/v1/artifacts",
             get(list_artifacts).post(upload_artifact),
         )
+        .route("/api/v1/artifacts/import", post(import_artifact))
+        .route("/api/v1/artifacts/export", get(export_artifacts))
+        .route(
+            "/api/v1/artifacts/import-archive",
+            post(import_artifact_archive).layer(DefaultBodyLimit::max(MAX_IMPORT_ARCHIVE_BYTES)),
+        )
         .route(
             "/api/v1/artifacts/{id}",
             get(download_artifact).delete(delete_artifact),
@@ -257,6 +510,31 @@ fn build_app(state: AppState, static_dir: Option<PathBuf>) -> Router {
             "/api/v1/artifacts/{id}/visibility",
             patch(update_artifact_visibility),
         )
+        .route("/api/v1/artifacts/{id}/clone", post(clone_artifact))
+        .route("/api/v1/artifacts/{id}/restore", post(restore_artifact))
+        .route(
+            "/api/v1/artifacts/{id}/star",
+            post(star_artifact).delete(unstar_artifact),
+        )
+        .route("/api/v1/artifacts/{id}/races", get(list_artifact_races))
+        .route(
+            "/api/v1/races",
+            post(submit_race).layer(DefaultBodyLimit::max(MAX_RACE_SUBMISSION_BYTES)),
+        )
+        .route("/api/v1/events", get(race_events))
+        .route(
+            "/api/v1/replays",
+            post(upload_replay).layer(DefaultBodyLimit::max(MAX_REPLAY_BYTES)),
+        )
+        .route("/api/v1/replays/{id}", get(get_replay))
+        .route("/api/v1/admin/gc", post(collect_garbage))
+        .route("/api/v1/admin/races/{id}", get(review_race))
+        .route("/api/v1/admin/races/{id}/verify", post(verify_race))
+        .route("/api/v1/admin/races/{id}/reject", post(reject_race))
+        .route(
+            "/api/v1/races/{id}/logs",
+            get(list_race_logs).post(upload_race_logs),
+        )
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
         .with_state(state);
@@ -275,11 +553,31 @@ async fn healthz() -> &'static str {
     "ok"
 }
 
+async fn server_time() -> Json<ServerTime> {
+    let now = Utc::now();
+    Json(ServerTime {
+        unix_millis: now.timestamp_millis(),
+        rfc3339: now.to_rfc3339(),
+    })
+}
+
 async fn capabilities(State(state): State<AppState>) -> Json<ServerCapabilities> {
     Json(ServerCapabilities {
         auth_required: state.auth_mode.auth_required(),
         mode: state.auth_mode.as_str().to_string(),
         registration_enabled: state.registration_enabled,
+        default_artifact_public: state.default_artifact_public,
+        max_artifact_bytes: Some(MAX_ARTIFACT_BYTES as u64),
+        allowed_targets: ALLOWED_ARTIFACT_TARGETS
+            .iter()
+            .map(|target| target.to_string())
+            .collect(),
+        compile_supported: false,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        auth_header: state.auth_header.to_string(),
+        auth_scheme: state.auth_scheme.clone(),
+        supported_abi_versions: SUPPORTED_BOT_ABI_VERSIONS.to_vec(),
+        max_field_size: Some(MAX_FIELD_SIZE as u32),
     })
 }
 
@@ -288,7 +586,7 @@ async fn web_game_entry(
     headers: HeaderMap,
     OriginalUri(uri): OriginalUri,
 ) -> Response {
-    if state.auth_mode == AuthMode::Required && authenticate(&state, &headers).await.is_err() {
+    if state.auth_mode.auth_required() && authenticate(&state, &headers).await.is_err() {
         let next = sanitize_next(
             uri.path_and_query()
                 .map(|v| v.as_str())
@@ -456,7 +754,7 @@ async fn logout(State(state): State<AppState>, headers: HeaderMap) -> Result<Res
     }
 
     let mut removed = false;
-    if let Some(token) = bearer_token_opt(&headers) {
+    if let Some(token) = header_token_opt(&state, &headers) {
         let db = state.db.lock().await;
         db.execute("DELETE FROM sessions WHERE token = ?1", params![token])
             .map_err(|e| ApiError::internal(format!("failed to logout bearer token: {e}")))?;
@@ -483,18 +781,201 @@ async fn me(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<Us
     Ok(Json(user))
 }
 
+/// Changes the caller's username. Sessions and API keys refer to the user
+/// id, so they stay valid under the new name.
+async fn rename_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<RenameUserRequest>,
+) -> Result<Json<UserInfo>, ApiError> {
+    if state.auth_mode == AuthMode::Disabled {
+        return Err(ApiError::bad_request("auth is disabled in standalone mode"));
+    }
+    let user = authenticate(&state, &headers).await?;
+    let username = payload.username.trim();
+    validate_username(username)?;
+    if username != user.username && is_reserved_username(&state, username) {
+        return Err(ApiError::bad_request(format!(
+            "username '{username}' is reserved"
+        )));
+    }
+
+    let db = state.db.lock().await;
+    let updated = db.execute(
+        "UPDATE users SET username = ?1 WHERE id = ?2",
+        params![username, user.id],
+    );
+    if let Err(err) = updated {
+        if err.to_string().contains("UNIQUE") {
+            return Err(ApiError::conflict("username already exists"));
+        }
+        return Err(ApiError::internal(format!("failed to rename user: {err}")));
+    }
+
+    Ok(Json(UserInfo {
+        id: user.id,
+        username: username.to_string(),
+    }))
+}
+
+/// Usernames nobody may register or rename to: the standalone user's and
+/// common operator names.
+const RESERVED_USERNAMES: &[&str] = &[LOCAL_USERNAME, "admin", "administrator", "root", "system"];
+
+/// Whether `username` is reserved or a configured admin name. Admin rights go
+/// by name, so an unclaimed admin name must not be up for grabs; operators
+/// list accounts that already exist.
+fn is_reserved_username(state: &AppState, username: &str) -> bool {
+    RESERVED_USERNAMES.contains(&username.to_ascii_lowercase().as_str())
+        || state.admin_users.iter().any(|admin| admin == username)
+}
+
+/// 3 to 32 ASCII letters, digits, `_` or `-`, starting with a letter or digit.
+fn validate_username(username: &str) -> Result<(), ApiError> {
+    if !(3..=32).contains(&username.len()) {
+        return Err(ApiError::bad_request(
+            "username must be 3 to 32 characters long",
+        ));
+    }
+    if !username.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(ApiError::bad_request(
+            "username may only contain letters, digits, '_' and '-', and must start with a letter or digit",
+        ));
+    }
+    Ok(())
+}
+
+/// Fails unless the server runs in `AuthMode::ApiKeys`.
+fn require_api_keys(state: &AppState) -> Result<(), ApiError> {
+    if state.auth_mode == AuthMode::ApiKeys {
+        Ok(())
+    } else {
+        Err(ApiError::not_found("api keys are disabled"))
+    }
+}
+
+async fn create_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreateApiKeyResponse>, ApiError> {
+    require_api_keys(&state)?;
+    let user = authenticate(&state, &headers).await?;
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::bad_request("api key name must not be empty"));
+    }
+
+    let key = format!("{API_KEY_PREFIX}{}", generate_token());
+    let created_at = now_utc();
+    let db = state.db.lock().await;
+    db.execute(
+        "INSERT INTO api_keys (user_id, name, key_hash, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![user.id, name, hash_api_key(&key), created_at],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to create api key: {e}")))?;
+
+    let id = db.last_insert_rowid();
+    info!(api_key_id = id, user_id = user.id, "api key created");
+    Ok(Json(CreateApiKeyResponse {
+        id,
+        name: name.to_string(),
+        key,
+        created_at,
+    }))
+}
+
+async fn list_api_keys(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ApiKeySummary>>, ApiError> {
+    require_api_keys(&state)?;
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
+    let mut stmt = db
+        .prepare("SELECT id, name, created_at FROM api_keys WHERE user_id = ?1 ORDER BY id ASC")
+        .map_err(|e| ApiError::internal(format!("failed to prepare api key query: {e}")))?;
+    let rows = stmt
+        .query_map(params![user.id], |row| {
+            Ok(ApiKeySummary {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| ApiError::internal(format!("failed to query api keys: {e}")))?;
+
+    let mut out = Vec::new();
+    for item in rows {
+        out.push(item.map_err(|e| ApiError::internal(format!("failed to read api key row: {e}")))?);
+    }
+    Ok(Json(out))
+}
+
+async fn revoke_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(key_id): AxumPath<i64>,
+) -> Result<Response, ApiError> {
+    require_api_keys(&state)?;
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
+    let removed = db
+        .execute(
+            "DELETE FROM api_keys WHERE id = ?1 AND user_id = ?2",
+            params![key_id, user.id],
+        )
+        .map_err(|e| ApiError::internal(format!("failed to revoke api key: {e}")))?;
+    if removed == 0 {
+        return Err(ApiError::not_found("api key not found"));
+    }
+
+    info!(api_key_id = key_id, user_id = user.id, "api key revoked");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
 async fn list_artifacts(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<ArtifactListQuery>,
 ) -> Result<Json<Vec<ArtifactSummary>>, ApiError> {
     let user = authenticate(&state, &headers).await?;
     let db = state.db.lock().await;
 
-    let mut sql = "SELECT a.id, a.owner_user_id, u.username, a.name, a.note, a.target, a.is_public, a.created_at FROM artifacts a JOIN users u ON u.id = a.owner_user_id".to_string();
-    if state.auth_mode == AuthMode::Required {
-        sql.push_str(" WHERE a.owner_user_id = ?1 OR a.is_public = 1");
+    let filter = match (query.scope, state.auth_mode) {
+        // The trash only ever shows the caller's own artifacts.
+        _ if query.deleted => Some("a.owner_user_id = ?1"),
+        (ArtifactScope::Mine, _) => Some("a.owner_user_id = ?1"),
+        (ArtifactScope::Public, _) => Some("a.is_public = 1"),
+        (ArtifactScope::All, AuthMode::Required | AuthMode::ApiKeys) => {
+            Some("a.owner_user_id = ?1 OR a.is_public = 1")
+        }
+        (ArtifactScope::All, AuthMode::Disabled) => None,
+        // Stars outlive visibility changes; only list what the caller can still download.
+        (ArtifactScope::Starred, _) => {
+            Some("s.user_id IS NOT NULL AND (a.owner_user_id = ?1 OR a.is_public = 1)")
+        }
+    };
+
+    let mut sql = "SELECT a.id, a.owner_user_id, u.username, a.name, a.note, a.target, a.is_public, a.created_at, s.user_id IS NOT NULL, a.abi_version, a.build_id, a.deleted_at, COALESCE(r.races, 0), COALESCE(r.wins, 0), r.best_lap FROM artifacts a JOIN users u ON u.id = a.owner_user_id LEFT JOIN artifact_stars s ON s.artifact_id = a.id AND s.user_id = ?1 LEFT JOIN (SELECT e.artifact_id, COUNT(*) AS races, SUM(e.position = 1) AS wins, MIN(e.best_lap_secs) AS best_lap FROM race_entries e JOIN races rv ON rv.id = e.race_id WHERE rv.verified_at IS NOT NULL GROUP BY e.artifact_id) r ON r.artifact_id = a.id".to_string();
+    sql.push_str(if query.deleted {
+        " WHERE a.deleted_at IS NOT NULL"
+    } else {
+        " WHERE a.deleted_at IS NULL"
+    });
+    if let Some(filter) = filter {
+        sql.push_str(" AND (");
+        sql.push_str(filter);
+        sql.push(')');
     }
-    sql.push_str(" ORDER BY a.created_at DESC");
+    // `id` breaks ties between artifacts created within the same second so that
+    // consecutive pages never overlap or skip rows.
+    sql.push_str(" ORDER BY a.created_at DESC, a.id DESC");
+    push_page_clause(&mut sql, query.limit, query.offset, MAX_ARTIFACT_PAGE_SIZE);
 
     let mut stmt = db
         .prepare(&sql)
@@ -503,7 +984,7 @@ async fn list_artifacts(
     let mapper = |row: &rusqlite::Row<'_>| {
         let owner_user_id: i64 = row.get(1)?;
         Ok(ArtifactSummary {
-            id: row.get(0)?,
+            id: ArtifactId(row.get(0)?),
             owner_user_id,
             owner_username: row.get(2)?,
             name: row.get(3)?,
@@ -511,16 +992,20 @@ async fn list_artifacts(
             target: row.get(5)?,
             is_public: row.get::<_, i64>(6)? != 0,
             owned_by_me: owner_user_id == user.id,
+            starred: row.get(8)?,
+            abi_version: row.get(9)?,
+            build_id: row.get(10)?,
+            races_count: row.get(12)?,
+            wins: row.get(13)?,
+            best_lap_secs: row.get(14)?,
             created_at: row.get(7)?,
+            deleted_at: row.get(11)?,
         })
     };
 
-    let rows = if state.auth_mode == AuthMode::Required {
-        stmt.query_map(params![user.id], mapper)
-    } else {
-        stmt.query_map([], mapper)
-    }
-    .map_err(|e| ApiError::internal(format!("failed to query artifacts: {e}")))?;
+    let rows = stmt
+        .query_map(params![user.id], mapper)
+        .map_err(|e| ApiError::internal(format!("failed to query artifacts: {e}")))?;
 
     let mut out = Vec::new();
     for item in rows {
@@ -542,28 +1027,173 @@ async fn upload_artifact(
     if payload.name.trim().is_empty() {
         return Err(ApiError::bad_request("artifact name must not be empty"));
     }
-    if payload.target.trim().is_empty() {
-        return Err(ApiError::bad_request("artifact target must not be empty"));
-    }
+    validate_artifact_target(&payload.target)?;
 
     let elf_bytes = base64::engine::general_purpose::STANDARD
         .decode(payload.elf_base64.as_bytes())
         .map_err(|e| ApiError::bad_request(format!("invalid elf base64: {e}")))?;
+
+    let build_id = validate_build_id(payload.build_id)?;
+    let is_public = payload.is_public.unwrap_or(state.default_artifact_public);
+    let (artifact_id, name) = store_artifact(
+        &state,
+        &user,
+        payload.name.trim(),
+        payload.note,
+        payload.target.trim(),
+        elf_bytes,
+        is_public,
+        build_id,
+    )
+    .await?;
+
+    info!(
+        artifact_id,
+        owner_user_id = user.id,
+        artifact_name = %name,
+        target = payload.target.trim(),
+        is_public,
+        "artifact uploaded"
+    );
+    Ok(Json(UploadArtifactResponse {
+        artifact_id: ArtifactId(artifact_id),
+        name,
+    }))
+}
+
+async fn import_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ImportArtifactRequest>,
+) -> Result<Json<UploadArtifactResponse>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+
+    validate_artifact_target(&payload.target)?;
+    let name = match payload.name.as_deref().map(str::trim) {
+        Some("") => return Err(ApiError::bad_request("artifact name must not be empty")),
+        Some(name) => name.to_string(),
+        None => import_name_from_url(&payload.url),
+    };
+
+    let url = payload.url.clone();
+    let allow_private_hosts = state.import_allow_private_hosts;
+    let elf_bytes =
+        tokio::task::spawn_blocking(move || fetch_import_url(&url, allow_private_hosts))
+            .await
+            .map_err(|e| ApiError::internal(format!("artifact import task failed: {e}")))?
+            .map_err(|e| {
+                warn!(url = %payload.url, error = %e, "artifact import fetch failed");
+                ApiError::bad_request(format!("failed to import artifact: {e}"))
+            })?;
+
+    let is_public = payload.is_public.unwrap_or(state.default_artifact_public);
+    let (artifact_id, name) = store_artifact(
+        &state,
+        &user,
+        &name,
+        payload.note,
+        payload.target.trim(),
+        elf_bytes,
+        is_public,
+        None,
+    )
+    .await?;
+
+    info!(
+        artifact_id,
+        owner_user_id = user.id,
+        artifact_name = %name,
+        url = %payload.url,
+        is_public,
+        "artifact imported"
+    );
+    Ok(Json(UploadArtifactResponse {
+        artifact_id: ArtifactId(artifact_id),
+        name,
+    }))
+}
+
+/// Trims a requested build id; blank ids count as absent.
+fn validate_build_id(build_id: Option<String>) -> Result<Option<String>, ApiError> {
+    let Some(build_id) = build_id.map(|id| id.trim().to_string()) else {
+        return Ok(None);
+    };
+    if build_id.is_empty() {
+        return Ok(None);
+    }
+    if build_id.len() > MAX_BUILD_ID_LEN {
+        return Err(ApiError::bad_request(format!(
+            "build id exceeds {MAX_BUILD_ID_LEN} bytes"
+        )));
+    }
+    Ok(Some(build_id))
+}
+
+fn validate_artifact_target(target: &str) -> Result<(), ApiError> {
+    if target.trim().is_empty() {
+        return Err(ApiError::bad_request("artifact target must not be empty"));
+    }
+    if !ALLOWED_ARTIFACT_TARGETS.contains(&target.trim()) {
+        return Err(ApiError::bad_request(format!(
+            "unsupported artifact target '{}'",
+            target.trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Writes a new artifact row plus its ELF file and returns the artifact id.
+/// Without an explicit `build_id` the one embedded in the ELF is recorded.
+#[allow(clippy::too_many_arguments)]
+async fn store_artifact(
+    state: &AppState,
+    user: &UserInfo,
+    name: &str,
+    note: Option<String>,
+    target: &str,
+    elf_bytes: Vec<u8>,
+    is_public: bool,
+    build_id: Option<String>,
+) -> Result<(i64, String), ApiError> {
+    let db = state.db.lock().await;
+    let (artifact_id, name, _) = insert_artifact(
+        &db, state, user, name, note, target, &elf_bytes, is_public, build_id,
+    )?;
+    Ok((artifact_id, name))
+}
+
+/// [`store_artifact`] on an already locked connection, which may be inside a
+/// transaction. Also returns the path of the written ELF, so a caller that
+/// rolls back can remove it.
+#[allow(clippy::too_many_arguments)]
+fn insert_artifact(
+    db: &Connection,
+    state: &AppState,
+    user: &UserInfo,
+    name: &str,
+    note: Option<String>,
+    target: &str,
+    elf_bytes: &[u8],
+    is_public: bool,
+    build_id: Option<String>,
+) -> Result<(i64, String, PathBuf), ApiError> {
     if elf_bytes.is_empty() {
         return Err(ApiError::bad_request("elf payload must not be empty"));
     }
+    if elf_bytes.len() > MAX_ARTIFACT_BYTES {
+        return Err(ApiError::bad_request(format!(
+            "elf payload exceeds {MAX_ARTIFACT_BYTES} bytes"
+        )));
+    }
 
-    let db = state.db.lock().await;
+    let abi_version = validate_abi_version(elf_bytes)?;
+    validate_entry_point(elf_bytes)?;
+    let build_id = build_id.or_else(|| artifact_build_id(elf_bytes));
+    let name = resolve_artifact_name(db, state.duplicate_names, user.id, name)?;
     let now = now_utc();
     db.execute(
-        "INSERT INTO artifacts (owner_user_id, name, note, target, elf_path, is_public, created_at) VALUES (?1, ?2, ?3, ?4, '', 0, ?5)",
-        params![
-            user.id,
-            payload.name.trim(),
-            payload.note,
-            payload.target.trim(),
-            now
-        ],
+        "INSERT INTO artifacts (owner_user_id, name, note, target, elf_path, is_public, abi_version, build_id, created_at) VALUES (?1, ?2, ?3, ?4, '', ?5, ?6, ?7, ?8)",
+        params![user.id, name, note, target, is_public as i64, abi_version, build_id, now],
     )
     .map_err(|e| ApiError::internal(format!("failed to create artifact row: {e}")))?;
 
@@ -583,16 +1213,213 @@ async fn upload_artifact(
         params![artifact_name, artifact_id],
     )
     .map_err(|e| ApiError::internal(format!("failed to update artifact path: {e}")))?;
+    Ok((artifact_id, name, artifact_path))
+}
 
-    info!(
-        artifact_id,
-        owner_user_id = user.id,
-        artifact_name = payload.name.trim(),
-        target = payload.target.trim(),
-        is_public = false,
-        "artifact uploaded"
-    );
-    Ok(Json(UploadArtifactResponse { artifact_id }))
+/// ABI version of an uploaded ELF, refusing versions the game cannot run.
+/// Images without an ABI section are accepted, as the game loads them too.
+fn validate_abi_version(elf_bytes: &[u8]) -> Result<Option<u32>, ApiError> {
+    match artifact_abi_version(elf_bytes) {
+        Some(version) if !SUPPORTED_BOT_ABI_VERSIONS.contains(&version) => {
+            let supported = SUPPORTED_BOT_ABI_VERSIONS
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(ApiError::bad_request(format!(
+                "bot was built for ABI version {version}, this server accepts {supported}; rebuild it against a current SDK"
+            )))
+        }
+        version => Ok(version),
+    }
+}
+
+/// Refuses an ELF whose entry point is not inside a loaded executable
+/// (`PF_X`) segment, which the game would reject when spawning the car.
+/// Images that do not parse are left to the game, as for the ABI check.
+fn validate_entry_point(elf_bytes: &[u8]) -> Result<(), ApiError> {
+    let Ok(elf) = ElfBytes::<LittleEndian>::minimal_parse(elf_bytes) else {
+        return Ok(());
+    };
+    let Some(segments) = elf.segments() else {
+        return Ok(());
+    };
+    let entry = elf.ehdr.e_entry;
+    if segments.iter().any(|phdr| {
+        phdr.p_type == PT_LOAD
+            && phdr.p_flags & PF_X != 0
+            && entry >= phdr.p_vaddr
+            && entry - phdr.p_vaddr < phdr.p_memsz
+    }) {
+        return Ok(());
+    }
+    Err(ApiError::bad_request(format!(
+        "entry point 0x{entry:x} is not inside a loaded executable segment"
+    )))
+}
+
+/// Bot ABI version recorded in an uploaded ELF, or `None` if the image is not
+/// an ELF or was built without the SDK's ABI section.
+fn artifact_abi_version(elf_bytes: &[u8]) -> Option<u32> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(elf_bytes).ok()?;
+    let header = elf.section_header_by_name(BOT_ABI_SECTION).ok()??;
+    let (data, _) = elf.section_data(&header).ok()?;
+    Some(u32::from_le_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+/// Build id embedded in an uploaded ELF ([`BOT_BUILD_ID_SECTION`]), if it is
+/// non-empty UTF-8 of at most [`MAX_BUILD_ID_LEN`] bytes.
+fn artifact_build_id(elf_bytes: &[u8]) -> Option<String> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(elf_bytes).ok()?;
+    let header = elf.section_header_by_name(BOT_BUILD_ID_SECTION).ok()??;
+    let (data, _) = elf.section_data(&header).ok()?;
+    let id = std::str::from_utf8(data)
+        .ok()?
+        .trim_end_matches('\0')
+        .trim();
+    (!id.is_empty() && id.len() <= MAX_BUILD_ID_LEN).then(|| id.to_string())
+}
+
+/// Applies the duplicate-name policy to a name `owner_user_id` is about to
+/// store an artifact under.
+fn resolve_artifact_name(
+    db: &Connection,
+    policy: DuplicateNamePolicy,
+    owner_user_id: i64,
+    name: &str,
+) -> Result<String, ApiError> {
+    let taken = |candidate: &str| -> Result<bool, ApiError> {
+        db.query_row(
+            "SELECT EXISTS(SELECT 1 FROM artifacts WHERE owner_user_id = ?1 AND name = ?2 AND deleted_at IS NULL)",
+            params![owner_user_id, candidate],
+            |r| r.get(0),
+        )
+        .map_err(|e| ApiError::internal(format!("failed to check artifact name: {e}")))
+    };
+    if !taken(name)? {
+        return Ok(name.to_string());
+    }
+    if policy == DuplicateNamePolicy::Reject {
+        return Err(ApiError::conflict(format!(
+            "you already have an artifact named '{name}'"
+        )));
+    }
+
+    // Keep a file extension at the end: `car.elf` becomes `car (2).elf`.
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{stem} ({counter}){extension}");
+        if !taken(&candidate)? {
+            return Ok(candidate);
+        }
+        counter += 1;
+    }
+}
+
+/// Last path segment of an import URL, used when the request names no artifact.
+fn import_name_from_url(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    path.split_once('/')
+        .and_then(|(_, path)| path.rsplit('/').find(|segment| !segment.is_empty()))
+        .map(|segment| urlencoding::decode(segment).map_or(segment.to_string(), |s| s.into_owned()))
+        .unwrap_or_else(|| "imported.elf".to_string())
+}
+
+/// Downloads an import URL with the artifact size limit and a timeout. Every
+/// host the request connects to, including redirect targets, must resolve to
+/// public addresses unless `allow_private_hosts` is set.
+fn fetch_import_url(url: &str, allow_private_hosts: bool) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    use std::net::ToSocketAddrs;
+
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+    if !matches!(scheme.as_deref(), Some("http" | "https")) {
+        return Err("only http and https URLs can be imported".to_string());
+    }
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout(IMPORT_TIMEOUT)
+        .redirects(IMPORT_MAX_REDIRECTS)
+        .resolver(move |netloc: &str| -> std::io::Result<Vec<SocketAddr>> {
+            let addrs: Vec<SocketAddr> = netloc.to_socket_addrs()?.collect();
+            let blocked = addrs
+                .iter()
+                .find(|addr| !allow_private_hosts && !is_public_address(addr.ip()));
+            match blocked {
+                Some(addr) => Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!("{} is not a public address", addr.ip()),
+                )),
+                None => Ok(addrs),
+            }
+        })
+        .build();
+    let response = agent.get(url).call().map_err(|e| e.to_string())?;
+
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_ARTIFACT_BYTES as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("failed to read response: {e}"))?;
+    if bytes.len() > MAX_ARTIFACT_BYTES {
+        return Err(format!("artifact exceeds {MAX_ARTIFACT_BYTES} bytes"));
+    }
+    Ok(bytes)
+}
+
+/// Whether `ip` is routable on the public internet, i.e. not loopback,
+/// private, link-local (cloud metadata), carrier-grade NAT, multicast or
+/// otherwise reserved.
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_unspecified()
+                || v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (18..20).contains(&b))
+                || a >= 240)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = embedded_ipv4(v6) {
+                return is_public_address(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_unspecified()
+                || v6.is_loopback()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// The IPv4 address carried by an IPv4-mapped (`::ffff:0:0/96`),
+/// IPv4-compatible (`::/96`), NAT64 (`64:ff9b::/96`) or 6to4 (`2002::/16`)
+/// address, since a request to it ends up at that IPv4 host.
+fn embedded_ipv4(v6: Ipv6Addr) -> Option<Ipv4Addr> {
+    let segments = v6.segments();
+    let [.., a, b, c, d] = v6.octets();
+    match segments {
+        [0, 0, 0, 0, 0, 0 | 0xffff, _, _] => Some(Ipv4Addr::new(a, b, c, d)),
+        [0x64, 0xff9b, 0, 0, 0, 0, _, _] => Some(Ipv4Addr::new(a, b, c, d)),
+        [0x2002, high, low, ..] => Some(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low))),
+        _ => None,
+    }
 }
 
 async fn download_artifact(
@@ -605,7 +1432,7 @@ async fn download_artifact(
 
     let row: Option<(i64, String, i64)> = db
         .query_row(
-            "SELECT owner_user_id, elf_path, is_public FROM artifacts WHERE id = ?1",
+            "SELECT owner_user_id, elf_path, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
             params![artifact_id],
             |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
         )
@@ -616,10 +1443,8 @@ async fn download_artifact(
         return Err(ApiError::not_found("artifact not found"));
     };
 
-    if state.auth_mode == AuthMode::Required && owner_user_id != user.id && is_public == 0 {
-        return Err(ApiError::unauthorized(
-            "artifact is not owned by current user",
-        ));
+    if state.auth_mode.auth_required() && owner_user_id != user.id && is_public == 0 {
+        return Err(ApiError::forbidden("artifact is not owned by current user"));
     }
 
     let full_path = state.artifacts_dir.join(rel_path);
@@ -637,58 +1462,602 @@ async fn download_artifact(
         .into_response())
 }
 
-async fn delete_artifact(
+/// An artifact row read for `/api/v1/artifacts/export`.
+struct ExportRow {
+    id: i64,
+    name: String,
+    note: Option<String>,
+    target: String,
+    elf_path: String,
+    is_public: bool,
+    abi_version: Option<u32>,
+    build_id: Option<String>,
+    created_at: String,
+}
+
+/// Streams the caller's live artifacts as a tar archive: one ELF per
+/// artifact, then [`ARTIFACT_EXPORT_MANIFEST`]. Only one ELF is held in memory
+/// at a time; the total is checked against `max_export_bytes` up front.
+async fn export_artifacts(
     State(state): State<AppState>,
     headers: HeaderMap,
-    AxumPath(artifact_id): AxumPath<i64>,
 ) -> Result<Response, ApiError> {
     let user = authenticate(&state, &headers).await?;
-    let db = state.db.lock().await;
+    let rows = {
+        let db = state.db.lock().await;
+        let mut stmt = db
+            .prepare(
+                "SELECT id, name, note, target, elf_path, is_public, abi_version, build_id, created_at
+                 FROM artifacts
+                 WHERE owner_user_id = ?1 AND deleted_at IS NULL
+                 ORDER BY id",
+            )
+            .map_err(|e| ApiError::internal(format!("failed to prepare export query: {e}")))?;
+        stmt.query_map(params![user.id], |r| {
+            Ok(ExportRow {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                note: r.get(2)?,
+                target: r.get(3)?,
+                elf_path: r.get(4)?,
+                is_public: r.get::<_, i64>(5)? != 0,
+                abi_version: r.get(6)?,
+                build_id: r.get(7)?,
+                created_at: r.get(8)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| ApiError::internal(format!("failed to query artifacts: {e}")))?
+    };
 
-    let row: Option<(i64, String)> = db
+    let mut total: u64 = 0;
+    for row in &rows {
+        let metadata = std::fs::metadata(state.artifacts_dir.join(&row.elf_path))
+            .map_err(|e| ApiError::internal(format!("failed to read artifact file: {e}")))?;
+        total += metadata.len();
+    }
+    if total > state.max_export_bytes {
+        return Err(ApiError::payload_too_large(format!(
+            "export would hold {total} bytes of artifacts; the limit is {}",
+            state.max_export_bytes
+        )));
+    }
+
+    let export = ArtifactExport {
+        rows: rows.into_iter(),
+        artifacts_dir: state.artifacts_dir.clone(),
+        exported_at: Utc::now(),
+        entries: Vec::new(),
+        finished: false,
+    };
+    let stream = futures_util::stream::unfold(export, |mut export| async move {
+        export.next_chunk().map(|chunk| (chunk, export))
+    });
+
+    Ok((
+        StatusCode::OK,
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/x-tar"),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_static("attachment; filename=\"botracers-artifacts.tar\""),
+            ),
+        ],
+        axum::body::Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+/// State of an export being streamed; each `next_chunk` yields one tar entry.
+struct ArtifactExport {
+    rows: std::vec::IntoIter<ExportRow>,
+    artifacts_dir: PathBuf,
+    exported_at: DateTime<Utc>,
+    entries: Vec<ArtifactExportEntry>,
+    finished: bool,
+}
+
+impl ArtifactExport {
+    fn next_chunk(&mut self) -> Option<std::io::Result<Bytes>> {
+        if self.finished {
+            return None;
+        }
+        let mtime = self.exported_at.timestamp().max(0) as u64;
+
+        let Some(row) = self.rows.next() else {
+            self.finished = true;
+            let manifest = ArtifactExportManifest {
+                exported_at: self.exported_at.to_rfc3339(),
+                artifacts: std::mem::take(&mut self.entries),
+            };
+            let json = match serde_json::to_vec_pretty(&manifest) {
+                Ok(json) => json,
+                Err(error) => return Some(Err(std::io::Error::other(error))),
+            };
+            let mut chunk = tar_entry(ARTIFACT_EXPORT_MANIFEST, &json, mtime);
+            // Two zero blocks end the archive.
+            chunk.extend_from_slice(&[0; 1024]);
+            return Some(Ok(Bytes::from(chunk)));
+        };
+
+        let bytes = match std::fs::read(self.artifacts_dir.join(&row.elf_path)) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                self.finished = true;
+                return Some(Err(error));
+            }
+        };
+        let file = export_file_name(row.id, &row.name);
+        let chunk = tar_entry(&file, &bytes, mtime);
+        self.entries.push(ArtifactExportEntry {
+            id: ArtifactId(row.id),
+            name: row.name,
+            note: row.note,
+            target: row.target,
+            is_public: row.is_public,
+            abi_version: row.abi_version,
+            build_id: row.build_id,
+            created_at: row.created_at,
+            file,
+            size: bytes.len() as u64,
+            sha256: hex::encode(Sha256::digest(&bytes)),
+        });
+        Some(Ok(Bytes::from(chunk)))
+    }
+}
+
+/// Tarball path of an exported ELF: the id keeps it unique, the name (reduced
+/// to portable characters) keeps it recognisable.
+fn export_file_name(id: i64, name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .take(MAX_EXPORT_NAME_LEN)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("artifacts/{id}-{safe}")
+}
+
+/// One ustar entry: the 512-byte header, `data`, and zero padding up to the
+/// next block boundary.
+fn tar_entry(path: &str, data: &[u8], mtime: u64) -> Vec<u8> {
+    let mut header = [0u8; 512];
+    let name = path.as_bytes();
+    header[..name.len().min(100)].copy_from_slice(&name[..name.len().min(100)]);
+    write_tar_octal(&mut header[100..108], 0o644);
+    write_tar_octal(&mut header[108..116], 0);
+    write_tar_octal(&mut header[116..124], 0);
+    write_tar_octal(&mut header[124..136], data.len() as u64);
+    write_tar_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // The checksum is taken with its own field filled with spaces.
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+    let padding = (512 - data.len() % 512) % 512;
+    let mut entry = Vec::with_capacity(512 + data.len() + padding);
+    entry.extend_from_slice(&header);
+    entry.extend_from_slice(data);
+    entry.resize(entry.len() + padding, 0);
+    entry
+}
+
+/// Writes `value` as NUL-terminated, zero-padded octal filling `field`.
+fn write_tar_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[field.len() - 1] = 0;
+}
+
+/// Splits a ustar archive into `(path, data)` entries, stopping at the first
+/// zero block. Only regular files are expected, as written by `tar_entry`.
+fn read_tar(archive: &[u8]) -> Result<Vec<(String, &[u8])>, String> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + 512 <= archive.len() {
+        let header = &archive[offset..offset + 512];
+        if header.iter().all(|&b| b == 0) {
+            return Ok(entries);
+        }
+        let field = |range: std::ops::Range<usize>| {
+            String::from_utf8_lossy(&header[range])
+                .trim_matches(|c| c == '\0' || c == ' ')
+                .to_string()
+        };
+        let checksum: u32 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u32::from(b)
+                }
+            })
+            .sum();
+        if u32::from_str_radix(&field(148..156), 8) != Ok(checksum) {
+            return Err(format!("bad tar header checksum at byte {offset}"));
+        }
+        let size = usize::from_str_radix(&field(124..136), 8)
+            .map_err(|_| format!("bad tar entry size at byte {offset}"))?;
+        let start = offset + 512;
+        let data = archive
+            .get(start..start + size)
+            .ok_or_else(|| format!("tar entry at byte {offset} is truncated"))?;
+        entries.push((field(0..100), data));
+        offset = start + size.div_ceil(512) * 512;
+    }
+    Err("tar archive has no end marker".to_string())
+}
+
+/// Recreates the artifacts of an `/api/v1/artifacts/export` tarball under the
+/// caller. Every entry is checked against the manifest (size, SHA-256, ELF
+/// magic, target, ABI version, build id) before anything is stored, and the
+/// rows are written in one transaction, so a bad archive imports nothing and
+/// replaces nothing.
+async fn import_artifact_archive(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ImportArchiveQuery>,
+    body: Bytes,
+) -> Result<Json<ImportArchiveResponse>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+
+    let entries = read_tar(&body).map_err(ApiError::bad_request)?;
+    let files: HashMap<&str, &[u8]> = entries
+        .iter()
+        .map(|(path, data)| (path.as_str(), *data))
+        .collect();
+    let manifest = files.get(ARTIFACT_EXPORT_MANIFEST).ok_or_else(|| {
+        ApiError::bad_request(format!("archive has no {ARTIFACT_EXPORT_MANIFEST}"))
+    })?;
+    let manifest: ArtifactExportManifest = serde_json::from_slice(manifest)
+        .map_err(|e| ApiError::bad_request(format!("invalid {ARTIFACT_EXPORT_MANIFEST}: {e}")))?;
+
+    if manifest.artifacts.len() > MAX_IMPORT_ARTIFACTS {
+        return Err(ApiError::bad_request(format!(
+            "archive holds {} artifacts, at most {MAX_IMPORT_ARTIFACTS} can be imported at once",
+            manifest.artifacts.len()
+        )));
+    }
+    let mut pending = Vec::with_capacity(manifest.artifacts.len());
+    for entry in manifest.artifacts {
+        let data = files
+            .get(entry.file.as_str())
+            .ok_or_else(|| ApiError::bad_request(format!("archive is missing {}", entry.file)))?;
+        if data.len() as u64 != entry.size || hex::encode(Sha256::digest(data)) != entry.sha256 {
+            return Err(ApiError::bad_request(format!(
+                "{} does not match its manifest checksum",
+                entry.file
+            )));
+        }
+        if !data.starts_with(b"\x7fELF") {
+            return Err(ApiError::bad_request(format!(
+                "{} is not an ELF",
+                entry.file
+            )));
+        }
+        if data.len() > MAX_ARTIFACT_BYTES {
+            return Err(ApiError::bad_request(format!(
+                "{} exceeds {MAX_ARTIFACT_BYTES} bytes",
+                entry.file
+            )));
+        }
+        if entry.name.trim().is_empty() {
+            return Err(ApiError::bad_request(format!(
+                "{} has an empty artifact name",
+                entry.file
+            )));
+        }
+        validate_artifact_target(&entry.target)?;
+        validate_abi_version(data)?;
+        validate_entry_point(data)?;
+        let build_id = validate_build_id(entry.build_id.clone())?;
+        pending.push((entry, build_id, *data));
+    }
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    let mut written = Vec::new();
+    let mut db = state.db.lock().await;
+    let tx = db
+        .transaction()
+        .map_err(|e| ApiError::internal(format!("failed to start import: {e}")))?;
+    let outcome = (|| {
+        for (entry, build_id, elf_bytes) in pending {
+            let name = entry.name.trim();
+            let exists: bool = tx
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM artifacts WHERE owner_user_id = ?1 AND name = ?2 AND deleted_at IS NULL)",
+                    params![user.id, name],
+                    |r| r.get(0),
+                )
+                .map_err(|e| ApiError::internal(format!("failed to check artifact name: {e}")))?;
+            if exists {
+                if query.on_duplicate == ArchiveDuplicates::Skip {
+                    skipped.push(name.to_string());
+                    continue;
+                }
+                tx.execute(
+                    "UPDATE artifacts SET deleted_at = ?1 WHERE owner_user_id = ?2 AND name = ?3 AND deleted_at IS NULL",
+                    params![now_utc(), user.id, name],
+                )
+                .map_err(|e| ApiError::internal(format!("failed to replace artifact: {e}")))?;
+            }
+            let (artifact_id, name, path) = insert_artifact(
+                &tx,
+                &state,
+                &user,
+                name,
+                entry.note,
+                entry.target.trim(),
+                elf_bytes,
+                entry.is_public,
+                build_id,
+            )?;
+            written.push(path);
+            imported.push(UploadArtifactResponse {
+                artifact_id: ArtifactId(artifact_id),
+                name,
+            });
+        }
+        tx.commit()
+            .map_err(|e| ApiError::internal(format!("failed to finish import: {e}")))
+    })();
+    if let Err(error) = outcome {
+        // The transaction rolled back; drop the files it had written.
+        for path in written {
+            let _ = std::fs::remove_file(path);
+        }
+        return Err(error);
+    }
+
+    info!(
+        owner_user_id = user.id,
+        imported = imported.len(),
+        skipped = skipped.len(),
+        "artifact archive imported"
+    );
+    Ok(Json(ImportArchiveResponse { imported, skipped }))
+}
+
+/// Moves an artifact to the trash. It stays restorable until
+/// `purge_expired_artifacts` removes it after the retention period.
+async fn delete_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(artifact_id): AxumPath<i64>,
+) -> Result<Response, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
+
+    let owner_user_id: Option<i64> = db
         .query_row(
-            "SELECT owner_user_id, elf_path FROM artifacts WHERE id = ?1",
+            "SELECT owner_user_id FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
             params![artifact_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+            |r| r.get(0),
         )
         .optional()
         .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
 
-    let Some((owner_user_id, rel_path)) = row else {
+    let Some(owner_user_id) = owner_user_id else {
         return Err(ApiError::not_found("artifact not found"));
     };
 
-    if state.auth_mode == AuthMode::Required && owner_user_id != user.id {
-        return Err(ApiError::unauthorized(
-            "artifact is not owned by current user",
-        ));
+    if state.auth_mode.auth_required() && owner_user_id != user.id {
+        return Err(ApiError::forbidden("artifact is not owned by current user"));
+    }
+
+    db.execute(
+        "UPDATE artifacts SET deleted_at = ?1 WHERE id = ?2",
+        params![now_utc(), artifact_id],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to delete artifact: {e}")))?;
+
+    info!(
+        artifact_id,
+        owner_user_id = user.id,
+        "artifact moved to trash"
+    );
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn restore_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(artifact_id): AxumPath<i64>,
+) -> Result<Json<UploadArtifactResponse>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
+
+    let row: Option<(i64, String)> = db
+        .query_row(
+            "SELECT owner_user_id, name FROM artifacts WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![artifact_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
+
+    let Some((owner_user_id, name)) = row else {
+        return Err(ApiError::not_found("artifact not found in trash"));
+    };
+
+    if state.auth_mode.auth_required() && owner_user_id != user.id {
+        return Err(ApiError::forbidden("artifact is not owned by current user"));
+    }
+
+    // The name may have been reused while the artifact was in the trash.
+    let name = resolve_artifact_name(&db, state.duplicate_names, owner_user_id, &name)?;
+    db.execute(
+        "UPDATE artifacts SET deleted_at = NULL, name = ?1 WHERE id = ?2",
+        params![name, artifact_id],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to restore artifact: {e}")))?;
+
+    info!(artifact_id, owner_user_id = user.id, "artifact restored");
+    Ok(Json(UploadArtifactResponse {
+        artifact_id: ArtifactId(artifact_id),
+        name,
+    }))
+}
+
+/// Permanently removes artifacts trashed longer than the retention period,
+/// deleting each ELF file once no other row (clones included) shares it.
+/// Returns how many artifacts were purged.
+async fn purge_expired_artifacts(state: &AppState, now: DateTime<Utc>) -> usize {
+    let cutoff = (now - TimeDelta::days(state.artifact_retention_days.into())).to_rfc3339();
+    let db = state.db.lock().await;
+    match purge_artifacts_deleted_before(&db, &state.artifacts_dir, &cutoff) {
+        Ok(0) => 0,
+        Ok(purged) => {
+            info!(purged, "purged expired artifacts from the trash");
+            purged
+        }
+        Err(error) => {
+            warn!(error = %error.message, "failed to purge expired artifacts");
+            0
+        }
+    }
+}
+
+fn purge_artifacts_deleted_before(
+    db: &Connection,
+    artifacts_dir: &Path,
+    cutoff: &str,
+) -> Result<usize, ApiError> {
+    let expired = {
+        let mut stmt = db
+            .prepare("SELECT id, elf_path FROM artifacts WHERE deleted_at IS NOT NULL AND deleted_at <= ?1")
+            .map_err(|e| ApiError::internal(format!("failed to prepare purge query: {e}")))?;
+        let rows = stmt
+            .query_map(params![cutoff], |r| {
+                Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))
+            })
+            .map_err(|e| ApiError::internal(format!("failed to query expired artifacts: {e}")))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ApiError::internal(format!("failed to read expired artifact: {e}")))?
+    };
+
+    for (artifact_id, rel_path) in &expired {
+        db.execute("DELETE FROM artifacts WHERE id = ?1", params![artifact_id])
+            .map_err(|e| ApiError::internal(format!("failed to delete artifact row: {e}")))?;
+
+        let shared_refs: i64 = db
+            .query_row(
+                "SELECT COUNT(*) FROM artifacts WHERE elf_path = ?1",
+                params![rel_path],
+                |r| r.get(0),
+            )
+            .map_err(|e| ApiError::internal(format!("failed to query artifact references: {e}")))?;
+        if shared_refs == 0 {
+            remove_artifact_file(artifacts_dir, rel_path)?;
+        }
+    }
+
+    Ok(expired.len())
+}
+
+/// Deletes files in the artifact store that no artifact row, trashed ones
+/// included, points at. Only names the server writes (`artifact_<id>.elf`)
+/// are considered, so other files in a shared directory are left alone. The
+/// scan holds the database lock, and uploads insert
+/// their row before writing the file under that same lock, so a blob that is
+/// being stored is never mistaken for an orphan.
+async fn collect_garbage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<GarbageCollectResponse>, ApiError> {
+    let user = require_admin(&state, &headers).await?;
+    let db = state.db.lock().await;
+    let collected = remove_orphaned_artifact_files(&db, &state.artifacts_dir)?;
+    info!(
+        admin = %user.username,
+        removed_files = collected.removed_files,
+        reclaimed_bytes = collected.reclaimed_bytes,
+        "collected orphaned artifact files"
+    );
+    Ok(Json(collected))
+}
+
+fn remove_orphaned_artifact_files(
+    db: &Connection,
+    artifacts_dir: &Path,
+) -> Result<GarbageCollectResponse, ApiError> {
+    let referenced: HashSet<String> = {
+        let mut stmt = db
+            .prepare("SELECT DISTINCT elf_path FROM artifacts")
+            .map_err(|e| ApiError::internal(format!("failed to prepare gc query: {e}")))?;
+        let rows = stmt
+            .query_map([], |r| r.get::<_, String>(0))
+            .map_err(|e| ApiError::internal(format!("failed to query artifact files: {e}")))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| ApiError::internal(format!("failed to read artifact file: {e}")))?
+    };
+
+    let entries = std::fs::read_dir(artifacts_dir)
+        .map_err(|e| ApiError::internal(format!("failed to scan artifact store: {e}")))?;
+    let mut collected = GarbageCollectResponse::default();
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| ApiError::internal(format!("failed to scan artifact store: {e}")))?;
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        // Names that are not UTF-8 were never written by the server.
+        let Ok(file_name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if !metadata.is_file()
+            || !is_artifact_file_name(&file_name)
+            || referenced.contains(&file_name)
+        {
+            continue;
+        }
+        remove_artifact_file(artifacts_dir, &file_name)?;
+        collected.removed_files += 1;
+        collected.reclaimed_bytes += metadata.len();
     }
+    Ok(collected)
+}
+
+/// Whether `file_name` has the `artifact_<id>.elf` form of a stored ELF.
+fn is_artifact_file_name(file_name: &str) -> bool {
+    file_name
+        .strip_prefix("artifact_")
+        .and_then(|rest| rest.strip_suffix(".elf"))
+        .is_some_and(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+}
 
-    let relative = Path::new(&rel_path);
+fn remove_artifact_file(artifacts_dir: &Path, rel_path: &str) -> Result<(), ApiError> {
+    let relative = Path::new(rel_path);
     if relative.is_absolute() || relative.components().count() != 1 {
         return Err(ApiError::internal("invalid artifact file path"));
     }
 
-    let full_path = state.artifacts_dir.join(relative);
-    if !full_path.starts_with(&state.artifacts_dir) {
+    let full_path = artifacts_dir.join(relative);
+    if !full_path.starts_with(artifacts_dir) {
         return Err(ApiError::internal("artifact path escaped storage root"));
     }
 
     match std::fs::remove_file(&full_path) {
-        Ok(()) => {}
-        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
-        Err(error) => {
-            return Err(ApiError::internal(format!(
-                "failed to delete artifact file: {error}"
-            )));
-        }
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(ApiError::internal(format!(
+            "failed to delete artifact file: {error}"
+        ))),
     }
-
-    db.execute("DELETE FROM artifacts WHERE id = ?1", params![artifact_id])
-        .map_err(|e| ApiError::internal(format!("failed to delete artifact row: {e}")))?;
-
-    info!(artifact_id, owner_user_id = user.id, "artifact deleted");
-    Ok(StatusCode::NO_CONTENT.into_response())
 }
 
 async fn update_artifact_visibility(
@@ -702,7 +2071,7 @@ async fn update_artifact_visibility(
 
     let owner_user_id: Option<i64> = db
         .query_row(
-            "SELECT owner_user_id FROM artifacts WHERE id = ?1",
+            "SELECT owner_user_id FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
             params![artifact_id],
             |r| r.get(0),
         )
@@ -713,10 +2082,8 @@ async fn update_artifact_visibility(
         return Err(ApiError::not_found("artifact not found"));
     };
 
-    if state.auth_mode == AuthMode::Required && owner_user_id != user.id {
-        return Err(ApiError::unauthorized(
-            "artifact is not owned by current user",
-        ));
+    if state.auth_mode.auth_required() && owner_user_id != user.id {
+        return Err(ApiError::forbidden("artifact is not owned by current user"));
     }
 
     let is_public_i64 = if payload.is_public { 1 } else { 0 };
@@ -736,507 +2103,4339 @@ async fn update_artifact_visibility(
     Ok(StatusCode::NO_CONTENT.into_response())
 }
 
-async fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<UserInfo, ApiError> {
-    if state.auth_mode == AuthMode::Disabled {
-        return Ok(UserInfo {
-            id: LOCAL_USER_ID,
-            username: LOCAL_USERNAME.to_string(),
-        });
-    }
-
-    let token = if let Some(token) = bearer_token_opt(headers) {
-        token
-    } else if let Some(token) = session_cookie_token(headers) {
-        token
-    } else {
-        debug!("authentication failed: no bearer token or session cookie");
-        return Err(ApiError::unauthorized(
-            "missing Authorization bearer token or session cookie",
-        ));
-    };
-
+async fn star_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(artifact_id): AxumPath<i64>,
+) -> Result<StatusCode, ApiError> {
+    let user = authenticate(&state, &headers).await?;
     let db = state.db.lock().await;
-    let user: Option<UserInfo> = db
+
+    let row: Option<(i64, i64)> = db
         .query_row(
-            "SELECT u.id, u.username FROM sessions s JOIN users u ON s.user_id = u.id WHERE s.token = ?1",
-            params![token],
-            |row| {
-                Ok(UserInfo {
-                    id: row.get(0)?,
-                    username: row.get(1)?,
-                })
-            },
+            "SELECT owner_user_id, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
+            params![artifact_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
         )
         .optional()
-        .map_err(|e| ApiError::internal(format!("failed to lookup session: {e}")))?;
-
-    if user.is_none() {
-        debug!("authentication failed: invalid or expired session");
+        .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
+    let Some((owner_user_id, is_public)) = row else {
+        return Err(ApiError::not_found("artifact not found"));
+    };
+    if state.auth_mode.auth_required() && owner_user_id != user.id && is_public == 0 {
+        return Err(ApiError::forbidden("artifact is not owned by current user"));
     }
-    user.ok_or_else(|| ApiError::unauthorized("invalid or expired session"))
+
+    db.execute(
+        "INSERT OR IGNORE INTO artifact_stars (user_id, artifact_id, created_at) VALUES (?1, ?2, ?3)",
+        params![user.id, artifact_id, now_utc()],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to star artifact: {e}")))?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-async fn create_session_for_credentials(
-    state: &AppState,
-    username: &str,
-    password: &str,
-) -> Result<(UserInfo, String), ApiError> {
-    if username.is_empty() {
-        return Err(ApiError::bad_request("username must not be empty"));
-    }
+async fn unstar_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(artifact_id): AxumPath<i64>,
+) -> Result<StatusCode, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
+    db.execute(
+        "DELETE FROM artifact_stars WHERE user_id = ?1 AND artifact_id = ?2",
+        params![user.id, artifact_id],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to unstar artifact: {e}")))?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
+async fn clone_artifact(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(artifact_id): AxumPath<i64>,
+) -> Result<Json<UploadArtifactResponse>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
     let db = state.db.lock().await;
-    let user_row: Option<(i64, String)> = db
+
+    let row: Option<(i64, String, Option<String>, String, String, i64)> = db
         .query_row(
-            "SELECT id, password_hash FROM users WHERE username = ?1",
-            params![username],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            "SELECT owner_user_id, name, note, target, elf_path, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
+            params![artifact_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)),
         )
         .optional()
-        .map_err(|e| ApiError::internal(format!("failed to query user: {e}")))?;
+        .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
 
-    let Some((user_id, password_hash)) = user_row else {
-        return Err(ApiError::unauthorized("invalid credentials"));
+    let Some((owner_user_id, name, note, target, rel_path, is_public)) = row else {
+        return Err(ApiError::not_found("artifact not found"));
     };
 
-    verify_password(password, &password_hash)?;
+    if state.auth_mode.auth_required() && owner_user_id != user.id && is_public == 0 {
+        return Err(ApiError::forbidden("artifact is not owned by current user"));
+    }
 
-    let token = generate_token();
+    let name = resolve_artifact_name(&db, state.duplicate_names, user.id, &name)?;
+    // The clone shares the source blob; purging trashed artifacts
+    // (`purge_artifacts_deleted_before`) only removes the file once no row,
+    // trashed ones included, references it anymore.
     db.execute(
-        "INSERT INTO sessions (token, user_id, created_at) VALUES (?1, ?2, ?3)",
-        params![token, user_id, now_utc()],
+        "INSERT INTO artifacts (owner_user_id, name, note, target, elf_path, is_public, abi_version, build_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5, 0, (SELECT abi_version FROM artifacts WHERE id = ?6), (SELECT build_id FROM artifacts WHERE id = ?6), ?7)",
+        params![user.id, name, note, target, rel_path, artifact_id, now_utc()],
     )
-    .map_err(|e| ApiError::internal(format!("failed to create session: {e}")))?;
+    .map_err(|e| ApiError::internal(format!("failed to create artifact row: {e}")))?;
 
-    Ok((
-        UserInfo {
-            id: user_id,
-            username: username.to_string(),
-        },
-        token,
-    ))
+    let clone_id = db.last_insert_rowid();
+    info!(
+        artifact_id = clone_id,
+        source_artifact_id = artifact_id,
+        owner_user_id = user.id,
+        "artifact cloned"
+    );
+    Ok(Json(UploadArtifactResponse {
+        artifact_id: ArtifactId(clone_id),
+        name,
+    }))
 }
 
-async fn create_user_with_password(
-    state: &AppState,
-    username: &str,
-    password: &str,
-) -> Result<UserInfo, ApiError> {
-    if username.is_empty() {
-        return Err(ApiError::bad_request("username must not be empty"));
-    }
-    if password.len() < 8 {
-        return Err(ApiError::bad_request("password must be at least 8 chars"));
+async fn submit_race(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SubmitRaceRequest>,
+) -> Result<Json<SubmitRaceResponse>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    if payload.entries.is_empty() {
+        return Err(ApiError::bad_request("race has no entries"));
+    }
+    if payload.entries.len() > MAX_FIELD_SIZE {
+        return Err(ApiError::bad_request(format!(
+            "race has {} entries, at most {MAX_FIELD_SIZE} are accepted",
+            payload.entries.len()
+        )));
     }
 
-    let hash = hash_password(password)?;
-    let db = state.db.lock().await;
-    let inserted = db.execute(
-        "INSERT INTO users (username, password_hash, created_at) VALUES (?1, ?2, ?3)",
-        params![username, hash, now_utc()],
-    );
+    let mut db = state.db.lock().await;
+    for entry in &payload.entries {
+        let row: Option<(i64, i64)> = db
+            .query_row(
+                "SELECT owner_user_id, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
+                params![entry.artifact_id.0],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
+        let Some((owner_user_id, is_public)) = row else {
+            return Err(ApiError::bad_request(format!(
+                "artifact #{} not found",
+                entry.artifact_id
+            )));
+        };
+        if state.auth_mode.auth_required() && owner_user_id != user.id && is_public == 0 {
+            return Err(ApiError::forbidden("artifact is not owned by current user"));
+        }
+    }
+    if let Some(replay) = &payload.replay {
+        validate_replay(replay)?;
+        let mut raced: Vec<_> = payload.entries.iter().map(|e| e.artifact_id.0).collect();
+        let mut recorded: Vec<_> = replay
+            .participants
+            .iter()
+            .map(|p| p.artifact_id.0)
+            .collect();
+        raced.sort_unstable();
+        recorded.sort_unstable();
+        if raced != recorded {
+            return Err(ApiError::bad_request(
+                "replay participants do not match the race entries",
+            ));
+        }
+    }
 
-    if let Err(err) = inserted {
-        if err.to_string().contains("UNIQUE") {
-            return Err(ApiError::bad_request("username already exists"));
+    let raced_at = now_utc();
+    // Claimed results are not trusted either: the server cannot re-run the
+    // physics, so races stay unverified until `botracers-game --verify-race`
+    // re-simulated their replay. With auth disabled the only user is the
+    // admin, so there is nobody to doubt.
+    let verified = !state.auth_mode.auth_required();
+    let verified_at = verified.then(|| raced_at.clone());
+    let tx = db
+        .transaction()
+        .map_err(|e| ApiError::internal(format!("failed to start transaction: {e}")))?;
+    let replay_id = match &payload.replay {
+        Some(replay) => {
+            let data = serde_json::to_string(replay)
+                .map_err(|e| ApiError::internal(format!("failed to encode replay: {e}")))?;
+            tx.execute(
+                "INSERT INTO replays (uploaded_by_user_id, data, created_at) VALUES (?1, ?2, ?3)",
+                params![user.id, data, raced_at],
+            )
+            .map_err(|e| ApiError::internal(format!("failed to store replay: {e}")))?;
+            Some(tx.last_insert_rowid())
         }
-        return Err(ApiError::internal(format!("failed to create user: {err}")));
+        None => None,
+    };
+    tx.execute(
+        "INSERT INTO races (submitted_by_user_id, car_count, created_at, verified_at, replay_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            user.id,
+            payload.entries.len() as i64,
+            raced_at,
+            verified_at,
+            replay_id
+        ],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to create race row: {e}")))?;
+    let race_id = tx.last_insert_rowid();
+
+    for (index, entry) in payload.entries.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO race_entries (race_id, artifact_id, position, laps_completed, best_lap_secs) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                race_id,
+                entry.artifact_id.0,
+                index as i64 + 1,
+                entry.laps_completed,
+                entry.best_lap_secs
+            ],
+        )
+        .map_err(|e| ApiError::internal(format!("failed to create race entry row: {e}")))?;
     }
+    tx.commit()
+        .map_err(|e| ApiError::internal(format!("failed to commit race: {e}")))?;
 
-    Ok(UserInfo {
-        id: db.last_insert_rowid(),
-        username: username.to_string(),
-    })
+    info!(
+        race_id,
+        submitted_by_user_id = user.id,
+        car_count = payload.entries.len(),
+        ?replay_id,
+        "race submitted"
+    );
+    // Sending only fails when nobody is subscribed.
+    let _ = state.race_events.send(RaceFinishedEvent {
+        race_id,
+        submitted_by: user.username,
+        entries: payload.entries,
+        verified,
+    });
+    Ok(Json(SubmitRaceResponse {
+        race_id,
+        raced_at,
+        verified,
+        replay_id,
+    }))
 }
 
-fn sanitize_next(next: &str) -> &str {
-    if next.starts_with('/') && !next.starts_with("//") {
-        next
-    } else {
-        "/"
-    }
+/// Reads a race's results, winner first, skipping artifacts purged since.
+fn stored_race_entries(
+    db: &Connection,
+    race_id: i64,
+) -> Result<Vec<Option<RaceEntrySubmission>>, ApiError> {
+    let mut stmt = db
+        .prepare("SELECT artifact_id, laps_completed, best_lap_secs FROM race_entries WHERE race_id = ?1 ORDER BY position ASC")
+        .map_err(|e| ApiError::internal(format!("failed to prepare race entry query: {e}")))?;
+    stmt.query_map(params![race_id], |row| {
+        let artifact_id: Option<i64> = row.get(0)?;
+        let laps_completed = row.get(1)?;
+        let best_lap_secs = row.get(2)?;
+        Ok(artifact_id.map(|id| RaceEntrySubmission {
+            artifact_id: ArtifactId(id),
+            laps_completed,
+            best_lap_secs,
+            // Not stored; the first event carried it.
+            finish_reason: None,
+        }))
+    })
+    .map_err(|e| ApiError::internal(format!("failed to query race entries: {e}")))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| ApiError::internal(format!("failed to read race entry row: {e}")))
 }
 
-fn next_login_with_error(next: &str, error: Option<&str>) -> String {
-    let mut url = format!("/login?next={}", urlencoding::encode(next));
-    if let Some(error) = error {
-        url.push_str("&error=");
-        url.push_str(&urlencoding::encode(error));
-    }
-    url
+/// Returns what a race claimed together with the replay it was submitted
+/// with, for `botracers-game --verify-race` to re-simulate.
+async fn review_race(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(race_id): AxumPath<i64>,
+) -> Result<Json<RaceReview>, ApiError> {
+    require_admin(&state, &headers).await?;
+    let db = state.db.lock().await;
+    let row: Option<(String, bool, Option<String>, Option<String>)> = db
+        .query_row(
+            "SELECT u.username, r.verified_at IS NOT NULL, r.rejection_reason, p.data FROM races r JOIN users u ON u.id = r.submitted_by_user_id LEFT JOIN replays p ON p.id = r.replay_id WHERE r.id = ?1",
+            params![race_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query race: {e}")))?;
+    let Some((submitted_by, verified, rejection, replay)) = row else {
+        return Err(ApiError::not_found("race not found"));
+    };
+    let Some(entries) = stored_race_entries(&db, race_id)?
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Err(ApiError::conflict(
+            "race includes a purged artifact and can no longer be re-simulated",
+        ));
+    };
+    let replay = replay
+        .map(|data| serde_json::from_str(&data))
+        .transpose()
+        .map_err(|e| ApiError::internal(format!("failed to read stored replay: {e}")))?;
+    Ok(Json(RaceReview {
+        race_id,
+        submitted_by,
+        entries,
+        replay,
+        verified,
+        rejection,
+    }))
 }
 
-fn render_login_page(
-    next: &str,
-    username: Option<&str>,
-    error: Option<&str>,
-    registration_enabled: bool,
-) -> Html<String> {
-    let escaped_next = escape_html(next);
-    let escaped_username = escape_html(username.unwrap_or(""));
-    let error_html = match error {
-        Some(message) => format!("<p class=\"error\">{}</p>", escape_html(message)),
-        None => String::new(),
-    };
-    let register_link = if registration_enabled {
-        format!(
-            "<p class=\"hint\"><a href=\"/register?next={}\">Create account</a></p>",
-            urlencoding::encode(next)
+/// Marks a race's claimed results as confirmed so they count towards
+/// artifact stats. `botracers-game --verify-race` calls this once the race's
+/// replay re-simulated to the same results; confirming twice is harmless,
+/// but a race without a replay or one already rejected cannot be confirmed.
+async fn verify_race(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(race_id): AxumPath<i64>,
+) -> Result<StatusCode, ApiError> {
+    let user = require_admin(&state, &headers).await?;
+    let db = state.db.lock().await;
+    let race: Option<(String, bool, bool, bool)> = db
+        .query_row(
+            "SELECT u.username, r.verified_at IS NOT NULL, r.rejected_at IS NOT NULL, r.replay_id IS NOT NULL FROM races r JOIN users u ON u.id = r.submitted_by_user_id WHERE r.id = ?1",
+            params![race_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
         )
-    } else {
-        String::new()
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query race: {e}")))?;
+    let Some((submitted_by, already_verified, rejected, has_replay)) = race else {
+        return Err(ApiError::not_found("race not found"));
     };
-    Html(format!(
-        "<!doctype html>\
-         <html lang=\"en\">\
-         <head>\
-         <meta charset=\"utf-8\" />\
-         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\
-         <title>BotRacers Login</title>\
-         <style>\
-         body {{ margin:0; min-height:100vh; display:grid; place-items:center; font-family:system-ui,sans-serif; background:#0f1217; color:#f5f7fb; }}\
-         .card {{ width:min(420px, calc(100vw - 2rem)); background:#171c24; border:1px solid #2a3240; border-radius:12px; padding:1.25rem; box-sizing:border-box; }}\
-         h1 {{ margin:0 0 1rem 0; font-size:1.25rem; }}\
-         label {{ display:block; margin:0 0 0.25rem 0; font-size:0.9rem; color:#c7d0dd; }}\
-         input {{ width:100%; box-sizing:border-box; border:1px solid #3a4455; border-radius:8px; padding:0.6rem 0.75rem; margin:0 0 0.75rem 0; background:#0f141c; color:#f5f7fb; }}\
-         button {{ width:100%; border:0; border-radius:8px; padding:0.65rem 0.75rem; font-weight:600; background:#6ad490; color:#131417; cursor:pointer; }}\
-         .error {{ margin:0 0 0.75rem 0; color:#ff8d8d; font-size:0.9rem; }}\
-         .hint {{ margin:0.75rem 0 0; color:#a9b5c5; font-size:0.8rem; }}\
-         a {{ color:#9bd0ff; }}\
-         </style>\
-         </head>\
-         <body>\
-         <main class=\"card\">\
-         <h1>Sign in to BotRacers</h1>\
-         {error_html}\
-         <form method=\"post\" action=\"/login\">\
-         <input type=\"hidden\" name=\"next\" value=\"{escaped_next}\" />\
-         <label for=\"username\">Username</label>\
-         <input id=\"username\" name=\"username\" autocomplete=\"username\" required value=\"{escaped_username}\" />\
-         <label for=\"password\">Password</label>\
-         <input id=\"password\" name=\"password\" type=\"password\" autocomplete=\"current-password\" required />\
-         <button type=\"submit\">Log in</button>\
-         </form>\
-         {register_link}\
-         <p class=\"hint\">You will be redirected back to the game after login.</p>\
-         </main>\
-         </body>\
-         </html>"
-    ))
+    if already_verified {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    if rejected {
+        return Err(ApiError::conflict("race was rejected"));
+    }
+    if !has_replay {
+        return Err(ApiError::conflict("race has no replay to verify against"));
+    }
+    db.execute(
+        "UPDATE races SET verified_at = ?1 WHERE id = ?2",
+        params![now_utc(), race_id],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to verify race: {e}")))?;
+
+    let entries = stored_race_entries(&db, race_id)?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    info!(race_id, admin = %user.username, "race verified");
+    // Sending only fails when nobody is subscribed.
+    let _ = state.race_events.send(RaceFinishedEvent {
+        race_id,
+        submitted_by,
+        entries,
+        verified: true,
+    });
+    Ok(StatusCode::NO_CONTENT)
 }
 
-fn render_register_page(next: &str, username: Option<&str>, error: Option<&str>) -> Html<String> {
-    let escaped_next = escape_html(next);
-    let escaped_username = escape_html(username.unwrap_or(""));
-    let error_html = match error {
-        Some(message) => format!("<p class=\"error\">{}</p>", escape_html(message)),
-        None => String::new(),
+/// Records that a race's replay contradicts its claimed results, so the race
+/// never counts towards artifact stats. Rejecting twice is harmless; a
+/// confirmed race cannot be rejected.
+async fn reject_race(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(race_id): AxumPath<i64>,
+    Json(payload): Json<RejectRaceRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user = require_admin(&state, &headers).await?;
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(ApiError::bad_request("rejection reason is required"));
+    }
+    let db = state.db.lock().await;
+    let race: Option<(bool, bool)> = db
+        .query_row(
+            "SELECT verified_at IS NOT NULL, rejected_at IS NOT NULL FROM races WHERE id = ?1",
+            params![race_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query race: {e}")))?;
+    let Some((verified, already_rejected)) = race else {
+        return Err(ApiError::not_found("race not found"));
     };
-    Html(format!(
-        "<!doctype html>\
-         <html lang=\"en\">\
-         <head>\
-         <meta charset=\"utf-8\" />\
-         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\
-         <title>BotRacers Register</title>\
-         <style>\
-         body {{ margin:0; min-height:100vh; display:grid; place-items:center; font-family:system-ui,sans-serif; background:#0f1217; color:#f5f7fb; }}\
-         .card {{ width:min(420px, calc(100vw - 2rem)); background:#171c24; border:1px solid #2a3240; border-radius:12px; padding:1.25rem; box-sizing:border-box; }}\
-         h1 {{ margin:0 0 1rem 0; font-size:1.25rem; }}\
-         label {{ display:block; margin:0 0 0.25rem 0; font-size:0.9rem; color:#c7d0dd; }}\
-         input {{ width:100%; box-sizing:border-box; border:1px solid #3a4455; border-radius:8px; padding:0.6rem 0.75rem; margin:0 0 0.75rem 0; background:#0f141c; color:#f5f7fb; }}\
-         button {{ width:100%; border:0; border-radius:8px; padding:0.65rem 0.75rem; font-weight:600; background:#6ad490; color:#131417; cursor:pointer; }}\
-         .error {{ margin:0 0 0.75rem 0; color:#ff8d8d; font-size:0.9rem; }}\
-         .hint {{ margin:0.75rem 0 0; color:#a9b5c5; font-size:0.8rem; }}\
-         a {{ color:#9bd0ff; }}\
-         </style>\
-         </head>\
-         <body>\
-         <main class=\"card\">\
-         <h1>Create BotRacers account</h1>\
-         {error_html}\
-         <form method=\"post\" action=\"/register\">\
-         <input type=\"hidden\" name=\"next\" value=\"{escaped_next}\" />\
-         <label for=\"username\">Username</label>\
-         <input id=\"username\" name=\"username\" autocomplete=\"username\" required value=\"{escaped_username}\" />\
-         <label for=\"password\">Password</label>\
-         <input id=\"password\" name=\"password\" type=\"password\" autocomplete=\"new-password\" required />\
-         <button type=\"submit\">Create account</button>\
-         </form>\
-         <p class=\"hint\">Already have an account? <a href=\"/login?next={}\">Sign in</a></p>\
-         </main>\
-         </body>\
-         </html>",
-        urlencoding::encode(next)
-    ))
+    if already_rejected {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    if verified {
+        return Err(ApiError::conflict("race was already verified"));
+    }
+    db.execute(
+        "UPDATE races SET rejected_at = ?1, rejection_reason = ?2 WHERE id = ?3",
+        params![now_utc(), reason, race_id],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to reject race: {e}")))?;
+
+    warn!(race_id, admin = %user.username, reason, "race rejected");
+    Ok(StatusCode::NO_CONTENT)
 }
 
-fn load_index_html(state: &AppState) -> Result<String, ApiError> {
-    let Some(static_dir) = &state.static_dir else {
-        return Err(ApiError::not_found("static file serving is disabled"));
+async fn race_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    let viewer = if state.events_public {
+        authenticate(&state, &headers).await.ok()
+    } else {
+        Some(authenticate(&state, &headers).await?)
     };
-    let index = static_dir.join("index.html");
-    std::fs::read_to_string(&index).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::NotFound {
-            ApiError::not_found("index.html was not found in static dir")
-        } else {
-            ApiError::internal(format!("failed to read '{}': {e}", index.display()))
-        }
-    })
+    let viewer = viewer.map(|user| user.id);
+    let receiver = state.race_events.subscribe();
+    let stream =
+        futures_util::stream::unfold((receiver, state), move |(mut receiver, state)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => match visible_race_event(&state, viewer, event).await {
+                        Ok(Some(event)) => {
+                            let event = Event::default().event("race_finished").json_data(&event);
+                            return Some((event, (receiver, state)));
+                        }
+                        Ok(None) => {}
+                        Err(error) => {
+                            warn!(error = %error.message, "failed to filter race event");
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "race event subscriber lagged");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
-fn escape_html(value: &str) -> String {
-    let mut out = String::with_capacity(value.len());
-    for ch in value.chars() {
-        match ch {
-            '&' => out.push_str("&amp;"),
-            '<' => out.push_str("&lt;"),
-            '>' => out.push_str("&gt;"),
-            '"' => out.push_str("&quot;"),
-            '\'' => out.push_str("&#39;"),
-            _ => out.push(ch),
+/// `event` with only the entries `viewer` could download, under the same
+/// rule as `download_artifact`: their own artifacts and public ones, or all
+/// of them when auth is off. Anonymous subscribers only see public ones.
+/// `None` when nothing is left to show.
+async fn visible_race_event(
+    state: &AppState,
+    viewer: Option<i64>,
+    mut event: RaceFinishedEvent,
+) -> Result<Option<RaceFinishedEvent>, ApiError> {
+    let db = state.db.lock().await;
+    let mut entries = Vec::with_capacity(event.entries.len());
+    for entry in event.entries {
+        let row: Option<(i64, i64)> = db
+            .query_row(
+                "SELECT owner_user_id, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
+                params![entry.artifact_id.0],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
+        let Some((owner_user_id, is_public)) = row else {
+            continue;
+        };
+        if !state.auth_mode.auth_required() || is_public != 0 || viewer == Some(owner_user_id) {
+            entries.push(entry);
         }
     }
-    out
+    if entries.is_empty() {
+        return Ok(None);
+    }
+    event.entries = entries;
+    Ok(Some(event))
 }
 
-fn bearer_token_opt(headers: &HeaderMap) -> Option<String> {
-    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
-    let token = value.strip_prefix("Bearer ")?.trim();
-    if token.is_empty() {
-        None
-    } else {
-        Some(token.to_string())
+async fn list_artifact_races(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(artifact_id): AxumPath<i64>,
+    Query(query): Query<RaceHistoryQuery>,
+) -> Result<Json<Vec<ArtifactRaceResult>>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
+
+    let row: Option<(i64, i64)> = db
+        .query_row(
+            "SELECT owner_user_id, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
+            params![artifact_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
+
+    let Some((owner_user_id, is_public)) = row else {
+        return Err(ApiError::not_found("artifact not found"));
+    };
+
+    if state.auth_mode.auth_required() && owner_user_id != user.id && is_public == 0 {
+        return Err(ApiError::forbidden("artifact is not owned by current user"));
     }
-}
 
-fn session_cookie_token(headers: &HeaderMap) -> Option<String> {
-    let cookie_header = headers.get(header::COOKIE)?.to_str().ok()?;
-    for pair in cookie_header.split(';') {
-        let mut parts = pair.trim().splitn(2, '=');
-        let key = parts.next()?.trim();
-        let value = parts.next()?.trim();
-        if key == COOKIE_NAME && !value.is_empty() {
-            return Some(value.to_string());
-        }
+    let mut sql = "SELECT r.id, e.position, r.car_count, e.laps_completed, e.best_lap_secs, r.created_at, r.verified_at IS NOT NULL, r.rejected_at IS NOT NULL FROM race_entries e JOIN races r ON r.id = e.race_id WHERE e.artifact_id = ?1 ORDER BY r.created_at DESC, r.id DESC, e.position ASC".to_string();
+    push_page_clause(&mut sql, query.limit, query.offset, MAX_RACE_PAGE_SIZE);
+
+    let mut stmt = db
+        .prepare(&sql)
+        .map_err(|e| ApiError::internal(format!("failed to prepare race query: {e}")))?;
+    let rows = stmt
+        .query_map(params![artifact_id], |row| {
+            Ok(ArtifactRaceResult {
+                race_id: row.get(0)?,
+                position: row.get(1)?,
+                car_count: row.get(2)?,
+                laps_completed: row.get(3)?,
+                best_lap_secs: row.get(4)?,
+                raced_at: row.get(5)?,
+                verified: row.get(6)?,
+                rejected: row.get(7)?,
+            })
+        })
+        .map_err(|e| ApiError::internal(format!("failed to query races: {e}")))?;
+
+    let mut out = Vec::new();
+    for item in rows {
+        out.push(item.map_err(|e| ApiError::internal(format!("failed to read race row: {e}")))?);
     }
-    None
+
+    Ok(Json(out))
 }
 
-fn session_cookie(token: &str, secure: bool) -> HeaderValue {
-    let secure_part = if secure { "; Secure" } else { "" };
-    HeaderValue::from_str(&format!(
-        "{COOKIE_NAME}={token}; HttpOnly; Path=/; SameSite=Lax{secure_part}"
-    ))
-    .expect("valid session cookie")
-}
+async fn upload_race_logs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(race_id): AxumPath<i64>,
+    Json(payload): Json<UploadRaceLogsRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    let mut db = state.db.lock().await;
 
-fn expired_session_cookie(secure: bool) -> HeaderValue {
-    let secure_part = if secure { "; Secure" } else { "" };
-    HeaderValue::from_str(&format!(
-        "{COOKIE_NAME}=; HttpOnly; Path=/; SameSite=Lax; Max-Age=0{secure_part}"
-    ))
-    .expect("valid expired cookie")
-}
+    let submitted_by: Option<i64> = db
+        .query_row(
+            "SELECT submitted_by_user_id FROM races WHERE id = ?1",
+            params![race_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query race: {e}")))?;
+    let Some(submitted_by) = submitted_by else {
+        return Err(ApiError::not_found("race not found"));
+    };
+    if state.auth_mode.auth_required() && submitted_by != user.id {
+        return Err(ApiError::forbidden(
+            "race was not submitted by current user",
+        ));
+    }
 
-fn generate_token() -> String {
-    let mut rng = rand::rng();
-    let bytes: [u8; 32] = rng.random();
-    hex::encode(bytes)
-}
+    for transcript in &payload.transcripts {
+        if transcript.log.len() > MAX_RACE_LOG_BYTES {
+            return Err(ApiError::bad_request(format!(
+                "log for artifact #{} exceeds {MAX_RACE_LOG_BYTES} bytes",
+                transcript.artifact_id
+            )));
+        }
+        let in_race: bool = db
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM race_entries WHERE race_id = ?1 AND artifact_id = ?2)",
+                params![race_id, transcript.artifact_id.0],
+                |r| r.get(0),
+            )
+            .map_err(|e| ApiError::internal(format!("failed to query race entry: {e}")))?;
+        if !in_race {
+            return Err(ApiError::bad_request(format!(
+                "artifact #{} did not take part in race #{race_id}",
+                transcript.artifact_id
+            )));
+        }
+    }
 
-fn hash_password(password: &str) -> Result<String, ApiError> {
-    let mut rng = rand::rng();
-    let salt_bytes: [u8; 16] = rng.random();
-    let salt = SaltString::encode_b64(&salt_bytes)
-        .map_err(|e| ApiError::internal(format!("failed to encode password salt: {e}")))?;
-    Argon2::default()
-        .hash_password(password.as_bytes(), &salt)
-        .map(|hash| hash.to_string())
-        .map_err(|e| ApiError::internal(format!("password hash failed: {e}")))
-}
+    let tx = db
+        .transaction()
+        .map_err(|e| ApiError::internal(format!("failed to start transaction: {e}")))?;
+    for transcript in &payload.transcripts {
+        tx.execute(
+            "INSERT OR REPLACE INTO race_logs (race_id, artifact_id, log) VALUES (?1, ?2, ?3)",
+            params![race_id, transcript.artifact_id.0, transcript.log],
+        )
+        .map_err(|e| ApiError::internal(format!("failed to store race log: {e}")))?;
+    }
+    tx.commit()
+        .map_err(|e| ApiError::internal(format!("failed to commit race logs: {e}")))?;
 
-fn verify_password(password: &str, hash: &str) -> Result<(), ApiError> {
-    let parsed = PasswordHash::new(hash)
-        .map_err(|e| ApiError::internal(format!("invalid password hash in database: {e}")))?;
-    Argon2::default()
-        .verify_password(password.as_bytes(), &parsed)
-        .map_err(|_| ApiError::unauthorized("invalid credentials"))
+    info!(
+        race_id,
+        transcript_count = payload.transcripts.len(),
+        "race logs uploaded"
+    );
+    Ok(StatusCode::NO_CONTENT)
 }
 
-fn now_utc() -> String {
-    Utc::now().to_rfc3339()
-}
+/// Returns the stored transcripts of every car in the race whose artifact the
+/// caller could download, in finishing order.
+async fn list_race_logs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(race_id): AxumPath<i64>,
+) -> Result<Json<Vec<RaceLogTranscript>>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
 
-fn ensure_local_user(conn: &Connection) -> Result<(), rusqlite::Error> {
-    let now = now_utc();
-    conn.execute(
-        "INSERT OR IGNORE INTO users (id, username, password_hash, created_at) VALUES (?1, ?2, ?3, ?4)",
-        params![LOCAL_USER_ID, LOCAL_USERNAME, "", now],
-    )?;
-    Ok(())
-}
+    let exists: bool = db
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM races WHERE id = ?1)",
+            params![race_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| ApiError::internal(format!("failed to query race: {e}")))?;
+    if !exists {
+        return Err(ApiError::not_found("race not found"));
+    }
 
-fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
-    conn.execute_batch(
-        "
-        PRAGMA foreign_keys = ON;
+    let mut stmt = db
+        .prepare(
+            "SELECT l.artifact_id, l.log, a.owner_user_id, a.is_public FROM race_logs l JOIN artifacts a ON a.id = l.artifact_id JOIN race_entries e ON e.race_id = l.race_id AND e.artifact_id = l.artifact_id WHERE l.race_id = ?1 ORDER BY e.position ASC",
+        )
+        .map_err(|e| ApiError::internal(format!("failed to prepare race log query: {e}")))?;
+    let rows = stmt
+        .query_map(params![race_id], |row| {
+            Ok((
+                RaceLogTranscript {
+                    artifact_id: ArtifactId(row.get(0)?),
+                    log: row.get(1)?,
+                },
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(|e| ApiError::internal(format!("failed to query race logs: {e}")))?;
 
-        CREATE TABLE IF NOT EXISTS users (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            username TEXT NOT NULL UNIQUE,
-            password_hash TEXT NOT NULL,
-            created_at TEXT NOT NULL
-        );
+    let mut out = Vec::new();
+    for item in rows {
+        let (transcript, owner_user_id, is_public) =
+            item.map_err(|e| ApiError::internal(format!("failed to read race log row: {e}")))?;
+        if state.auth_mode.auth_required() && owner_user_id != user.id && is_public == 0 {
+            continue;
+        }
+        out.push(transcript);
+    }
 
-        CREATE TABLE IF NOT EXISTS sessions (
-            token TEXT PRIMARY KEY,
-            user_id INTEGER NOT NULL,
-            created_at TEXT NOT NULL,
-            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
-        );
+    Ok(Json(out))
+}
 
-        CREATE TABLE IF NOT EXISTS artifacts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            owner_user_id INTEGER NOT NULL,
-            name TEXT NOT NULL,
-            note TEXT,
-            target TEXT NOT NULL,
-            elf_path TEXT NOT NULL,
-            is_public INTEGER NOT NULL DEFAULT 0,
-            created_at TEXT NOT NULL,
-            FOREIGN KEY(owner_user_id) REFERENCES users(id) ON DELETE CASCADE
-        );
-        ",
-    )?;
+async fn upload_replay(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<UploadReplayResponse>, ApiError> {
+    let user = authenticate(&state, &headers).await?;
 
-    let mut has_is_public = false;
-    let mut stmt = conn.prepare("PRAGMA table_info(artifacts)")?;
-    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
-    for row in rows {
-        if row? == "is_public" {
-            has_is_public = true;
-            break;
-        }
+    let ReplayVersion { version } = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::bad_request(format!("invalid replay: {e}")))?;
+    if version != REPLAY_FORMAT_VERSION {
+        return Err(ApiError::bad_request(format!(
+            "unsupported replay version {version} (this server reads version {REPLAY_FORMAT_VERSION})"
+        )));
     }
+    let replay: ReplayFile = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::bad_request(format!("invalid replay: {e}")))?;
+    validate_replay(&replay)?;
 
-    if !has_is_public {
-        conn.execute(
-            "ALTER TABLE artifacts ADD COLUMN is_public INTEGER NOT NULL DEFAULT 0",
-            [],
-        )?;
+    let db = state.db.lock().await;
+    for participant in &replay.participants {
+        let row: Option<(i64, i64)> = db
+            .query_row(
+                "SELECT owner_user_id, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
+                params![participant.artifact_id.0],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
+        let Some((owner_user_id, is_public)) = row else {
+            return Err(ApiError::bad_request(format!(
+                "artifact #{} not found",
+                participant.artifact_id
+            )));
+        };
+        if state.auth_mode.auth_required() && owner_user_id != user.id && is_public == 0 {
+            return Err(ApiError::forbidden("artifact is not owned by current user"));
+        }
     }
 
-    Ok(())
+    // The body parsed as JSON above, so it is valid UTF-8.
+    let data = String::from_utf8_lossy(&body);
+    db.execute(
+        "INSERT INTO replays (uploaded_by_user_id, data, created_at) VALUES (?1, ?2, ?3)",
+        params![user.id, data, now_utc()],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to store replay: {e}")))?;
+    let replay_id = db.last_insert_rowid();
+
+    info!(
+        replay_id,
+        uploaded_by_user_id = user.id,
+        participants = replay.participants.len(),
+        ticks = replay.ticks.len(),
+        "replay uploaded"
+    );
+    Ok(Json(UploadReplayResponse { replay_id }))
 }
 
-#[allow(dead_code)]
-fn _ensure_under(path: &Path, root: &Path) -> bool {
-    path.starts_with(root)
+/// Checks what `upload_replay` and `submit_race` accept as a replay: the
+/// current format, a grid no larger than a race's, and one input per car.
+fn validate_replay(replay: &ReplayFile) -> Result<(), ApiError> {
+    if replay.version != REPLAY_FORMAT_VERSION {
+        return Err(ApiError::bad_request(format!(
+            "unsupported replay version {} (this server reads version {REPLAY_FORMAT_VERSION})",
+            replay.version
+        )));
+    }
+    if replay.participants.is_empty() {
+        return Err(ApiError::bad_request("replay has no participants"));
+    }
+    if replay.participants.len() > MAX_FIELD_SIZE {
+        return Err(ApiError::bad_request(format!(
+            "replay has {} participants, at most {MAX_FIELD_SIZE} are accepted",
+            replay.participants.len()
+        )));
+    }
+    if replay.ticks.cars() != replay.participants.len() {
+        return Err(ApiError::bad_request(format!(
+            "ticks have {} inputs for {} participants",
+            replay.ticks.cars(),
+            replay.participants.len()
+        )));
+    }
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::{
-        body::{Body, to_bytes},
-        http::Request,
-    };
-    use botracers_protocol::{
-        ArtifactSummary, LoginResponse, UpdateArtifactVisibilityRequest, UploadArtifactRequest,
+/// Returns a stored replay to its uploader, or to anyone who may download
+/// every artifact in it; a replay carries each car's per-tick controls, so it
+/// follows the same rule as the artifacts themselves.
+async fn get_replay(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(replay_id): AxumPath<i64>,
+) -> Result<Response, ApiError> {
+    let user = authenticate(&state, &headers).await?;
+    let db = state.db.lock().await;
+    let row: Option<(i64, String)> = db
+        .query_row(
+            "SELECT uploaded_by_user_id, data FROM replays WHERE id = ?1",
+            params![replay_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query replay: {e}")))?;
+    let Some((uploaded_by_user_id, data)) = row else {
+        return Err(ApiError::not_found("replay not found"));
     };
-    use tower::ServiceExt;
 
-    fn unique_temp_dir(prefix: &str) -> PathBuf {
-        let mut rng = rand::rng();
-        let suffix: u64 = rng.random();
-        let dir = std::env::temp_dir().join(format!("{prefix}_{suffix}"));
-        std::fs::create_dir_all(&dir).expect("create temp dir");
-        dir
+    if state.auth_mode.auth_required() && uploaded_by_user_id != user.id {
+        let ReplayParticipants { participants } = serde_json::from_str(&data)
+            .map_err(|e| ApiError::internal(format!("failed to read stored replay: {e}")))?;
+        for participant in &participants {
+            let row: Option<(i64, i64)> = db
+                .query_row(
+                    "SELECT owner_user_id, is_public FROM artifacts WHERE id = ?1 AND deleted_at IS NULL",
+                    params![participant.artifact_id.0],
+                    |r| Ok((r.get(0)?, r.get(1)?)),
+                )
+                .optional()
+                .map_err(|e| ApiError::internal(format!("failed to query artifact: {e}")))?;
+            let visible = row.is_some_and(|(owner_user_id, is_public)| {
+                owner_user_id == user.id || is_public != 0
+            });
+            if !visible {
+                return Err(ApiError::forbidden(
+                    "replay includes an artifact not visible to current user",
+                ));
+            }
+        }
     }
 
-    fn setup_test_state(
-        auth_mode: AuthMode,
-        registration_enabled: bool,
-    ) -> (AppState, PathBuf, PathBuf) {
-        let static_dir = unique_temp_dir("botracers_static");
-        let artifacts_dir = unique_temp_dir("botracers_artifacts");
-        std::fs::write(
-            static_dir.join("index.html"),
-            "<html><body>game entry</body></html>",
-        )
-        .expect("write index");
+    Ok(([(header::CONTENT_TYPE, "application/json")], data).into_response())
+}
 
-        let conn = Connection::open_in_memory().expect("open in-memory sqlite");
-        run_migrations(&conn).expect("run migrations");
-        ensure_local_user(&conn).expect("ensure local user");
-        let state = AppState {
-            db: Arc::new(Mutex::new(conn)),
-            artifacts_dir: artifacts_dir.clone(),
-            static_dir: Some(static_dir.clone()),
-            auth_mode,
-            cookie_secure: false,
-            registration_enabled,
-        };
-        (state, static_dir, artifacts_dir)
+/// Appends `LIMIT`/`OFFSET` for a paged list query; `limit` is clamped to `1..=max`.
+fn push_page_clause(sql: &mut String, limit: Option<u32>, offset: u32, max: u32) {
+    if let Some(limit) = limit {
+        let limit = limit.clamp(1, max);
+        sql.push_str(&format!(" LIMIT {limit} OFFSET {offset}"));
+    } else if offset > 0 {
+        sql.push_str(&format!(" LIMIT -1 OFFSET {offset}"));
     }
+}
 
-    async fn create_user(state: &AppState, username: &str, password: &str) {
-        let hash = hash_password(password).expect("hash password");
-        let db = state.db.lock().await;
-        db.execute(
-            "INSERT INTO users (username, password_hash, created_at) VALUES (?1, ?2, ?3)",
-            params![username, hash, now_utc()],
-        )
-        .expect("insert user");
+async fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<UserInfo, ApiError> {
+    if state.auth_mode == AuthMode::Disabled {
+        return Ok(UserInfo {
+            id: LOCAL_USER_ID,
+            username: LOCAL_USERNAME.to_string(),
+        });
     }
 
-    async fn make_session_cookie(state: &AppState, username: &str, password: &str) -> String {
-        let (_, token) = create_session_for_credentials(state, username, password)
-            .await
-            .expect("create session");
-        format!("{COOKIE_NAME}={token}")
+    let token = if let Some(token) = header_token_opt(state, headers) {
+        token
+    } else if let Some(token) = session_cookie_token(headers) {
+        token
+    } else {
+        debug!("authentication failed: no bearer token or session cookie");
+        return Err(ApiError::unauthorized(format!(
+            "missing {} token or session cookie",
+            state.auth_header
+        )));
+    };
+
+    let db = state.db.lock().await;
+    let is_api_key = state.auth_mode == AuthMode::ApiKeys && token.starts_with(API_KEY_PREFIX);
+    let query = if is_api_key {
+        "SELECT u.id, u.username FROM api_keys k JOIN users u ON k.user_id = u.id WHERE k.key_hash = ?1"
+    } else {
+        "SELECT u.id, u.username FROM sessions s JOIN users u ON s.user_id = u.id WHERE s.token = ?1"
+    };
+    let lookup = if is_api_key {
+        hash_api_key(&token)
+    } else {
+        token
+    };
+    let user: Option<UserInfo> = db
+        .query_row(query, params![lookup], |row| {
+            Ok(UserInfo {
+                id: row.get(0)?,
+                username: row.get(1)?,
+            })
+        })
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to lookup session: {e}")))?;
+
+    if user.is_none() && is_api_key {
+        debug!("authentication failed: unknown or revoked api key");
+        return Err(ApiError::unauthorized("unknown or revoked api key"));
+    }
+    if user.is_none() {
+        debug!("authentication failed: invalid or expired session");
     }
+    user.ok_or_else(|| ApiError::unauthorized("invalid or expired session"))
+}
 
-    async fn upload_artifact_with_cookie(
-        app: &Router,
-        cookie: &str,
-        name: &str,
-    ) -> (StatusCode, i64) {
-        let payload = UploadArtifactRequest {
-            name: name.to_string(),
-            note: None,
-            target: "riscv32imafc-unknown-none-elf".to_string(),
-            elf_base64: base64::engine::general_purpose::STANDARD.encode([0x7f, b'E', b'L', b'F']),
-        };
-        let resp = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/v1/artifacts")
-                    .header(header::COOKIE, cookie)
+/// Authenticates the caller and refuses anyone not in `admin_users`.
+async fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<UserInfo, ApiError> {
+    let user = authenticate(state, headers).await?;
+    if state.auth_mode.auth_required() && !state.admin_users.contains(&user.username) {
+        return Err(ApiError::forbidden("admin access required"));
+    }
+    Ok(user)
+}
+
+async fn create_session_for_credentials(
+    state: &AppState,
+    username: &str,
+    password: &str,
+) -> Result<(UserInfo, String), ApiError> {
+    if username.is_empty() {
+        return Err(ApiError::bad_request("username must not be empty"));
+    }
+
+    let db = state.db.lock().await;
+    let user_row: Option<(i64, String)> = db
+        .query_row(
+            "SELECT id, password_hash FROM users WHERE username = ?1",
+            params![username],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| ApiError::internal(format!("failed to query user: {e}")))?;
+
+    let Some((user_id, password_hash)) = user_row else {
+        return Err(ApiError::unauthorized("invalid credentials"));
+    };
+
+    verify_password(password, &password_hash)?;
+
+    let token = generate_token();
+    db.execute(
+        "INSERT INTO sessions (token, user_id, created_at) VALUES (?1, ?2, ?3)",
+        params![token, user_id, now_utc()],
+    )
+    .map_err(|e| ApiError::internal(format!("failed to create session: {e}")))?;
+
+    Ok((
+        UserInfo {
+            id: user_id,
+            username: username.to_string(),
+        },
+        token,
+    ))
+}
+
+async fn create_user_with_password(
+    state: &AppState,
+    username: &str,
+    password: &str,
+) -> Result<UserInfo, ApiError> {
+    validate_username(username)?;
+    if password.len() < 8 {
+        return Err(ApiError::bad_request("password must be at least 8 chars"));
+    }
+    if is_reserved_username(state, username) {
+        return Err(ApiError::bad_request(format!(
+            "username '{username}' is reserved"
+        )));
+    }
+
+    let hash = hash_password(password)?;
+    let db = state.db.lock().await;
+    let inserted = db.execute(
+        "INSERT INTO users (username, password_hash, created_at) VALUES (?1, ?2, ?3)",
+        params![username, hash, now_utc()],
+    );
+
+    if let Err(err) = inserted {
+        if err.to_string().contains("UNIQUE") {
+            return Err(ApiError::bad_request("username already exists"));
+        }
+        return Err(ApiError::internal(format!("failed to create user: {err}")));
+    }
+
+    Ok(UserInfo {
+        id: db.last_insert_rowid(),
+        username: username.to_string(),
+    })
+}
+
+fn sanitize_next(next: &str) -> &str {
+    if next.starts_with('/') && !next.starts_with("//") {
+        next
+    } else {
+        "/"
+    }
+}
+
+fn next_login_with_error(next: &str, error: Option<&str>) -> String {
+    let mut url = format!("/login?next={}", urlencoding::encode(next));
+    if let Some(error) = error {
+        url.push_str("&error=");
+        url.push_str(&urlencoding::encode(error));
+    }
+    url
+}
+
+fn render_login_page(
+    next: &str,
+    username: Option<&str>,
+    error: Option<&str>,
+    registration_enabled: bool,
+) -> Html<String> {
+    let escaped_next = escape_html(next);
+    let escaped_username = escape_html(username.unwrap_or(""));
+    let error_html = match error {
+        Some(message) => format!("<p class=\"error\">{}</p>", escape_html(message)),
+        None => String::new(),
+    };
+    let register_link = if registration_enabled {
+        format!(
+            "<p class=\"hint\"><a href=\"/register?next={}\">Create account</a></p>",
+            urlencoding::encode(next)
+        )
+    } else {
+        String::new()
+    };
+    Html(format!(
+        "<!doctype html>\
+         <html lang=\"en\">\
+         <head>\
+         <meta charset=\"utf-8\" />\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\
+         <title>BotRacers Login</title>\
+         <style>\
+         body {{ margin:0; min-height:100vh; display:grid; place-items:center; font-family:system-ui,sans-serif; background:#0f1217; color:#f5f7fb; }}\
+         .card {{ width:min(420px, calc(100vw - 2rem)); background:#171c24; border:1px solid #2a3240; border-radius:12px; padding:1.25rem; box-sizing:border-box; }}\
+         h1 {{ margin:0 0 1rem 0; font-size:1.25rem; }}\
+         label {{ display:block; margin:0 0 0.25rem 0; font-size:0.9rem; color:#c7d0dd; }}\
+         input {{ width:100%; box-sizing:border-box; border:1px solid #3a4455; border-radius:8px; padding:0.6rem 0.75rem; margin:0 0 0.75rem 0; background:#0f141c; color:#f5f7fb; }}\
+         button {{ width:100%; border:0; border-radius:8px; padding:0.65rem 0.75rem; font-weight:600; background:#6ad490; color:#131417; cursor:pointer; }}\
+         .error {{ margin:0 0 0.75rem 0; color:#ff8d8d; font-size:0.9rem; }}\
+         .hint {{ margin:0.75rem 0 0; color:#a9b5c5; font-size:0.8rem; }}\
+         a {{ color:#9bd0ff; }}\
+         </style>\
+         </head>\
+         <body>\
+         <main class=\"card\">\
+         <h1>Sign in to BotRacers</h1>\
+         {error_html}\
+         <form method=\"post\" action=\"/login\">\
+         <input type=\"hidden\" name=\"next\" value=\"{escaped_next}\" />\
+         <label for=\"username\">Username</label>\
+         <input id=\"username\" name=\"username\" autocomplete=\"username\" required value=\"{escaped_username}\" />\
+         <label for=\"password\">Password</label>\
+         <input id=\"password\" name=\"password\" type=\"password\" autocomplete=\"current-password\" required />\
+         <button type=\"submit\">Log in</button>\
+         </form>\
+         {register_link}\
+         <p class=\"hint\">You will be redirected back to the game after login.</p>\
+         </main>\
+         </body>\
+         </html>"
+    ))
+}
+
+fn render_register_page(next: &str, username: Option<&str>, error: Option<&str>) -> Html<String> {
+    let escaped_next = escape_html(next);
+    let escaped_username = escape_html(username.unwrap_or(""));
+    let error_html = match error {
+        Some(message) => format!("<p class=\"error\">{}</p>", escape_html(message)),
+        None => String::new(),
+    };
+    Html(format!(
+        "<!doctype html>\
+         <html lang=\"en\">\
+         <head>\
+         <meta charset=\"utf-8\" />\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\
+         <title>BotRacers Register</title>\
+         <style>\
+         body {{ margin:0; min-height:100vh; display:grid; place-items:center; font-family:system-ui,sans-serif; background:#0f1217; color:#f5f7fb; }}\
+         .card {{ width:min(420px, calc(100vw - 2rem)); background:#171c24; border:1px solid #2a3240; border-radius:12px; padding:1.25rem; box-sizing:border-box; }}\
+         h1 {{ margin:0 0 1rem 0; font-size:1.25rem; }}\
+         label {{ display:block; margin:0 0 0.25rem 0; font-size:0.9rem; color:#c7d0dd; }}\
+         input {{ width:100%; box-sizing:border-box; border:1px solid #3a4455; border-radius:8px; padding:0.6rem 0.75rem; margin:0 0 0.75rem 0; background:#0f141c; color:#f5f7fb; }}\
+         button {{ width:100%; border:0; border-radius:8px; padding:0.65rem 0.75rem; font-weight:600; background:#6ad490; color:#131417; cursor:pointer; }}\
+         .error {{ margin:0 0 0.75rem 0; color:#ff8d8d; font-size:0.9rem; }}\
+         .hint {{ margin:0.75rem 0 0; color:#a9b5c5; font-size:0.8rem; }}\
+         a {{ color:#9bd0ff; }}\
+         </style>\
+         </head>\
+         <body>\
+         <main class=\"card\">\
+         <h1>Create BotRacers account</h1>\
+         {error_html}\
+         <form method=\"post\" action=\"/register\">\
+         <input type=\"hidden\" name=\"next\" value=\"{escaped_next}\" />\
+         <label for=\"username\">Username</label>\
+         <input id=\"username\" name=\"username\" autocomplete=\"username\" required value=\"{escaped_username}\" />\
+         <label for=\"password\">Password</label>\
+         <input id=\"password\" name=\"password\" type=\"password\" autocomplete=\"new-password\" required />\
+         <button type=\"submit\">Create account</button>\
+         </form>\
+         <p class=\"hint\">Already have an account? <a href=\"/login?next={}\">Sign in</a></p>\
+         </main>\
+         </body>\
+         </html>",
+        urlencoding::encode(next)
+    ))
+}
+
+fn load_index_html(state: &AppState) -> Result<String, ApiError> {
+    let Some(static_dir) = &state.static_dir else {
+        return Err(ApiError::not_found("static file serving is disabled"));
+    };
+    let index = static_dir.join("index.html");
+    std::fs::read_to_string(&index).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            ApiError::not_found("index.html was not found in static dir")
+        } else {
+            ApiError::internal(format!("failed to read '{}': {e}", index.display()))
+        }
+    })
+}
+
+fn escape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Session token from the configured auth header, with the configured scheme
+/// stripped.
+fn header_token_opt(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(&state.auth_header)?.to_str().ok()?;
+    let token = if state.auth_scheme.is_empty() {
+        value
+    } else {
+        value
+            .strip_prefix(state.auth_scheme.as_str())?
+            .strip_prefix(' ')?
+    }
+    .trim();
+    if token.is_empty() {
+        None
+    } else {
+        Some(token.to_string())
+    }
+}
+
+fn session_cookie_token(headers: &HeaderMap) -> Option<String> {
+    let cookie_header = headers.get(header::COOKIE)?.to_str().ok()?;
+    for pair in cookie_header.split(';') {
+        let mut parts = pair.trim().splitn(2, '=');
+        let key = parts.next()?.trim();
+        let value = parts.next()?.trim();
+        if key == COOKIE_NAME && !value.is_empty() {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn session_cookie(token: &str, secure: bool) -> HeaderValue {
+    let secure_part = if secure { "; Secure" } else { "" };
+    HeaderValue::from_str(&format!(
+        "{COOKIE_NAME}={token}; HttpOnly; Path=/; SameSite=Lax{secure_part}"
+    ))
+    .expect("valid session cookie")
+}
+
+fn expired_session_cookie(secure: bool) -> HeaderValue {
+    let secure_part = if secure { "; Secure" } else { "" };
+    HeaderValue::from_str(&format!(
+        "{COOKIE_NAME}=; HttpOnly; Path=/; SameSite=Lax; Max-Age=0{secure_part}"
+    ))
+    .expect("valid expired cookie")
+}
+
+fn generate_token() -> String {
+    let mut rng = rand::rng();
+    let bytes: [u8; 32] = rng.random();
+    hex::encode(bytes)
+}
+
+/// API keys are stored as SHA-256 digests; the plaintext only ever appears in
+/// the `CreateApiKeyResponse`.
+fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn hash_password(password: &str) -> Result<String, ApiError> {
+    let mut rng = rand::rng();
+    let salt_bytes: [u8; 16] = rng.random();
+    let salt = SaltString::encode_b64(&salt_bytes)
+        .map_err(|e| ApiError::internal(format!("failed to encode password salt: {e}")))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ApiError::internal(format!("password hash failed: {e}")))
+}
+
+fn verify_password(password: &str, hash: &str) -> Result<(), ApiError> {
+    let parsed = PasswordHash::new(hash)
+        .map_err(|e| ApiError::internal(format!("invalid password hash in database: {e}")))?;
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .map_err(|_| ApiError::unauthorized("invalid credentials"))
+}
+
+fn now_utc() -> String {
+    Utc::now().to_rfc3339()
+}
+
+fn ensure_local_user(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let now = now_utc();
+    conn.execute(
+        "INSERT OR IGNORE INTO users (id, username, password_hash, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![LOCAL_USER_ID, LOCAL_USERNAME, "", now],
+    )?;
+    Ok(())
+}
+
+fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "
+        PRAGMA foreign_keys = ON;
+
+        CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS sessions (
+            token TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS artifacts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner_user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            note TEXT,
+            target TEXT NOT NULL,
+            elf_path TEXT NOT NULL,
+            is_public INTEGER NOT NULL DEFAULT 0,
+            abi_version INTEGER,
+            build_id TEXT,
+            created_at TEXT NOT NULL,
+            deleted_at TEXT,
+            FOREIGN KEY(owner_user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS races (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            submitted_by_user_id INTEGER NOT NULL,
+            car_count INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            verified_at TEXT,
+            rejected_at TEXT,
+            rejection_reason TEXT,
+            replay_id INTEGER,
+            FOREIGN KEY(submitted_by_user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY(replay_id) REFERENCES replays(id) ON DELETE SET NULL
+        );
+
+        -- A purged artifact leaves its race rows behind with a NULL
+        -- artifact_id, so past races keep every position.
+        CREATE TABLE IF NOT EXISTS race_entries (
+            race_id INTEGER NOT NULL,
+            artifact_id INTEGER,
+            position INTEGER NOT NULL,
+            laps_completed INTEGER NOT NULL,
+            best_lap_secs REAL,
+            FOREIGN KEY(race_id) REFERENCES races(id) ON DELETE CASCADE,
+            FOREIGN KEY(artifact_id) REFERENCES artifacts(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS race_logs (
+            race_id INTEGER NOT NULL,
+            artifact_id INTEGER,
+            log TEXT NOT NULL,
+            PRIMARY KEY(race_id, artifact_id),
+            FOREIGN KEY(race_id) REFERENCES races(id) ON DELETE CASCADE,
+            FOREIGN KEY(artifact_id) REFERENCES artifacts(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS artifact_stars (
+            user_id INTEGER NOT NULL,
+            artifact_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY(user_id, artifact_id),
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE,
+            FOREIGN KEY(artifact_id) REFERENCES artifacts(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS replays (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uploaded_by_user_id INTEGER NOT NULL,
+            data TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(uploaded_by_user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+        ",
+    )?;
+
+    let mut columns = Vec::new();
+    let mut stmt = conn.prepare("PRAGMA table_info(artifacts)")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for row in rows {
+        columns.push(row?);
+    }
+
+    if !columns.iter().any(|c| c == "is_public") {
+        conn.execute(
+            "ALTER TABLE artifacts ADD COLUMN is_public INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+    if !columns.iter().any(|c| c == "abi_version") {
+        conn.execute("ALTER TABLE artifacts ADD COLUMN abi_version INTEGER", [])?;
+    }
+    if !columns.iter().any(|c| c == "build_id") {
+        conn.execute("ALTER TABLE artifacts ADD COLUMN build_id TEXT", [])?;
+    }
+    if !columns.iter().any(|c| c == "deleted_at") {
+        conn.execute("ALTER TABLE artifacts ADD COLUMN deleted_at TEXT", [])?;
+    }
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+fn _ensure_under(path: &Path, root: &Path) -> bool {
+    path.starts_with(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{Body, to_bytes},
+        http::Request,
+    };
+    use botracers_protocol::{
+        ArtifactSummary, BOT_ABI_VERSION, LoginResponse, RaceEntrySubmission, ReplayInput,
+        ReplayParticipant, UpdateArtifactVisibilityRequest, UploadArtifactRequest,
+    };
+    use emulator::test_elf::{elf_with_code, elf_with_load, with_section};
+    use tower::ServiceExt;
+
+    fn unique_temp_dir(prefix: &str) -> PathBuf {
+        let mut rng = rand::rng();
+        let suffix: u64 = rng.random();
+        let dir = std::env::temp_dir().join(format!("{prefix}_{suffix}"));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    fn setup_test_state(
+        auth_mode: AuthMode,
+        registration_enabled: bool,
+    ) -> (AppState, PathBuf, PathBuf) {
+        let static_dir = unique_temp_dir("botracers_static");
+        let artifacts_dir = unique_temp_dir("botracers_artifacts");
+        std::fs::write(
+            static_dir.join("index.html"),
+            "<html><body>game entry</body></html>",
+        )
+        .expect("write index");
+
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite");
+        run_migrations(&conn).expect("run migrations");
+        ensure_local_user(&conn).expect("ensure local user");
+        let state = AppState {
+            db: Arc::new(Mutex::new(conn)),
+            artifacts_dir: artifacts_dir.clone(),
+            static_dir: Some(static_dir.clone()),
+            auth_mode,
+            cookie_secure: false,
+            registration_enabled,
+            default_artifact_public: false,
+            events_public: false,
+            race_events: broadcast::channel(RACE_EVENT_BUFFER).0,
+            import_allow_private_hosts: false,
+            auth_header: header::AUTHORIZATION,
+            auth_scheme: DEFAULT_AUTH_SCHEME.to_string(),
+            duplicate_names: DuplicateNamePolicy::Suffix,
+            artifact_retention_days: 30,
+            max_export_bytes: MAX_EXPORT_BYTES,
+            admin_users: vec!["admin".to_string()],
+        };
+        (state, static_dir, artifacts_dir)
+    }
+
+    async fn create_user(state: &AppState, username: &str, password: &str) {
+        let hash = hash_password(password).expect("hash password");
+        let db = state.db.lock().await;
+        db.execute(
+            "INSERT INTO users (username, password_hash, created_at) VALUES (?1, ?2, ?3)",
+            params![username, hash, now_utc()],
+        )
+        .expect("insert user");
+    }
+
+    async fn make_session_cookie(state: &AppState, username: &str, password: &str) -> String {
+        let (_, token) = create_session_for_credentials(state, username, password)
+            .await
+            .expect("create session");
+        format!("{COOKIE_NAME}={token}")
+    }
+
+    async fn upload_artifact_with_cookie(
+        app: &Router,
+        cookie: &str,
+        name: &str,
+    ) -> (StatusCode, ArtifactId) {
+        upload_artifact_with_visibility(app, cookie, name, None).await
+    }
+
+    async fn upload_artifact_with_visibility(
+        app: &Router,
+        cookie: &str,
+        name: &str,
+        is_public: Option<bool>,
+    ) -> (StatusCode, ArtifactId) {
+        upload_elf_with_cookie(app, cookie, name, is_public, &[0x7f, b'E', b'L', b'F']).await
+    }
+
+    async fn upload_elf_with_cookie(
+        app: &Router,
+        cookie: &str,
+        name: &str,
+        is_public: Option<bool>,
+        elf_bytes: &[u8],
+    ) -> (StatusCode, ArtifactId) {
+        let payload = UploadArtifactRequest {
+            name: name.to_string(),
+            note: None,
+            target: "riscv32imafc-unknown-none-elf".to_string(),
+            elf_base64: base64::engine::general_purpose::STANDARD.encode(elf_bytes),
+            is_public,
+            build_id: None,
+        };
+        post_upload_with_cookie(app, cookie, &payload).await
+    }
+
+    async fn post_upload_with_cookie(
+        app: &Router,
+        cookie: &str,
+        payload: &UploadArtifactRequest,
+    ) -> (StatusCode, ArtifactId) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/artifacts")
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(payload).expect("serialize payload"),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let parsed: UploadArtifactResponse = serde_json::from_slice(&body).expect("upload json");
+        (status, parsed.artifact_id)
+    }
+
+    async fn import_artifact_with_cookie(
+        app: &Router,
+        cookie: &str,
+        url: &str,
+    ) -> (StatusCode, Option<ArtifactId>) {
+        let payload = ImportArtifactRequest {
+            url: url.to_string(),
+            name: None,
+            note: None,
+            target: "riscv32imafc-unknown-none-elf".to_string(),
+            is_public: None,
+        };
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/artifacts/import")
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&payload).expect("serialize payload"),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let parsed = serde_json::from_slice::<UploadArtifactResponse>(&body)
+            .ok()
+            .map(|r| r.artifact_id);
+        (status, parsed)
+    }
+
+    /// Serves `body` once over plain HTTP on a loopback port and returns the URL.
+    fn serve_once(path: &str, body: &'static [u8]) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}{path}", listener.local_addr().expect("addr"));
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).expect("write header");
+            stream.write_all(body).expect("write body");
+        });
+        url
+    }
+
+    async fn list_artifacts_with_cookie(app: &Router, cookie: &str) -> Vec<ArtifactSummary> {
+        list_artifacts_with_cookie_at(app, cookie, "/api/v1/artifacts").await
+    }
+
+    async fn list_artifacts_with_cookie_at(
+        app: &Router,
+        cookie: &str,
+        uri: &str,
+    ) -> Vec<ArtifactSummary> {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        serde_json::from_slice(&body).expect("artifact list json")
+    }
+
+    async fn update_visibility_with_cookie(
+        app: &Router,
+        cookie: &str,
+        artifact_id: ArtifactId,
+        is_public: bool,
+    ) -> StatusCode {
+        let payload = UpdateArtifactVisibilityRequest { is_public };
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PATCH")
+                    .uri(format!("/api/v1/artifacts/{artifact_id}/visibility"))
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&payload).expect("serialize payload"),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        resp.status()
+    }
+
+    async fn download_artifact_with_cookie(
+        app: &Router,
+        cookie: &str,
+        artifact_id: ArtifactId,
+    ) -> StatusCode {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/artifacts/{artifact_id}"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        resp.status()
+    }
+
+    async fn clone_artifact_with_cookie(
+        app: &Router,
+        cookie: &str,
+        artifact_id: ArtifactId,
+    ) -> (StatusCode, Option<ArtifactId>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/artifacts/{artifact_id}/clone"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let parsed = serde_json::from_slice::<UploadArtifactResponse>(&body)
+            .ok()
+            .map(|r| r.artifact_id);
+        (status, parsed)
+    }
+
+    async fn delete_artifact_with_cookie(
+        app: &Router,
+        cookie: &str,
+        artifact_id: ArtifactId,
+    ) -> StatusCode {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/api/v1/artifacts/{artifact_id}"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response")
+            .status()
+    }
+
+    async fn restore_artifact_with_cookie(
+        app: &Router,
+        cookie: &str,
+        artifact_id: ArtifactId,
+    ) -> (StatusCode, Option<UploadArtifactResponse>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/artifacts/{artifact_id}/restore"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    async fn collect_garbage_with_cookie(
+        app: &Router,
+        cookie: &str,
+    ) -> (StatusCode, Option<GarbageCollectResponse>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/admin/gc")
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    async fn export_artifacts_with_cookie(app: &Router, cookie: &str) -> (StatusCode, Vec<u8>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/artifacts/export")
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        (status, body.to_vec())
+    }
+
+    fn tar_entries(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+        read_tar(archive)
+            .expect("valid tar")
+            .into_iter()
+            .map(|(path, data)| (path, data.to_vec()))
+            .collect()
+    }
+
+    async fn import_archive_with_cookie(
+        app: &Router,
+        cookie: &str,
+        uri: &str,
+        archive: Vec<u8>,
+    ) -> (StatusCode, Option<ImportArchiveResponse>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/x-tar")
+                    .body(Body::from(archive))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    async fn star_artifact_with_cookie(
+        app: &Router,
+        cookie: &str,
+        artifact_id: ArtifactId,
+        starred: bool,
+    ) -> StatusCode {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(if starred { "POST" } else { "DELETE" })
+                    .uri(format!("/api/v1/artifacts/{artifact_id}/star"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        resp.status()
+    }
+
+    async fn submit_race_with_cookie(
+        app: &Router,
+        cookie: &str,
+        artifact_ids: &[ArtifactId],
+    ) -> (StatusCode, Option<i64>) {
+        let laps: Vec<_> = artifact_ids.iter().map(|&id| (id, Some(41.5))).collect();
+        submit_race_laps_with_cookie(app, cookie, &laps).await
+    }
+
+    /// Submits a race in the given finishing order with each car's best lap,
+    /// and a replay of it.
+    async fn submit_race_laps_with_cookie(
+        app: &Router,
+        cookie: &str,
+        entries: &[(ArtifactId, Option<f32>)],
+    ) -> (StatusCode, Option<i64>) {
+        let artifact_ids: Vec<_> = entries.iter().map(|&(id, _)| id).collect();
+        let payload = SubmitRaceRequest {
+            entries: entries
+                .iter()
+                .map(|&(artifact_id, best_lap_secs)| RaceEntrySubmission {
+                    artifact_id,
+                    laps_completed: 3,
+                    best_lap_secs,
+                    finish_reason: None,
+                })
+                .collect(),
+            replay: Some(sample_replay(&artifact_ids)),
+        };
+        let (status, race) = submit_race_request_with_cookie(app, cookie, &payload).await;
+        (status, race.map(|r| r.race_id))
+    }
+
+    async fn submit_race_request_with_cookie(
+        app: &Router,
+        cookie: &str,
+        payload: &SubmitRaceRequest,
+    ) -> (StatusCode, Option<SubmitRaceResponse>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/races")
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(payload).expect("serialize payload"),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    async fn verify_race_with_cookie(app: &Router, cookie: &str, race_id: i64) -> StatusCode {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/admin/races/{race_id}/verify"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response")
+            .status()
+    }
+
+    async fn reject_race_with_cookie(
+        app: &Router,
+        cookie: &str,
+        race_id: i64,
+        reason: &str,
+    ) -> StatusCode {
+        let payload = RejectRaceRequest {
+            reason: reason.to_string(),
+        };
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/admin/races/{race_id}/reject"))
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&payload).expect("serialize payload"),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response")
+            .status()
+    }
+
+    async fn review_race_with_cookie(
+        app: &Router,
+        cookie: &str,
+        race_id: i64,
+    ) -> (StatusCode, Option<RaceReview>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/admin/races/{race_id}"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    async fn list_artifact_races_with_cookie(
+        app: &Router,
+        cookie: &str,
+        uri: &str,
+    ) -> (StatusCode, Vec<ArtifactRaceResult>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let races = serde_json::from_slice(&body).unwrap_or_default();
+        (status, races)
+    }
+
+    async fn upload_race_logs_with_cookie(
+        app: &Router,
+        cookie: &str,
+        race_id: i64,
+        logs: &[(ArtifactId, String)],
+    ) -> StatusCode {
+        let payload = UploadRaceLogsRequest {
+            transcripts: logs
+                .iter()
+                .map(|(artifact_id, log)| RaceLogTranscript {
+                    artifact_id: *artifact_id,
+                    log: log.clone(),
+                })
+                .collect(),
+        };
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/races/{race_id}/logs"))
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&payload).expect("serialize payload"),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        resp.status()
+    }
+
+    async fn list_race_logs_with_cookie(
+        app: &Router,
+        cookie: &str,
+        race_id: i64,
+    ) -> (StatusCode, Vec<RaceLogTranscript>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/races/{race_id}/logs"))
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let logs = serde_json::from_slice(&body).unwrap_or_default();
+        (status, logs)
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_game_entry_shows_login_in_required_mode() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let text = String::from_utf8(body.to_vec()).expect("utf8");
+        assert!(text.contains("Sign in to BotRacers"));
+        assert!(text.contains("name=\"next\" value=\"/\""));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn authenticated_game_entry_serves_index() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(header::COOKIE, cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let text = String::from_utf8(body.to_vec()).expect("utf8");
+        assert!(text.contains("game entry"));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn web_login_success_sets_cookie_and_redirects() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/login")
+                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "username=alice&password=password123&next=%2Findex.html",
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers()
+                .get(header::LOCATION)
+                .expect("location")
+                .to_str()
+                .expect("location str"),
+            "/index.html"
+        );
+        let cookie = resp
+            .headers()
+            .get(header::SET_COOKIE)
+            .expect("set-cookie")
+            .to_str()
+            .expect("cookie str");
+        assert!(cookie.contains("botracers_session="));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn web_login_failure_renders_error() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/login")
+                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "username=alice&password=wrongpassword&next=%2Findex.html",
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(resp.headers().get(header::SET_COOKIE).is_none());
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let text = String::from_utf8(body.to_vec()).expect("utf8");
+        assert!(text.contains("Invalid username or password"));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[test]
+    fn sanitize_next_rejects_external_targets() {
+        assert_eq!(sanitize_next("https://evil.com"), "/");
+        assert_eq!(sanitize_next("//evil.com"), "/");
+        assert_eq!(sanitize_next("/assets/foo"), "/assets/foo");
+    }
+
+    #[tokio::test]
+    async fn api_login_still_returns_json_and_cookie() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/auth/login")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        "{\"username\":\"alice\",\"password\":\"password123\"}",
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key(header::SET_COOKIE));
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let parsed: LoginResponse = serde_json::from_slice(&body).expect("login response json");
+        assert_eq!(parsed.user.username, "alice");
+        assert!(!parsed.token.is_empty());
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[test]
+    fn auth_mode_parses_known_modes_and_refuses_typos() {
+        assert_eq!(AuthMode::parse("required"), Ok(AuthMode::Required));
+        assert_eq!(AuthMode::parse("api_keys"), Ok(AuthMode::ApiKeys));
+        assert_eq!(AuthMode::parse("disabled"), Ok(AuthMode::Disabled));
+        let error = AuthMode::parse("disable").expect_err("typo is refused");
+        assert!(error.contains("'disable'"), "{error}");
+
+        assert_eq!(AuthMode::from_env("api_keys"), AuthMode::ApiKeys);
+        assert_eq!(AuthMode::from_env("disabled"), AuthMode::Disabled);
+        assert_eq!(AuthMode::from_env("disable"), AuthMode::Required);
+        assert_eq!(AuthMode::from_env(""), AuthMode::Required);
+    }
+
+    #[test]
+    fn duplicate_name_policy_parses_known_values_and_refuses_typos() {
+        assert_eq!(
+            DuplicateNamePolicy::parse("reject"),
+            Ok(DuplicateNamePolicy::Reject)
+        );
+        assert_eq!(
+            DuplicateNamePolicy::parse("suffix"),
+            Ok(DuplicateNamePolicy::Suffix)
+        );
+        let error = DuplicateNamePolicy::parse("rejct").expect_err("typo is refused");
+        assert!(error.contains("'rejct'"), "{error}");
+
+        assert_eq!(
+            DuplicateNamePolicy::from_env("suffix"),
+            DuplicateNamePolicy::Suffix
+        );
+        assert_eq!(
+            DuplicateNamePolicy::from_env("rejct"),
+            DuplicateNamePolicy::Reject
+        );
+        assert_eq!(
+            DuplicateNamePolicy::from_env(""),
+            DuplicateNamePolicy::Reject
+        );
+    }
+
+    #[tokio::test]
+    async fn capabilities_include_registration_enabled() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
+        let app = build_app(state, Some(static_dir.clone()));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/capabilities")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let caps: ServerCapabilities = serde_json::from_slice(&body).expect("caps json");
+        assert!(caps.auth_required);
+        assert!(!caps.registration_enabled);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn capabilities_report_limits_and_features() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        let app = build_app(state, Some(static_dir.clone()));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/capabilities")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let caps: ServerCapabilities = serde_json::from_slice(&body).expect("caps json");
+        assert_eq!(caps.max_artifact_bytes, Some(MAX_ARTIFACT_BYTES as u64));
+        assert_eq!(caps.allowed_targets, vec!["riscv32imafc-unknown-none-elf"]);
+        assert!(!caps.compile_supported);
+        assert_eq!(caps.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(caps.supported_abi_versions, vec![BOT_ABI_VERSION]);
+        assert_eq!(caps.max_field_size, Some(MAX_FIELD_SIZE as u32));
+
+        // Fields survive a client-side round trip unchanged.
+        let again: ServerCapabilities =
+            serde_json::from_str(&serde_json::to_string(&caps).expect("serialize caps"))
+                .expect("caps json");
+        assert_eq!(again.max_artifact_bytes, caps.max_artifact_bytes);
+        assert_eq!(again.allowed_targets, caps.allowed_targets);
+        assert_eq!(again.compile_supported, caps.compile_supported);
+        assert_eq!(again.version, caps.version);
+
+        // Older servers omit the new fields entirely.
+        let legacy: ServerCapabilities =
+            serde_json::from_str(r#"{"auth_required":false,"mode":"disabled"}"#)
+                .expect("legacy caps json");
+        assert_eq!(legacy.max_artifact_bytes, None);
+        assert!(legacy.allowed_targets.is_empty());
+        assert!(legacy.version.is_empty());
+        assert_eq!(legacy.auth_header, "Authorization");
+        assert_eq!(legacy.auth_scheme, "Bearer");
+        assert!(legacy.supported_abi_versions.is_empty());
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn custom_auth_header_is_advertised_and_accepted() {
+        let (mut state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        state.auth_header = HeaderName::from_static("x-botracers-auth");
+        state.auth_scheme = "Token".to_string();
+        create_user(&state, "alice", "password123").await;
+        let (_, token) = create_session_for_credentials(&state, "alice", "password123")
+            .await
+            .expect("create session");
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/capabilities")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let caps: ServerCapabilities = serde_json::from_slice(&body).expect("caps json");
+        assert_eq!(caps.auth_header, "x-botracers-auth");
+        assert_eq!(caps.auth_scheme, "Token");
+
+        let me_with = |name: &'static str, value: String| {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri("/api/v1/me")
+                    .header(name, value)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+        };
+        let resp = me_with("x-botracers-auth", format!("Token {token}"))
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The default header and a mismatched scheme are no longer honored.
+        let resp = me_with("authorization", format!("Bearer {token}"))
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = me_with("x-botracers-auth", format!("Bearer {token}"))
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn api_key_authenticates_until_revoked() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::ApiKeys, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/api-keys")
+                    .header(header::COOKIE, &alice_cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"name":"ci"}"#))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let created: CreateApiKeyResponse = serde_json::from_slice(&body).expect("key json");
+        let stored: String = state
+            .db
+            .lock()
+            .await
+            .query_row(
+                "SELECT key_hash FROM api_keys WHERE id = ?1",
+                params![created.id],
+                |row| row.get(0),
+            )
+            .expect("stored key");
+        assert_ne!(stored, created.key);
+        assert_eq!(stored, hash_api_key(&created.key));
+
+        let with_key = |method: &'static str, uri: String| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(header::AUTHORIZATION, format!("Bearer {}", created.key))
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+        };
+        let resp = with_key("GET", "/api/v1/me".to_string())
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let me: UserInfo = serde_json::from_slice(&body).expect("me json");
+        assert_eq!(me.username, "alice");
+
+        let resp = with_key("GET", "/api/v1/api-keys".to_string())
+            .await
+            .expect("response");
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let keys: Vec<ApiKeySummary> = serde_json::from_slice(&body).expect("keys json");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].id, created.id);
+        assert_eq!(keys[0].name, "ci");
+
+        let resp = with_key("DELETE", format!("/api/v1/api-keys/{}", created.id))
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = with_key("GET", "/api/v1/me".to_string())
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    async fn rename_with_cookie(app: &Router, cookie: &str, username: &str) -> StatusCode {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("PATCH")
+                    .uri("/api/v1/users/me")
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&RenameUserRequest {
+                            username: username.to_string(),
+                        })
+                        .expect("rename json"),
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response")
+            .status()
+    }
+
+    #[tokio::test]
+    async fn rename_keeps_the_session_under_the_new_name() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+
+        assert_eq!(
+            rename_with_cookie(&app, &cookie, " alice_2 ").await,
+            StatusCode::OK
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/me")
+                    .header(header::COOKIE, &cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let me: UserInfo = serde_json::from_slice(&body).expect("me json");
+        assert_eq!(me.username, "alice_2");
+        assert!(
+            create_session_for_credentials(&state, "alice_2", "password123")
+                .await
+                .is_ok()
+        );
+        assert!(
+            create_session_for_credentials(&state, "alice", "password123")
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn rename_rejects_taken_reserved_and_malformed_names() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        assert_eq!(
+            rename_with_cookie(&app, &cookie, "bob").await,
+            StatusCode::CONFLICT
+        );
+        for name in ["admin", "Root", "local", "al", "alice smith", "-alice"] {
+            assert_eq!(
+                rename_with_cookie(&app, &cookie, name).await,
+                StatusCode::BAD_REQUEST,
+                "{name}"
+            );
+        }
+        assert_eq!(
+            rename_with_cookie(&app, "botracers_session=nope", "carol").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn api_keys_are_refused_outside_api_key_mode() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/api-keys")
+                    .header(header::COOKIE, &alice_cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"name":"ci"}"#))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn api_register_blocked_when_registration_disabled() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
+        let app = build_app(state, Some(static_dir.clone()));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/auth/register")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        "{\"username\":\"alice\",\"password\":\"password123\"}",
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let error: ErrorResponse = serde_json::from_slice(&body).expect("error json");
+        assert_eq!(error.error, "registration is disabled");
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn api_register_creates_user_when_registration_enabled() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/auth/register")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        "{\"username\":\"alice\",\"password\":\"password123\"}",
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let user: UserInfo = serde_json::from_slice(&body).expect("user json");
+        assert_eq!(user.username, "alice");
+        let (session_user, _) = create_session_for_credentials(&state, "alice", "password123")
+            .await
+            .expect("registered user can log in");
+        assert_eq!(session_user.id, user.id);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn registration_refuses_admin_and_reserved_names() {
+        let (mut state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        state.admin_users.push("ops-team".to_string());
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+
+        for username in ["ops-team", "admin", "Root", "alice"] {
+            let resp = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/auth/register")
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from(format!(
+                            "{{\"username\":\"{username}\",\"password\":\"password123\"}}"
+                        )))
+                        .expect("request"),
+                )
+                .await
+                .expect("response");
+            let expected = if username == "alice" {
+                StatusCode::OK
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            assert_eq!(resp.status(), expected, "{username}");
+        }
+        assert!(
+            create_session_for_credentials(&state, "ops-team", "password123")
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn registration_refuses_malformed_names() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+
+        for username in ["bob.smith", "a", "", "_bob", "bob-smith"] {
+            let resp = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/auth/register")
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from(format!(
+                            "{{\"username\":\"{username}\",\"password\":\"password123\"}}"
+                        )))
+                        .expect("request"),
+                )
+                .await
+                .expect("response");
+            let expected = if username == "bob-smith" {
+                StatusCode::OK
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            assert_eq!(resp.status(), expected, "{username}");
+        }
+        assert!(
+            create_session_for_credentials(&state, "bob.smith", "password123")
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn register_flow_creates_session_and_redirects() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        let app = build_app(state, Some(static_dir.clone()));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/register")
+                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "username=bob&password=password123&next=%2Findex.html",
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+
+        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers()
+                .get(header::LOCATION)
+                .expect("location")
+                .to_str()
+                .expect("location str"),
+            "/index.html"
+        );
+        assert!(resp.headers().contains_key(header::SET_COOKIE));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn register_routes_redirect_when_disabled() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
+        let app = build_app(state, Some(static_dir.clone()));
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/register?next=%2Findex.html")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers()
+                .get(header::LOCATION)
+                .expect("location")
+                .to_str()
+                .expect("location str"),
+            "/login?next=%2Findex.html&error=registration_disabled"
+        );
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/register")
+                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "username=bob&password=password123&next=%2Findex.html",
+                    ))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers()
+                .get(header::LOCATION)
+                .expect("location")
+                .to_str()
+                .expect("location str"),
+            "/login?next=%2Findex.html&error=registration_disabled"
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn login_page_hides_register_link_when_disabled() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
+        let app = build_app(state, Some(static_dir.clone()));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/login?next=%2F")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let text = String::from_utf8(body.to_vec()).expect("utf8");
+        assert!(!text.contains("Create account"));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn uploads_are_private_by_default() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (status, artifact_id) = upload_artifact_with_cookie(&app, &alice_cookie, "a.elf").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let artifacts = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        let artifact = artifacts
+            .into_iter()
+            .find(|a| a.id == artifact_id)
+            .expect("artifact exists");
+        assert!(!artifact.is_public);
+        assert!(artifact.owned_by_me);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    /// An idling RV32 bot whose ABI section holds `version`.
+    fn elf_with_abi_version(version: u32) -> Vec<u8> {
+        with_section(
+            elf_with_code(&[0x0000_006f]),
+            BOT_ABI_SECTION,
+            &version.to_le_bytes(),
+        )
+    }
+
+    #[tokio::test]
+    async fn upload_records_the_bot_abi_version() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (status, tagged_id) = upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "tagged.elf",
+            None,
+            &elf_with_abi_version(BOT_ABI_VERSION),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, untagged_id) =
+            upload_artifact_with_cookie(&app, &alice_cookie, "untagged.elf").await;
+
+        let artifacts = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        let abi_of = |id: ArtifactId| {
+            artifacts
+                .iter()
+                .find(|a| a.id == id)
+                .expect("artifact exists")
+                .abi_version
+        };
+        assert_eq!(abi_of(tagged_id), Some(BOT_ABI_VERSION));
+        assert_eq!(abi_of(untagged_id), None);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn upload_for_an_unsupported_abi_is_rejected() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let payload = UploadArtifactRequest {
+            name: "future.elf".to_string(),
+            note: None,
+            target: "riscv32imafc-unknown-none-elf".to_string(),
+            elf_base64: base64::engine::general_purpose::STANDARD
+                .encode(elf_with_abi_version(BOT_ABI_VERSION + 1)),
+            is_public: None,
+            build_id: None,
+        };
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/artifacts")
+                    .header(header::COOKIE, &alice_cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(serde_json::to_vec(&payload).expect("payload")))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let error: ErrorResponse = serde_json::from_slice(&body).expect("error json");
+        assert!(
+            error
+                .error
+                .contains(&format!("ABI version {}", BOT_ABI_VERSION + 1)),
+            "{}",
+            error.error
+        );
+        assert!(
+            list_artifacts_with_cookie(&app, &alice_cookie)
+                .await
+                .is_empty()
+        );
+
+        let (status, _) = upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "current.elf",
+            None,
+            &elf_with_abi_version(BOT_ABI_VERSION),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            list_artifacts_with_cookie(&app, &alice_cookie).await.len(),
+            1
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn upload_with_an_entry_outside_executable_code_is_rejected() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let payload = UploadArtifactRequest {
+            name: "data.elf".to_string(),
+            note: None,
+            target: "riscv32imafc-unknown-none-elf".to_string(),
+            elf_base64: base64::engine::general_purpose::STANDARD.encode(elf_with_load(
+                0x1000,
+                &0x0000_006fu32.to_le_bytes(),
+                4,
+                6,
+            )),
+            is_public: None,
+            build_id: None,
+        };
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/artifacts")
+                    .header(header::COOKIE, &alice_cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(serde_json::to_vec(&payload).expect("payload")))
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let error: ErrorResponse = serde_json::from_slice(&body).expect("error json");
+        assert_eq!(
+            error.error,
+            "entry point 0x1000 is not inside a loaded executable segment"
+        );
+        assert!(
+            list_artifacts_with_cookie(&app, &alice_cookie)
+                .await
+                .is_empty()
+        );
+
+        let (status, _) = upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "code.elf",
+            None,
+            &elf_with_code(&[0x0000_006f]),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn upload_records_the_build_id() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let embedded = with_section(
+            elf_with_code(&[0x0000_006f]),
+            BOT_BUILD_ID_SECTION,
+            b"abc123-dirty\0\0",
+        );
+        let payload = UploadArtifactRequest {
+            name: "requested.elf".to_string(),
+            note: None,
+            target: "riscv32imafc-unknown-none-elf".to_string(),
+            elf_base64: base64::engine::general_purpose::STANDARD.encode(&embedded),
+            is_public: None,
+            build_id: Some(" 0123abcd ".to_string()),
+        };
+        let (status, requested_id) = post_upload_with_cookie(&app, &alice_cookie, &payload).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, embedded_id) =
+            upload_elf_with_cookie(&app, &alice_cookie, "embedded.elf", None, &embedded).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, untagged_id) =
+            upload_artifact_with_cookie(&app, &alice_cookie, "untagged.elf").await;
+
+        let too_long = UploadArtifactRequest {
+            name: "too-long.elf".to_string(),
+            build_id: Some("x".repeat(MAX_BUILD_ID_LEN + 1)),
+            ..payload
+        };
+        let (status, _) = upload_artifact_request(&app, &alice_cookie, &too_long).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let artifacts = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        let build_of = |id: ArtifactId| {
+            artifacts
+                .iter()
+                .find(|a| a.id == id)
+                .expect("artifact exists")
+                .build_id
+                .clone()
+        };
+        assert_eq!(build_of(requested_id).as_deref(), Some("0123abcd"));
+        assert_eq!(build_of(embedded_id).as_deref(), Some("abc123-dirty"));
+        assert_eq!(build_of(untagged_id), None);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn list_artifacts_in_required_mode_shows_own_and_public_others() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, alice_artifact_id) =
+            upload_artifact_with_cookie(&app, &alice_cookie, "alice.elf").await;
+        let (_, bob_artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, bob_artifact_id, true).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let alice_view = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert!(
+            alice_view
+                .iter()
+                .any(|a| a.id == alice_artifact_id && a.owned_by_me)
+        );
+        assert!(
+            alice_view
+                .iter()
+                .any(|a| a.id == bob_artifact_id && !a.owned_by_me && a.is_public)
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn list_artifacts_hides_private_others() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, bob_artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        let alice_view = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert!(!alice_view.iter().any(|a| a.id == bob_artifact_id));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn list_artifacts_scope_mine_excludes_public_others() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, alice_artifact_id) =
+            upload_artifact_with_cookie(&app, &alice_cookie, "alice.elf").await;
+        let (_, bob_artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, bob_artifact_id, true).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let alice_view =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=mine")
+                .await;
+        assert!(alice_view.iter().any(|a| a.id == alice_artifact_id));
+        assert!(!alice_view.iter().any(|a| a.id == bob_artifact_id));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn list_artifacts_scope_public_excludes_private() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, alice_private_id) =
+            upload_artifact_with_cookie(&app, &alice_cookie, "alice.elf").await;
+        let (_, bob_public_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, bob_public_id, true).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let alice_view =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=public")
+                .await;
+        assert!(alice_view.iter().all(|a| a.is_public));
+        assert!(alice_view.iter().any(|a| a.id == bob_public_id));
+        assert!(!alice_view.iter().any(|a| a.id == alice_private_id));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn stars_are_per_user_and_filter_scope_starred() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, alice_id) = upload_artifact_with_cookie(&app, &alice_cookie, "alice.elf").await;
+        let (_, bob_public) =
+            upload_artifact_with_visibility(&app, &bob_cookie, "bob.elf", Some(true)).await;
+        let (_, bob_private) =
+            upload_artifact_with_visibility(&app, &bob_cookie, "secret.elf", Some(false)).await;
+
+        assert_eq!(
+            star_artifact_with_cookie(&app, &alice_cookie, bob_public, true).await,
+            StatusCode::NO_CONTENT
+        );
+        // Starring twice is a no-op; artifacts the caller cannot see cannot be starred.
+        assert_eq!(
+            star_artifact_with_cookie(&app, &alice_cookie, bob_public, true).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            star_artifact_with_cookie(&app, &alice_cookie, bob_private, true).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            star_artifact_with_cookie(&app, &alice_cookie, ArtifactId(bob_private.0 + 100), true)
+                .await,
+            StatusCode::NOT_FOUND
+        );
+
+        let alice_view = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert!(alice_view.iter().any(|a| a.id == bob_public && a.starred));
+        assert!(alice_view.iter().any(|a| a.id == alice_id && !a.starred));
+        let bob_view = list_artifacts_with_cookie(&app, &bob_cookie).await;
+        assert!(bob_view.iter().all(|a| !a.starred));
+
+        let starred =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=starred")
+                .await;
+        assert_eq!(
+            starred.iter().map(|a| a.id).collect::<Vec<_>>(),
+            vec![bob_public]
+        );
+        let bob_starred =
+            list_artifacts_with_cookie_at(&app, &bob_cookie, "/api/v1/artifacts?scope=starred")
+                .await;
+        assert!(bob_starred.is_empty());
+
+        // A star on an artifact that later turns private is hidden, not leaked.
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, bob_public, false).await,
+            StatusCode::NO_CONTENT
+        );
+        let starred =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=starred")
+                .await;
+        assert!(starred.is_empty());
+
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, bob_public, true).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            star_artifact_with_cookie(&app, &alice_cookie, bob_public, false).await,
+            StatusCode::NO_CONTENT
+        );
+        let starred =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=starred")
+                .await;
+        assert!(starred.is_empty());
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn download_public_artifact_allowed_for_non_owner() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, artifact_id, true).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            download_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
+            StatusCode::OK
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn download_private_artifact_denied_for_non_owner() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        assert_eq!(
+            download_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    async fn upload_artifact_response(
+        app: &Router,
+        cookie: &str,
+        name: &str,
+    ) -> (StatusCode, Result<UploadArtifactResponse, ErrorResponse>) {
+        let payload = UploadArtifactRequest {
+            name: name.to_string(),
+            note: None,
+            target: "riscv32imafc-unknown-none-elf".to_string(),
+            elf_base64: base64::engine::general_purpose::STANDARD.encode([0x7f, b'E', b'L', b'F']),
+            is_public: None,
+            build_id: None,
+        };
+        upload_artifact_request(app, cookie, &payload).await
+    }
+
+    async fn upload_artifact_request(
+        app: &Router,
+        cookie: &str,
+        payload: &UploadArtifactRequest,
+    ) -> (StatusCode, Result<UploadArtifactResponse, ErrorResponse>) {
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/artifacts")
+                    .header(header::COOKIE, cookie)
                     .header(header::CONTENT_TYPE, "application/json")
                     .body(Body::from(
-                        serde_json::to_vec(&payload).expect("serialize payload"),
+                        serde_json::to_vec(payload).expect("serialize payload"),
                     ))
                     .expect("request"),
             )
             .await
-            .expect("response");
-        let status = resp.status();
-        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let parsed: UploadArtifactResponse = serde_json::from_slice(&body).expect("upload json");
-        (status, parsed.artifact_id)
+            .expect("response");
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let parsed = serde_json::from_slice::<UploadArtifactResponse>(&body)
+            .map_err(|_| serde_json::from_slice::<ErrorResponse>(&body).expect("error json"));
+        (status, parsed)
+    }
+
+    #[tokio::test]
+    async fn duplicate_artifact_names_are_suffixed_by_default() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            let (status, upload) = upload_artifact_response(&app, &alice_cookie, "car.elf").await;
+            assert_eq!(status, StatusCode::OK);
+            names.push(upload.expect("upload json").name);
+        }
+        assert_eq!(names, vec!["car.elf", "car (2).elf", "car (3).elf"]);
+
+        let (_, upload) = upload_artifact_response(&app, &alice_cookie, "plain").await;
+        assert_eq!(upload.expect("upload json").name, "plain");
+        let (_, upload) = upload_artifact_response(&app, &alice_cookie, "plain").await;
+        assert_eq!(upload.expect("upload json").name, "plain (2)");
+
+        // Names are only unique per owner.
+        let (_, upload) = upload_artifact_response(&app, &bob_cookie, "car.elf").await;
+        assert_eq!(upload.expect("upload json").name, "car.elf");
+
+        let listed: Vec<String> =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=mine")
+                .await
+                .into_iter()
+                .map(|a| a.name)
+                .collect();
+        assert!(listed.contains(&"car (3).elf".to_string()));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn duplicate_artifact_names_conflict_when_rejected() {
+        let (mut state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        state.duplicate_names = DuplicateNamePolicy::Reject;
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (status, _) = upload_artifact_response(&app, &alice_cookie, "car.elf").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, upload) = upload_artifact_response(&app, &alice_cookie, "car.elf").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let error = upload.expect_err("conflict error").error;
+        assert!(
+            error.contains("already have an artifact named 'car.elf'"),
+            "{error}"
+        );
+        assert_eq!(
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=mine")
+                .await
+                .len(),
+            1
+        );
+
+        // Cloning into a name the caller already uses is refused the same way.
+        let (_, bob_car) =
+            upload_artifact_with_visibility(&app, &bob_cookie, "car.elf", Some(true)).await;
+        let (status, clone_id) = clone_artifact_with_cookie(&app, &alice_cookie, bob_car).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(clone_id, None);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn negative_artifact_ids_are_not_found() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let missing = ArtifactId(-1);
+        assert_eq!(
+            download_artifact_with_cookie(&app, &alice_cookie, missing).await,
+            StatusCode::NOT_FOUND
+        );
+        let (status, _) = submit_race_with_cookie(&app, &alice_cookie, &[missing]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn race_larger_than_the_advertised_field_is_rejected() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &alice_cookie, "car.elf").await;
+
+        let full_field = vec![artifact_id; MAX_FIELD_SIZE];
+        let (status, _) = submit_race_with_cookie(&app, &alice_cookie, &full_field).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let oversized = vec![artifact_id; MAX_FIELD_SIZE + 1];
+        let (status, race_id) = submit_race_with_cookie(&app, &alice_cookie, &oversized).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(race_id, None);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn owner_can_toggle_visibility() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &alice_cookie, "alice.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &alice_cookie, artifact_id, true).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let artifacts = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        let artifact = artifacts
+            .into_iter()
+            .find(|a| a.id == artifact_id)
+            .expect("artifact exists");
+        assert!(artifact.is_public);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn non_owner_cannot_toggle_visibility() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &alice_cookie, artifact_id, true).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[test]
+    fn migration_adds_is_public_column() {
+        let conn = Connection::open_in_memory().expect("open in-memory sqlite");
+        conn.execute_batch(
+            "
+            CREATE TABLE artifacts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                owner_user_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                note TEXT,
+                target TEXT NOT NULL,
+                elf_path TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            ",
+        )
+        .expect("create legacy artifacts table");
+
+        run_migrations(&conn).expect("run migrations");
+
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(artifacts)")
+            .expect("prepare pragma");
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(1))
+            .expect("query columns");
+        let mut has_is_public = false;
+        for row in rows {
+            if row.expect("column") == "is_public" {
+                has_is_public = true;
+                break;
+            }
+        }
+        assert!(has_is_public);
+    }
+
+    #[tokio::test]
+    async fn list_artifacts_pages_with_limit_and_offset() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        for name in ["a.elf", "b.elf", "c.elf", "d.elf", "e.elf"] {
+            upload_artifact_with_cookie(&app, &alice_cookie, name).await;
+        }
+
+        let full = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(full.len(), 5);
+
+        let mut paged = Vec::new();
+        for offset in [0, 2, 4] {
+            let uri = format!("/api/v1/artifacts?limit=2&offset={offset}");
+            paged.extend(list_artifacts_with_cookie_at(&app, &alice_cookie, &uri).await);
+        }
+        let full_ids: Vec<ArtifactId> = full.iter().map(|a| a.id).collect();
+        let paged_ids: Vec<ArtifactId> = paged.iter().map(|a| a.id).collect();
+        assert_eq!(paged_ids, full_ids);
+
+        let past_end = list_artifacts_with_cookie_at(
+            &app,
+            &alice_cookie,
+            "/api/v1/artifacts?limit=2&offset=6",
+        )
+        .await;
+        assert!(past_end.is_empty());
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn clone_public_artifact_creates_private_copy_for_caller() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, artifact_id, true).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let (status, clone_id) = clone_artifact_with_cookie(&app, &alice_cookie, artifact_id).await;
+        assert_eq!(status, StatusCode::OK);
+        let clone_id = clone_id.expect("clone id");
+
+        let mine =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=mine")
+                .await;
+        assert_eq!(mine.len(), 1);
+        assert_eq!(mine[0].id, clone_id);
+        assert_eq!(mine[0].name, "bob.elf");
+        assert!(mine[0].owned_by_me);
+        assert!(!mine[0].is_public);
+
+        // Deleting the original must not remove the blob the clone still uses.
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/api/v1/artifacts/{artifact_id}"))
+                    .header(header::COOKIE, &bob_cookie)
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            download_artifact_with_cookie(&app, &alice_cookie, clone_id).await,
+            StatusCode::OK
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn deleted_artifact_is_hidden_until_restored() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &alice_cookie, "car.elf").await;
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &bob_cookie, artifact_id).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
+            StatusCode::NO_CONTENT
+        );
+
+        assert!(
+            list_artifacts_with_cookie(&app, &alice_cookie)
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            download_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
+            StatusCode::NOT_FOUND
+        );
+        let trash =
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?deleted=true")
+                .await;
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, artifact_id);
+        assert!(trash[0].deleted_at.is_some());
+        assert!(
+            list_artifacts_with_cookie_at(&app, &bob_cookie, "/api/v1/artifacts?deleted=true")
+                .await
+                .is_empty()
+        );
+
+        // The name is free again while the original sits in the trash.
+        let (_, replacement_id) = upload_artifact_with_cookie(&app, &alice_cookie, "car.elf").await;
+        let (status, _) = restore_artifact_with_cookie(&app, &bob_cookie, artifact_id).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, restored) =
+            restore_artifact_with_cookie(&app, &alice_cookie, artifact_id).await;
+        assert_eq!(status, StatusCode::OK);
+        let restored = restored.expect("restore response");
+        assert_eq!(restored.artifact_id, artifact_id);
+        assert_eq!(restored.name, "car (2).elf");
+        let (status, _) = restore_artifact_with_cookie(&app, &alice_cookie, artifact_id).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let mut listed: Vec<_> = list_artifacts_with_cookie(&app, &alice_cookie)
+            .await
+            .into_iter()
+            .map(|a| (a.id, a.deleted_at))
+            .collect();
+        listed.sort_by_key(|(id, _)| id.0);
+        assert_eq!(listed, vec![(artifact_id, None), (replacement_id, None)]);
+        assert_eq!(
+            download_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
+            StatusCode::OK
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn trashed_artifacts_are_purged_after_retention() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+
+        let (_, trashed_id) = upload_artifact_with_cookie(&app, &alice_cookie, "old.elf").await;
+        let (_, kept_id) = upload_artifact_with_cookie(&app, &alice_cookie, "kept.elf").await;
+        let elf_path: String = state
+            .db
+            .lock()
+            .await
+            .query_row(
+                "SELECT elf_path FROM artifacts WHERE id = ?1",
+                params![trashed_id.0],
+                |r| r.get(0),
+            )
+            .expect("artifact row");
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &alice_cookie, trashed_id).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let retention = TimeDelta::days(state.artifact_retention_days.into());
+        let within = Utc::now() + retention - TimeDelta::hours(1);
+        assert_eq!(purge_expired_artifacts(&state, within).await, 0);
+        assert_eq!(
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?deleted=true")
+                .await
+                .len(),
+            1
+        );
+
+        let after = Utc::now() + retention + TimeDelta::hours(1);
+        assert_eq!(purge_expired_artifacts(&state, after).await, 1);
+        assert!(
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?deleted=true")
+                .await
+                .is_empty()
+        );
+        assert!(!artifacts_dir.join(&elf_path).exists());
+        let (status, _) = restore_artifact_with_cookie(&app, &alice_cookie, trashed_id).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Live artifacts are never purged.
+        let listed = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, kept_id);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn purged_artifacts_leave_their_races_intact() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+
+        let (_, winner) = upload_artifact_with_cookie(&app, &alice_cookie, "winner.elf").await;
+        let (_, runner_up) =
+            upload_artifact_with_cookie(&app, &alice_cookie, "runner_up.elf").await;
+        let (_, race_id) = submit_race_with_cookie(&app, &alice_cookie, &[winner, runner_up]).await;
+        let race_id = race_id.expect("race id");
+        assert_eq!(
+            upload_race_logs_with_cookie(
+                &app,
+                &alice_cookie,
+                race_id,
+                &[(winner, "won".to_string())]
+            )
+            .await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &alice_cookie, winner).await,
+            StatusCode::NO_CONTENT
+        );
+        let after = Utc::now()
+            + TimeDelta::days(state.artifact_retention_days.into())
+            + TimeDelta::hours(1);
+        assert_eq!(purge_expired_artifacts(&state, after).await, 1);
+
+        let entries: Vec<(Option<i64>, i64)> = {
+            let db = state.db.lock().await;
+            let mut stmt = db
+                .prepare("SELECT artifact_id, position FROM race_entries WHERE race_id = ?1 ORDER BY position")
+                .expect("prepare");
+            stmt.query_map(params![race_id], |r| Ok((r.get(0)?, r.get(1)?)))
+                .expect("query")
+                .collect::<Result<_, _>>()
+                .expect("rows")
+        };
+        assert_eq!(entries, vec![(None, 1), (Some(runner_up.0), 2)]);
+        let logs: i64 = state
+            .db
+            .lock()
+            .await
+            .query_row(
+                "SELECT COUNT(*) FROM race_logs WHERE race_id = ?1",
+                params![race_id],
+                |r| r.get(0),
+            )
+            .expect("log count");
+        assert_eq!(logs, 1);
+
+        let (status, races) = list_artifact_races_with_cookie(
+            &app,
+            &alice_cookie,
+            &format!("/api/v1/artifacts/{}/races", runner_up.0),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(races.len(), 1);
+        assert_eq!(races[0].position, 2);
+        assert_eq!(races[0].car_count, 2);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn gc_removes_orphaned_files_and_keeps_referenced_ones() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "admin", "password123").await;
+        create_user(&state, "alice", "password123").await;
+        let admin_cookie = make_session_cookie(&state, "admin", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state.clone(), Some(static_dir.clone()));
+
+        let (_, live_id) = upload_artifact_with_cookie(&app, &alice_cookie, "live.elf").await;
+        let (_, trashed_id) = upload_artifact_with_cookie(&app, &alice_cookie, "old.elf").await;
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &alice_cookie, trashed_id).await,
+            StatusCode::NO_CONTENT
+        );
+        let referenced: Vec<String> = {
+            let db = state.db.lock().await;
+            let mut stmt = db
+                .prepare("SELECT elf_path FROM artifacts")
+                .expect("prepare");
+            stmt.query_map([], |r| r.get(0))
+                .expect("query")
+                .collect::<Result<_, _>>()
+                .expect("rows")
+        };
+        assert_eq!(referenced.len(), 2);
+        let orphan = artifacts_dir.join("artifact_999.elf");
+        std::fs::write(&orphan, [0u8; 100]).expect("write orphan");
+        // Files the server did not write, e.g. a database sharing the directory.
+        let foreign = ["botracers.db", "artifact_1.elf.bak", "artifact_x.elf"]
+            .map(|name| artifacts_dir.join(name));
+        for path in &foreign {
+            std::fs::write(path, [0u8; 10]).expect("write foreign file");
+        }
+
+        let (status, _) = collect_garbage_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(orphan.exists());
+
+        let (status, collected) = collect_garbage_with_cookie(&app, &admin_cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        let collected = collected.expect("gc response");
+        assert_eq!(collected.removed_files, 1);
+        assert_eq!(collected.reclaimed_bytes, 100);
+        assert!(!orphan.exists());
+        for path in &foreign {
+            assert!(path.exists(), "{} was removed", path.display());
+        }
+        for path in &referenced {
+            assert!(artifacts_dir.join(path).exists(), "{path} was removed");
+        }
+        let (status, _) = restore_artifact_with_cookie(&app, &alice_cookie, trashed_id).await;
+        assert_eq!(status, StatusCode::OK);
+        let listed = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert!(listed.iter().any(|artifact| artifact.id == live_id));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn export_contains_own_live_artifacts_and_manifest() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let fast_elf = [0x7f, b'E', b'L', b'F', 1];
+        let slow_elf = [0x7f, b'E', b'L', b'F', 2];
+        let (_, fast_id) =
+            upload_elf_with_cookie(&app, &alice_cookie, "fast car.elf", None, &fast_elf).await;
+        let (_, slow_id) =
+            upload_elf_with_cookie(&app, &alice_cookie, "slow.elf", None, &slow_elf).await;
+        let (_, trashed_id) = upload_artifact_with_cookie(&app, &alice_cookie, "old.elf").await;
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &alice_cookie, trashed_id).await,
+            StatusCode::NO_CONTENT
+        );
+        let (_, bob_id) =
+            upload_artifact_with_visibility(&app, &bob_cookie, "bob.elf", Some(true)).await;
+
+        let (status, archive) = export_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = tar_entries(&archive);
+        let paths: Vec<&str> = entries.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                format!("artifacts/{fast_id}-fast_car.elf").as_str(),
+                format!("artifacts/{slow_id}-slow.elf").as_str(),
+                ARTIFACT_EXPORT_MANIFEST,
+            ]
+        );
+        assert_eq!(entries[0].1, fast_elf);
+        assert_eq!(entries[1].1, slow_elf);
+
+        let manifest: ArtifactExportManifest =
+            serde_json::from_slice(&entries[2].1).expect("manifest json");
+        let ids: Vec<ArtifactId> = manifest.artifacts.iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![fast_id, slow_id]);
+        assert!(!ids.contains(&trashed_id) && !ids.contains(&bob_id));
+        for (entry, (path, data)) in manifest.artifacts.iter().zip(&entries) {
+            assert_eq!(&entry.file, path);
+            assert_eq!(entry.size, data.len() as u64);
+            assert_eq!(entry.sha256, hex::encode(Sha256::digest(data)));
+        }
+        assert_eq!(manifest.artifacts[0].name, "fast car.elf");
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn exported_archive_imports_into_a_fresh_account() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "carol", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let carol_cookie = make_session_cookie(&state, "carol", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "fast.elf",
+            None,
+            &[0x7f, b'E', b'L', b'F', 1],
+        )
+        .await;
+        upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "slow.elf",
+            Some(true),
+            &[0x7f, b'E', b'L', b'F', 2],
+        )
+        .await;
+        let (_, alice_archive) = export_artifacts_with_cookie(&app, &alice_cookie).await;
+
+        let (status, response) = import_archive_with_cookie(
+            &app,
+            &carol_cookie,
+            "/api/v1/artifacts/import-archive",
+            alice_archive.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let response = response.expect("import response");
+        let names: Vec<&str> = response.imported.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["fast.elf", "slow.elf"]);
+        assert!(response.skipped.is_empty());
+
+        let carol_view =
+            list_artifacts_with_cookie_at(&app, &carol_cookie, "/api/v1/artifacts?scope=mine")
+                .await;
+        assert_eq!(carol_view.len(), 2);
+        assert!(
+            carol_view
+                .iter()
+                .any(|a| a.name == "slow.elf" && a.is_public)
+        );
+
+        // The re-exported ELFs are byte-for-byte the originals.
+        let (_, carol_archive) = export_artifacts_with_cookie(&app, &carol_cookie).await;
+        let original = tar_entries(&alice_archive);
+        let copied = tar_entries(&carol_archive);
+        assert_eq!(copied.len(), original.len());
+        for ((_, original), (_, copied)) in original.iter().zip(&copied).take(2) {
+            assert_eq!(original, copied);
+        }
+
+        // Importing again skips the names carol already has, unless asked to overwrite.
+        let (_, again) = import_archive_with_cookie(
+            &app,
+            &carol_cookie,
+            "/api/v1/artifacts/import-archive",
+            alice_archive.clone(),
+        )
+        .await;
+        let again = again.expect("import response");
+        assert!(again.imported.is_empty());
+        assert_eq!(again.skipped, vec!["fast.elf", "slow.elf"]);
+
+        let (status, overwritten) = import_archive_with_cookie(
+            &app,
+            &carol_cookie,
+            "/api/v1/artifacts/import-archive?on_duplicate=overwrite",
+            alice_archive,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(overwritten.expect("import response").imported.len(), 2);
+        let carol_view =
+            list_artifacts_with_cookie_at(&app, &carol_cookie, "/api/v1/artifacts?scope=mine")
+                .await;
+        assert_eq!(carol_view.len(), 2);
+        let trash =
+            list_artifacts_with_cookie_at(&app, &carol_cookie, "/api/v1/artifacts?deleted=true")
+                .await;
+        assert_eq!(trash.len(), 2);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn tampered_archive_imports_nothing() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "carol", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let carol_cookie = make_session_cookie(&state, "carol", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "a.elf",
+            None,
+            &[0x7f, b'E', b'L', b'F', 1],
+        )
+        .await;
+        upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "b.elf",
+            None,
+            &[0x7f, b'E', b'L', b'F', 2],
+        )
+        .await;
+        let (_, archive) = export_artifacts_with_cookie(&app, &alice_cookie).await;
+
+        // Flip the last byte of the second ELF; its header checksum still holds.
+        let mut tampered = archive.clone();
+        tampered[512 * 3 + 4] ^= 0xff;
+        let (status, _) = import_archive_with_cookie(
+            &app,
+            &carol_cookie,
+            "/api/v1/artifacts/import-archive",
+            tampered,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(
+            list_artifacts_with_cookie_at(&app, &carol_cookie, "/api/v1/artifacts?scope=mine")
+                .await
+                .is_empty()
+        );
+
+        let (status, _) = import_archive_with_cookie(
+            &app,
+            &carol_cookie,
+            "/api/v1/artifacts/import-archive",
+            archive[..512 * 3].to_vec(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn archive_with_an_unsupported_abi_replaces_nothing() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "carol", "password123").await;
+        let carol_cookie = make_session_cookie(&state, "carol", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, original_id) = upload_artifact_with_cookie(&app, &carol_cookie, "car.elf").await;
+        let files_before = std::fs::read_dir(&artifacts_dir)
+            .expect("artifacts dir")
+            .count();
+
+        let good = elf_with_abi_version(SUPPORTED_BOT_ABI_VERSIONS[0]);
+        let bad = elf_with_abi_version(9999);
+        let mut archive = Vec::new();
+        let mut artifacts = Vec::new();
+        for (id, name, elf) in [(1, "car.elf", &good), (2, "new.elf", &bad)] {
+            let file = format!("artifacts/{id}-{name}");
+            archive.extend(tar_entry(&file, elf, 0));
+            artifacts.push(ArtifactExportEntry {
+                id: ArtifactId(id),
+                name: name.to_string(),
+                note: None,
+                target: ALLOWED_ARTIFACT_TARGETS[0].to_string(),
+                is_public: false,
+                abi_version: None,
+                build_id: None,
+                created_at: now_utc(),
+                file,
+                size: elf.len() as u64,
+                sha256: hex::encode(Sha256::digest(elf)),
+            });
+        }
+        let manifest = serde_json::to_vec(&ArtifactExportManifest {
+            exported_at: now_utc(),
+            artifacts,
+        })
+        .expect("manifest");
+        archive.extend(tar_entry(ARTIFACT_EXPORT_MANIFEST, &manifest, 0));
+        archive.resize(archive.len() + 1024, 0);
+
+        let (status, _) = import_archive_with_cookie(
+            &app,
+            &carol_cookie,
+            "/api/v1/artifacts/import-archive?on_duplicate=overwrite",
+            archive,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let mine =
+            list_artifacts_with_cookie_at(&app, &carol_cookie, "/api/v1/artifacts?scope=mine")
+                .await;
+        assert_eq!(mine.len(), 1);
+        assert_eq!(mine[0].id, original_id);
+        assert!(
+            list_artifacts_with_cookie_at(&app, &carol_cookie, "/api/v1/artifacts?deleted=true")
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            std::fs::read_dir(&artifacts_dir)
+                .expect("artifacts dir")
+                .count(),
+            files_before
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn export_over_the_size_limit_is_refused() {
+        let (mut state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        state.max_export_bytes = 4;
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (status, archive) = export_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(tar_entries(&archive).len(), 1);
+
+        upload_elf_with_cookie(
+            &app,
+            &alice_cookie,
+            "big.elf",
+            None,
+            &[0x7f, b'E', b'L', b'F', 0],
+        )
+        .await;
+        let (status, _) = export_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn clone_private_artifact_denied_for_non_owner() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        let (status, _) = clone_artifact_with_cookie(&app, &alice_cookie, artifact_id).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(
+            list_artifacts_with_cookie_at(&app, &alice_cookie, "/api/v1/artifacts?scope=mine")
+                .await
+                .is_empty()
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn verified_races_update_artifact_stats() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "admin", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let admin_cookie = make_session_cookie(&state, "admin", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, fast) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (_, slow) = upload_artifact_with_cookie(&app, &alice_cookie, "slow.elf").await;
+        let (_, idle) = upload_artifact_with_cookie(&app, &alice_cookie, "idle.elf").await;
+
+        let stats = |artifacts: &[ArtifactSummary], id: ArtifactId| {
+            let artifact = artifacts.iter().find(|a| a.id == id).expect("listed");
+            (artifact.races_count, artifact.wins, artifact.best_lap_secs)
+        };
+        let before = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(stats(&before, fast), (0, 0, None));
+
+        for race in [
+            vec![(fast, Some(40.0)), (slow, Some(44.0))],
+            vec![(slow, Some(43.0)), (fast, Some(41.5))],
+            vec![(fast, Some(42.0)), (slow, None)],
+        ] {
+            let (status, race_id) = submit_race_laps_with_cookie(&app, &alice_cookie, &race).await;
+            assert_eq!(status, StatusCode::OK);
+            let race_id = race_id.expect("race id");
+            assert_eq!(
+                verify_race_with_cookie(&app, &admin_cookie, race_id).await,
+                StatusCode::NO_CONTENT
+            );
+        }
+
+        let after = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(stats(&after, fast), (3, 2, Some(40.0)));
+        assert_eq!(stats(&after, slow), (3, 1, Some(43.0)));
+        assert_eq!(stats(&after, idle), (0, 0, None));
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn unverified_races_are_left_out_of_artifact_stats() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "admin", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let admin_cookie = make_session_cookie(&state, "admin", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (status, race_id) =
+            submit_race_laps_with_cookie(&app, &alice_cookie, &[(artifact, Some(12.0))]).await;
+        assert_eq!(status, StatusCode::OK);
+        let race_id = race_id.expect("race id");
+
+        let stats = |artifacts: &[ArtifactSummary]| {
+            let artifact = artifacts.iter().find(|a| a.id == artifact).expect("listed");
+            (artifact.races_count, artifact.wins, artifact.best_lap_secs)
+        };
+        let listed = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(stats(&listed), (0, 0, None));
+        let (_, history) = list_artifact_races_with_cookie(
+            &app,
+            &alice_cookie,
+            &format!("/api/v1/artifacts/{artifact}/races"),
+        )
+        .await;
+        assert_eq!(history.len(), 1);
+        assert!(!history[0].verified);
+
+        assert_eq!(
+            verify_race_with_cookie(&app, &alice_cookie, race_id).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            verify_race_with_cookie(&app, &admin_cookie, race_id + 1).await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            verify_race_with_cookie(&app, &admin_cookie, race_id).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let listed = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        assert_eq!(stats(&listed), (1, 1, Some(12.0)));
+        let (_, history) = list_artifact_races_with_cookie(
+            &app,
+            &alice_cookie,
+            &format!("/api/v1/artifacts/{artifact}/races"),
+        )
+        .await;
+        assert!(history[0].verified);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn races_are_stored_with_their_replay_for_review() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "admin", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let admin_cookie = make_session_cookie(&state, "admin", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, fast) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (_, slow) = upload_artifact_with_cookie(&app, &alice_cookie, "slow.elf").await;
+        let entry = |artifact_id, best_lap_secs| RaceEntrySubmission {
+            artifact_id,
+            laps_completed: 3,
+            best_lap_secs,
+            finish_reason: None,
+        };
+        let mut payload = SubmitRaceRequest {
+            entries: vec![entry(fast, Some(40.0)), entry(slow, None)],
+            // Spawn order need not be finishing order.
+            replay: Some(sample_replay(&[slow, fast])),
+        };
+        let (status, race) = submit_race_request_with_cookie(&app, &alice_cookie, &payload).await;
+        assert_eq!(status, StatusCode::OK);
+        let race = race.expect("race");
+        let replay_id = race.replay_id.expect("replay stored with the race");
+        assert_eq!(
+            get_replay_with_cookie(&app, &alice_cookie, replay_id).await,
+            StatusCode::OK
+        );
+
+        assert_eq!(
+            review_race_with_cookie(&app, &alice_cookie, race.race_id)
+                .await
+                .0,
+            StatusCode::FORBIDDEN
+        );
+        let (status, review) = review_race_with_cookie(&app, &admin_cookie, race.race_id).await;
+        assert_eq!(status, StatusCode::OK);
+        let review = review.expect("review");
+        assert_eq!(review.submitted_by, "alice");
+        let claimed: Vec<_> = review
+            .entries
+            .iter()
+            .map(|e| (e.artifact_id, e.best_lap_secs))
+            .collect();
+        assert_eq!(claimed, [(fast, Some(40.0)), (slow, None)]);
+        assert_eq!(review.replay, payload.replay);
+        assert!(!review.verified);
+        assert_eq!(review.rejection, None);
+
+        payload.replay = Some(sample_replay(&[fast, fast]));
+        assert_eq!(
+            submit_race_request_with_cookie(&app, &alice_cookie, &payload)
+                .await
+                .0,
+            StatusCode::BAD_REQUEST
+        );
+        payload.replay = Some(ReplayFile {
+            version: REPLAY_FORMAT_VERSION + 1,
+            ..sample_replay(&[fast, slow])
+        });
+        assert_eq!(
+            submit_race_request_with_cookie(&app, &alice_cookie, &payload)
+                .await
+                .0,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Without a replay there is nothing to check the results against.
+        payload.replay = None;
+        let (status, race) = submit_race_request_with_cookie(&app, &alice_cookie, &payload).await;
+        assert_eq!(status, StatusCode::OK);
+        let race = race.expect("race");
+        assert_eq!(race.replay_id, None);
+        assert_eq!(
+            verify_race_with_cookie(&app, &admin_cookie, race.race_id).await,
+            StatusCode::CONFLICT
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn rejected_races_stay_out_of_artifact_stats() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "admin", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let admin_cookie = make_session_cookie(&state, "admin", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, artifact) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (_, race_id) =
+            submit_race_laps_with_cookie(&app, &alice_cookie, &[(artifact, Some(12.0))]).await;
+        let race_id = race_id.expect("race id");
+
+        let reason = "#1 claims a best lap of 12.000s, the replay gives 19.250s";
+        assert_eq!(
+            reject_race_with_cookie(&app, &alice_cookie, race_id, reason).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            reject_race_with_cookie(&app, &admin_cookie, race_id, " ").await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            reject_race_with_cookie(&app, &admin_cookie, race_id + 1, reason).await,
+            StatusCode::NOT_FOUND
+        );
+        for _ in 0..2 {
+            assert_eq!(
+                reject_race_with_cookie(&app, &admin_cookie, race_id, reason).await,
+                StatusCode::NO_CONTENT
+            );
+        }
+        assert_eq!(
+            verify_race_with_cookie(&app, &admin_cookie, race_id).await,
+            StatusCode::CONFLICT
+        );
+
+        let (_, review) = review_race_with_cookie(&app, &admin_cookie, race_id).await;
+        let review = review.expect("review");
+        assert!(!review.verified);
+        assert_eq!(review.rejection.as_deref(), Some(reason));
+        let (_, history) = list_artifact_races_with_cookie(
+            &app,
+            &alice_cookie,
+            &format!("/api/v1/artifacts/{artifact}/races"),
+        )
+        .await;
+        assert!(history[0].rejected);
+        assert!(!history[0].verified);
+        let listed = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        let listed = listed.iter().find(|a| a.id == artifact).expect("listed");
+        assert_eq!(listed.races_count, 0);
+
+        // A confirmed race stays confirmed.
+        let (_, race_id) =
+            submit_race_laps_with_cookie(&app, &alice_cookie, &[(artifact, Some(12.0))]).await;
+        let race_id = race_id.expect("race id");
+        assert_eq!(
+            verify_race_with_cookie(&app, &admin_cookie, race_id).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            reject_race_with_cookie(&app, &admin_cookie, race_id, reason).await,
+            StatusCode::CONFLICT
+        );
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    #[tokio::test]
+    async fn artifact_race_history_lists_submitted_races_newest_first() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, fast) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (_, slow) = upload_artifact_with_cookie(&app, &alice_cookie, "slow.elf").await;
+
+        let mut race_ids = Vec::new();
+        for order in [vec![fast, slow], vec![slow, fast], vec![fast]] {
+            let (status, race_id) = submit_race_with_cookie(&app, &alice_cookie, &order).await;
+            assert_eq!(status, StatusCode::OK);
+            race_ids.push(race_id.expect("race id"));
+        }
+
+        let (status, history) = list_artifact_races_with_cookie(
+            &app,
+            &alice_cookie,
+            &format!("/api/v1/artifacts/{fast}/races"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let summary: Vec<(i64, u32, u32)> = history
+            .iter()
+            .map(|r| (r.race_id, r.position, r.car_count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (race_ids[2], 1, 1),
+                (race_ids[1], 2, 2),
+                (race_ids[0], 1, 2),
+            ]
+        );
+        assert_eq!(history[0].laps_completed, 3);
+        assert_eq!(history[0].best_lap_secs, Some(41.5));
+
+        let (_, page) = list_artifact_races_with_cookie(
+            &app,
+            &alice_cookie,
+            &format!("/api/v1/artifacts/{fast}/races?limit=2&offset=2"),
+        )
+        .await;
+        let page_ids: Vec<i64> = page.iter().map(|r| r.race_id).collect();
+        assert_eq!(page_ids, vec![race_ids[0]]);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
-    async fn list_artifacts_with_cookie(app: &Router, cookie: &str) -> Vec<ArtifactSummary> {
+    async fn server_time(app: &Router) -> ServerTime {
         let resp = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/v1/artifacts")
-                    .header(header::COOKIE, cookie)
+                    .uri("/api/v1/time")
                     .body(Body::empty())
                     .expect("request"),
             )
@@ -1244,23 +6443,39 @@ mod tests {
             .expect("response");
         assert_eq!(resp.status(), StatusCode::OK);
         let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        serde_json::from_slice(&body).expect("artifact list json")
+        serde_json::from_slice(&body).expect("time json")
     }
 
-    async fn update_visibility_with_cookie(
-        app: &Router,
-        cookie: &str,
-        artifact_id: i64,
-        is_public: bool,
-    ) -> StatusCode {
-        let payload = UpdateArtifactVisibilityRequest { is_public };
+    #[tokio::test]
+    async fn race_submissions_are_stamped_with_server_time() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let app = build_app(state, Some(static_dir.clone()));
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &alice_cookie, "car.elf").await;
+
+        let before = server_time(&app).await;
+        let parsed = DateTime::parse_from_rfc3339(&before.rfc3339).expect("rfc3339");
+        assert_eq!(parsed.timestamp_millis(), before.unix_millis);
+        assert!((before.unix_millis - Utc::now().timestamp_millis()).abs() < 60_000);
+
+        // Nothing in the request carries a client timestamp to trust.
+        let payload = SubmitRaceRequest {
+            entries: vec![RaceEntrySubmission {
+                artifact_id,
+                laps_completed: 3,
+                best_lap_secs: Some(41.5),
+                finish_reason: None,
+            }],
+            replay: None,
+        };
         let resp = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .method("PATCH")
-                    .uri(format!("/api/v1/artifacts/{artifact_id}/visibility"))
-                    .header(header::COOKIE, cookie)
+                    .method("POST")
+                    .uri("/api/v1/races")
+                    .header(header::COOKIE, &alice_cookie)
                     .header(header::CONTENT_TYPE, "application/json")
                     .body(Body::from(
                         serde_json::to_vec(&payload).expect("serialize payload"),
@@ -1269,419 +6484,604 @@ mod tests {
             )
             .await
             .expect("response");
-        resp.status()
-    }
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let submitted: SubmitRaceResponse = serde_json::from_slice(&body).expect("race json");
+
+        let after = server_time(&app).await;
+        assert!(after.unix_millis >= before.unix_millis);
+        let raced_at = DateTime::parse_from_rfc3339(&submitted.raced_at)
+            .expect("raced_at rfc3339")
+            .timestamp_millis();
+        assert!((before.unix_millis..=after.unix_millis).contains(&raced_at));
+
+        let (_, history) = list_artifact_races_with_cookie(
+            &app,
+            &alice_cookie,
+            &format!("/api/v1/artifacts/{artifact_id}/races"),
+        )
+        .await;
+        assert_eq!(history[0].race_id, submitted.race_id);
+        assert_eq!(history[0].raced_at, submitted.raced_at);
 
-    async fn download_artifact_with_cookie(
-        app: &Router,
-        cookie: &str,
-        artifact_id: i64,
-    ) -> StatusCode {
-        let resp = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri(format!("/api/v1/artifacts/{artifact_id}"))
-                    .header(header::COOKIE, cookie)
-                    .body(Body::empty())
-                    .expect("request"),
-            )
-            .await
-            .expect("response");
-        resp.status()
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn unauthenticated_game_entry_shows_login_in_required_mode() {
+    async fn private_artifact_race_history_denied_for_non_owner() {
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
+        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
+        let (status, _) = submit_race_with_cookie(&app, &bob_cookie, &[artifact_id]).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let uri = format!("/api/v1/artifacts/{artifact_id}/races");
+        let (status, _) = list_artifact_races_with_cookie(&app, &alice_cookie, &uri).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = submit_race_with_cookie(&app, &alice_cookie, &[artifact_id]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let _ = std::fs::remove_dir_all(static_dir);
+        let _ = std::fs::remove_dir_all(artifacts_dir);
+    }
+
+    /// Opens `/api/v1/events`, with `cookie` when given.
+    async fn subscribe_to_race_events(
+        app: &Router,
+        cookie: Option<&str>,
+    ) -> axum::body::BodyDataStream {
+        let mut request = Request::builder().uri("/api/v1/events");
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
         let resp = app
-            .oneshot(
-                Request::builder()
-                    .uri("/")
-                    .body(Body::empty())
-                    .expect("request"),
-            )
+            .clone()
+            .oneshot(request.body(Body::empty()).expect("request"))
             .await
             .expect("response");
-
         assert_eq!(resp.status(), StatusCode::OK);
-        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let text = String::from_utf8(body.to_vec()).expect("utf8");
-        assert!(text.contains("Sign in to BotRacers"));
-        assert!(text.contains("name=\"next\" value=\"/\""));
+        resp.into_body().into_data_stream()
+    }
 
-        let _ = std::fs::remove_dir_all(static_dir);
-        let _ = std::fs::remove_dir_all(artifacts_dir);
+    /// The next `race_finished` event on `events`.
+    async fn next_race_event(events: &mut axum::body::BodyDataStream) -> RaceFinishedEvent {
+        use futures_util::StreamExt;
+
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), events.next())
+            .await
+            .expect("event before timeout")
+            .expect("stream open")
+            .expect("frame");
+        let text = String::from_utf8(frame.to_vec()).expect("utf8");
+        assert!(text.starts_with("event: race_finished\n"), "{text}");
+        let data = text
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .expect("data line");
+        serde_json::from_str(data).expect("event json")
     }
 
     #[tokio::test]
-    async fn authenticated_game_entry_serves_index() {
+    async fn submitted_race_is_pushed_to_event_subscribers() {
+        use futures_util::StreamExt;
+
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
         create_user(&state, "alice", "password123").await;
-        let cookie = make_session_cookie(&state, "alice", "password123").await;
+        create_user(&state, "admin", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let admin_cookie = make_session_cookie(&state, "admin", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let resp = app
+        let anonymous = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/")
-                    .header(header::COOKIE, cookie)
+                    .uri("/api/v1/events")
                     .body(Body::empty())
                     .expect("request"),
             )
             .await
             .expect("response");
-
-        assert_eq!(resp.status(), StatusCode::OK);
-        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let text = String::from_utf8(body.to_vec()).expect("utf8");
-        assert!(text.contains("game entry"));
-
-        let _ = std::fs::remove_dir_all(static_dir);
-        let _ = std::fs::remove_dir_all(artifacts_dir);
-    }
-
-    #[tokio::test]
-    async fn web_login_success_sets_cookie_and_redirects() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
-        create_user(&state, "alice", "password123").await;
-        let app = build_app(state, Some(static_dir.clone()));
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
 
         let resp = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .method("POST")
-                    .uri("/login")
-                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-                    .body(Body::from(
-                        "username=alice&password=password123&next=%2Findex.html",
-                    ))
+                    .uri("/api/v1/events")
+                    .header(header::COOKIE, &alice_cookie)
+                    .body(Body::empty())
                     .expect("request"),
             )
             .await
             .expect("response");
-
-        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+        assert_eq!(resp.status(), StatusCode::OK);
         assert_eq!(
             resp.headers()
-                .get(header::LOCATION)
-                .expect("location")
-                .to_str()
-                .expect("location str"),
-            "/index.html"
+                .get(header::CONTENT_TYPE)
+                .expect("content type"),
+            "text/event-stream"
+        );
+        let mut events = resp.into_body().into_data_stream();
+
+        let (_, fast) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (_, slow) = upload_artifact_with_cookie(&app, &alice_cookie, "slow.elf").await;
+        let (status, race_id) = submit_race_with_cookie(&app, &alice_cookie, &[fast, slow]).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let event = next_race_event(&mut events).await;
+        assert_eq!(Some(event.race_id), race_id);
+        assert_eq!(event.submitted_by, "alice");
+        assert!(!event.verified);
+        let order: Vec<ArtifactId> = event.entries.iter().map(|e| e.artifact_id).collect();
+        assert_eq!(order, vec![fast, slow]);
+
+        let race_id = race_id.expect("race id");
+        assert_eq!(
+            verify_race_with_cookie(&app, &admin_cookie, race_id).await,
+            StatusCode::NO_CONTENT
+        );
+        let event = next_race_event(&mut events).await;
+        assert_eq!(event.race_id, race_id);
+        assert_eq!(event.submitted_by, "alice");
+        assert!(event.verified);
+        let order: Vec<ArtifactId> = event.entries.iter().map(|e| e.artifact_id).collect();
+        assert_eq!(order, vec![fast, slow]);
+
+        // Confirming again changes nothing, so nothing is sent.
+        assert_eq!(
+            verify_race_with_cookie(&app, &admin_cookie, race_id).await,
+            StatusCode::NO_CONTENT
+        );
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(200), events.next())
+                .await
+                .is_err()
         );
-        let cookie = resp
-            .headers()
-            .get(header::SET_COOKIE)
-            .expect("set-cookie")
-            .to_str()
-            .expect("cookie str");
-        assert!(cookie.contains("botracers_session="));
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn web_login_failure_renders_error() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+    async fn private_race_results_stay_out_of_other_users_event_streams() {
+        let (mut state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        state.events_public = true;
         create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let resp = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/login")
-                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-                    .body(Body::from(
-                        "username=alice&password=wrongpassword&next=%2Findex.html",
-                    ))
-                    .expect("request"),
-            )
-            .await
-            .expect("response");
+        let mut alice_events = subscribe_to_race_events(&app, Some(&alice_cookie)).await;
+        let mut bob_events = subscribe_to_race_events(&app, Some(&bob_cookie)).await;
+        let mut anonymous_events = subscribe_to_race_events(&app, None).await;
 
-        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
-        assert!(resp.headers().get(header::SET_COOKIE).is_none());
-        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let text = String::from_utf8(body.to_vec()).expect("utf8");
-        assert!(text.contains("Invalid username or password"));
+        let (_, secret) = upload_artifact_with_cookie(&app, &alice_cookie, "secret.elf").await;
+        let (_, shown) = upload_artifact_with_cookie(&app, &alice_cookie, "shown.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &alice_cookie, shown, true).await,
+            StatusCode::NO_CONTENT
+        );
+
+        let (_, mixed) = submit_race_with_cookie(&app, &alice_cookie, &[secret, shown]).await;
+        let (_, hidden) = submit_race_with_cookie(&app, &alice_cookie, &[secret]).await;
+        let (_, public) = submit_race_with_cookie(&app, &alice_cookie, &[shown]).await;
+        let ids = |event: &RaceFinishedEvent| -> Vec<ArtifactId> {
+            event.entries.iter().map(|e| e.artifact_id).collect()
+        };
+
+        let event = next_race_event(&mut alice_events).await;
+        assert_eq!(
+            (Some(event.race_id), ids(&event)),
+            (mixed, vec![secret, shown])
+        );
+        let event = next_race_event(&mut alice_events).await;
+        assert_eq!((Some(event.race_id), ids(&event)), (hidden, vec![secret]));
+
+        for events in [&mut bob_events, &mut anonymous_events] {
+            let event = next_race_event(events).await;
+            assert_eq!((Some(event.race_id), ids(&event)), (mixed, vec![shown]));
+            // The race with only the private artifact is skipped entirely.
+            let event = next_race_event(events).await;
+            assert_eq!((Some(event.race_id), ids(&event)), (public, vec![shown]));
+        }
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
-    #[test]
-    fn sanitize_next_rejects_external_targets() {
-        assert_eq!(sanitize_next("https://evil.com"), "/");
-        assert_eq!(sanitize_next("//evil.com"), "/");
-        assert_eq!(sanitize_next("/assets/foo"), "/assets/foo");
-    }
-
     #[tokio::test]
-    async fn api_login_still_returns_json_and_cookie() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+    async fn artifact_import_fetches_url_into_new_artifact() {
+        let (mut state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        // The test server listens on loopback, which imports refuse by default.
+        state.import_allow_private_hosts = true;
         create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let resp = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/v1/auth/login")
-                    .header(header::CONTENT_TYPE, "application/json")
-                    .body(Body::from(
-                        "{\"username\":\"alice\",\"password\":\"password123\"}",
-                    ))
-                    .expect("request"),
-            )
-            .await
-            .expect("response");
-
-        assert_eq!(resp.status(), StatusCode::OK);
-        assert!(resp.headers().contains_key(header::SET_COOKIE));
-        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let parsed: LoginResponse = serde_json::from_slice(&body).expect("login response json");
-        assert_eq!(parsed.user.username, "alice");
-        assert!(!parsed.token.is_empty());
+        let url = serve_once("/bots/fast%20bot.elf?v=2", b"\x7fELF-imported");
+        let (status, artifact_id) = import_artifact_with_cookie(&app, &alice_cookie, &url).await;
+        assert_eq!(status, StatusCode::OK);
+        let artifact_id = artifact_id.expect("artifact id");
 
-        let _ = std::fs::remove_dir_all(static_dir);
-        let _ = std::fs::remove_dir_all(artifacts_dir);
-    }
+        let listed = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        let summary = listed
+            .iter()
+            .find(|a| a.id == artifact_id)
+            .expect("imported artifact listed");
+        assert_eq!(summary.name, "fast bot.elf");
+        assert!(!summary.is_public);
 
-    #[tokio::test]
-    async fn capabilities_include_registration_enabled() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
-        let app = build_app(state, Some(static_dir.clone()));
         let resp = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/v1/capabilities")
+                    .uri(format!("/api/v1/artifacts/{artifact_id}"))
+                    .header(header::COOKIE, &alice_cookie)
                     .body(Body::empty())
                     .expect("request"),
             )
             .await
             .expect("response");
-
-        assert_eq!(resp.status(), StatusCode::OK);
         let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let caps: ServerCapabilities = serde_json::from_slice(&body).expect("caps json");
-        assert!(caps.auth_required);
-        assert!(!caps.registration_enabled);
+        assert_eq!(&body[..], b"\x7fELF-imported");
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn api_register_blocked_when_registration_disabled() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
+    async fn artifact_import_rejects_internal_and_non_http_urls() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
-        let resp = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/v1/auth/register")
-                    .header(header::CONTENT_TYPE, "application/json")
-                    .body(Body::from(
-                        "{\"username\":\"alice\",\"password\":\"password123\"}",
-                    ))
-                    .expect("request"),
-            )
-            .await
-            .expect("response");
 
-        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
-        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let error: ErrorResponse = serde_json::from_slice(&body).expect("error json");
-        assert_eq!(error.error, "registration is disabled");
+        for url in [
+            "http://127.0.0.1:8787/bot.elf",
+            "http://localhost/bot.elf",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]:8787/bot.elf",
+            "http://10.0.0.8/bot.elf",
+            "file:///etc/passwd",
+        ] {
+            let (status, artifact_id) = import_artifact_with_cookie(&app, &alice_cookie, url).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{url}");
+            assert_eq!(artifact_id, None, "{url}");
+        }
+        assert!(
+            list_artifacts_with_cookie(&app, &alice_cookie)
+                .await
+                .is_empty()
+        );
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
-    #[tokio::test]
-    async fn register_flow_creates_session_and_redirects() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
-        let app = build_app(state, Some(static_dir.clone()));
+    #[test]
+    fn public_address_check_covers_reserved_ranges() {
+        for ip in [
+            "93.184.216.34",
+            "2606:4700::1111",
+            "::ffff:93.184.216.34",
+            "64:ff9b::5db8:d822",
+            "2002:5db8:d822::1",
+        ] {
+            assert!(is_public_address(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "64:ff9b::7f00:1",
+            "2002:c0a8:101::1",
+            "2002:a00:1::",
+        ] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    fn sample_replay(artifact_ids: &[ArtifactId]) -> ReplayFile {
+        ReplayFile {
+            version: REPLAY_FORMAT_VERSION,
+            tick_hz: 200,
+            track: "track1.toml".to_string(),
+            car_scale: 1.0,
+            cars_collide: false,
+            tire_wear_per_m: Some(0.0),
+            participants: artifact_ids
+                .iter()
+                .enumerate()
+                .map(|(index, &artifact_id)| ReplayParticipant {
+                    artifact_id,
+                    start_x: index as f32 * 2.0,
+                    start_y: 0.0,
+                    start_heading: std::f32::consts::FRAC_PI_2,
+                    brake_bias: Some(0.55),
+                })
+                .collect(),
+            ticks: (0..3)
+                .map(|tick| {
+                    artifact_ids
+                        .iter()
+                        .map(|_| ReplayInput {
+                            steering: 0.1 * tick as f32,
+                            accelerator: 1.0,
+                            brake: 0.0,
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        }
+    }
+
+    async fn upload_replay_with_cookie(
+        app: &Router,
+        cookie: &str,
+        body: Vec<u8>,
+    ) -> (StatusCode, Option<i64>) {
         let resp = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/register")
-                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-                    .body(Body::from(
-                        "username=bob&password=password123&next=%2Findex.html",
-                    ))
+                    .uri("/api/v1/replays")
+                    .header(header::COOKIE, cookie)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))
                     .expect("request"),
             )
             .await
             .expect("response");
-
-        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
-        assert_eq!(
-            resp.headers()
-                .get(header::LOCATION)
-                .expect("location")
-                .to_str()
-                .expect("location str"),
-            "/index.html"
-        );
-        assert!(resp.headers().contains_key(header::SET_COOKIE));
-
-        let _ = std::fs::remove_dir_all(static_dir);
-        let _ = std::fs::remove_dir_all(artifacts_dir);
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let parsed = serde_json::from_slice::<UploadReplayResponse>(&body)
+            .ok()
+            .map(|r| r.replay_id);
+        (status, parsed)
     }
 
     #[tokio::test]
-    async fn register_routes_redirect_when_disabled() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
+    async fn replays_are_stored_and_fetched_by_id() {
+        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
+        let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
+
+        let (_, fast) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (_, slow) = upload_artifact_with_cookie(&app, &alice_cookie, "slow.elf").await;
+        let replay = sample_replay(&[fast, slow]);
+        let (status, replay_id) = upload_replay_with_cookie(
+            &app,
+            &alice_cookie,
+            serde_json::to_vec(&replay).expect("serialize replay"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let replay_id = replay_id.expect("replay id");
+
+        // Replays of public artifacts can be loaded by other players.
+        for artifact_id in [fast, slow] {
+            assert_eq!(
+                update_visibility_with_cookie(&app, &alice_cookie, artifact_id, true).await,
+                StatusCode::NO_CONTENT
+            );
+        }
         let resp = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/register?next=%2Findex.html")
+                    .uri(format!("/api/v1/replays/{replay_id}"))
+                    .header(header::COOKIE, &bob_cookie)
                     .body(Body::empty())
                     .expect("request"),
             )
             .await
             .expect("response");
-        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
-        assert_eq!(
-            resp.headers()
-                .get(header::LOCATION)
-                .expect("location")
-                .to_str()
-                .expect("location str"),
-            "/login?next=%2Findex.html&error=registration_disabled"
-        );
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let fetched: ReplayFile = serde_json::from_slice(&body).expect("replay json");
+        assert_eq!(fetched, replay);
 
         let resp = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .method("POST")
-                    .uri("/register")
-                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-                    .body(Body::from(
-                        "username=bob&password=password123&next=%2Findex.html",
-                    ))
+                    .uri(format!("/api/v1/replays/{}", replay_id + 1))
+                    .header(header::COOKIE, &bob_cookie)
+                    .body(Body::empty())
                     .expect("request"),
             )
             .await
             .expect("response");
-        assert_eq!(resp.status(), StatusCode::SEE_OTHER);
-        assert_eq!(
-            resp.headers()
-                .get(header::LOCATION)
-                .expect("location")
-                .to_str()
-                .expect("location str"),
-            "/login?next=%2Findex.html&error=registration_disabled"
-        );
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
-    #[tokio::test]
-    async fn login_page_hides_register_link_when_disabled() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, false);
-        let app = build_app(state, Some(static_dir.clone()));
-        let resp = app
+    async fn get_replay_with_cookie(app: &Router, cookie: &str, replay_id: i64) -> StatusCode {
+        app.clone()
             .oneshot(
                 Request::builder()
-                    .uri("/login?next=%2F")
+                    .uri(format!("/api/v1/replays/{replay_id}"))
+                    .header(header::COOKIE, cookie)
                     .body(Body::empty())
                     .expect("request"),
             )
             .await
-            .expect("response");
-        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
-        let text = String::from_utf8(body.to_vec()).expect("utf8");
-        assert!(!text.contains("Create account"));
-
-        let _ = std::fs::remove_dir_all(static_dir);
-        let _ = std::fs::remove_dir_all(artifacts_dir);
+            .expect("response")
+            .status()
     }
 
     #[tokio::test]
-    async fn uploads_are_private_by_default() {
+    async fn replays_with_private_artifacts_stay_with_their_owners() {
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
         create_user(&state, "alice", "password123").await;
+        create_user(&state, "bob", "password123").await;
         let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
+        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let (status, artifact_id) = upload_artifact_with_cookie(&app, &alice_cookie, "a.elf").await;
+        let (_, private) = upload_artifact_with_cookie(&app, &alice_cookie, "private.elf").await;
+        let (_, bobs) = upload_artifact_with_cookie(&app, &bob_cookie, "bobs.elf").await;
+        assert_eq!(
+            update_visibility_with_cookie(&app, &bob_cookie, bobs, true).await,
+            StatusCode::NO_CONTENT
+        );
+        let (status, replay_id) = upload_replay_with_cookie(
+            &app,
+            &alice_cookie,
+            serde_json::to_vec(&sample_replay(&[private, bobs])).expect("serialize replay"),
+        )
+        .await;
         assert_eq!(status, StatusCode::OK);
+        let replay_id = replay_id.expect("replay id");
 
-        let artifacts = list_artifacts_with_cookie(&app, &alice_cookie).await;
-        let artifact = artifacts
-            .into_iter()
-            .find(|a| a.id == artifact_id)
-            .expect("artifact exists");
-        assert!(!artifact.is_public);
-        assert!(artifact.owned_by_me);
+        // Bob's own car in the race does not reveal Alice's controls to him.
+        assert_eq!(
+            get_replay_with_cookie(&app, &bob_cookie, replay_id).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            get_replay_with_cookie(&app, &alice_cookie, replay_id).await,
+            StatusCode::OK
+        );
+
+        assert_eq!(
+            update_visibility_with_cookie(&app, &alice_cookie, private, true).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            get_replay_with_cookie(&app, &bob_cookie, replay_id).await,
+            StatusCode::OK
+        );
+
+        // A trashed artifact is no longer downloadable, so neither is its replay.
+        assert_eq!(
+            delete_artifact_with_cookie(&app, &alice_cookie, private).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            get_replay_with_cookie(&app, &bob_cookie, replay_id).await,
+            StatusCode::FORBIDDEN
+        );
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn list_artifacts_in_required_mode_shows_own_and_public_others() {
+    async fn replay_upload_rejects_unknown_versions_and_mismatched_ticks() {
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
         create_user(&state, "alice", "password123").await;
-        create_user(&state, "bob", "password123").await;
         let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
-        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
+        let (_, bot) = upload_artifact_with_cookie(&app, &alice_cookie, "bot.elf").await;
+
+        // A future version may change the layout entirely; only the version is read.
+        let future = serde_json::json!({ "version": REPLAY_FORMAT_VERSION + 1, "frames": [] });
+        let (status, replay_id) = upload_replay_with_cookie(
+            &app,
+            &alice_cookie,
+            serde_json::to_vec(&future).expect("serialize"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(replay_id, None);
+
+        let mut mismatched = sample_replay(&[bot]);
+        mismatched.ticks = sample_replay(&[bot, bot]).ticks;
+        let (status, _) = upload_replay_with_cookie(
+            &app,
+            &alice_cookie,
+            serde_json::to_vec(&mismatched).expect("serialize"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Longer than any race may run, even if it compresses to almost nothing.
+        let mut endless = sample_replay(&[bot]);
+        endless.ticks = (0..=MAX_REPLAY_TICKS)
+            .map(|_| {
+                [ReplayInput {
+                    steering: 0.0,
+                    accelerator: 1.0,
+                    brake: 0.0,
+                }]
+            })
+            .collect();
+        let (status, _) = upload_replay_with_cookie(
+            &app,
+            &alice_cookie,
+            serde_json::to_vec(&endless).expect("serialize"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
 
-        let (_, alice_artifact_id) =
-            upload_artifact_with_cookie(&app, &alice_cookie, "alice.elf").await;
-        let (_, bob_artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
-        assert_eq!(
-            update_visibility_with_cookie(&app, &bob_cookie, bob_artifact_id, true).await,
-            StatusCode::NO_CONTENT
-        );
-
-        let alice_view = list_artifacts_with_cookie(&app, &alice_cookie).await;
-        assert!(
-            alice_view
-                .iter()
-                .any(|a| a.id == alice_artifact_id && a.owned_by_me)
-        );
-        assert!(
-            alice_view
-                .iter()
-                .any(|a| a.id == bob_artifact_id && !a.owned_by_me && a.is_public)
-        );
+        let (status, _) =
+            upload_replay_with_cookie(&app, &alice_cookie, b"not json".to_vec()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn list_artifacts_hides_private_others() {
+    async fn full_grid_replay_of_the_longest_race_fits_the_upload_limit() {
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
         create_user(&state, "alice", "password123").await;
-        create_user(&state, "bob", "password123").await;
         let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
-        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
+        let (_, bot) = upload_artifact_with_cookie(&app, &alice_cookie, "bot.elf").await;
+
+        // Every control changes on every tick, the worst case for the encoding.
+        let mut replay = sample_replay(&vec![bot; MAX_FIELD_SIZE]);
+        replay.ticks = ReplayTicks::new(MAX_FIELD_SIZE);
+        for tick in 0..MAX_REPLAY_TICKS {
+            let value = if tick % 2 == 0 { 0.5 } else { -0.5 };
+            let input = ReplayInput {
+                steering: value,
+                accelerator: value,
+                brake: value,
+            };
+            replay.ticks.push(&[input; MAX_FIELD_SIZE]);
+        }
+        let body = serde_json::to_vec(&replay).expect("serialize replay");
+        drop(replay);
+        assert!(body.len() <= MAX_REPLAY_BYTES);
+        assert!(body.len() > MAX_REPLAY_BYTES - REPLAY_HEADER_BYTES);
 
-        let (_, bob_artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
-        let alice_view = list_artifacts_with_cookie(&app, &alice_cookie).await;
-        assert!(!alice_view.iter().any(|a| a.id == bob_artifact_id));
+        let (status, replay_id) = upload_replay_with_cookie(&app, &alice_cookie, body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(replay_id.is_some());
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn download_public_artifact_allowed_for_non_owner() {
+    async fn race_logs_are_stored_and_returned_in_finishing_order() {
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
         create_user(&state, "alice", "password123").await;
         create_user(&state, "bob", "password123").await;
@@ -1689,115 +7089,161 @@ mod tests {
         let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
-        assert_eq!(
-            update_visibility_with_cookie(&app, &bob_cookie, artifact_id, true).await,
-            StatusCode::NO_CONTENT
-        );
-        assert_eq!(
-            download_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
-            StatusCode::OK
-        );
+        let (_, fast) = upload_artifact_with_cookie(&app, &alice_cookie, "fast.elf").await;
+        let (_, slow) = upload_artifact_with_cookie(&app, &alice_cookie, "slow.elf").await;
+        let (_, race_id) = submit_race_with_cookie(&app, &alice_cookie, &[fast, slow]).await;
+        let race_id = race_id.expect("race id");
+
+        let status = upload_race_logs_with_cookie(
+            &app,
+            &alice_cookie,
+            race_id,
+            &[
+                (slow, "slow: lap 1\n".into()),
+                (fast, "fast: stale\n".into()),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let status = upload_race_logs_with_cookie(
+            &app,
+            &alice_cookie,
+            race_id,
+            &[(fast, "fast: lap 1\n".into())],
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, logs) = list_race_logs_with_cookie(&app, &alice_cookie, race_id).await;
+        assert_eq!(status, StatusCode::OK);
+        let logs: Vec<(ArtifactId, &str)> = logs
+            .iter()
+            .map(|t| (t.artifact_id, t.log.as_str()))
+            .collect();
+        assert_eq!(logs, vec![(fast, "fast: lap 1\n"), (slow, "slow: lap 1\n")]);
+
+        let (status, logs) = list_race_logs_with_cookie(&app, &bob_cookie, race_id).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(logs.is_empty());
+        let status = upload_race_logs_with_cookie(
+            &app,
+            &bob_cookie,
+            race_id,
+            &[(fast, "bob was here".into())],
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn download_private_artifact_denied_for_non_owner() {
+    async fn race_log_upload_rejects_oversized_and_foreign_transcripts() {
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
         create_user(&state, "alice", "password123").await;
-        create_user(&state, "bob", "password123").await;
         let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
-        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
-        assert_eq!(
-            download_artifact_with_cookie(&app, &alice_cookie, artifact_id).await,
-            StatusCode::UNAUTHORIZED
-        );
+        let (_, raced) = upload_artifact_with_cookie(&app, &alice_cookie, "raced.elf").await;
+        let (_, idle) = upload_artifact_with_cookie(&app, &alice_cookie, "idle.elf").await;
+        let (_, race_id) = submit_race_with_cookie(&app, &alice_cookie, &[raced]).await;
+        let race_id = race_id.expect("race id");
+
+        let at_cap = "x".repeat(MAX_RACE_LOG_BYTES);
+        let over_cap = "x".repeat(MAX_RACE_LOG_BYTES + 1);
+        let status =
+            upload_race_logs_with_cookie(&app, &alice_cookie, race_id, &[(raced, over_cap)]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let status =
+            upload_race_logs_with_cookie(&app, &alice_cookie, race_id, &[(idle, "hi".into())])
+                .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (_, logs) = list_race_logs_with_cookie(&app, &alice_cookie, race_id).await;
+        assert!(logs.is_empty());
+
+        let status =
+            upload_race_logs_with_cookie(&app, &alice_cookie, race_id, &[(raced, at_cap)]).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, logs) = list_race_logs_with_cookie(&app, &alice_cookie, race_id).await;
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].log.len(), MAX_RACE_LOG_BYTES);
+
+        let status = upload_race_logs_with_cookie(&app, &alice_cookie, race_id + 1, &[]).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn owner_can_toggle_visibility() {
-        let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+    async fn upload_uses_configured_default_visibility_unless_overridden() {
+        let (mut state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
+        state.default_artifact_public = true;
         create_user(&state, "alice", "password123").await;
         let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let (_, artifact_id) = upload_artifact_with_cookie(&app, &alice_cookie, "alice.elf").await;
-        assert_eq!(
-            update_visibility_with_cookie(&app, &alice_cookie, artifact_id, true).await,
-            StatusCode::NO_CONTENT
-        );
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/capabilities")
+                    .body(Body::empty())
+                    .expect("request"),
+            )
+            .await
+            .expect("response");
+        let body = to_bytes(resp.into_body(), usize::MAX).await.expect("body");
+        let caps: ServerCapabilities = serde_json::from_slice(&body).expect("caps json");
+        assert!(caps.default_artifact_public);
+
+        let (status, defaulted) =
+            upload_artifact_with_visibility(&app, &alice_cookie, "defaulted.elf", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, private) =
+            upload_artifact_with_visibility(&app, &alice_cookie, "private.elf", Some(false)).await;
+        assert_eq!(status, StatusCode::OK);
 
         let artifacts = list_artifacts_with_cookie(&app, &alice_cookie).await;
-        let artifact = artifacts
-            .into_iter()
-            .find(|a| a.id == artifact_id)
-            .expect("artifact exists");
-        assert!(artifact.is_public);
+        let visibility = |id: ArtifactId| {
+            artifacts
+                .iter()
+                .find(|a| a.id == id)
+                .map(|a| a.is_public)
+                .expect("artifact listed")
+        };
+        assert!(visibility(defaulted));
+        assert!(!visibility(private));
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
 
     #[tokio::test]
-    async fn non_owner_cannot_toggle_visibility() {
+    async fn upload_is_private_by_default_but_can_request_public() {
         let (state, static_dir, artifacts_dir) = setup_test_state(AuthMode::Required, true);
         create_user(&state, "alice", "password123").await;
-        create_user(&state, "bob", "password123").await;
         let alice_cookie = make_session_cookie(&state, "alice", "password123").await;
-        let bob_cookie = make_session_cookie(&state, "bob", "password123").await;
         let app = build_app(state, Some(static_dir.clone()));
 
-        let (_, artifact_id) = upload_artifact_with_cookie(&app, &bob_cookie, "bob.elf").await;
-        assert_eq!(
-            update_visibility_with_cookie(&app, &alice_cookie, artifact_id, true).await,
-            StatusCode::UNAUTHORIZED
-        );
+        let (_, defaulted) =
+            upload_artifact_with_cookie(&app, &alice_cookie, "defaulted.elf").await;
+        let (_, public) =
+            upload_artifact_with_visibility(&app, &alice_cookie, "public.elf", Some(true)).await;
+
+        let artifacts = list_artifacts_with_cookie(&app, &alice_cookie).await;
+        let visibility = |id: ArtifactId| {
+            artifacts
+                .iter()
+                .find(|a| a.id == id)
+                .map(|a| a.is_public)
+                .expect("artifact listed")
+        };
+        assert!(!visibility(defaulted));
+        assert!(visibility(public));
 
         let _ = std::fs::remove_dir_all(static_dir);
         let _ = std::fs::remove_dir_all(artifacts_dir);
     }
-
-    #[test]
-    fn migration_adds_is_public_column() {
-        let conn = Connection::open_in_memory().expect("open in-memory sqlite");
-        conn.execute_batch(
-            "
-            CREATE TABLE artifacts (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                owner_user_id INTEGER NOT NULL,
-                name TEXT NOT NULL,
-                note TEXT,
-                target TEXT NOT NULL,
-                elf_path TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            );
-            ",
-        )
-        .expect("create legacy artifacts table");
-
-        run_migrations(&conn).expect("run migrations");
-
-        let mut stmt = conn
-            .prepare("PRAGMA table_info(artifacts)")
-            .expect("prepare pragma");
-        let rows = stmt
-            .query_map([], |row| row.get::<_, String>(1))
-            .expect("query columns");
-        let mut has_is_public = false;
-        for row in rows {
-            if row.expect("column") == "is_public" {
-                has_is_public = true;
-                break;
-            }
-        }
-        assert!(has_is_public);
-    }
 }
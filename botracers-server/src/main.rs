@@ -1,4 +1,4 @@
-use botracers_server::{AuthMode, ServerConfig, run_server};
+use botracers_server::{AuthMode, DuplicateNamePolicy, ServerConfig, run_server};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -31,6 +31,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "1" | "true" | "TRUE" | "True"
         );
     }
+    if let Ok(default_public) = std::env::var("BOTRACERS_DEFAULT_ARTIFACT_PUBLIC") {
+        config.default_artifact_public =
+            matches!(default_public.as_str(), "1" | "true" | "TRUE" | "True");
+    }
+    if let Ok(allow_private) = std::env::var("BOTRACERS_IMPORT_ALLOW_PRIVATE_HOSTS") {
+        config.import_allow_private_hosts =
+            matches!(allow_private.as_str(), "1" | "true" | "TRUE" | "True");
+    }
+    if let Ok(events_public) = std::env::var("BOTRACERS_EVENTS_PUBLIC") {
+        config.events_public = matches!(events_public.as_str(), "1" | "true" | "TRUE" | "True");
+    }
+    if let Ok(duplicate_names) = std::env::var("BOTRACERS_DUPLICATE_ARTIFACT_NAMES") {
+        config.duplicate_names = DuplicateNamePolicy::from_env(&duplicate_names);
+    }
+    if let Ok(retention_days) = std::env::var("BOTRACERS_ARTIFACT_RETENTION_DAYS") {
+        config.artifact_retention_days = retention_days
+            .trim()
+            .parse()
+            .map_err(|e| format!("invalid BOTRACERS_ARTIFACT_RETENTION_DAYS: {e}"))?;
+    }
+    if let Ok(admin_users) = std::env::var("BOTRACERS_ADMIN_USERS") {
+        config.admin_users = admin_users
+            .split(',')
+            .map(str::trim)
+            .filter(|username| !username.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    if let Ok(auth_header) = std::env::var("BOTRACERS_AUTH_HEADER") {
+        config.auth_header = auth_header;
+    }
+    if let Ok(auth_scheme) = std::env::var("BOTRACERS_AUTH_SCHEME") {
+        config.auth_scheme = auth_scheme;
+    }
     if let Ok(static_dir) = std::env::var("BOTRACERS_STATIC_DIR") {
         if static_dir.trim().is_empty() {
             config.static_dir = None;
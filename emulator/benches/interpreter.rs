@@ -0,0 +1,71 @@
+//! Interpreter throughput: `cargo bench -p emulator`.
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use emulator::bevy::CpuComponent;
+use emulator::cpu::Device;
+use emulator::test_elf::elf_with_code;
+
+const CYCLES: u32 = 100_000;
+
+/// A CPU-bound loop with no memory traffic, like a bot crunching numbers.
+const ARITHMETIC_LOOP: &[u32] = &[
+    0x00128293, // loop: addi t0, t0, 1
+    0x00534333, // xor t1, t1, t0
+    0x026283b3, // mul t2, t0, t1
+    0xff5ff06f, // j loop
+];
+
+/// Round-trips through slot 1, like a bot polling sensors and writing
+/// controls.
+const MMIO_LOOP: &[u32] = &[
+    0x10000513, // addi a0, zero, 0x100
+    0x00552023, // loop: sw t0, 0(a0)
+    0x00452303, // lw t1, 4(a0)
+    0x00128293, // addi t0, t0, 1
+    0xff1ff06f, // j loop
+];
+
+/// Two plain registers at offsets 0 and 4.
+struct RegisterDevice([u32; 2]);
+
+impl Device for RegisterDevice {
+    fn load(&self, addr: u32, _size: u32) -> Result<u32, ()> {
+        self.0.get(addr as usize / 4).copied().ok_or(())
+    }
+
+    fn store(&mut self, addr: u32, _size: u32, value: u32) -> Result<(), ()> {
+        let register = self.0.get_mut(addr as usize / 4).ok_or(())?;
+        *register = value;
+        Ok(())
+    }
+}
+
+fn bench_loop(c: &mut Criterion, name: &str, code: &[u32], decode_cache: bool) {
+    let elf = elf_with_code(code);
+    let mut cpu = CpuComponent::new(&elf, CYCLES).expect("benchmark image loads");
+    cpu.set_decode_cache(decode_cache);
+    let mut device = RegisterDevice([0; 2]);
+
+    let mut group = c.benchmark_group("interpreter");
+    group.throughput(Throughput::Elements(CYCLES as u64));
+    group.bench_function(name, |b| {
+        b.iter(|| {
+            cpu.run_n_cycles(CYCLES, &mut [&mut device])
+                .expect("benchmark loop does not trap")
+        })
+    });
+    group.finish();
+}
+
+fn arithmetic(c: &mut Criterion) {
+    bench_loop(c, "arithmetic", ARITHMETIC_LOOP, true);
+    bench_loop(c, "arithmetic_uncached", ARITHMETIC_LOOP, false);
+}
+
+fn mmio(c: &mut Criterion) {
+    bench_loop(c, "mmio", MMIO_LOOP, true);
+    bench_loop(c, "mmio_uncached", MMIO_LOOP, false);
+}
+
+criterion_group!(benches, arithmetic, mmio);
+criterion_main!(benches);
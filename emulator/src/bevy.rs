@@ -1,10 +1,15 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use bevy::{
     ecs::query::{QueryData, QueryItem},
     prelude::*,
 };
 
 use crate::CpuBuilder;
-use crate::cpu::{Device, Instruction, Mmu};
+use crate::coverage::InstructionCoverage;
+use crate::cpu::{Device, Instruction, LoadError, Mmu, Trap};
+use crate::decode_cache::{DecodeCache, InvalidatingRam};
+use tracing::warn;
 
 #[macro_export]
 macro_rules! define_cpu_config {
@@ -93,20 +98,87 @@ pub trait CpuConfig: Send + Sync + 'static {
 pub struct CpuComponent {
     hart: crate::cpu::Hart,
     dram: crate::cpu::Dram,
+    /// The ELF image, DRAM size and initial registers the CPU was built
+    /// from, for `reset`.
+    image: Vec<u8>,
+    dram_size: u32,
+    initial_regs: Vec<(usize, u32)>,
+    /// Entry point of the image, for `soft_restart`.
+    entry: u32,
     instructions_per_update: u32,
+    /// Instructions the last [`run_n_cycles`](Self::run_n_cycles) executed.
+    last_run_cycles: u32,
+    /// Consecutive runs that spent their whole budget without yielding.
+    exhausted_runs: u32,
+    coverage: Option<InstructionCoverage>,
+    decode_cache: Option<DecodeCache>,
+    trap: Option<Trap>,
 }
 
 impl CpuComponent {
-    /// Create a new CpuComponent from an ELF binary.
-    pub fn new(elf: &[u8], instructions_per_update: u32) -> Self {
-        let (hart, dram) = CpuBuilder::default().build(elf);
-        Self {
+    /// Create a new CpuComponent from an ELF binary with the default DRAM size.
+    pub fn new(elf: &[u8], instructions_per_update: u32) -> Result<Self, LoadError> {
+        Self::with_builder(CpuBuilder::default(), elf, instructions_per_update)
+    }
+
+    /// Create a new CpuComponent from an ELF binary, e.g. with a larger DRAM.
+    pub fn with_builder(
+        builder: CpuBuilder,
+        elf: &[u8],
+        instructions_per_update: u32,
+    ) -> Result<Self, LoadError> {
+        let dram_size = builder.dram_size;
+        let initial_regs = builder.initial_regs.clone();
+        let (hart, dram) = builder.build(elf)?;
+        Ok(Self {
+            entry: hart.pc,
             hart,
             dram,
+            image: elf.to_vec(),
+            dram_size,
+            initial_regs,
             instructions_per_update,
+            last_run_cycles: 0,
+            exhausted_runs: 0,
+            coverage: None,
+            decode_cache: Some(DecodeCache::new()),
+            trap: None,
+        })
+    }
+
+    /// Return to the state the CPU was created in: registers back to their
+    /// initial values, PC at the entry point and DRAM re-initialized from the
+    /// image. Also clears the trap, the decode cache, coverage counts and
+    /// budget usage; the instruction budget is kept.
+    pub fn reset(&mut self) {
+        let (hart, dram) = CpuBuilder::default()
+            .dram_size(self.dram_size)
+            .initial_regs(&self.initial_regs)
+            .build(&self.image)
+            .expect("image already loaded once");
+        self.hart = hart;
+        self.dram = dram;
+        self.trap = None;
+        self.last_run_cycles = 0;
+        self.exhausted_runs = 0;
+        if self.decode_cache.is_some() {
+            self.decode_cache = Some(DecodeCache::new());
+        }
+        if self.coverage.is_some() {
+            self.coverage = Some(InstructionCoverage::new());
         }
     }
 
+    /// Restart execution but keep memory: registers cleared except `sp`,
+    /// which points at the top of the stack again, and PC at the entry point.
+    /// Unlike [`reset`](Self::reset), DRAM keeps whatever the bot stored, so
+    /// state it keeps in statics survives. Clears the trap.
+    pub fn soft_restart(&mut self) {
+        self.hart = crate::cpu::Hart::new(self.entry);
+        self.hart.regs[2] = crate::stack_pointer_for_dram_len(self.dram.dram.len() as u32);
+        self.trap = None;
+    }
+
     pub fn instructions_per_update(&self) -> u32 {
         self.instructions_per_update
     }
@@ -114,28 +186,229 @@ impl CpuComponent {
     pub fn set_instructions_per_update(&mut self, value: u32) {
         self.instructions_per_update = value.max(1);
     }
+
+    /// Instructions the last [`run_n_cycles`](Self::run_n_cycles) executed,
+    /// counting a yielding `ecall`.
+    pub fn last_run_cycles(&self) -> u32 {
+        self.last_run_cycles
+    }
+
+    /// How many runs in a row used their whole budget without yielding; 0
+    /// after a run that yielded. A bot that never yields keeps counting up.
+    pub fn exhausted_runs(&self) -> u32 {
+        self.exhausted_runs
+    }
+
+    /// Start tallying executed instructions (see [`InstructionCoverage`]).
+    pub fn enable_coverage(&mut self) {
+        self.coverage.get_or_insert_with(InstructionCoverage::new);
+    }
+
+    /// Coverage collected so far, if it was enabled.
+    pub fn coverage(&self) -> Option<&InstructionCoverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Reuse decoded instructions across executions (on by default, see
+    /// [`DecodeCache`]). Turning it off decodes every fetched instruction.
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        if !enabled {
+            self.decode_cache = None;
+        } else if self.decode_cache.is_none() {
+            self.decode_cache = Some(DecodeCache::new());
+        }
+    }
+
+    /// The trap that halted this CPU, if any. A trapped CPU executes no
+    /// further instructions.
+    pub fn trap(&self) -> Option<Trap> {
+        self.trap
+    }
+
+    /// Execute up to `n` instructions against `devices` (slot 1 first),
+    /// outside of [`cpu_system`], e.g. for benchmarks and headless drivers.
+    /// Stops at the first trap, which is kept like in `cpu_system`, and right
+    /// after an `ecall`, with which the bot yields the rest of the budget;
+    /// the next call resumes after it.
+    pub fn run_n_cycles(&mut self, n: u32, devices: &mut [&mut dyn Device]) -> Result<(), Trap> {
+        if let Some(trap) = self.trap {
+            return Err(trap);
+        }
+        let mut executed = 0;
+        let mut yielded = false;
+        while executed < n {
+            let step = run_one_instruction(self, devices);
+            executed += 1;
+            match step {
+                Ok(Step::Continue) => {}
+                Ok(Step::Yield) => {
+                    yielded = true;
+                    break;
+                }
+                Err(trap) => {
+                    self.last_run_cycles = executed;
+                    self.trap = Some(trap);
+                    return Err(trap);
+                }
+            }
+        }
+        self.last_run_cycles = executed;
+        self.exhausted_runs = if yielded {
+            0
+        } else {
+            self.exhausted_runs.saturating_add(1)
+        };
+        Ok(())
+    }
+
+    /// Execute until the first successful store into the device in `slot`
+    /// (1-based, mapped at `slot * 0x100`), at most `max_cycles` instructions.
+    /// Returns how many instructions that took, counting the store, or `None`
+    /// if the cap ran out first. Traps are kept like in [`Self::run_n_cycles`];
+    /// yields do not stop it, as it measures across updates.
+    ///
+    /// Panics if `devices` has no device in `slot`.
+    pub fn run_until_store(
+        &mut self,
+        slot: usize,
+        max_cycles: u32,
+        devices: &mut [&mut dyn Device],
+    ) -> Result<Option<u32>, Trap> {
+        if let Some(trap) = self.trap {
+            return Err(trap);
+        }
+        let stored = AtomicBool::new(false);
+        let index = slot.checked_sub(1).expect("device slots start at 1");
+        let (before, rest) = devices.split_at_mut(index);
+        let (target, after) = rest
+            .split_first_mut()
+            .expect("no device in the watched slot");
+        let mut watch = StoreWatch {
+            inner: &mut **target,
+            stored: &stored,
+        };
+        let mut watched: Vec<&mut dyn Device> = Vec::with_capacity(before.len() + 1 + after.len());
+        watched.extend(
+            before
+                .iter_mut()
+                .map(|device| &mut **device as &mut dyn Device),
+        );
+        watched.push(&mut watch);
+        watched.extend(
+            after
+                .iter_mut()
+                .map(|device| &mut **device as &mut dyn Device),
+        );
+
+        for cycle in 1..=max_cycles {
+            if let Err(trap) = run_one_instruction(self, &mut watched) {
+                self.trap = Some(trap);
+                return Err(trap);
+            }
+            if stored.load(Ordering::Relaxed) {
+                return Ok(Some(cycle));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Forwards to `inner`, noting when a store to it succeeds.
+struct StoreWatch<'a> {
+    inner: &'a mut dyn Device,
+    stored: &'a AtomicBool,
+}
+
+impl Device for StoreWatch<'_> {
+    fn load(&self, addr: u32, size: u32) -> Result<u32, ()> {
+        self.inner.load(addr, size)
+    }
+
+    fn store(&mut self, addr: u32, size: u32, value: u32) -> Result<(), ()> {
+        self.inner.store(addr, size, value)?;
+        self.stored.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Whether the run loop may go on after an instruction.
+enum Step {
+    Continue,
+    /// The bot executed `ecall` and is done until the next update.
+    Yield,
+}
+
+impl Step {
+    fn after(inst: Instruction) -> Self {
+        if matches!(inst, Instruction::Ecall) {
+            Self::Yield
+        } else {
+            Self::Continue
+        }
+    }
 }
 
-fn run_one_instruction(cpu: &mut CpuComponent, device_refs: &mut [&mut dyn Device]) {
+fn run_one_instruction(
+    cpu: &mut CpuComponent,
+    device_refs: &mut [&mut dyn Device],
+) -> Result<Step, Trap> {
     let mut mmu = Mmu::new(&mut cpu.dram, device_refs);
 
-    // 1. Fetch.
-    let inst = cpu.hart.fetch(&mmu);
+    let Some(cache) = cpu.decode_cache.as_mut() else {
+        // 1. Fetch.
+        let inst = cpu.hart.fetch(&mmu)?;
+        if let Some(coverage) = cpu.coverage.as_mut() {
+            coverage.record(inst);
+        }
+
+        // 2. Decode.
+        let (decoded, len) = Instruction::parse_with_len(inst);
+        // 3. Execute.
+        return cpu
+            .hart
+            .execute(decoded, len, &mut mmu)
+            .map(|()| Step::after(decoded));
+    };
+
+    // 1. + 2. Fetch and decode, unless the cache already has this PC.
+    let pc = cpu.hart.pc;
+    let (inst, decoded, len) = match cache.get(pc) {
+        Some(hit) => hit,
+        None => {
+            let inst = cpu.hart.fetch(&mmu)?;
+            let (decoded, len) = Instruction::parse_with_len(inst);
+            cache.insert(pc, inst, decoded, len);
+            (inst, decoded, len)
+        }
+    };
+    if let Some(coverage) = cpu.coverage.as_mut() {
+        coverage.record(inst);
+    }
 
-    // 2. Decode.
-    let (decoded, len) = Instruction::parse_with_len(inst);
-    // 3. Execute.
-    cpu.hart.execute(decoded, len, &mut mmu);
+    // 3. Execute, dropping cached code the instruction overwrites.
+    let mut ram = InvalidatingRam {
+        ram: &mut mmu,
+        cache,
+    };
+    cpu.hart
+        .execute(decoded, len, &mut ram)
+        .map(|()| Step::after(decoded))
 }
 
 fn run_cpu(cpu: &mut CpuComponent, device_refs: &mut [&mut dyn Device]) {
-    for _ in 0..cpu.instructions_per_update {
-        run_one_instruction(cpu, device_refs);
+    if cpu.trap.is_some() {
+        return;
+    }
+    if let Err(trap) = cpu.run_n_cycles(cpu.instructions_per_update, device_refs) {
+        warn!("cpu halted: {trap}");
     }
 }
 
+/// Steps every CPU for its per-update budget. CPUs run in parallel on the
+/// compute task pool: each one only reaches the device components on its own
+/// entity, so the result does not depend on scheduling.
 pub fn cpu_system<C: CpuConfig>(mut cpu_query: Query<(&mut CpuComponent, C::Devices)>) {
-    for (mut cpu, devices) in cpu_query.iter_mut() {
+    cpu_query.par_iter_mut().for_each(|(mut cpu, devices)| {
         C::with_slotted_devices(devices, |slotted| {
             slotted.sort_by_key(|entry| entry.0);
 
@@ -154,5 +427,217 @@ pub fn cpu_system<C: CpuConfig>(mut cpu_query: Query<(&mut CpuComponent, C::Devi
             }
             run_cpu(cpu.as_mut(), &mut device_refs);
         });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::RamLike;
+    use crate::test_elf::elf_with_code;
+
+    #[test]
+    fn soft_restart_keeps_dram_but_clears_registers() {
+        let elf = elf_with_code(&[
+            0x000025b7, // lui a1, 0x2
+            0x02a00293, // addi t0, zero, 42
+            0x0055a023, // sw t0, 0(a1)
+            0x0000006f, // j .
+        ]);
+        let mut cpu = CpuComponent::new(&elf, 1).expect("image loads");
+        let fresh_sp = cpu.hart.regs[2];
+        let entry = cpu.hart.pc;
+        cpu.run_n_cycles(4, &mut []).expect("no trap");
+        assert_eq!(cpu.hart.regs[5], 42);
+
+        cpu.soft_restart();
+        assert_eq!(cpu.dram.load(0x2000, 32), Ok(42));
+        assert_eq!(cpu.hart.pc, entry);
+        assert_eq!(cpu.hart.regs[2], fresh_sp);
+        assert!(
+            cpu.hart
+                .regs
+                .iter()
+                .enumerate()
+                .all(|(index, &value)| index == 2 || value == 0),
+            "{:?}",
+            cpu.hart.regs
+        );
+
+        cpu.reset();
+        assert_eq!(cpu.dram.load(0x2000, 32), Ok(0));
+    }
+
+    #[test]
+    fn decode_cache_matches_uncached_execution() {
+        let elf = elf_with_code(&[
+            0x000025b7, // lui a1, 0x2
+            0x002283b7, // lui t2, 0x228
+            0x29338393, // addi t2, t2, 0x293 (encodes `addi t0, t0, 2`)
+            0x00001637, // lui a2, 0x1
+            0x01460613, // addi a2, a2, 0x14
+            0x00128293, // loop: addi t0, t0, 1
+            0x00530333, // add t1, t1, t0
+            0x0065a023, // sw t1, 0(a1)
+            0x00458593, // addi a1, a1, 4
+            0x00762023, // sw t2, 0(a2) (patches the loop's first instruction)
+            0xfedff06f, // j loop
+        ]);
+        let mut cached = CpuComponent::new(&elf, 1).expect("image loads");
+        let mut uncached = CpuComponent::new(&elf, 1).expect("image loads");
+        uncached.set_decode_cache(false);
+
+        cached
+            .run_n_cycles(1000, &mut [])
+            .expect("loop does not trap");
+        uncached
+            .run_n_cycles(1000, &mut [])
+            .expect("loop does not trap");
+
+        assert_eq!(cached.hart.regs, uncached.hart.regs);
+        assert_eq!(cached.hart.fregs, uncached.hart.fregs);
+        assert_eq!(cached.hart.pc, uncached.hart.pc);
+        assert!(cached.dram.dram == uncached.dram.dram);
+        // The patched increment was picked up despite the cached decode.
+        assert_eq!(cached.hart.regs[5], 1 + 2 * 165);
+    }
+
+    /// Plain register at offset 0, seeded per car.
+    #[derive(Component)]
+    struct SeedDevice(u32);
+
+    impl Device for SeedDevice {
+        fn load(&self, _addr: u32, _size: u32) -> Result<u32, ()> {
+            Ok(self.0)
+        }
+
+        fn store(&mut self, _addr: u32, _size: u32, value: u32) -> Result<(), ()> {
+            self.0 = value;
+            Ok(())
+        }
+    }
+
+    crate::define_cpu_config! {
+        SeedCpuConfig {
+            1 => SeedDevice,
+        }
+    }
+
+    #[test]
+    fn parallel_cpu_system_matches_serial_stepping() {
+        const CARS: u32 = 16;
+        const BUDGET: u32 = 500;
+        const UPDATES: usize = 4;
+
+        bevy::tasks::ComputeTaskPool::get_or_init(bevy::tasks::TaskPool::default);
+        let elf = elf_with_code(&[
+            0x10000513, // addi a0, zero, 0x100
+            0x00052283, // lw t0, 0(a0)
+            0x00328293, // loop: addi t0, t0, 3
+            0x02528333, // mul t1, t0, t0
+            0x006383b3, // add t2, t2, t1
+            0x00752023, // sw t2, 0(a0)
+            0xff1ff06f, // j loop
+        ]);
+
+        let mut world = World::new();
+        let entities: Vec<Entity> = (0..CARS)
+            .map(|seed| {
+                let cpu = CpuComponent::new(&elf, BUDGET).expect("image loads");
+                world.spawn((cpu, SeedDevice(seed * 1000))).id()
+            })
+            .collect();
+        let mut serial: Vec<(CpuComponent, SeedDevice)> = (0..CARS)
+            .map(|seed| {
+                let cpu = CpuComponent::new(&elf, BUDGET).expect("image loads");
+                (cpu, SeedDevice(seed * 1000))
+            })
+            .collect();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(cpu_system::<SeedCpuConfig>);
+        for _ in 0..UPDATES {
+            schedule.run(&mut world);
+            for (cpu, device) in &mut serial {
+                cpu.run_n_cycles(BUDGET, &mut [device])
+                    .expect("loop does not trap");
+            }
+        }
+
+        for (entity, (cpu, device)) in entities.into_iter().zip(&serial) {
+            let parallel_cpu = world.get::<CpuComponent>(entity).unwrap();
+            let parallel_device = world.get::<SeedDevice>(entity).unwrap();
+            assert_eq!(parallel_cpu.hart.regs, cpu.hart.regs);
+            assert_eq!(parallel_cpu.hart.pc, cpu.hart.pc);
+            assert_eq!(parallel_device.0, device.0);
+        }
+        // Each car kept its own seed.
+        assert_ne!(serial[0].1.0, serial[1].1.0);
+    }
+
+    #[test]
+    fn store_outside_the_arena_traps_without_touching_other_cars() {
+        let stray = elf_with_code(&[
+            0x20000513, // addi a0, zero, 0x200 (slot 2, which has no device)
+            0x02a00293, // addi t0, zero, 42
+            0x00552023, // sw t0, 0(a0)
+        ]);
+        let reader = elf_with_code(&[
+            0x10000513, // addi a0, zero, 0x100
+            0x00052283, // lw t0, 0(a0)
+            0x0000006f, // j .
+        ]);
+
+        let mut world = World::new();
+        let stray_car = world
+            .spawn((CpuComponent::new(&stray, 10).unwrap(), SeedDevice(1)))
+            .id();
+        let other_car = world
+            .spawn((CpuComponent::new(&reader, 10).unwrap(), SeedDevice(2)))
+            .id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(cpu_system::<SeedCpuConfig>);
+        schedule.run(&mut world);
+
+        let trapped = world.get::<CpuComponent>(stray_car).unwrap();
+        assert_eq!(
+            trapped.trap(),
+            Some(Trap::StoreAccessFault {
+                addr: 0x200,
+                pc: 0x1008,
+            })
+        );
+        assert_eq!(world.get::<SeedDevice>(stray_car).unwrap().0, 1);
+
+        let other = world.get::<CpuComponent>(other_car).unwrap();
+        assert_eq!(other.trap(), None);
+        assert_eq!(other.hart.regs[5], 2);
+        assert_eq!(world.get::<SeedDevice>(other_car).unwrap().0, 2);
+    }
+
+    #[test]
+    fn reset_matches_a_freshly_built_cpu() {
+        let elf = elf_with_code(&[
+            0x000025b7, // lui a1, 0x2
+            0x00b5a023, // sw a1, 0(a1)
+            0x00001637, // lui a2, 0x1
+            0x00062023, // sw zero, 0(a2) (overwrites the first instruction)
+            0x1234b00b, // custom-0, traps
+        ]);
+        let fresh = CpuComponent::new(&elf, 1).unwrap();
+        let mut cpu = CpuComponent::new(&elf, 1).unwrap();
+        cpu.enable_coverage();
+        assert!(cpu.run_n_cycles(10, &mut []).is_err());
+        assert!(cpu.trap().is_some());
+
+        cpu.reset();
+
+        assert_eq!(cpu.hart.regs, fresh.hart.regs);
+        assert_eq!(cpu.hart.fregs, fresh.hart.fregs);
+        assert_eq!(cpu.hart.pc, fresh.hart.pc);
+        assert_eq!(cpu.hart.reservation_addr, fresh.hart.reservation_addr);
+        assert!(cpu.dram.dram == fresh.dram.dram);
+        assert_eq!(cpu.trap(), None);
+        assert_eq!(cpu.coverage().unwrap().total(), 0);
     }
 }
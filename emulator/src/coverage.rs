@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Encoding class an executed instruction is tallied under.
+///
+/// 32-bit instructions are keyed by major opcode (`inst[6:0]`) and `funct3`
+/// (`inst[14:12]`). Compressed instructions are keyed by quadrant (`inst[1:0]`)
+/// and their `funct3` (`inst[15:13]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CoverageKey {
+    pub compressed: bool,
+    pub opcode: u32,
+    pub funct3: u32,
+}
+
+impl CoverageKey {
+    pub fn from_raw(inst: u32) -> Self {
+        if (inst & 0x3) != 0x3 {
+            return Self {
+                compressed: true,
+                opcode: inst & 0x3,
+                funct3: (inst >> 13) & 0x7,
+            };
+        }
+
+        let opcode = inst & 0x7f;
+        // LUI, AUIPC and JAL carry immediate bits where funct3 would be.
+        let funct3 = match opcode {
+            0x17 | 0x37 | 0x6f => 0,
+            _ => (inst >> 12) & 0x7,
+        };
+        Self {
+            compressed: false,
+            opcode,
+            funct3,
+        }
+    }
+
+    /// Name of the RISC-V major opcode (or compressed quadrant) for reports.
+    pub fn opcode_name(&self) -> &'static str {
+        if self.compressed {
+            return match self.opcode {
+                0x0 => "C0",
+                0x1 => "C1",
+                _ => "C2",
+            };
+        }
+        match self.opcode {
+            0x03 => "LOAD",
+            0x07 => "LOAD-FP",
+            0x0f => "MISC-MEM",
+            0x13 => "OP-IMM",
+            0x17 => "AUIPC",
+            0x23 => "STORE",
+            0x27 => "STORE-FP",
+            0x2f => "AMO",
+            0x33 => "OP",
+            0x37 => "LUI",
+            0x43 => "MADD",
+            0x47 => "MSUB",
+            0x4b => "NMSUB",
+            0x4f => "NMADD",
+            0x53 => "OP-FP",
+            0x63 => "BRANCH",
+            0x67 => "JALR",
+            0x6f => "JAL",
+            0x73 => "SYSTEM",
+            _ => "UNKNOWN",
+        }
+    }
+}
+
+/// Opt-in execution histogram over instruction encodings.
+///
+/// Useful for finding out which parts of the ISA a bot actually exercises,
+/// e.g. to decide which missing instructions to implement next.
+#[derive(Debug, Default, Clone)]
+pub struct InstructionCoverage {
+    counts: BTreeMap<CoverageKey, u64>,
+}
+
+impl InstructionCoverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tally one execution of the raw (fetched, undecoded) instruction word.
+    pub fn record(&mut self, inst: u32) {
+        *self.counts.entry(CoverageKey::from_raw(inst)).or_insert(0) += 1;
+    }
+
+    pub fn count(&self, key: CoverageKey) -> u64 {
+        self.counts.get(&key).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// All recorded encoding classes in key order.
+    pub fn histogram(&self) -> impl Iterator<Item = (CoverageKey, u64)> + '_ {
+        self.counts.iter().map(|(key, count)| (*key, *count))
+    }
+
+    /// Human-readable dump, most executed encoding classes first.
+    pub fn report(&self) -> String {
+        let mut entries: Vec<_> = self.histogram().collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let total = self.total();
+        let mut out = format!("instruction coverage ({total} executed)\n");
+        for (key, count) in entries {
+            let percent = count as f64 * 100.0 / total as f64;
+            let _ = writeln!(
+                out,
+                "  {:<9} opcode=0x{:02x} funct3={} {:>12} ({percent:5.1}%)",
+                key.opcode_name(),
+                key.opcode,
+                key.funct3,
+                count
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::{Device, Dram, Hart, Instruction, Mmu};
+
+    fn key(opcode: u32, funct3: u32) -> CoverageKey {
+        CoverageKey {
+            compressed: false,
+            opcode,
+            funct3,
+        }
+    }
+
+    #[test]
+    fn counting_loop_reports_expected_opcode_histogram() {
+        // Sums 5 + 4 + 3 + 2 + 1 into x2.
+        let program: [u32; 6] = [
+            0x0050_0093, // addi x1, x0, 5
+            0x0000_0113, // addi x2, x0, 0
+            0x1234_51b7, // lui  x3, 0x12345
+            0x0011_0133, // loop: add x2, x2, x1
+            0xfff0_8093, // addi x1, x1, -1
+            0xfe00_9ce3, // bne  x1, x0, loop
+        ];
+        let base = 0x1000u32;
+        let end = base + 4 * program.len() as u32;
+
+        let mut dram = Dram {
+            dram: vec![0; 0x2000],
+            segments: Vec::new(),
+        };
+        for (i, word) in program.iter().enumerate() {
+            let at = base as usize + 4 * i;
+            dram.dram[at..at + 4].copy_from_slice(&word.to_le_bytes());
+        }
+
+        let mut hart = Hart::new(base);
+        let mut devices: Vec<&mut dyn Device> = Vec::new();
+        let mut mmu = Mmu::new(&mut dram, &mut devices);
+        let mut coverage = InstructionCoverage::new();
+        while hart.pc != end {
+            let inst = hart.fetch(&mmu).unwrap();
+            coverage.record(inst);
+            let (decoded, len) = Instruction::parse_with_len(inst);
+            hart.execute(decoded, len, &mut mmu).unwrap();
+        }
+
+        assert_eq!(hart.regs[2], 15);
+        let histogram: Vec<_> = coverage.histogram().collect();
+        assert_eq!(
+            histogram,
+            vec![
+                (key(0x13, 0), 7),
+                (key(0x33, 0), 5),
+                (key(0x37, 0), 1),
+                (key(0x63, 1), 5),
+            ]
+        );
+        assert_eq!(coverage.total(), 18);
+        assert!(
+            coverage
+                .report()
+                .starts_with("instruction coverage (18 executed)")
+        );
+    }
+
+    #[test]
+    fn compressed_instructions_are_keyed_by_quadrant() {
+        let mut coverage = InstructionCoverage::new();
+        coverage.record(0x4505); // c.li a0, 1
+        coverage.record(0x8082); // c.jr ra
+
+        assert_eq!(
+            coverage.count(CoverageKey {
+                compressed: true,
+                opcode: 0x1,
+                funct3: 0x2,
+            }),
+            1
+        );
+        assert_eq!(
+            coverage.count(CoverageKey {
+                compressed: true,
+                opcode: 0x2,
+                funct3: 0x4,
+            }),
+            1
+        );
+    }
+}
@@ -1,10 +1,98 @@
 use bevy::prelude::Component;
-use elf::{ElfBytes, abi::PT_LOAD, endian::LittleEndian};
+use elf::{
+    ElfBytes,
+    abi::{PF_R, PF_W, PF_X, PT_LOAD},
+    endian::LittleEndian,
+};
+use std::collections::BTreeSet;
+use std::ops::Range;
 use tracing::{debug, trace};
 
 pub use instruction::Instruction;
 mod instruction;
 
+/// Synchronous exception that stops a hart instead of misexecuting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    /// The instruction at `pc` is not implemented by this emulator.
+    IllegalInstruction {
+        raw: u32,
+        pc: u32,
+        opcode: u32,
+        funct3: u32,
+    },
+    /// The load at `pc` reads `addr`, which is outside the hart's DRAM and
+    /// its device slots.
+    LoadAccessFault { addr: u32, pc: u32 },
+    /// The store or AMO at `pc` writes `addr`, which is outside the hart's
+    /// DRAM and its device slots.
+    StoreAccessFault { addr: u32, pc: u32 },
+    /// The bot executed `ebreak` at `pc`.
+    Breakpoint { pc: u32 },
+    /// Execution reached `pc`, where there is no instruction to fetch:
+    /// outside DRAM, or an instruction running past its end.
+    InstructionAccessFault { pc: u32 },
+}
+
+impl Trap {
+    pub fn illegal_instruction(raw: u32, pc: u32) -> Self {
+        Self::IllegalInstruction {
+            raw,
+            pc,
+            opcode: raw & 0x7f,
+            funct3: (raw >> 12) & 0x7,
+        }
+    }
+
+    /// Short machine-readable name, e.g. `illegal_instruction`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::IllegalInstruction { .. } => "illegal_instruction",
+            Self::LoadAccessFault { .. } => "load_access_fault",
+            Self::StoreAccessFault { .. } => "store_access_fault",
+            Self::Breakpoint { .. } => "breakpoint",
+            Self::InstructionAccessFault { .. } => "instruction_access_fault",
+        }
+    }
+
+    /// Address of the instruction that raised the trap.
+    pub fn pc(&self) -> u32 {
+        match self {
+            Self::IllegalInstruction { pc, .. }
+            | Self::LoadAccessFault { pc, .. }
+            | Self::StoreAccessFault { pc, .. }
+            | Self::Breakpoint { pc }
+            | Self::InstructionAccessFault { pc } => *pc,
+        }
+    }
+}
+
+impl std::fmt::Display for Trap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IllegalInstruction {
+                raw,
+                pc,
+                opcode,
+                funct3,
+            } => write!(
+                f,
+                "unimplemented instruction 0x{raw:08x} at 0x{pc:08x} (opcode 0x{opcode:02x}, funct3 {funct3})"
+            ),
+            Self::LoadAccessFault { addr, pc } => {
+                write!(f, "load from unmapped address 0x{addr:08x} at 0x{pc:08x}")
+            }
+            Self::StoreAccessFault { addr, pc } => {
+                write!(f, "store to unmapped address 0x{addr:08x} at 0x{pc:08x}")
+            }
+            Self::Breakpoint { pc } => write!(f, "ebreak at 0x{pc:08x}"),
+            Self::InstructionAccessFault { pc } => {
+                write!(f, "fetch from unmapped address 0x{pc:08x}")
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Hart {
     pub regs: [u32; 32],
@@ -24,8 +112,18 @@ impl Hart {
         cpu.regs[2] = (DRAM_SIZE - 16) & !0xf;
         cpu
     }
-    pub fn fetch(&self, dram: &impl RamLike) -> u32 {
-        dram.load(self.pc, 32).unwrap_or(0)
+    /// The instruction word at `pc`. Where a full word does not fit, e.g. a
+    /// compressed instruction in the last two bytes of DRAM, only the low half
+    /// is read; if that does not hold a complete instruction either, the
+    /// fetch traps instead of wrapping past the end of memory.
+    pub fn fetch(&self, dram: &impl RamLike) -> Result<u32, Trap> {
+        if let Ok(word) = dram.load(self.pc, 32) {
+            return Ok(word);
+        }
+        match dram.load(self.pc, 16) {
+            Ok(half) if half & 0b11 != 0b11 => Ok(half),
+            _ => Err(Trap::InstructionAccessFault { pc: self.pc }),
+        }
     }
     pub fn set_reservation(&mut self, addr: u32) {
         self.reservation_addr = Some(addr);
@@ -44,7 +142,85 @@ impl Hart {
         }
     }
 
-    pub fn execute(&mut self, inst: Instruction, inst_len: u32, dram: &mut impl RamLike) {
+    /// Address, size in bits and direction (`true` for stores and AMOs) of
+    /// the memory access `inst` makes, if any.
+    fn memory_access(&self, inst: &Instruction) -> Option<(u32, u32, bool)> {
+        use instruction::{AFunct, FLFunct, FSFunct, IFunct, SFunct};
+        match *inst {
+            Instruction::I {
+                funct, rs1, imm, ..
+            } => {
+                let size = match funct {
+                    IFunct::LB | IFunct::LBU => 8,
+                    IFunct::LH | IFunct::LHU => 16,
+                    IFunct::LW => 32,
+                    _ => return None,
+                };
+                Some((self.regs[rs1].wrapping_add_signed(imm), size, false))
+            }
+            Instruction::S {
+                funct, rs1, imm, ..
+            } => {
+                let size = match funct {
+                    SFunct::SB => 8,
+                    SFunct::SH => 16,
+                    SFunct::SW => 32,
+                };
+                Some((self.regs[rs1].wrapping_add_signed(imm), size, true))
+            }
+            Instruction::FL {
+                funct, rs1, imm, ..
+            } => {
+                let size = match funct {
+                    FLFunct::FLH => 16,
+                    FLFunct::FLW => 32,
+                    FLFunct::FLD => 64,
+                };
+                Some((self.regs[rs1].wrapping_add_signed(imm), size, false))
+            }
+            Instruction::FS {
+                funct, rs1, imm, ..
+            } => {
+                let size = match funct {
+                    FSFunct::FSH => 16,
+                    FSFunct::FSW => 32,
+                    FSFunct::FSD => 64,
+                };
+                Some((self.regs[rs1].wrapping_add_signed(imm), size, true))
+            }
+            Instruction::A { funct, rs1, .. } => {
+                Some((self.regs[rs1], 32, !matches!(funct, AFunct::LrW)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Executes one decoded instruction. On a trap the PC is left pointing at
+    /// the faulting instruction and no architectural state is modified.
+    pub fn execute(
+        &mut self,
+        inst: Instruction,
+        inst_len: u32,
+        dram: &mut impl RamLike,
+    ) -> Result<(), Trap> {
+        match inst {
+            Instruction::Illegal { raw } => {
+                return Err(Trap::illegal_instruction(raw, self.pc));
+            }
+            Instruction::Ebreak => return Err(Trap::Breakpoint { pc: self.pc }),
+            _ => {}
+        }
+        let fault = self
+            .memory_access(&inst)
+            .filter(|&(addr, size, _)| !dram.accessible(addr, size));
+        if let Some((addr, _, store)) = fault {
+            let pc = self.pc;
+            return Err(if store {
+                Trap::StoreAccessFault { addr, pc }
+            } else {
+                Trap::LoadAccessFault { addr, pc }
+            });
+        }
         self.regs[0] = 0; // Simulate hard wired x0
         self.pc = self.pc.wrapping_add(inst_len);
 
@@ -651,36 +827,272 @@ impl Hart {
             } => {
                 // Single-hart simplified model: fence/fence.i are no-ops.
             }
-            Instruction::Ebreak => {
-                panic!("ebreak");
+            Instruction::Ecall => {
+                // Only a yield to the host, which watches for it in its run loop.
+            }
+            Instruction::Ebreak | Instruction::Illegal { .. } => {
+                unreachable!("ebreak and illegal instructions trap before execution")
             }
         }
         self.regs[0] = 0;
+        Ok(())
     }
 }
 
-/// Minimum DRAM size (64KiB).
-pub const DRAM_SIZE: u32 = 1024 * 64;
+/// Default DRAM size (1MiB); `CpuBuilder::dram_size` overrides it.
+pub const DRAM_SIZE: u32 = 1024 * 1024;
 /// Stack headroom reserved above loaded ELF segments.
 pub const STACK_HEADROOM: u32 = 1024 * 256;
 
 fn align_up_16(value: u32) -> u32 {
-    (value + 0xf) & !0xf
+    value.saturating_add(0xf) & !0xf
 }
 
+/// DRAM needed for segments ending at `max_load_end` plus the stack. The SDK
+/// heap is a static, so it is part of the loaded segments.
 fn dram_size_for_loaded_end(max_load_end: u32) -> u32 {
-    align_up_16(max_load_end.saturating_add(STACK_HEADROOM).max(DRAM_SIZE))
+    align_up_16(max_load_end.saturating_add(STACK_HEADROOM))
+}
+
+/// Why an ELF image could not be loaded into DRAM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The image is not a well-formed ELF file.
+    Malformed(String),
+    /// Loaded segments plus stack headroom need more than the configured DRAM.
+    TooLarge { required: u32, dram_size: u32 },
+    /// The image records an ABI version other than the one the host provides.
+    IncompatibleAbi { expected: u32, found: u32 },
+    /// The entry point does not lie inside a loaded executable segment, so
+    /// the first fetch would trap.
+    BadEntry { entry: u32 },
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(reason) => write!(f, "malformed elf: {reason}"),
+            Self::TooLarge {
+                required,
+                dram_size,
+            } => write!(
+                f,
+                "image needs {required} bytes of DRAM including stack, only {dram_size} configured"
+            ),
+            Self::IncompatibleAbi { expected, found } => write!(
+                f,
+                "image was built for ABI version {found}, this host provides {expected}"
+            ),
+            Self::BadEntry { entry } => write!(
+                f,
+                "entry point 0x{entry:x} is not inside a loaded executable segment"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// The little-endian `u32` at the start of the ELF section `name`, or `None`
+/// if the image has no such section.
+pub fn section_u32(code: &[u8], name: &str) -> Result<Option<u32>, LoadError> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(code)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let header = elf
+        .section_header_by_name(name)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let Some(header) = header else {
+        return Ok(None);
+    };
+    let (data, _) = elf
+        .section_data(&header)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let Some(bytes) = data.get(..4) else {
+        return Err(LoadError::Malformed(format!(
+            "section {name} is shorter than 4 bytes"
+        )));
+    };
+    Ok(Some(u32::from_le_bytes(bytes.try_into().unwrap())))
+}
+
+/// The NUL-padded UTF-8 string stored in section `name`, if the ELF has it.
+pub fn section_str(code: &[u8], name: &str) -> Result<Option<String>, LoadError> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(code)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let header = elf
+        .section_header_by_name(name)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let Some(header) = header else {
+        return Ok(None);
+    };
+    let (data, _) = elf
+        .section_data(&header)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let text = std::str::from_utf8(data)
+        .map_err(|_| LoadError::Malformed(format!("section {name} is not UTF-8")))?;
+    Ok(Some(text.trim_end_matches('\0').to_string()))
+}
+
+/// Whether the ELF's symbol table defines `name`, or `None` when it has no
+/// symbol table (e.g. a stripped binary).
+pub fn defines_symbol(code: &[u8], name: &str) -> Result<Option<bool>, LoadError> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(code)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let Some((symbols, strings)) = elf
+        .symbol_table()
+        .map_err(|e| LoadError::Malformed(e.to_string()))?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(symbols.iter().any(|symbol| {
+        !symbol.is_undefined()
+            && strings
+                .get(symbol.st_name as usize)
+                .is_ok_and(|s| s == name)
+    })))
+}
+
+/// Device slots (1-based, mapped at `slot * 0x100`) that an `addi rd, x0,
+/// imm` (`li`, also compressed) in an executable segment points into. Bots
+/// address their devices through such constants, so this is a cheap static
+/// hint of the slots a bot uses; addresses computed otherwise are missed.
+pub fn referenced_slots(code: &[u8]) -> Result<BTreeSet<usize>, LoadError> {
+    let elf = ElfBytes::<LittleEndian>::minimal_parse(code)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let segments = elf
+        .segments()
+        .ok_or_else(|| LoadError::Malformed("no program headers".to_string()))?;
+    let mut slots = BTreeSet::new();
+    for phdr in segments
+        .iter()
+        .filter(|phdr| phdr.p_type == PT_LOAD && phdr.p_flags & PF_X != 0)
+    {
+        let bytes = elf
+            .segment_data(&phdr)
+            .map_err(|e| LoadError::Malformed(e.to_string()))?;
+        let halfword = |at: usize| {
+            bytes
+                .get(at..at + 2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)
+        };
+        let mut offset = 0;
+        while let Some(low) = halfword(offset) {
+            let raw = if low & 0x3 == 0x3 {
+                let Some(high) = halfword(offset + 2) else {
+                    break;
+                };
+                low | (high << 16)
+            } else {
+                low
+            };
+            let (inst, len) = Instruction::parse_with_len(raw);
+            if let Instruction::I {
+                funct: instruction::IFunct::ADDI,
+                rs1: 0,
+                imm,
+                ..
+            } = inst
+                && (0x100..DRAM_BASE as i32).contains(&imm)
+            {
+                slots.insert((imm >> 8) as usize);
+            }
+            offset += len as usize;
+        }
+    }
+    Ok(slots)
 }
 
 pub trait RamLike: Send + Sync {
     fn load(&self, addr: u32, size: u32) -> Result<u32, ()>;
     fn store(&mut self, addr: u32, size: u32, value: u32) -> Result<(), ()>;
+    /// Whether the `size`-bit access at `addr` is backed by memory or a
+    /// device. Accesses outside raise an access fault instead.
+    fn accessible(&self, addr: u32, size: u32) -> bool;
 }
 
 /// The dynamic random access dram (DRAM).
 #[derive(Debug)]
 pub struct Dram {
     pub dram: Vec<u8>,
+    /// The ELF segments loaded into `dram`, in program header order.
+    pub segments: Vec<LoadedSegment>,
+}
+
+/// An ELF `PT_LOAD` segment as laid into DRAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadedSegment {
+    pub start: u32,
+    /// One past the last byte, zero-filled bytes (`.bss`) included.
+    pub end: u32,
+    /// Bytes copied from the file; the rest up to `end` is zeroed.
+    pub file_bytes: u32,
+    /// ELF `p_flags` (`PF_R`, `PF_W`, `PF_X`).
+    pub flags: u32,
+}
+
+/// Where an image ended up in the address space, for debugging its layout.
+/// `Display` prints one region per line, lowest address first, and marks
+/// segments that overlap the device windows or each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    /// The MMIO window of each device slot, slot 0 first.
+    pub slots: Vec<Range<u32>>,
+    pub segments: Vec<LoadedSegment>,
+    pub entry: u32,
+    /// Initial `sp`; the stack grows down from here towards the segments.
+    pub stack_pointer: u32,
+    pub dram_end: u32,
+}
+
+/// First address served by DRAM; everything below is device windows.
+const DRAM_BASE: u32 = 0x1000;
+
+impl std::fmt::Display for MemoryMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (slot, window) in self.slots.iter().enumerate() {
+            writeln!(
+                f,
+                "0x{:08x}-0x{:08x}  slot {slot}",
+                window.start,
+                window.end - 1
+            )?;
+        }
+        let mut segments = self.segments.clone();
+        segments.sort_by_key(|segment| segment.start);
+        let mut loaded_end = DRAM_BASE;
+        for (index, segment) in segments.iter().enumerate() {
+            let flag = |bit, c| if segment.flags & bit != 0 { c } else { '-' };
+            write!(
+                f,
+                "0x{:08x}-0x{:08x}  segment {}{}{} ({} bytes, {} from file)",
+                segment.start,
+                segment.end.saturating_sub(1),
+                flag(PF_R, 'r'),
+                flag(PF_W, 'w'),
+                flag(PF_X, 'x'),
+                segment.end - segment.start,
+                segment.file_bytes
+            )?;
+            if segment.start < DRAM_BASE {
+                write!(f, "  !! overlaps device slots")?;
+            }
+            if index > 0 && segment.start < segments[index - 1].end {
+                write!(f, "  !! overlaps previous segment")?;
+            }
+            writeln!(f)?;
+            loaded_end = loaded_end.max(segment.end);
+        }
+        if loaded_end < self.stack_pointer {
+            writeln!(
+                f,
+                "0x{loaded_end:08x}-0x{:08x}  free (stack grows down from sp)",
+                self.stack_pointer - 1
+            )?;
+        }
+        writeln!(f, "entry 0x{:08x}", self.entry)?;
+        writeln!(f, "sp    0x{:08x}", self.stack_pointer)?;
+        write!(f, "dram  ends at 0x{:08x}", self.dram_end)
+    }
 }
 
 #[allow(dead_code)]
@@ -741,39 +1153,101 @@ impl RamLike for Dram {
             _ => unreachable!(),
         }
     }
+
+    fn accessible(&self, addr: u32, size: u32) -> bool {
+        (addr as usize)
+            .checked_add(size as usize / 8)
+            .is_some_and(|end| end <= self.dram.len())
+    }
 }
 
 impl Dram {
-    /// Create a new `Dram` instance with default dram size.
-    pub fn new(code: &[u8]) -> (Dram, u32) {
-        let elf = ElfBytes::<LittleEndian>::minimal_parse(code).expect("failed to parse elf file");
+    /// Create a `dram_size`-byte `Dram` holding the ELF's loadable segments
+    /// and return it with the entry point, which must lie in an executable
+    /// segment.
+    pub fn new(code: &[u8], dram_size: u32) -> Result<(Dram, u32), LoadError> {
+        let elf = ElfBytes::<LittleEndian>::minimal_parse(code)
+            .map_err(|e| LoadError::Malformed(e.to_string()))?;
 
         let all_load_phdrs = elf
             .segments()
-            .unwrap()
+            .ok_or_else(|| LoadError::Malformed("no program headers".to_string()))?
             .iter()
             .filter(|phdr| phdr.p_type == PT_LOAD)
             .collect::<Vec<_>>();
 
         let max_load_end = all_load_phdrs
             .iter()
-            .map(|phdr| phdr.p_vaddr as u32 + phdr.p_memsz as u32)
+            .map(|phdr| phdr.p_vaddr.saturating_add(phdr.p_memsz))
             .max()
             .unwrap_or(0);
-        let dram_size = dram_size_for_loaded_end(max_load_end) as usize;
-        let mut mem = vec![0u8; dram_size];
+        let max_load_end = u32::try_from(max_load_end).unwrap_or(u32::MAX);
+        let required = dram_size_for_loaded_end(max_load_end);
+        if required > dram_size {
+            return Err(LoadError::TooLarge {
+                required,
+                dram_size,
+            });
+        }
+        let entry = elf.ehdr.e_entry;
+        if !all_load_phdrs.iter().any(|phdr| {
+            phdr.p_flags & PF_X != 0 && entry >= phdr.p_vaddr && entry - phdr.p_vaddr < phdr.p_memsz
+        }) {
+            return Err(LoadError::BadEntry {
+                entry: entry as u32,
+            });
+        }
+        let mut mem = vec![0u8; dram_size as usize];
+        let mut segments = Vec::with_capacity(all_load_phdrs.len());
 
         for phdr in all_load_phdrs {
             let vaddr = phdr.p_vaddr as usize;
             let offset = phdr.p_offset as usize;
             let filesz = phdr.p_filesz as usize;
-
-            mem[vaddr..vaddr + filesz].copy_from_slice(&code[offset..offset + filesz]);
+            if phdr.p_filesz > phdr.p_memsz {
+                return Err(LoadError::Malformed(format!(
+                    "segment at 0x{vaddr:x} has more file than memory bytes"
+                )));
+            }
+            let Some(bytes) = code.get(offset..offset.saturating_add(filesz)) else {
+                return Err(LoadError::Malformed(format!(
+                    "segment at 0x{vaddr:x} extends past the end of the file"
+                )));
+            };
+
+            mem[vaddr..vaddr + filesz].copy_from_slice(bytes);
+            segments.push(LoadedSegment {
+                start: vaddr as u32,
+                end: (phdr.p_vaddr + phdr.p_memsz) as u32,
+                file_bytes: filesz as u32,
+                flags: phdr.p_flags,
+            });
         }
 
-        let entry = elf.ehdr.e_entry as u32;
+        let entry = entry as u32;
         debug!("entry: {entry:x}");
-        (Self { dram: mem }, entry)
+        Ok((
+            Self {
+                dram: mem,
+                segments,
+            },
+            entry,
+        ))
+    }
+
+    /// The layout of this DRAM for a hart starting at `entry` with devices
+    /// in the first `slot_count` slots.
+    pub fn memory_map(&self, entry: u32, slot_count: usize) -> MemoryMap {
+        let dram_end = self.dram.len() as u32;
+        MemoryMap {
+            slots: (1..=slot_count as u32)
+                .map(|slot| slot << 8..(slot + 1) << 8)
+                .collect(),
+            segments: self.segments.clone(),
+            entry,
+            stack_pointer: crate::stack_pointer_for_dram_len(dram_end),
+            dram_end,
+        }
     }
 
     /// Load a byte from the little-endian dram.
@@ -861,6 +1335,20 @@ impl RamLike for Mmu<'_, '_, '_> {
             Err(())
         }
     }
+
+    /// DRAM from 0x1000 up, plus the whole 256-byte window of every slot this
+    /// hart has a device in. Registers a device rejects inside its window are
+    /// left to the device.
+    fn accessible(&self, addr: u32, size: u32) -> bool {
+        if addr >= 0x1000 {
+            self.dram.accessible(addr, size)
+        } else if addr >= 0x100 {
+            let device_index = ((addr >> 8) & 0xF) as usize - 1;
+            device_index < self.devices.len() && (addr & 0xFF) + size / 8 <= 0x100
+        } else {
+            false
+        }
+    }
 }
 
 pub trait Device: Send + Sync {
@@ -869,29 +1357,121 @@ pub trait Device: Send + Sync {
 }
 
 /// Memory-mapped log device that captures character output into a buffer.
-/// Characters are written as 32-bit values (Unicode code points).
-/// The buffer can be drained to retrieve accumulated output.
+/// Characters are written either as 32-bit values (Unicode code points) or
+/// byte by byte as UTF-8; a sequence split across byte writes is held back
+/// until it completes. Invalid code points and byte sequences show up as
+/// U+FFFD. The buffer can be drained to retrieve accumulated output.
+///
+/// Register layout (all 32-bit unless noted):
+/// - `0x00` data (write): a 32-bit write appends one code point, an 8-bit
+///   write one UTF-8 byte; ignored while the buffer is full
+/// - `0x04` written (read): total characters accepted, wrapping
+/// - `0x08` consumed (read): total characters drained by the host, wrapping
+/// - `0x0C` capacity (read): characters the buffer holds before refusing writes
+///
+/// A bot has `capacity - (written - consumed)` characters of room.
 #[derive(Component)]
 pub struct LogDevice {
     buffer: String,
+    /// Leading bytes of a UTF-8 sequence still waiting for the rest.
+    partial_utf8: Vec<u8>,
+    capacity: u32,
+    written: u32,
+    consumed: u32,
 }
 
+pub const LOG_DATA: u32 = 0x00;
+pub const LOG_WRITTEN: u32 = 0x04;
+pub const LOG_CONSUMED: u32 = 0x08;
+pub const LOG_CAPACITY: u32 = 0x0C;
+pub const DEFAULT_LOG_CAPACITY: u32 = 4096;
+
 impl LogDevice {
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_LOG_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: u32) -> Self {
         Self {
             buffer: String::new(),
+            partial_utf8: Vec::new(),
+            capacity,
+            written: 0,
+            consumed: 0,
         }
     }
 
+    /// Characters written but not yet drained.
+    pub fn pending(&self) -> u32 {
+        self.written.wrapping_sub(self.consumed)
+    }
+
+    /// Drain up to `max_chars` of the oldest output, advancing the consumed
+    /// cursor so the bot sees the freed room.
+    pub fn consume(&mut self, max_chars: usize) -> String {
+        let split = self
+            .buffer
+            .char_indices()
+            .nth(max_chars)
+            .map_or(self.buffer.len(), |(index, _)| index);
+        let rest = self.buffer.split_off(split);
+        let taken = std::mem::replace(&mut self.buffer, rest);
+        self.consumed = self.consumed.wrapping_add(taken.chars().count() as u32);
+        taken
+    }
+
     /// Drain all accumulated output, leaving the buffer empty.
     pub fn drain_output(&mut self) -> String {
-        std::mem::take(&mut self.buffer)
+        self.consume(usize::MAX)
     }
 
     /// Read all accumulated output without clearing.
     pub fn output(&self) -> &str {
         &self.buffer
     }
+
+    fn push_char(&mut self, ch: char) {
+        self.buffer.push(ch);
+        self.written = self.written.wrapping_add(1);
+    }
+
+    /// Feeds one byte of UTF-8 output. Complete characters are appended;
+    /// bytes that cannot start or continue a sequence become U+FFFD.
+    fn push_utf8_byte(&mut self, byte: u8) {
+        self.partial_utf8.push(byte);
+        while !self.partial_utf8.is_empty() {
+            match std::str::from_utf8(&self.partial_utf8) {
+                Ok(text) => {
+                    let ch = text.chars().next().expect("not empty");
+                    self.partial_utf8.clear();
+                    self.push_char(ch);
+                }
+                // Incomplete but valid so far: wait for the next byte.
+                Err(err) if err.error_len().is_none() => return,
+                Err(err) => {
+                    // `partial_utf8` never holds a complete character, so the
+                    // error is at the start.
+                    self.partial_utf8.drain(..err.error_len().unwrap_or(1));
+                    self.push_char(char::REPLACEMENT_CHARACTER);
+                }
+            }
+        }
+    }
+
+    /// Replaces a sequence interrupted by a code point write with U+FFFD.
+    fn abandon_partial_utf8(&mut self) {
+        if !self.partial_utf8.is_empty() {
+            self.partial_utf8.clear();
+            self.push_char(char::REPLACEMENT_CHARACTER);
+        }
+    }
+
+    /// Append host-side text, e.g. emulator diagnostics, to the buffer. It
+    /// bypasses the capacity but counts towards the cursors.
+    pub fn push_str(&mut self, text: &str) {
+        self.buffer.push_str(text);
+        self.written = self.written.wrapping_add(text.chars().count() as u32);
+    }
 }
 
 impl Default for LogDevice {
@@ -901,16 +1481,27 @@ impl Default for LogDevice {
 }
 
 impl Device for LogDevice {
-    fn load(&self, _addr: u32, _size: u32) -> Result<u32, ()> {
-        Ok(0)
+    fn load(&self, addr: u32, _size: u32) -> Result<u32, ()> {
+        Ok(match addr {
+            LOG_WRITTEN => self.written,
+            LOG_CONSUMED => self.consumed,
+            LOG_CAPACITY => self.capacity,
+            _ => 0,
+        })
     }
 
-    fn store(&mut self, _addr: u32, size: u32, value: u32) -> Result<(), ()> {
-        if size != 32 {
+    fn store(&mut self, addr: u32, size: u32, value: u32) -> Result<(), ()> {
+        if !matches!(size, 8 | 32) || addr != LOG_DATA {
             return Err(());
         }
-        if let Some(ch) = char::from_u32(value) {
-            self.buffer.push(ch);
+        if self.pending() >= self.capacity {
+            return Ok(());
+        }
+        if size == 8 {
+            self.push_utf8_byte(value as u8);
+        } else {
+            self.abandon_partial_utf8();
+            self.push_char(char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER));
         }
         Ok(())
     }
@@ -967,6 +1558,108 @@ mod tests {
             }
             Ok(())
         }
+
+        fn accessible(&self, addr: u32, size: u32) -> bool {
+            addr as usize + size as usize / 8 <= self.bytes.len()
+        }
+    }
+
+    #[test]
+    fn unimplemented_opcode_traps_with_offending_encoding() {
+        let mut h = Hart::new(0);
+        let mut ram = TestRam::new(1024);
+        h.pc = 0x40;
+
+        let (inst, len) = Instruction::parse_with_len(0x1234_b00b); // custom-0, funct3 3
+        let trap = h.execute(inst, len, &mut ram).unwrap_err();
+
+        assert_eq!(
+            trap,
+            Trap::IllegalInstruction {
+                raw: 0x1234_b00b,
+                pc: 0x40,
+                opcode: 0x0b,
+                funct3: 3,
+            }
+        );
+        assert_eq!(h.pc, 0x40);
+        assert_eq!(
+            trap.to_string(),
+            "unimplemented instruction 0x1234b00b at 0x00000040 (opcode 0x0b, funct3 3)"
+        );
+    }
+
+    /// xorshift32, so fuzz failures reproduce from the printed seed.
+    fn next_random(state: &mut u32) -> u32 {
+        *state ^= *state << 13;
+        *state ^= *state >> 17;
+        *state ^= *state << 5;
+        *state
+    }
+
+    #[test]
+    fn random_instruction_words_execute_or_trap() {
+        const RAM_SIZE: u32 = 0x1_0000;
+        const SEED: u32 = 0x5eed_1234;
+        const ITERATIONS: u32 = 200_000;
+
+        let mut rng = SEED;
+        let mut h = Hart::new(0);
+        let mut ram = TestRam::new(RAM_SIZE as usize);
+        for iteration in 0..ITERATIONS {
+            // Fresh registers now and then: mostly in-range addresses, some
+            // anywhere, so both accesses and faults get exercised.
+            if iteration % 64 == 0 {
+                for reg in 1..32 {
+                    let value = next_random(&mut rng);
+                    h.regs[reg] = if value & 1 == 0 {
+                        value % RAM_SIZE
+                    } else {
+                        value
+                    };
+                    h.fregs[reg] = next_random(&mut rng);
+                }
+            }
+            h.pc = (next_random(&mut rng) % RAM_SIZE) & !1;
+            let word = next_random(&mut rng);
+            let pc = h.pc;
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let (inst, len) = Instruction::parse_with_len(word);
+                h.execute(inst, len, &mut ram)
+            }));
+            let Ok(result) = result else {
+                panic!(
+                    "0x{word:08x} at 0x{pc:08x} panicked (seed 0x{SEED:08x}, iteration {iteration})"
+                );
+            };
+            if let Err(trap) = result {
+                assert_eq!(trap.pc(), pc, "0x{word:08x}: {trap}");
+                assert_eq!(h.pc, pc, "0x{word:08x} trapped but moved the pc");
+            }
+            assert_eq!(h.regs[0], 0, "0x{word:08x} wrote x0");
+        }
+    }
+
+    #[test]
+    fn out_of_range_load_traps_without_writing_rd() {
+        let mut h = Hart::new(0);
+        let mut ram = TestRam::new(1024);
+        h.pc = 0x40;
+        h.regs[1] = 2000;
+        h.regs[5] = 7;
+
+        let (inst, len) = Instruction::parse_with_len(0x0000_a283); // lw t0, 0(ra)
+        let trap = h.execute(inst, len, &mut ram).unwrap_err();
+
+        assert_eq!(
+            trap,
+            Trap::LoadAccessFault {
+                addr: 2000,
+                pc: 0x40
+            }
+        );
+        assert_eq!(h.pc, 0x40);
+        assert_eq!(h.regs[5], 7);
     }
 
     #[test]
@@ -985,7 +1678,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.regs[3], i32::MIN as u32);
 
         h.regs[2] = 0;
@@ -998,7 +1692,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.regs[4], u32::MAX);
     }
 
@@ -1021,7 +1716,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.regs[3], 10);
 
         h.execute(
@@ -1035,7 +1731,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.regs[4], 0);
         assert_eq!(ram.load(100, 32).unwrap(), 55);
 
@@ -1051,7 +1748,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         h.execute(
             Instruction::S {
                 funct: SFunct::SW,
@@ -1061,7 +1759,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         h.execute(
             Instruction::A {
                 funct: AFunct::ScW,
@@ -1073,7 +1772,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.regs[5], 1);
     }
 
@@ -1096,7 +1796,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.regs[4], 3);
         assert_eq!(ram.load(100, 32).unwrap(), 10);
     }
@@ -1108,7 +1809,7 @@ mod tests {
         h.pc = 100;
         h.regs[1] = 200;
         let (inst, len) = Instruction::parse_with_len(0x8082); // c.jr ra
-        h.execute(inst, len, &mut ram);
+        h.execute(inst, len, &mut ram).unwrap();
         assert_eq!(h.pc, 200);
     }
 
@@ -1120,7 +1821,7 @@ mod tests {
         h.fregs[1] = 0x3f80_0000;
 
         let fswsp = Instruction::parse_with_len(0xe206).0; // c.fswsp f1, 4(sp)
-        h.execute(fswsp, 2, &mut ram);
+        h.execute(fswsp, 2, &mut ram).unwrap();
         assert_eq!(ram.load(132, 32).unwrap(), 0x3f80_0000);
 
         h.fregs[1] = 0;
@@ -1134,8 +1835,9 @@ mod tests {
             },
             4,
             &mut ram,
-        );
-        h.execute(flwsp, 2, &mut ram);
+        )
+        .unwrap();
+        h.execute(flwsp, 2, &mut ram).unwrap();
         assert_eq!(h.fregs[1], 0x3f80_0000);
     }
 
@@ -1155,7 +1857,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(ram.load(200, 16).unwrap(), 0x5678);
 
         h.fregs[3] = 0;
@@ -1168,7 +1871,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.fregs[3], 0x5678);
 
         h.execute(
@@ -1180,7 +1884,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(ram.load(208, 32).unwrap(), 0x1234_5678);
         assert_eq!(ram.load(212, 32).unwrap(), u32::MAX);
 
@@ -1194,7 +1899,8 @@ mod tests {
             },
             4,
             &mut ram,
-        );
+        )
+        .unwrap();
         assert_eq!(h.fregs[4], 0x1234_5678);
     }
 
@@ -1211,7 +1917,7 @@ mod tests {
             } => {}
             _ => panic!("unexpected decode"),
         }
-        h.execute(inst, len, &mut ram);
+        h.execute(inst, len, &mut ram).unwrap();
         assert_eq!(h.regs[1], 6);
     }
 
@@ -1265,7 +1971,7 @@ mod tests {
     #[test]
     fn dram_size_for_loaded_end_has_headroom_and_alignment() {
         let min = dram_size_for_loaded_end(0x2000);
-        assert!(min >= DRAM_SIZE);
+        assert!(min <= DRAM_SIZE);
         assert_eq!(min & 0xf, 0);
 
         let large_end = 0x12345;
@@ -1273,4 +1979,67 @@ mod tests {
         assert!(sized >= large_end + STACK_HEADROOM);
         assert_eq!(sized & 0xf, 0);
     }
+
+    #[test]
+    fn log_device_reports_partial_consumption_and_refuses_overwrites() {
+        let mut log = LogDevice::with_capacity(8);
+        for ch in "abcdefghij".chars() {
+            log.store(LOG_DATA, 32, ch as u32).unwrap();
+        }
+        assert_eq!(log.output(), "abcdefgh");
+        assert_eq!(log.load(LOG_WRITTEN, 32), Ok(8));
+        assert_eq!(log.load(LOG_CONSUMED, 32), Ok(0));
+        assert_eq!(log.load(LOG_CAPACITY, 32), Ok(8));
+
+        assert_eq!(log.consume(3), "abc");
+        assert_eq!(log.load(LOG_CONSUMED, 32), Ok(3));
+        assert_eq!(log.pending(), 5);
+
+        for ch in "xyzw".chars() {
+            log.store(LOG_DATA, 32, ch as u32).unwrap();
+        }
+        assert_eq!(log.load(LOG_WRITTEN, 32), Ok(11));
+        assert_eq!(log.drain_output(), "defghxyz");
+        assert_eq!(log.load(LOG_CONSUMED, 32), Ok(11));
+        assert_eq!(log.store(LOG_WRITTEN, 32, 0), Err(()));
+    }
+
+    fn write_bytes(log: &mut LogDevice, bytes: &[u8]) {
+        for &byte in bytes {
+            log.store(LOG_DATA, 8, byte as u32).unwrap();
+        }
+    }
+
+    #[test]
+    fn log_device_reassembles_utf8_split_across_writes() {
+        let mut log = LogDevice::new();
+        let [first, rest @ ..] = "é€".as_bytes() else {
+            unreachable!()
+        };
+        write_bytes(&mut log, &[b'a', *first]);
+        // Half a character is held back, not shown or counted.
+        assert_eq!(log.output(), "a");
+        assert_eq!(log.load(LOG_WRITTEN, 32), Ok(1));
+
+        write_bytes(&mut log, rest);
+        assert_eq!(log.drain_output(), "aé€");
+        assert_eq!(log.load(LOG_WRITTEN, 32), Ok(3));
+    }
+
+    #[test]
+    fn log_device_replaces_invalid_utf8_and_code_points() {
+        let mut log = LogDevice::new();
+        // A stray continuation byte, a lead byte cut short by ASCII, and an
+        // overlong encoding of '/'.
+        write_bytes(&mut log, &[0x80, b'x', 0xe2, 0x82, b'y', 0xc0, 0xaf, b'z']);
+        assert_eq!(log.drain_output(), "\u{fffd}x\u{fffd}y\u{fffd}\u{fffd}z");
+
+        // A code point write interrupts a pending sequence; surrogates are
+        // not characters.
+        write_bytes(&mut log, &[0xf0, 0x9f]);
+        log.store(LOG_DATA, 32, 'k' as u32).unwrap();
+        log.store(LOG_DATA, 32, 0xd800).unwrap();
+        assert_eq!(log.drain_output(), "\u{fffd}k\u{fffd}");
+        assert_eq!(log.pending(), 0);
+    }
 }
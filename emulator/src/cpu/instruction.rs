@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Instruction {
     R {
         funct: RFunct,
@@ -87,10 +87,19 @@ pub enum Instruction {
         succ: u32,
         fm: u32,
     },
+    /// Environment call. The bot uses it to yield the rest of its
+    /// instruction budget for the current update.
+    Ecall,
     Ebreak,
+    /// A word this emulator cannot decode: an unimplemented major opcode or
+    /// a malformed encoding (reserved funct bits, illegal compressed forms).
+    /// `raw` holds only the low 16 bits of a compressed instruction.
+    Illegal {
+        raw: u32,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum RFunct {
     ADD,
     SUB,
@@ -104,7 +113,7 @@ pub enum RFunct {
     AND,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum MFunct {
     MUL,
     MULH,
@@ -116,7 +125,7 @@ pub enum MFunct {
     REMU,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum IFunct {
     JALR,
     LB,
@@ -134,13 +143,13 @@ pub enum IFunct {
     SRLI,
     SRAI,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum SFunct {
     SB,
     SH,
     SW,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum BFunct {
     BEQ,
     BNE,
@@ -150,18 +159,18 @@ pub enum BFunct {
     BGEU,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum UFunct {
     LUI,
     AUIPC,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum JFunct {
     JAL,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum R4Funct {
     FmaddS,
     FmsubS,
@@ -169,7 +178,7 @@ pub enum R4Funct {
     FnmaddS,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FRFunct {
     FaddS,
     FsubS,
@@ -185,7 +194,7 @@ pub enum FRFunct {
     FleS,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FIFunct {
     FsqrtS,
     FcvtWS,
@@ -197,21 +206,21 @@ pub enum FIFunct {
     FmvWX,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FLFunct {
     FLH,
     FLW,
     FLD,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FSFunct {
     FSH,
     FSW,
     FSD,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum AFunct {
     LrW,
     ScW,
@@ -226,7 +235,7 @@ pub enum AFunct {
     AmoMaxuW,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FenceFunct {
     Fence,
     FenceI,
@@ -271,7 +280,7 @@ impl Instruction {
                     0x2 => LW,
                     0x4 => LBU,
                     0x5 => LHU,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 Self::I {
                     funct,
@@ -284,7 +293,7 @@ impl Instruction {
                 let funct = match funct3 {
                     0x0 => FenceFunct::Fence,
                     0x1 => FenceFunct::FenceI,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 let pred = (inst >> 24) & 0xf;
                 let succ = (inst >> 20) & 0xf;
@@ -314,7 +323,7 @@ impl Instruction {
                     }
                     0x6 => ORI,
                     0x7 => ANDI,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 match funct {
                     SLLI | SRLI | SRAI => Self::I {
@@ -339,7 +348,7 @@ impl Instruction {
                     0x0 => SB,
                     0x1 => SH,
                     0x2 => SW,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 Self::S {
                     funct,
@@ -350,7 +359,7 @@ impl Instruction {
             }
             0x2f => {
                 if funct3 != 0x2 {
-                    panic!("invalid atomic funct3");
+                    return Self::Illegal { raw: inst };
                 }
                 let funct5 = (inst >> 27) & 0x1f;
                 let aq = cbit(inst, 26) == 1;
@@ -367,10 +376,10 @@ impl Instruction {
                     0x14 => AFunct::AmoMaxW,
                     0x18 => AFunct::AmoMinuW,
                     0x1c => AFunct::AmoMaxuW,
-                    _ => panic!("invalid atomic funct5"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 if matches!(funct, AFunct::LrW) && rs2 != 0 {
-                    panic!("invalid LR.W encoding");
+                    return Self::Illegal { raw: inst };
                 }
                 Self::A {
                     funct,
@@ -394,7 +403,7 @@ impl Instruction {
                         0x5 => DIVU,
                         0x6 => REM,
                         0x7 => REMU,
-                        _ => panic!("invalid M funct3"),
+                        _ => return Self::Illegal { raw: inst },
                     };
                     return Self::M {
                         funct,
@@ -426,7 +435,7 @@ impl Instruction {
                     }
                     0x6 => OR,
                     0x7 => AND,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 Self::R {
                     funct,
@@ -465,7 +474,7 @@ impl Instruction {
                     0x5 => BGE,
                     0x6 => BLTU,
                     0x7 => BGEU,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 Self::B {
                     funct,
@@ -489,7 +498,7 @@ impl Instruction {
                     0x1 => FLFunct::FLH,
                     0x2 => FLFunct::FLW,
                     0x3 => FLFunct::FLD,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 Self::FL {
                     funct,
@@ -504,7 +513,7 @@ impl Instruction {
                     0x1 => FSFunct::FSH,
                     0x2 => FSFunct::FSW,
                     0x3 => FSFunct::FSD,
-                    _ => panic!("invalid funct3"),
+                    _ => return Self::Illegal { raw: inst },
                 };
                 Self::FS {
                     funct,
@@ -517,7 +526,7 @@ impl Instruction {
                 let rs3 = ((inst >> 27) & 0x1f) as usize;
                 let fmt = (inst >> 25) & 0x3;
                 if fmt != 0 {
-                    panic!("only single-precision format is supported");
+                    return Self::Illegal { raw: inst };
                 }
                 let rm = funct3;
                 let funct = match opcode {
@@ -573,7 +582,7 @@ impl Instruction {
                             0x0 => FRFunct::FsgnjS,
                             0x1 => FRFunct::FsgnjnS,
                             0x2 => FRFunct::FsgnjxS,
-                            _ => panic!("invalid funct3"),
+                            _ => return Self::Illegal { raw: inst },
                         };
                         Self::FR {
                             funct,
@@ -587,7 +596,7 @@ impl Instruction {
                         let funct = match rm {
                             0x0 => FRFunct::FminS,
                             0x1 => FRFunct::FmaxS,
-                            _ => panic!("invalid funct3"),
+                            _ => return Self::Illegal { raw: inst },
                         };
                         Self::FR {
                             funct,
@@ -608,7 +617,7 @@ impl Instruction {
                             0x2 => FRFunct::FeqS,
                             0x1 => FRFunct::FltS,
                             0x0 => FRFunct::FleS,
-                            _ => panic!("invalid funct3"),
+                            _ => return Self::Illegal { raw: inst },
                         };
                         Self::FR {
                             funct,
@@ -622,7 +631,7 @@ impl Instruction {
                         let funct = match rs2 {
                             0x0 => FIFunct::FcvtWS,
                             0x1 => FIFunct::FcvtWuS,
-                            _ => panic!("invalid rs2"),
+                            _ => return Self::Illegal { raw: inst },
                         };
                         Self::FI { funct, rd, rs1, rm }
                     }
@@ -630,7 +639,7 @@ impl Instruction {
                         let funct = match rs2 {
                             0x0 => FIFunct::FcvtSW,
                             0x1 => FIFunct::FcvtSWU,
-                            _ => panic!("invalid rs2"),
+                            _ => return Self::Illegal { raw: inst },
                         };
                         Self::FI { funct, rd, rs1, rm }
                     }
@@ -638,7 +647,7 @@ impl Instruction {
                         let funct = match rm {
                             0x0 => FIFunct::FmvXW,
                             0x1 => FIFunct::FclassS,
-                            _ => panic!("invalid funct3"),
+                            _ => return Self::Illegal { raw: inst },
                         };
                         Self::FI { funct, rd, rs1, rm }
                     }
@@ -648,7 +657,7 @@ impl Instruction {
                         rs1,
                         rm,
                     },
-                    _ => panic!("invalid funct7"),
+                    _ => Self::Illegal { raw: inst },
                 }
             }
             0x6f => {
@@ -664,10 +673,13 @@ impl Instruction {
                     imm: sign_extend(imm_u, 21),
                 }
             }
-            _ => {
-                dbg!("opcode not implemented yet", opcode);
-                panic!()
-            }
+            // CSR access and the other privileged forms are not implemented.
+            0x73 => match inst {
+                0x0000_0073 => Self::Ecall,
+                0x0010_0073 => Self::Ebreak,
+                _ => Self::Illegal { raw: inst },
+            },
+            _ => Self::Illegal { raw: inst },
         }
     }
 
@@ -684,7 +696,7 @@ impl Instruction {
                         | ((inst >> 11) & 0x3) << 4
                         | ((inst >> 7) & 0xf) << 6;
                     if nzuimm == 0 {
-                        panic!("illegal C.ADDI4SPN");
+                        return Self::Illegal { raw: inst };
                     }
                     let rd = 8 + ((inst >> 2) & 0x7) as usize;
                     Self::I {
@@ -750,7 +762,7 @@ impl Instruction {
                         imm: uimm as i32,
                     }
                 }
-                _ => panic!("illegal compressed instruction (quadrant 0)"),
+                _ => Self::Illegal { raw: inst },
             },
             0b01 => match funct3 {
                 0b000 => {
@@ -794,7 +806,7 @@ impl Instruction {
                             | (((inst >> 3) & 0x3) << 7)
                             | (cbit(inst, 2) << 5);
                         if nzimm == 0 {
-                            panic!("illegal C.ADDI16SP");
+                            return Self::Illegal { raw: inst };
                         }
                         Self::I {
                             funct: IFunct::ADDI,
@@ -806,7 +818,7 @@ impl Instruction {
                         // C.LUI
                         let imm6 = ((inst >> 2) & 0x1f) | (cbit(inst, 12) << 5);
                         if rd == 0 || rd == 2 || imm6 == 0 {
-                            panic!("illegal C.LUI");
+                            return Self::Illegal { raw: inst };
                         }
                         Self::U {
                             funct: UFunct::LUI,
@@ -822,7 +834,7 @@ impl Instruction {
                         0b00 => {
                             // C.SRLI
                             if cbit(inst, 12) == 1 {
-                                panic!("illegal C.SRLI for RV32");
+                                return Self::Illegal { raw: inst };
                             }
                             let shamt = (((inst >> 12) & 0x1) << 5) | ((inst >> 2) & 0x1f);
                             Self::I {
@@ -835,7 +847,7 @@ impl Instruction {
                         0b01 => {
                             // C.SRAI
                             if cbit(inst, 12) == 1 {
-                                panic!("illegal C.SRAI for RV32");
+                                return Self::Illegal { raw: inst };
                             }
                             let shamt = (((inst >> 12) & 0x1) << 5) | ((inst >> 2) & 0x1f);
                             Self::I {
@@ -859,7 +871,7 @@ impl Instruction {
                             // C.SUB/C.XOR/C.OR/C.AND
                             if cbit(inst, 12) == 1 {
                                 // RV64C uses this space for C.SUBW/C.ADDW.
-                                panic!("illegal RV32C ALU op");
+                                return Self::Illegal { raw: inst };
                             }
                             let rs2 = 8 + ((inst >> 2) & 0x7) as usize;
                             let funct2 = (inst >> 5) & 0x3;
@@ -909,7 +921,7 @@ impl Instruction {
                         imm: decode_cb_imm(inst),
                     }
                 }
-                _ => panic!("illegal compressed instruction (quadrant 1)"),
+                _ => Self::Illegal { raw: inst },
             },
             0b10 => match funct3 {
                 0b000 => {
@@ -917,7 +929,7 @@ impl Instruction {
                     let rd = ((inst >> 7) & 0x1f) as usize;
                     let shamt = (((inst >> 12) & 0x1) << 5) | ((inst >> 2) & 0x1f);
                     if rd == 0 || cbit(inst, 12) == 1 {
-                        panic!("illegal C.SLLI");
+                        return Self::Illegal { raw: inst };
                     }
                     Self::I {
                         funct: IFunct::SLLI,
@@ -930,7 +942,7 @@ impl Instruction {
                     // C.LWSP
                     let rd = ((inst >> 7) & 0x1f) as usize;
                     if rd == 0 {
-                        panic!("illegal C.LWSP");
+                        return Self::Illegal { raw: inst };
                     }
                     let uimm = ((inst >> 4) & 0x7) << 2
                         | ((inst >> 12) & 0x1) << 5
@@ -946,7 +958,7 @@ impl Instruction {
                     // C.FLWSP
                     let rd = ((inst >> 7) & 0x1f) as usize;
                     if rd == 0 {
-                        panic!("illegal C.FLWSP");
+                        return Self::Illegal { raw: inst };
                     }
                     let uimm = ((inst >> 4) & 0x7) << 2
                         | ((inst >> 12) & 0x1) << 5
@@ -966,7 +978,7 @@ impl Instruction {
                         if rs2 == 0 {
                             // C.JR
                             if rd == 0 {
-                                panic!("illegal C.JR");
+                                return Self::Illegal { raw: inst };
                             }
                             Self::I {
                                 funct: IFunct::JALR,
@@ -977,7 +989,7 @@ impl Instruction {
                         } else {
                             // C.MV
                             if rd == 0 {
-                                panic!("illegal C.MV");
+                                return Self::Illegal { raw: inst };
                             }
                             Self::R {
                                 funct: RFunct::ADD,
@@ -991,7 +1003,7 @@ impl Instruction {
                     } else if rs2 == 0 {
                         // C.JALR
                         if rd == 0 {
-                            panic!("illegal C.JALR");
+                            return Self::Illegal { raw: inst };
                         }
                         Self::I {
                             funct: IFunct::JALR,
@@ -1002,7 +1014,7 @@ impl Instruction {
                     } else {
                         // C.ADD
                         if rd == 0 {
-                            panic!("illegal C.ADD");
+                            return Self::Illegal { raw: inst };
                         }
                         Self::R {
                             funct: RFunct::ADD,
@@ -1034,9 +1046,9 @@ impl Instruction {
                         imm: uimm as i32,
                     }
                 }
-                _ => panic!("illegal compressed instruction (quadrant 2)"),
+                _ => Self::Illegal { raw: inst },
             },
-            _ => panic!("illegal compressed quadrant"),
+            _ => Self::Illegal { raw: inst },
         }
     }
 }
@@ -0,0 +1,101 @@
+use crate::cpu::{Instruction, RamLike};
+
+/// Entries in a [`DecodeCache`]; a power of two so the index is a mask.
+pub const DECODE_CACHE_ENTRIES: usize = 4096;
+
+/// Lowest address backed by DRAM. Device registers below it can change
+/// without a store from the hart, so code there is never cached.
+const DRAM_START: u32 = 0x1000;
+
+/// Direct-mapped cache of fetched and decoded instructions, keyed by address.
+///
+/// Hot loops re-execute the same few addresses, so a hit skips both the
+/// memory fetch and [`Instruction::parse_with_len`]. Stores made through
+/// [`InvalidatingRam`] drop every entry they overlap, so code that is
+/// overwritten at runtime is fetched again and execution stays identical to
+/// decoding every instruction.
+pub struct DecodeCache {
+    entries: Vec<Option<Entry>>,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    pc: u32,
+    raw: u32,
+    decoded: Instruction,
+    len: u32,
+}
+
+impl Default for DecodeCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecodeCache {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![None; DECODE_CACHE_ENTRIES],
+        }
+    }
+
+    fn index(pc: u32) -> usize {
+        (pc >> 1) as usize & (DECODE_CACHE_ENTRIES - 1)
+    }
+
+    /// The raw encoding, decoded instruction and its length at `pc`, if
+    /// cached.
+    pub fn get(&self, pc: u32) -> Option<(u32, Instruction, u32)> {
+        self.entries[Self::index(pc)]
+            .filter(|entry| entry.pc == pc)
+            .map(|entry| (entry.raw, entry.decoded, entry.len))
+    }
+
+    /// Remember the instruction fetched from `pc`. Addresses outside DRAM
+    /// are ignored.
+    pub fn insert(&mut self, pc: u32, raw: u32, decoded: Instruction, len: u32) {
+        if pc < DRAM_START {
+            return;
+        }
+        self.entries[Self::index(pc)] = Some(Entry {
+            pc,
+            raw,
+            decoded,
+            len,
+        });
+    }
+
+    /// Drop entries for instructions overlapping the `width` bytes at
+    /// `addr`. Instructions are 2-byte aligned and at most 4 bytes long.
+    pub fn invalidate(&mut self, addr: u32, width: u32) {
+        let first = (addr.saturating_sub(3) + 1) & !1;
+        let end = addr.saturating_add(width);
+        for pc in (first..end).step_by(2) {
+            let slot = &mut self.entries[Self::index(pc)];
+            if slot.is_some_and(|entry| entry.pc == pc) {
+                *slot = None;
+            }
+        }
+    }
+}
+
+/// Memory wrapper that keeps a [`DecodeCache`] coherent with stores.
+pub struct InvalidatingRam<'ram, 'cache, R> {
+    pub ram: &'ram mut R,
+    pub cache: &'cache mut DecodeCache,
+}
+
+impl<R: RamLike> RamLike for InvalidatingRam<'_, '_, R> {
+    fn load(&self, addr: u32, size: u32) -> Result<u32, ()> {
+        self.ram.load(addr, size)
+    }
+
+    fn store(&mut self, addr: u32, size: u32, value: u32) -> Result<(), ()> {
+        self.cache.invalidate(addr, size / 8);
+        self.ram.store(addr, size, value)
+    }
+
+    fn accessible(&self, addr: u32, size: u32) -> bool {
+        self.ram.accessible(addr, size)
+    }
+}
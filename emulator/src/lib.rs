@@ -1,10 +1,29 @@
-use cpu::{Dram, Hart};
+use cpu::{DRAM_SIZE, Dram, Hart, LoadError, section_u32};
+use tracing::warn;
 
 pub mod bevy;
+pub mod coverage;
 pub mod cpu;
+pub mod decode_cache;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_elf;
 
-#[derive(Default)]
-pub struct CpuBuilder {}
+/// Sets up a hart and its DRAM for an ELF image.
+pub struct CpuBuilder {
+    dram_size: u32,
+    abi: Option<(&'static str, u32)>,
+    initial_regs: Vec<(usize, u32)>,
+}
+
+impl Default for CpuBuilder {
+    fn default() -> Self {
+        Self {
+            dram_size: DRAM_SIZE,
+            abi: None,
+            initial_regs: Vec::new(),
+        }
+    }
+}
 
 fn stack_pointer_for_dram_len(dram_len: u32) -> u32 {
     let stack_top = dram_len & !0xf;
@@ -12,17 +31,401 @@ fn stack_pointer_for_dram_len(dram_len: u32) -> u32 {
 }
 
 impl CpuBuilder {
-    pub fn build(self, elf: &[u8]) -> (Hart, Dram) {
-        let (dram, entry) = Dram::new(elf);
+    /// DRAM to allocate in bytes, defaulting to [`DRAM_SIZE`]. The image's
+    /// loaded segments plus `STACK_HEADROOM` must fit.
+    pub fn dram_size(mut self, bytes: u32) -> Self {
+        self.dram_size = bytes;
+        self
+    }
+
+    /// Refuse images whose `section` records an ABI version other than
+    /// `version` (a little-endian `u32`). Images without the section predate
+    /// versioning and are loaded with a warning.
+    pub fn abi_version(mut self, section: &'static str, version: u32) -> Self {
+        self.abi = Some((section, version));
+        self
+    }
+
+    /// Registers to set once the hart is reset, e.g. an argument in `a0`
+    /// (`x10`) to run one routine in isolation. They are applied after `sp`
+    /// is pointed at the top of the stack, so an `x2` entry replaces that
+    /// stack pointer; `x0` stays hardwired to zero.
+    ///
+    /// Panics if a register index is 32 or more.
+    pub fn initial_regs(mut self, regs: &[(usize, u32)]) -> Self {
+        assert!(
+            regs.iter().all(|&(reg, _)| reg < 32),
+            "register index out of range"
+        );
+        self.initial_regs = regs.to_vec();
+        self
+    }
+
+    pub fn build(self, elf: &[u8]) -> Result<(Hart, Dram), LoadError> {
+        if let Some((section, expected)) = self.abi {
+            match section_u32(elf, section)? {
+                Some(found) if found != expected => {
+                    return Err(LoadError::IncompatibleAbi { expected, found });
+                }
+                Some(_) => {}
+                None => warn!("image has no {section} section, assuming ABI version {expected}"),
+            }
+        }
+        let (dram, entry) = Dram::new(elf, self.dram_size)?;
         let mut hart = Hart::new(entry);
         hart.regs[2] = stack_pointer_for_dram_len(dram.dram.len() as u32);
-        (hart, dram)
+        for &(reg, value) in self.initial_regs.iter().filter(|&&(reg, _)| reg != 0) {
+            hart.regs[reg] = value;
+        }
+        Ok((hart, dram))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::stack_pointer_for_dram_len;
+    use super::*;
+    use crate::bevy::CpuComponent;
+    use crate::cpu::{Device, LoadedSegment, STACK_HEADROOM, Trap};
+    use crate::test_elf::{elf_with_code, elf_with_load, elf_with_segment, with_section};
+
+    #[test]
+    fn incompatible_abi_is_refused_at_load() {
+        let built_for = |version: u32| {
+            with_section(elf_with_code(&[0x00000013]), ".abi", &version.to_le_bytes())
+        };
+        let host = || CpuBuilder::default().abi_version(".abi", 2);
+
+        assert_eq!(
+            host().build(&built_for(1)).unwrap_err(),
+            LoadError::IncompatibleAbi {
+                expected: 2,
+                found: 1
+            }
+        );
+        assert!(host().build(&built_for(2)).is_ok());
+        // Images from before the ABI was recorded still load.
+        assert!(host().build(&elf_with_code(&[0x00000013])).is_ok());
+    }
+
+    #[test]
+    fn build_id_section_reads_without_padding() {
+        let elf = with_section(elf_with_code(&[0x00000013]), ".id", b"1a2b3c\0\0");
+        assert_eq!(
+            cpu::section_str(&elf, ".id").unwrap().as_deref(),
+            Some("1a2b3c")
+        );
+        assert_eq!(cpu::section_str(&elf, ".other").unwrap(), None);
+    }
+
+    #[test]
+    fn referenced_slots_come_from_device_address_constants() {
+        let elf = elf_with_code(&[
+            0x3000_0513, // addi a0, x0, 0x300
+            0x0001_4595, // c.li a1, 5; c.nop
+            0x1040_0613, // addi a2, x0, 0x104
+            0x2005_0693, // addi a3, a0, 0x200 (not from x0)
+            0x0000_006f, // j .
+        ]);
+        assert_eq!(
+            cpu::referenced_slots(&elf)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(cpu::defines_symbol(&elf, "main").unwrap(), None);
+    }
+
+    /// Remembers the last word stored to it.
+    struct LatchDevice(u32);
+
+    impl Device for LatchDevice {
+        fn load(&self, _addr: u32, _size: u32) -> Result<u32, ()> {
+            Ok(self.0)
+        }
+
+        fn store(&mut self, _addr: u32, _size: u32, value: u32) -> Result<(), ()> {
+            self.0 = value;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run_n_cycles_executes_exactly_n_instructions() {
+        let elf = elf_with_code(&[
+            0x10000513, // addi a0, zero, 0x100
+            0x00128293, // loop: addi t0, t0, 1
+            0x00552023, // sw t0, 0(a0)
+            0xff9ff06f, // j loop
+        ]);
+        let mut cpu = CpuComponent::new(&elf, 1).expect("image loads");
+        let mut latch = LatchDevice(0);
+
+        cpu.run_n_cycles(1 + 3 * 10, &mut [&mut latch])
+            .expect("loop does not trap");
+        assert_eq!(latch.0, 10);
+
+        // Two more reach the next store but not the jump.
+        cpu.run_n_cycles(2, &mut [&mut latch])
+            .expect("loop does not trap");
+        assert_eq!(latch.0, 11);
+        assert_eq!(cpu.trap(), None);
+    }
+
+    #[test]
+    fn built_cpu_starts_with_the_initial_registers() {
+        let elf = elf_with_code(&[
+            0x00a5a023, // sw a0, 0(a1)
+            0x0000006f, // j .
+        ]);
+        let builder = || CpuBuilder::default().initial_regs(&[(10, 42), (11, 0x100), (0, 7)]);
+
+        let (hart, dram) = builder().build(&elf).expect("image loads");
+        assert_eq!(hart.regs[10], 42);
+        assert_eq!(hart.regs[11], 0x100);
+        assert_eq!(hart.regs[0], 0);
+        assert_eq!(
+            hart.regs[2],
+            stack_pointer_for_dram_len(dram.dram.len() as u32)
+        );
+        let (hart, _) = CpuBuilder::default()
+            .initial_regs(&[(2, 0x800)])
+            .build(&elf)
+            .expect("image loads");
+        assert_eq!(hart.regs[2], 0x800);
+
+        // `reset` brings them back too.
+        let mut cpu = CpuComponent::with_builder(builder(), &elf, 1).expect("image loads");
+        let mut latch = LatchDevice(0);
+        cpu.run_n_cycles(1, &mut [&mut latch])
+            .expect("store succeeds");
+        assert_eq!(latch.0, 42);
+        latch.0 = 0;
+        cpu.reset();
+        cpu.run_n_cycles(1, &mut [&mut latch])
+            .expect("store succeeds");
+        assert_eq!(latch.0, 42);
+    }
+
+    /// The trap `code` runs into, with and without the decode cache.
+    fn trap_of(code: &[u32]) -> Trap {
+        let elf = elf_with_code(code);
+        let traps = [true, false].map(|cached| {
+            let mut cpu = CpuComponent::new(&elf, 1).expect("image loads");
+            cpu.set_decode_cache(cached);
+            cpu.run_n_cycles(100, &mut []).expect_err("code traps")
+        });
+        assert_eq!(traps[0], traps[1]);
+        traps[0]
+    }
+
+    #[test]
+    fn accesses_near_the_top_of_the_address_space_trap_cleanly() {
+        assert_eq!(
+            trap_of(&[
+                0xfff00513, // addi a0, zero, -1
+                0x00052583, // lw a1, 0(a0)
+            ]),
+            Trap::LoadAccessFault {
+                addr: u32::MAX,
+                pc: 0x1004
+            }
+        );
+        assert_eq!(
+            trap_of(&[
+                0xffc00513, // addi a0, zero, -4
+                0x00b52023, // sw a1, 0(a0)
+            ]),
+            Trap::StoreAccessFault {
+                addr: 0xffff_fffc,
+                pc: 0x1004
+            }
+        );
+        assert_eq!(
+            trap_of(&[
+                0xffc00513, // addi a0, zero, -4
+                0x00053007, // fld ft0, 0(a0): 8 bytes from 4 below the top
+            ]),
+            Trap::LoadAccessFault {
+                addr: 0xffff_fffc,
+                pc: 0x1004
+            }
+        );
+        assert_eq!(
+            trap_of(&[0xffe00067]), // jalr zero, -2(zero)
+            Trap::InstructionAccessFault { pc: 0xffff_fffe }
+        );
+    }
+
+    #[test]
+    fn compressed_instruction_at_the_end_of_dram_runs_then_fetch_traps() {
+        assert_eq!(
+            trap_of(&[
+                0x00100537, // lui a0, 0x100 (DRAM_SIZE)
+                0x00100593, // addi a1, zero, 1 (c.nop)
+                0xfeb51f23, // sh a1, -2(a0)
+                0xffe50067, // jalr zero, -2(a0)
+            ]),
+            Trap::InstructionAccessFault { pc: DRAM_SIZE }
+        );
+    }
+
+    #[test]
+    fn ecall_yields_the_budget_and_resumes_on_the_next_run() {
+        let elf = elf_with_code(&[
+            0x10000513, // addi a0, zero, 0x100
+            0x00128293, // loop: addi t0, t0, 1
+            0x00552023, // sw t0, 0(a0)
+            0x00000073, // ecall
+            0xff5ff06f, // j loop
+        ]);
+        let mut cpu = CpuComponent::new(&elf, 1).expect("image loads");
+        let mut latch = LatchDevice(0);
+
+        for tick in 1..=3 {
+            cpu.run_n_cycles(1000, &mut [&mut latch])
+                .expect("loop does not trap");
+            // One pass per run despite the budget, counting on from the last.
+            assert_eq!(latch.0, tick);
+            assert_eq!(cpu.exhausted_runs(), 0);
+        }
+        assert_eq!(cpu.trap(), None);
+        // The first run also sets up a0; later ones loop from the jump.
+        assert_eq!(cpu.last_run_cycles(), 4);
+    }
+
+    #[test]
+    fn runs_without_a_yield_are_capped_at_the_budget_and_counted() {
+        let elf = elf_with_code(&[
+            0x00128293, // loop: addi t0, t0, 1
+            0xffdff06f, // j loop
+        ]);
+        let mut cpu = CpuComponent::new(&elf, 1).expect("image loads");
+
+        for run in 1..=3 {
+            cpu.run_n_cycles(500, &mut []).expect("loop does not trap");
+            assert_eq!(cpu.last_run_cycles(), 500);
+            assert_eq!(cpu.exhausted_runs(), run);
+        }
+
+        cpu.reset();
+        assert_eq!(cpu.exhausted_runs(), 0);
+    }
+
+    #[test]
+    fn run_until_store_stops_at_the_first_store_into_the_slot() {
+        let elf = elf_with_code(&[
+            0x10000513, // addi a0, zero, 0x100
+            0x20000593, // addi a1, zero, 0x200
+            0x00a00293, // addi t0, zero, 10
+            0xfff28293, // loop: addi t0, t0, -1
+            0x00552023, // sw t0, 0(a0) (slot 1, not watched)
+            0xfe029ce3, // bnez t0, loop
+            0x00b5a023, // sw a1, 0(a1) (slot 2)
+            0x0000006f, // j .
+        ]);
+        let mut cpu = CpuComponent::new(&elf, 1).expect("image loads");
+        let mut log = LatchDevice(0);
+        let mut controls = LatchDevice(0);
+
+        let cycles = cpu
+            .run_until_store(2, 1000, &mut [&mut log, &mut controls])
+            .expect("loop does not trap");
+        assert_eq!(cycles, Some(3 + 3 * 10 + 1));
+        assert_eq!(controls.0, 0x200);
+
+        // The CPU never stores to slot 2 again.
+        assert_eq!(
+            cpu.run_until_store(2, 100, &mut [&mut log, &mut controls]),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn larger_dram_accepts_image_the_default_rejects() {
+        let elf = elf_with_segment(DRAM_SIZE);
+
+        let Err(error) = CpuBuilder::default().build(&elf) else {
+            panic!("default DRAM should not fit the image");
+        };
+        assert_eq!(
+            error,
+            LoadError::TooLarge {
+                required: DRAM_SIZE + STACK_HEADROOM,
+                dram_size: DRAM_SIZE,
+            }
+        );
+
+        let (hart, dram) = CpuBuilder::default()
+            .dram_size(DRAM_SIZE * 2)
+            .build(&elf)
+            .expect("larger DRAM fits the image");
+        assert_eq!(dram.dram.len(), (DRAM_SIZE * 2) as usize);
+        assert_eq!(hart.regs[2], DRAM_SIZE * 2 - 16);
+    }
+
+    #[test]
+    fn memory_map_reports_segments_entry_stack_and_slots() {
+        let code = [0x0000_006fu32.to_le_bytes(), [0; 4]].concat();
+        let elf = elf_with_load(0x1000, &code, 0x40, 5);
+        let (hart, dram) = CpuBuilder::default().build(&elf).expect("load image");
+
+        let map = dram.memory_map(hart.pc, 2);
+        assert_eq!(
+            map.segments,
+            vec![LoadedSegment {
+                start: 0x1000,
+                end: 0x1040,
+                file_bytes: 8,
+                flags: 5,
+            }]
+        );
+        assert_eq!(map.entry, 0x1000);
+        assert_eq!(map.stack_pointer, hart.regs[2]);
+        assert_eq!(map.dram_end, DRAM_SIZE);
+        assert_eq!(map.slots, vec![0x100..0x200, 0x200..0x300]);
+        let rendered = map.to_string();
+        assert!(
+            rendered.contains("0x00001000-0x0000103f  segment r-x (64 bytes, 8 from file)"),
+            "{rendered}"
+        );
+        assert!(!rendered.contains("overlaps"), "{rendered}");
+
+        // A segment linked below DRAM lands under the device windows.
+        let low = elf_with_load(0x800, &code, 8, 5);
+        let (hart, dram) = CpuBuilder::default().build(&low).expect("load image");
+        let rendered = dram.memory_map(hart.pc, 1).to_string();
+        assert!(rendered.contains("overlaps device slots"), "{rendered}");
+    }
+
+    #[test]
+    fn entry_outside_executable_segments_is_rejected() {
+        let code = 0x0000_006fu32.to_le_bytes();
+        let mut elf = elf_with_code(&[0x0000_006f]);
+        elf[24..28].copy_from_slice(&0x8000u32.to_le_bytes()); // e_entry
+        assert_eq!(
+            CpuBuilder::default().build(&elf).err(),
+            Some(LoadError::BadEntry { entry: 0x8000 })
+        );
+
+        let data_only = elf_with_load(0x1000, &code, code.len() as u32, 6);
+        assert_eq!(
+            CpuBuilder::default().build(&data_only).err(),
+            Some(LoadError::BadEntry { entry: 0x1000 })
+        );
+        assert_eq!(
+            LoadError::BadEntry { entry: 0x8000 }.to_string(),
+            "entry point 0x8000 is not inside a loaded executable segment"
+        );
+    }
+
+    #[test]
+    fn malformed_image_is_an_error() {
+        assert!(matches!(
+            CpuBuilder::default().build(b"not an elf"),
+            Err(LoadError::Malformed(_))
+        ));
+    }
 
     #[test]
     fn stack_pointer_for_dram_len_is_16_byte_aligned() {
@@ -1,35 +1,102 @@
 use emulator::CpuBuilder;
-use emulator::cpu::{Device, Dram, Hart, Instruction, LogDevice, Mmu};
+use emulator::coverage::InstructionCoverage;
+use emulator::cpu::{Device, Dram, Hart, Instruction, LogDevice, Mmu, section_str};
 use std::env;
 use std::fs;
 
+/// Same name as `botracers_protocol::BOT_BUILD_ID_SECTION`.
+const BUILD_ID_SECTION: &str = ".botracers.build_id";
+
+const USAGE: &str = "Usage: emulator [--coverage] [--memory-map] [--max-instructions <n>] [--dram-size <bytes>] <filename>";
+
 fn main() {
     //tracing_subscriber::FmtSubscriber::builder()
     //    .with_max_level(LevelFilter::DEBUG)
     //    .init();
 
-    let args: Vec<String> = env::args().collect();
+    let mut coverage = false;
+    let mut memory_map = false;
+    let mut max_instructions = None;
+    let mut builder = CpuBuilder::default();
+    let mut filename = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--coverage" => coverage = true,
+            "--memory-map" => memory_map = true,
+            "--max-instructions" => {
+                let value = args.next().expect(USAGE);
+                max_instructions = Some(value.parse::<u64>().expect(USAGE));
+            }
+            "--dram-size" => {
+                let value = args.next().expect(USAGE);
+                builder = builder.dram_size(value.parse::<u32>().expect(USAGE));
+            }
+            _ if filename.is_none() => filename = Some(arg),
+            _ => panic!("{USAGE}"),
+        }
+    }
+
+    let Some(filename) = filename else {
+        panic!("{USAGE}");
+    };
+    let code = fs::read(&filename).unwrap();
+    if let Ok(Some(build_id)) = section_str(&code, BUILD_ID_SECTION) {
+        eprintln!("{filename}: build {build_id}");
+    }
+    let (cpu, dram) = match builder.build(&code) {
+        Ok(cpu) => cpu,
+        Err(error) => {
+            eprintln!("{filename}: {error}");
+            std::process::exit(1);
+        }
+    };
 
-    if args.len() != 2 {
-        panic!("Usage: emulator <filename>");
+    if memory_map {
+        // `run_plain` maps the log device alone, into slot 0.
+        eprintln!("{}", dram.memory_map(cpu.pc, 1));
     }
-    let code = fs::read(&args[1]).unwrap();
-    let (cpu, dram) = CpuBuilder::default().build(&code);
 
-    run_plain(cpu, dram);
+    let mut coverage = coverage.then(InstructionCoverage::new);
+    run_plain(cpu, dram, max_instructions, coverage.as_mut());
+
+    if let Some(coverage) = coverage {
+        eprint!("{}", coverage.report());
+    }
 }
 
-fn run_plain(mut cpu: Hart, mut dram: Dram) {
-    let mut log = LogDevice::new();
+fn run_plain(
+    mut cpu: Hart,
+    mut dram: Dram,
+    max_instructions: Option<u64>,
+    mut coverage: Option<&mut InstructionCoverage>,
+) {
+    // Nothing drains the log here, so never make the bot wait for room.
+    let mut log = LogDevice::with_capacity(u32::MAX);
     let mut devices: Vec<&mut dyn Device> = vec![&mut log];
     let mut mmu = Mmu::new(&mut dram, &mut devices);
-    loop {
+    let mut executed = 0u64;
+    while max_instructions.is_none_or(|max| executed < max) {
         // 1. Fetch.
-        let inst = cpu.fetch(&mmu);
+        let inst = match cpu.fetch(&mmu) {
+            Ok(inst) => inst,
+            Err(trap) => {
+                eprintln!("halted after {executed} instructions: {trap}");
+                break;
+            }
+        };
+        if let Some(coverage) = coverage.as_deref_mut() {
+            coverage.record(inst);
+        }
 
         // 2. Decode.
         let (decoded, len) = Instruction::parse_with_len(inst);
         // 3. Execute.
-        cpu.execute(decoded, len, &mut mmu);
+        if let Err(trap) = cpu.execute(decoded, len, &mut mmu) {
+            eprintln!("halted after {executed} instructions: {trap}");
+            break;
+        }
+        executed += 1;
     }
 }
@@ -0,0 +1,79 @@
+//! Hand-built RV32 executables for tests, so emulator, game and server tests
+//! can load bots without a RISC-V toolchain. Enabled by the `test-support`
+//! feature.
+
+/// A minimal RV32 executable with one zero-filled `memsz`-byte segment
+/// at address 0, like a bot whose statics need that much memory.
+pub fn elf_with_segment(memsz: u32) -> Vec<u8> {
+    elf_with_load(0, &[], memsz, 7)
+}
+
+/// A minimal RV32 executable running `code` from address 0x1000.
+pub fn elf_with_code(code: &[u32]) -> Vec<u8> {
+    let bytes: Vec<u8> = code.iter().flat_map(|word| word.to_le_bytes()).collect();
+    elf_with_load(0x1000, &bytes, bytes.len() as u32, 5)
+}
+
+/// One `PT_LOAD` segment at `vaddr` (also the entry point) holding
+/// `bytes`, zero-filled up to `memsz`.
+pub fn elf_with_load(vaddr: u32, bytes: &[u8], memsz: u32, flags: u32) -> Vec<u8> {
+    let mut elf = Vec::new();
+    elf.extend_from_slice(b"\x7fELF\x01\x01\x01\0\0\0\0\0\0\0\0\0");
+    elf.extend_from_slice(&2u16.to_le_bytes()); // e_type: EXEC
+    elf.extend_from_slice(&0xf3u16.to_le_bytes()); // e_machine: RISC-V
+    elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    elf.extend_from_slice(&vaddr.to_le_bytes()); // e_entry
+    elf.extend_from_slice(&52u32.to_le_bytes()); // e_phoff
+    elf.extend_from_slice(&0u32.to_le_bytes()); // e_shoff
+    elf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+    elf.extend_from_slice(&52u16.to_le_bytes()); // e_ehsize
+    elf.extend_from_slice(&32u16.to_le_bytes()); // e_phentsize
+    elf.extend_from_slice(&1u16.to_le_bytes()); // e_phnum
+    elf.extend_from_slice(&40u16.to_le_bytes()); // e_shentsize
+    elf.extend_from_slice(&0u16.to_le_bytes()); // e_shnum
+    elf.extend_from_slice(&0u16.to_le_bytes()); // e_shstrndx
+    let offset = if bytes.is_empty() { 0 } else { 84 };
+    let filesz = bytes.len() as u32;
+    for field in [1, offset, vaddr, vaddr, filesz, memsz, flags, 16] {
+        // PT_LOAD, offset, vaddr, paddr, filesz, memsz, flags, align
+        elf.extend_from_slice(&field.to_le_bytes());
+    }
+    elf.extend_from_slice(bytes);
+    elf
+}
+
+/// `elf` plus a section table holding one `name` section with `data`.
+pub fn with_section(mut elf: Vec<u8>, name: &str, data: &[u8]) -> Vec<u8> {
+    let data_offset = elf.len() as u32;
+    elf.extend_from_slice(data);
+    let names = format!("\0{name}\0.shstrtab\0");
+    let names_offset = elf.len() as u32;
+    elf.extend_from_slice(names.as_bytes());
+    elf.resize(elf.len().next_multiple_of(4), 0);
+    let shoff = elf.len() as u32;
+    let sections = [
+        [0; 10],
+        // name, type PROGBITS, flags, addr, offset, size, link, info, align, entsize
+        [1, 1, 0, 0, data_offset, data.len() as u32, 0, 0, 1, 0],
+        // `.shstrtab`, type STRTAB
+        [
+            name.len() as u32 + 2,
+            3,
+            0,
+            0,
+            names_offset,
+            names.len() as u32,
+            0,
+            0,
+            1,
+            0,
+        ],
+    ];
+    for field in sections.iter().flatten() {
+        elf.extend_from_slice(&field.to_le_bytes());
+    }
+    elf[32..36].copy_from_slice(&shoff.to_le_bytes()); // e_shoff
+    elf[48..50].copy_from_slice(&3u16.to_le_bytes()); // e_shnum
+    elf[50..52].copy_from_slice(&2u16.to_le_bytes()); // e_shstrndx
+    elf
+}
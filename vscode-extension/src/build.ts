@@ -1,26 +1,174 @@
-import { spawn } from 'child_process';
+import { execFile, spawn } from 'child_process';
 import * as vscode from 'vscode';
 
-import { defaultArtifactTarget } from './config';
+import { BuildProfile, buildProfile, defaultArtifactTarget } from './config';
+import { CargoDiagnostic, checkDiagnostics, parseCargoMessages } from './diagnostics';
 
-export async function buildBinary(rootPath: string, binName: string, targetTriple?: string): Promise<void> {
+/** A failed build that rustc reported structured diagnostics for. */
+export class CargoBuildError extends Error {
+  constructor(
+    message: string,
+    readonly diagnostics: CargoDiagnostic[]
+  ) {
+    super(message);
+  }
+}
+
+/** Selects the owning package when the bot lives in a cargo workspace. */
+function packageArgs(crateName?: string): string[] {
+  return crateName ? ['-p', crateName] : [];
+}
+
+/** Cargo builds the dev profile (output in `debug/`) unless told `--release`. */
+function profileArgs(profile: BuildProfile): string[] {
+  return profile === 'release' ? ['--release'] : [];
+}
+
+/**
+ * Git commit of the bot source, suffixed with `-dirty` when the work tree has
+ * uncommitted changes. Undefined outside a git checkout or without git.
+ */
+export async function gitBuildId(rootPath: string): Promise<string | undefined> {
+  const git = (args: string[]) =>
+    new Promise<string | undefined>((resolve) => {
+      execFile('git', args, { cwd: rootPath }, (error, stdout) => {
+        resolve(error ? undefined : stdout.trim());
+      });
+    });
+
+  const commit = await git(['rev-parse', 'HEAD']);
+  if (!commit) {
+    return undefined;
+  }
+  const status = await git(['status', '--porcelain']);
+  return status ? `${commit}-dirty` : commit;
+}
+
+/**
+ * Builds a bot binary and returns the compiler diagnostics (warnings) of the
+ * successful build. Failures reject with a `CargoBuildError` when rustc
+ * produced diagnostics, or a plain `Error` with the raw cargo output otherwise.
+ * `profile` defaults to the `botracers.buildProfile` setting.
+ */
+export async function buildBinary(
+  rootPath: string,
+  binName: string,
+  crateName?: string,
+  targetTriple?: string,
+  profile?: BuildProfile
+): Promise<CargoDiagnostic[]> {
+  const target = targetTriple ?? defaultArtifactTarget();
+  const selected = profile ?? buildProfile();
+
+  return await vscode.window.withProgress(
+    {
+      location: vscode.ProgressLocation.Notification,
+      title: `Building ${binName} (${selected})`,
+      cancellable: false
+    },
+    async () => await runCargoBuild(rootPath, binName, crateName, target, selected)
+  );
+}
+
+/**
+ * Type-checks a bot binary with `cargo check` without producing an ELF and
+ * returns the compiler diagnostics (empty when the bot is clean).
+ */
+export async function checkBinary(
+  rootPath: string,
+  binName: string,
+  crateName?: string,
+  targetTriple?: string,
+  profile?: BuildProfile
+): Promise<CargoDiagnostic[]> {
   const target = targetTriple ?? defaultArtifactTarget();
+  const selected = profile ?? buildProfile();
 
-  await vscode.window.withProgress(
+  return await vscode.window.withProgress(
     {
       location: vscode.ProgressLocation.Notification,
-      title: `Building ${binName}`,
+      title: `Checking ${binName}`,
       cancellable: false
     },
-    async () => await runCargoBuild(rootPath, binName, target)
+    async () => await runCargoCheck(rootPath, binName, crateName, target, selected)
   );
 }
 
-function runCargoBuild(rootPath: string, binName: string, target: string): Promise<void> {
+function runCargoCheck(
+  rootPath: string,
+  binName: string,
+  crateName: string | undefined,
+  target: string,
+  profile: BuildProfile
+): Promise<CargoDiagnostic[]> {
   return new Promise((resolve, reject) => {
-    const args = ['build', '--release', '--target', target, '--bin', binName];
+    const args = [
+      'check',
+      ...profileArgs(profile),
+      '--target',
+      target,
+      ...packageArgs(crateName),
+      '--bin',
+      binName,
+      '--message-format=json'
+    ];
+    const child = spawn('cargo', args, {
+      cwd: rootPath,
+      shell: false
+    });
+
+    let stderr = '';
+    let stdout = '';
+
+    child.stdout?.on('data', (chunk: Buffer) => {
+      stdout += chunk.toString();
+    });
+
+    child.stderr?.on('data', (chunk: Buffer) => {
+      stderr += chunk.toString();
+    });
+
+    child.on('error', (error) => {
+      reject(error);
+    });
+
+    child.on('close', (code) => {
+      const diagnostics = checkDiagnostics(code, stdout);
+      if (diagnostics) {
+        resolve(diagnostics);
+        return;
+      }
+
+      // Failed before rustc reported anything (bad manifest, missing target, ...).
+      reject(new Error(`cargo check failed for '${binName}' (${target})\n${truncateTail(stderr.trim())}`));
+    });
+  });
+}
+
+async function runCargoBuild(
+  rootPath: string,
+  binName: string,
+  crateName: string | undefined,
+  target: string,
+  profile: BuildProfile
+): Promise<CargoDiagnostic[]> {
+  const buildId = await gitBuildId(rootPath);
+  return await new Promise((resolve, reject) => {
+    const args = [
+      'build',
+      ...profileArgs(profile),
+      '--target',
+      target,
+      ...packageArgs(crateName),
+      '--bin',
+      binName,
+      '--message-format=json'
+    ];
+    // The SDK embeds BOTRACERS_BUILD_ID in the ELF for provenance.
+    const env = buildId ? { ...process.env, BOTRACERS_BUILD_ID: buildId } : process.env;
     const child = spawn('cargo', args, {
       cwd: rootPath,
+      env,
       shell: false
     });
 
@@ -40,14 +188,35 @@ function runCargoBuild(rootPath: string, binName: string, target: string): Promi
     });
 
     child.on('close', (code) => {
+      const diagnostics = parseCargoMessages(stdout);
       if (code === 0) {
-        resolve();
+        resolve(diagnostics);
+        return;
+      }
+
+      const errors = diagnostics.filter((d) => d.level === 'error');
+      if (errors.length > 0) {
+        const rendered = errors.map((d) => d.rendered).join('\n\n');
+        reject(
+          new CargoBuildError(
+            `cargo build failed for '${binName}' (${target}): ${errors.length} error(s)\n${truncateTail(rendered)}`,
+            diagnostics
+          )
+        );
         return;
       }
 
-      const combined = `${stdout}\n${stderr}`.trim();
-      const tail = combined.length > 3000 ? combined.slice(-3000) : combined;
-      reject(new Error(`cargo build failed for '${binName}' (${target})\n${tail}`));
+      // No JSON diagnostics (e.g. manifest or linker errors): fall back to raw output.
+      const plainStdout = stdout
+        .split(/\r?\n/)
+        .filter((line) => !line.startsWith('{'))
+        .join('\n');
+      const combined = `${plainStdout}\n${stderr}`.trim();
+      reject(new Error(`cargo build failed for '${binName}' (${target})\n${truncateTail(combined)}`));
     });
   });
 }
+
+function truncateTail(text: string): string {
+  return text.length > 3000 ? text.slice(-3000) : text;
+}
@@ -6,11 +6,19 @@ export const DEFAULT_TARGET_TRIPLE = 'riscv32imafc-unknown-none-elf';
 
 export type ServerProfile = 'production' | 'localhost' | 'custom';
 
+/** Cargo profile for local bot builds; also the `target/<triple>/` subdirectory it writes to. */
+export type BuildProfile = 'release' | 'debug';
+
 export function defaultArtifactTarget(): string {
   const cfg = vscode.workspace.getConfiguration('botracers');
   return cfg.get<string>('defaultArtifactTarget') ?? DEFAULT_TARGET_TRIPLE;
 }
 
+export function buildProfile(): BuildProfile {
+  const cfg = vscode.workspace.getConfiguration('botracers');
+  return cfg.get<string>('buildProfile') === 'debug' ? 'debug' : 'release';
+}
+
 export function configuredBotWorkspacePath(): string | undefined {
   const cfg = vscode.workspace.getConfiguration('botracers');
   const value = cfg.get<string>('botWorkspacePath');
@@ -0,0 +1,100 @@
+import * as path from 'path';
+
+export type CargoDiagnostic = {
+  level: string;
+  message: string;
+  /** Path of the primary span, relative to the workspace root cargo ran in. */
+  file?: string;
+  line?: number;
+  column?: number;
+  rendered: string;
+};
+
+type CompilerSpan = {
+  file_name: string;
+  line_start: number;
+  column_start: number;
+  is_primary: boolean;
+};
+
+type CompilerMessage = {
+  reason: string;
+  message?: {
+    level: string;
+    message: string;
+    rendered: string | null;
+    spans: CompilerSpan[];
+  };
+};
+
+// rustc closes every failing crate with span-less summaries that repeat the counts.
+const SUMMARY_MESSAGE = /^(aborting due to|\d+ warnings? emitted)/;
+
+/**
+ * Extracts compiler diagnostics from `cargo --message-format=json` stdout.
+ * Lines that are not JSON compiler messages are ignored.
+ */
+export function parseCargoMessages(stdout: string): CargoDiagnostic[] {
+  const diagnostics: CargoDiagnostic[] = [];
+  for (const line of stdout.split(/\r?\n/)) {
+    if (!line.startsWith('{')) {
+      continue;
+    }
+
+    let parsed: CompilerMessage;
+    try {
+      parsed = JSON.parse(line) as CompilerMessage;
+    } catch {
+      continue;
+    }
+
+    const message = parsed.message;
+    if (parsed.reason !== 'compiler-message' || !message) {
+      continue;
+    }
+    if (message.spans.length === 0 && SUMMARY_MESSAGE.test(message.message)) {
+      continue;
+    }
+
+    const span = message.spans.find((s) => s.is_primary) ?? message.spans[0];
+    diagnostics.push({
+      level: message.level,
+      message: message.message,
+      file: span?.file_name,
+      line: span?.line_start,
+      column: span?.column_start,
+      rendered: (message.rendered ?? message.message).trimEnd()
+    });
+  }
+  return diagnostics;
+}
+
+/**
+ * Outcome of a `cargo check` that exited with `code`: its diagnostics when it
+ * succeeded or rustc reported errors (an empty list means the bot is clean),
+ * or `undefined` when cargo failed before rustc reported anything.
+ */
+export function checkDiagnostics(code: number | null, stdout: string): CargoDiagnostic[] | undefined {
+  const diagnostics = parseCargoMessages(stdout);
+  if (code === 0 || diagnostics.some((d) => d.level === 'error')) {
+    return diagnostics;
+  }
+  return undefined;
+}
+
+/**
+ * Diagnostics that point at a file, grouped by absolute path under
+ * `rootPath`. The Problems panel is cleared and refilled from this, so a
+ * clean build leaves it empty.
+ */
+export function diagnosticsByFile(rootPath: string, diagnostics: CargoDiagnostic[]): Map<string, CargoDiagnostic[]> {
+  const byFile = new Map<string, CargoDiagnostic[]>();
+  for (const diagnostic of diagnostics) {
+    if (!diagnostic.file || diagnostic.line === undefined) {
+      continue;
+    }
+    const file = path.resolve(rootPath, diagnostic.file);
+    byFile.set(file, [...(byFile.get(file) ?? []), diagnostic]);
+  }
+  return byFile;
+}
@@ -91,6 +91,10 @@ export function activate(context: vscode.ExtensionContext): void {
     await provider.buildBinaryItem(item);
   });
 
+  registerCommand(context, 'botracers.view.checkBinary', async (item?: BotRacersItem) => {
+    await provider.checkBinaryItem(item);
+  });
+
   registerCommand(context, 'botracers.view.revealElfPath', async (item?: BotRacersItem) => {
     await provider.revealElfPath(item);
   });
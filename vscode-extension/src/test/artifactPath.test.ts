@@ -0,0 +1,31 @@
+import { settings } from './vscodeStub';
+
+import * as assert from 'node:assert/strict';
+import * as path from 'node:path';
+import { afterEach, test } from 'node:test';
+
+import { buildProfile, DEFAULT_TARGET_TRIPLE } from '../config';
+import { artifactOutputPath } from '../workspace';
+
+const root = path.resolve('/work/bot');
+
+afterEach(() => settings.clear());
+
+test('the debug profile resolves to the debug target directory', () => {
+  settings.set('botracers.buildProfile', 'debug');
+
+  assert.equal(
+    artifactOutputPath(root, 'car', undefined, buildProfile()),
+    path.join(root, 'target', DEFAULT_TARGET_TRIPLE, 'debug', 'car')
+  );
+});
+
+test('release is the default profile and honors a configured target', () => {
+  settings.set('botracers.defaultArtifactTarget', 'riscv32imac-unknown-none-elf');
+
+  assert.equal(buildProfile(), 'release');
+  assert.equal(
+    artifactOutputPath(root, 'car'),
+    path.join(root, 'target', 'riscv32imac-unknown-none-elf', 'release', 'car')
+  );
+});
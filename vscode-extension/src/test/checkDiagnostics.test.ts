@@ -0,0 +1,59 @@
+import * as assert from 'node:assert/strict';
+import * as path from 'node:path';
+import { test } from 'node:test';
+
+import { checkDiagnostics, diagnosticsByFile } from '../diagnostics';
+import { compilerMessage } from './fixtures';
+
+const root = path.resolve('/work/bot');
+
+const failedCheck = [
+  compilerMessage('error', 'cannot find value `sped` in this scope', [
+    { file_name: 'src/bin/car.rs', line_start: 8, column_start: 5, is_primary: true }
+  ]),
+  compilerMessage('warning', 'unused import: `core::fmt`', [
+    { file_name: 'src/lib.rs', line_start: 1, column_start: 5, is_primary: true }
+  ]),
+  compilerMessage('error', 'mismatched types', [
+    { file_name: 'src/bin/car.rs', line_start: 14, column_start: 20, is_primary: true }
+  ]),
+  compilerMessage('error', 'aborting due to 2 previous errors', []),
+  JSON.stringify({ reason: 'build-finished', success: false })
+].join('\n');
+
+const cleanCheck = [
+  JSON.stringify({ reason: 'compiler-artifact', package_id: 'bot 0.1.0', fresh: false }),
+  JSON.stringify({ reason: 'build-finished', success: true })
+].join('\n');
+
+test('a failing check publishes its errors and warnings per file', () => {
+  const diagnostics = checkDiagnostics(101, failedCheck);
+  assert.ok(diagnostics);
+
+  const byFile = diagnosticsByFile(root, diagnostics);
+  assert.deepEqual(
+    [...byFile.keys()],
+    [path.join(root, 'src/bin/car.rs'), path.join(root, 'src/lib.rs')]
+  );
+  assert.deepEqual(
+    byFile.get(path.join(root, 'src/bin/car.rs'))?.map((d) => [d.level, d.line, d.column]),
+    [
+      ['error', 8, 5],
+      ['error', 14, 20]
+    ]
+  );
+  assert.deepEqual(
+    byFile.get(path.join(root, 'src/lib.rs'))?.map((d) => d.level),
+    ['warning']
+  );
+});
+
+test('a clean check clears the published diagnostics', () => {
+  const diagnostics = checkDiagnostics(0, cleanCheck);
+  assert.deepEqual(diagnostics, []);
+  assert.equal(diagnosticsByFile(root, diagnostics ?? []).size, 0);
+});
+
+test('a check that fails before rustc reports anything is an error', () => {
+  assert.equal(checkDiagnostics(101, 'error: failed to parse manifest'), undefined);
+});
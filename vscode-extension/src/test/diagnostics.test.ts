@@ -0,0 +1,81 @@
+import * as assert from 'node:assert/strict';
+import { test } from 'node:test';
+
+import { parseCargoMessages } from '../diagnostics';
+import { compilerMessage } from './fixtures';
+
+const cargoOutput = [
+  JSON.stringify({ reason: 'compiler-artifact', package_id: 'botracers-bot-sdk 0.1.0', fresh: true }),
+  'warning: this line is not JSON',
+  compilerMessage('warning', 'unused variable: `speed`', [
+    { file_name: 'src/bin/car.rs', line_start: 12, column_start: 9, is_primary: true }
+  ]),
+  compilerMessage(
+    'error',
+    'mismatched types',
+    [
+      { file_name: 'src/lib.rs', line_start: 3, column_start: 1, is_primary: false },
+      { file_name: 'src/bin/car.rs', line_start: 20, column_start: 17, is_primary: true }
+    ],
+    'error[E0308]: mismatched types\n  --> src/bin/car.rs:20:17\n\n'
+  ),
+  compilerMessage('error', 'aborting due to 1 previous error', []),
+  compilerMessage('warning', '1 warning emitted', []),
+  '{"reason":"compiler-message","message":',
+  JSON.stringify({ reason: 'build-finished', success: false })
+].join('\n');
+
+test('compiler messages become diagnostics at their primary span', () => {
+  assert.deepEqual(parseCargoMessages(cargoOutput), [
+    {
+      level: 'warning',
+      message: 'unused variable: `speed`',
+      file: 'src/bin/car.rs',
+      line: 12,
+      column: 9,
+      rendered: 'warning: unused variable: `speed`'
+    },
+    {
+      level: 'error',
+      message: 'mismatched types',
+      file: 'src/bin/car.rs',
+      line: 20,
+      column: 17,
+      rendered: 'error[E0308]: mismatched types\n  --> src/bin/car.rs:20:17'
+    }
+  ]);
+});
+
+test('span-less messages keep their text and fall back to it when unrendered', () => {
+  const output = [
+    compilerMessage('error', 'linking with `rust-lld` failed', [], null),
+    compilerMessage('warning', 'no span here', [], 'warning: no span here\r\n')
+  ].join('\r\n');
+
+  assert.deepEqual(parseCargoMessages(output), [
+    {
+      level: 'error',
+      message: 'linking with `rust-lld` failed',
+      file: undefined,
+      line: undefined,
+      column: undefined,
+      rendered: 'linking with `rust-lld` failed'
+    },
+    {
+      level: 'warning',
+      message: 'no span here',
+      file: undefined,
+      line: undefined,
+      column: undefined,
+      rendered: 'warning: no span here'
+    }
+  ]);
+});
+
+test('output without compiler messages yields no diagnostics', () => {
+  assert.deepEqual(parseCargoMessages(''), []);
+  assert.deepEqual(
+    parseCargoMessages(JSON.stringify({ reason: 'build-finished', success: true })),
+    []
+  );
+});
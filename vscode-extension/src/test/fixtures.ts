@@ -0,0 +1,13 @@
+/** One `compiler-message` line of `cargo --message-format=json` output. */
+export function compilerMessage(
+  level: string,
+  message: string,
+  spans: { file_name: string; line_start: number; column_start: number; is_primary: boolean }[],
+  rendered: string | null = `${level}: ${message}\n`
+): string {
+  return JSON.stringify({
+    reason: 'compiler-message',
+    package_id: 'bot 0.1.0 (path+file:///work/bot)',
+    message: { level, message, rendered, spans }
+  });
+}
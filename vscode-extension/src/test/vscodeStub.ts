@@ -0,0 +1,33 @@
+import Module = require('module');
+
+/**
+ * `botracers.*` settings the stubbed `vscode.workspace.getConfiguration`
+ * reports; tests set and delete entries to exercise configured behavior.
+ */
+export const settings = new Map<string, unknown>();
+
+const vscodeStub = {
+  workspace: {
+    workspaceFolders: undefined,
+    getConfiguration: (section: string) => ({
+      get: <T>(key: string): T | undefined => settings.get(`${section}.${key}`) as T | undefined
+    })
+  }
+};
+
+// The `vscode` module only exists inside the extension host. Tests for the
+// helpers that read settings import this file first so `require('vscode')`
+// resolves to the stub above under plain `node --test`.
+const loader = Module as unknown as {
+  _resolveFilename(this: unknown, request: string, ...rest: unknown[]): string;
+};
+const resolveFilename = loader._resolveFilename;
+loader._resolveFilename = function (this: unknown, request: string, ...rest: unknown[]): string {
+  return request === 'vscode' ? 'vscode' : resolveFilename.call(this, request, ...rest);
+};
+require.cache['vscode'] = {
+  id: 'vscode',
+  filename: 'vscode',
+  loaded: true,
+  exports: vscodeStub
+} as NodeModule;
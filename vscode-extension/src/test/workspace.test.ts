@@ -0,0 +1,70 @@
+import './vscodeStub';
+
+import * as assert from 'node:assert/strict';
+import * as fs from 'node:fs';
+import * as os from 'node:os';
+import * as path from 'node:path';
+import { after, test } from 'node:test';
+
+import { listLocalBinaries } from '../workspace';
+
+const tempRoot = fs.mkdtempSync(path.join(os.tmpdir(), 'botracers-workspace-'));
+after(() => fs.rmSync(tempRoot, { recursive: true, force: true }));
+
+function writeFile(root: string, relative: string, contents: string): void {
+  const file = path.join(root, relative);
+  fs.mkdirSync(path.dirname(file), { recursive: true });
+  fs.writeFileSync(file, contents);
+}
+
+test('a two-member workspace lists every member binary, built from the root', () => {
+  const root = path.join(tempRoot, 'two-members');
+  writeFile(root, 'Cargo.toml', '[workspace]\nresolver = "2"\nmembers = ["bots/*", "tools/lapper"]\n');
+  writeFile(
+    root,
+    'bots/chaser/Cargo.toml',
+    '[package]\nname = "chaser"\nversion = "0.1.0"\n\n[[bin]]\nname = "chase"\npath = "src/main.rs"\n'
+  );
+  writeFile(root, 'bots/chaser/src/main.rs', 'fn main() {}\n');
+  writeFile(root, 'tools/lapper/Cargo.toml', '[package]\nname = "lapper"\nversion = "0.1.0"\n');
+  writeFile(root, 'tools/lapper/src/bin/fast.rs', 'fn main() {}\n');
+  writeFile(root, 'tools/lapper/src/bin/steady.rs', 'fn main() {}\n');
+  // Not a crate: `bots/*` only expands to directories with a Cargo.toml.
+  writeFile(root, 'bots/notes/README.md', 'scratch\n');
+
+  assert.deepEqual(listLocalBinaries(root), [
+    {
+      name: 'chase',
+      rootPath: root,
+      crateName: 'chaser',
+      sourcePath: path.join(root, 'bots/chaser/src/main.rs')
+    },
+    {
+      name: 'fast',
+      rootPath: root,
+      crateName: 'lapper',
+      sourcePath: path.join(root, 'tools/lapper/src/bin/fast.rs')
+    },
+    {
+      name: 'steady',
+      rootPath: root,
+      crateName: 'lapper',
+      sourcePath: path.join(root, 'tools/lapper/src/bin/steady.rs')
+    }
+  ]);
+});
+
+test('a plain crate lists its own binaries without a crate name', () => {
+  const root = path.join(tempRoot, 'single-crate');
+  writeFile(root, 'Cargo.toml', '[package]\nname = "bot"\nversion = "0.1.0"\n');
+  writeFile(root, 'src/bin/car.rs', 'fn main() {}\n');
+
+  assert.deepEqual(listLocalBinaries(root), [
+    {
+      name: 'car',
+      rootPath: root,
+      crateName: undefined,
+      sourcePath: path.join(root, 'src/bin/car.rs')
+    }
+  ]);
+});
@@ -2,6 +2,15 @@ export type Capabilities = {
   auth_required: boolean;
   mode: string;
   registration_enabled?: boolean;
+  default_artifact_public?: boolean;
+  max_artifact_bytes?: number | null;
+  allowed_targets?: string[];
+  compile_supported?: boolean;
+  version?: string;
+  auth_header?: string;
+  auth_scheme?: string;
+  supported_abi_versions?: number[];
+  max_field_size?: number | null;
 };
 
 export type UserInfo = {
@@ -23,7 +32,18 @@ export type ArtifactSummary = {
   target: string;
   is_public: boolean;
   owned_by_me: boolean;
+  starred?: boolean;
+  abi_version?: number | null;
+  build_id?: string | null;
+  /** Verified races this artifact took part in. */
+  races_count?: number;
+  /** Races it finished first in. */
+  wins?: number;
+  /** Fastest lap across its verified races. */
+  best_lap_secs?: number | null;
   created_at: string;
+  /** Set only in `?deleted=true` (trash) listings. */
+  deleted_at?: string | null;
 };
 
 export type UploadArtifactRequest = {
@@ -31,8 +51,13 @@ export type UploadArtifactRequest = {
   note: string | null;
   target: string;
   elf_base64: string;
+  /** Omit to use the server's default visibility. */
+  is_public?: boolean;
+  /** Source revision the ELF was built from, when known. */
+  build_id?: string | null;
 };
 
 export type UploadArtifactResponse = {
   artifact_id: number;
+  name?: string;
 };
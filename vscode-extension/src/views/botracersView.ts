@@ -10,8 +10,9 @@ import {
   uploadArtifact
 } from '../api';
 import { clearToken, readToken } from '../auth';
-import { buildBinary } from '../build';
-import { defaultArtifactTarget } from '../config';
+import { buildBinary, CargoBuildError, checkBinary, gitBuildId } from '../build';
+import { BuildProfile, buildProfile, defaultArtifactTarget } from '../config';
+import { CargoDiagnostic, diagnosticsByFile } from '../diagnostics';
 import { ArtifactSummary } from '../types';
 import {
   artifactOutputPath,
@@ -38,15 +39,19 @@ export class BotRacersItem extends vscode.TreeItem {
     this.contextValue = contextValue(node);
 
     if (node.kind === 'localBin') {
-      this.description = node.bin.rootPath;
-      this.tooltip = `${node.bin.name} (${node.bin.rootPath})`;
+      this.description = node.bin.crateName ?? node.bin.rootPath;
+      this.tooltip = node.bin.crateName
+        ? `${node.bin.crateName}::${node.bin.name} (${node.bin.rootPath})`
+        : `${node.bin.name} (${node.bin.rootPath})`;
       this.iconPath = new vscode.ThemeIcon('symbol-method');
     }
 
     if (node.kind === 'remoteArtifact') {
       const artifact = node.artifact;
       this.description = `${artifact.owner_username} · ${artifact.is_public ? 'public' : 'private'}`;
-      this.tooltip = `${artifact.name} (#${artifact.id})`;
+      this.tooltip = artifact.build_id
+        ? `${artifact.name} (#${artifact.id}) built from ${artifact.build_id}`
+        : `${artifact.name} (#${artifact.id})`;
       this.iconPath = new vscode.ThemeIcon('package');
     }
 
@@ -108,8 +113,11 @@ export class BotRacersViewProvider implements vscode.TreeDataProvider<BotRacersI
   private workspaceRoot: string | undefined;
   private localBinaries: LocalBinary[] = [];
   private artifacts: ArtifactSummary[] = [];
+  private readonly diagnostics = vscode.languages.createDiagnosticCollection('botracers');
 
-  constructor(private readonly context: vscode.ExtensionContext) {}
+  constructor(private readonly context: vscode.ExtensionContext) {
+    context.subscriptions.push(this.diagnostics);
+  }
 
   async refreshArtifacts(): Promise<void> {
     this.artifacts = [];
@@ -223,8 +231,60 @@ export class BotRacersViewProvider implements vscode.TreeDataProvider<BotRacersI
     if (!node || node.kind !== 'localBin') {
       return;
     }
-    await buildBinary(node.bin.rootPath, node.bin.name);
-    void vscode.window.showInformationMessage(`Built binary '${node.bin.name}'`);
+    const profile = buildProfile();
+    await this.buildWithDiagnostics(node.bin, profile);
+    void vscode.window.showInformationMessage(`Built binary '${node.bin.name}' (${profile})`);
+  }
+
+  async checkBinaryItem(item?: BotRacersItem): Promise<void> {
+    const node = item?.node;
+    if (!node || node.kind !== 'localBin') {
+      return;
+    }
+    const diagnostics = await checkBinary(node.bin.rootPath, node.bin.name, node.bin.crateName);
+    this.publishDiagnostics(node.bin.rootPath, diagnostics);
+
+    const errors = diagnostics.filter((d) => d.level === 'error').length;
+    const warnings = diagnostics.filter((d) => d.level === 'warning').length;
+    if (errors === 0 && warnings === 0) {
+      void vscode.window.showInformationMessage(`No problems in '${node.bin.name}'`);
+    } else {
+      void vscode.window.showWarningMessage(
+        `'${node.bin.name}': ${errors} error(s), ${warnings} warning(s). See the Problems panel.`
+      );
+    }
+  }
+
+  private async buildWithDiagnostics(bin: LocalBinary, profile: BuildProfile): Promise<void> {
+    try {
+      this.publishDiagnostics(
+        bin.rootPath,
+        await buildBinary(bin.rootPath, bin.name, bin.crateName, undefined, profile)
+      );
+    } catch (error) {
+      if (error instanceof CargoBuildError) {
+        this.publishDiagnostics(bin.rootPath, error.diagnostics);
+      }
+      throw error;
+    }
+  }
+
+  private publishDiagnostics(rootPath: string, diagnostics: CargoDiagnostic[]): void {
+    this.diagnostics.clear();
+    for (const [file, fileDiagnostics] of diagnosticsByFile(rootPath, diagnostics)) {
+      const entries = fileDiagnostics.map((diagnostic) => {
+        const line = Math.max((diagnostic.line ?? 1) - 1, 0);
+        const column = Math.max((diagnostic.column ?? 1) - 1, 0);
+        const entry = new vscode.Diagnostic(
+          new vscode.Range(line, column, line, column),
+          diagnostic.message,
+          diagnostic.level === 'error' ? vscode.DiagnosticSeverity.Error : vscode.DiagnosticSeverity.Warning
+        );
+        entry.source = 'cargo';
+        return entry;
+      });
+      this.diagnostics.set(vscode.Uri.file(file), entries);
+    }
   }
 
   async revealElfPath(item?: BotRacersItem): Promise<void> {
@@ -233,7 +293,7 @@ export class BotRacersViewProvider implements vscode.TreeDataProvider<BotRacersI
       return;
     }
 
-    const elfPath = artifactOutputPath(node.bin.rootPath, node.bin.name);
+    const elfPath = artifactOutputPath(node.bin.rootPath, node.bin.name, undefined, buildProfile());
     if (!fs.existsSync(elfPath)) {
       throw new Error(`ELF not found: ${elfPath}. Build the binary first.`);
     }
@@ -340,9 +400,11 @@ export class BotRacersViewProvider implements vscode.TreeDataProvider<BotRacersI
   }
 
   private async uploadFromLocalBinary(bin: LocalBinary, defaultName: string): Promise<void> {
-    await buildBinary(bin.rootPath, bin.name);
+    // Uploads are always release builds: debug ELFs are larger and need
+    // many more instructions per tick, so they race worse.
+    await this.buildWithDiagnostics(bin, 'release');
 
-    const elfPath = artifactOutputPath(bin.rootPath, bin.name);
+    const elfPath = artifactOutputPath(bin.rootPath, bin.name, undefined, 'release');
     if (!fs.existsSync(elfPath)) {
       throw new Error(`ELF not found after build: ${elfPath}`);
     }
@@ -363,13 +425,15 @@ export class BotRacersViewProvider implements vscode.TreeDataProvider<BotRacersI
     const target = defaultArtifactTarget();
 
     const bytes = fs.readFileSync(elfPath);
+    const buildId = await gitBuildId(bin.rootPath);
 
     const data = await uploadArtifact(
       {
         name,
         note: note && note.trim().length > 0 ? note.trim() : null,
         target,
-        elf_base64: bytes.toString('base64')
+        elf_base64: bytes.toString('base64'),
+        build_id: buildId ?? null
       },
       this.token
     );
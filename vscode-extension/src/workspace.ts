@@ -2,11 +2,14 @@ import * as fs from 'fs';
 import * as path from 'path';
 import * as vscode from 'vscode';
 
-import { configuredBotWorkspacePath, defaultArtifactTarget } from './config';
+import { BuildProfile, configuredBotWorkspacePath, defaultArtifactTarget } from './config';
 
 export type LocalBinary = {
   name: string;
+  /** Directory cargo runs in; the workspace root for workspace members. */
   rootPath: string;
+  /** Package that owns the binary; set when discovered through a workspace. */
+  crateName?: string;
   sourcePath?: string;
 };
 
@@ -32,7 +35,7 @@ function normalizePath(filePath: string): string {
   return path.normalize(filePath);
 }
 
-function parseExplicitBins(cargoToml: string, rootPath: string): LocalBinary[] {
+function parseExplicitBins(cargoToml: string, crateRoot: string, rootPath: string, crateName?: string): LocalBinary[] {
   const bins: LocalBinary[] = [];
   const sections = cargoToml.split('[[bin]]');
   for (let i = 1; i < sections.length; i += 1) {
@@ -49,23 +52,25 @@ function parseExplicitBins(cargoToml: string, rootPath: string): LocalBinary[] {
       bins.push({
         name,
         rootPath,
-        sourcePath: normalizePath(path.resolve(rootPath, pathMatch[1]))
+        crateName,
+        sourcePath: normalizePath(path.resolve(crateRoot, pathMatch[1]))
       });
       continue;
     }
 
-    const inferredPath = path.join(rootPath, 'src', 'bin', `${name}.rs`);
+    const inferredPath = path.join(crateRoot, 'src', 'bin', `${name}.rs`);
     bins.push({
       name,
       rootPath,
+      crateName,
       sourcePath: fs.existsSync(inferredPath) ? normalizePath(inferredPath) : undefined
     });
   }
   return bins;
 }
 
-function parseImplicitBinFiles(rootPath: string): LocalBinary[] {
-  const srcBin = path.join(rootPath, 'src', 'bin');
+function parseImplicitBinFiles(crateRoot: string, rootPath: string, crateName?: string): LocalBinary[] {
+  const srcBin = path.join(crateRoot, 'src', 'bin');
   if (!fs.existsSync(srcBin)) {
     return [];
   }
@@ -77,19 +82,64 @@ function parseImplicitBinFiles(rootPath: string): LocalBinary[] {
       return {
         name: path.basename(file, '.rs'),
         rootPath,
+        crateName,
         sourcePath: normalizePath(filePath)
       };
     });
 }
 
-export function listLocalBinaries(rootPath: string): LocalBinary[] {
-  if (!hasCargoToml(rootPath)) {
-    return [];
+/** Text of a top-level `[section]` up to the next section header. */
+function tomlSection(cargoToml: string, name: string): string | undefined {
+  const lines = cargoToml.split(/\r?\n/);
+  const start = lines.findIndex((line) => line.trim() === `[${name}]`);
+  if (start < 0) {
+    return undefined;
   }
+  const rest = lines.slice(start + 1);
+  const end = rest.findIndex((line) => /^\s*\[/.test(line));
+  return (end < 0 ? rest : rest.slice(0, end)).join('\n');
+}
 
-  const cargoToml = fs.readFileSync(cargoTomlPath(rootPath), 'utf8');
-  const explicit = parseExplicitBins(cargoToml, rootPath);
-  const implicit = parseImplicitBinFiles(rootPath);
+function parsePackageName(cargoToml: string): string | undefined {
+  return tomlSection(cargoToml, 'package')?.match(/\bname\s*=\s*"([^"]+)"/)?.[1];
+}
+
+/**
+ * Member crate directories of a `[workspace]`, with trailing `*` globs
+ * (e.g. `bots/*`) expanded to the subdirectories that contain a Cargo.toml.
+ */
+function parseWorkspaceMembers(cargoToml: string, rootPath: string): string[] | undefined {
+  const workspace = tomlSection(cargoToml, 'workspace');
+  if (workspace === undefined) {
+    return undefined;
+  }
+
+  const list = workspace.match(/\bmembers\s*=\s*\[([^\]]*)\]/)?.[1] ?? '';
+  const members: string[] = [];
+  for (const match of list.matchAll(/"([^"]+)"/g)) {
+    const member = match[1];
+    if (member.endsWith('/*')) {
+      const parent = path.join(rootPath, member.slice(0, -2));
+      if (!fs.existsSync(parent)) {
+        continue;
+      }
+      for (const entry of fs.readdirSync(parent, { withFileTypes: true })) {
+        const dir = path.join(parent, entry.name);
+        if (entry.isDirectory() && hasCargoToml(dir)) {
+          members.push(dir);
+        }
+      }
+      continue;
+    }
+    members.push(path.join(rootPath, member));
+  }
+  return members;
+}
+
+function listCrateBinaries(crateRoot: string, rootPath: string, crateName?: string): LocalBinary[] {
+  const cargoToml = fs.readFileSync(cargoTomlPath(crateRoot), 'utf8');
+  const explicit = parseExplicitBins(cargoToml, crateRoot, rootPath, crateName);
+  const implicit = parseImplicitBinFiles(crateRoot, rootPath, crateName);
   const byName = new Map<string, LocalBinary>();
 
   for (const bin of explicit) {
@@ -108,7 +158,43 @@ export function listLocalBinaries(rootPath: string): LocalBinary[] {
     }
   }
 
-  return [...byName.values()].sort((a, b) => a.name.localeCompare(b.name));
+  return [...byName.values()];
+}
+
+/**
+ * Lists the bot binaries under `rootPath`. A plain crate yields its own bins;
+ * a cargo workspace yields the bins of every member crate (and of the root
+ * package, if any), each tagged with its `crateName` and built from the
+ * workspace root.
+ */
+export function listLocalBinaries(rootPath: string): LocalBinary[] {
+  if (!hasCargoToml(rootPath)) {
+    return [];
+  }
+
+  const cargoToml = fs.readFileSync(cargoTomlPath(rootPath), 'utf8');
+  const members = parseWorkspaceMembers(cargoToml, rootPath);
+  if (members === undefined) {
+    return listCrateBinaries(rootPath, rootPath).sort((a, b) => a.name.localeCompare(b.name));
+  }
+
+  const bins: LocalBinary[] = [];
+  const rootPackage = parsePackageName(cargoToml);
+  if (rootPackage) {
+    bins.push(...listCrateBinaries(rootPath, rootPath, rootPackage));
+  }
+  for (const member of members) {
+    if (!hasCargoToml(member)) {
+      continue;
+    }
+    const memberToml = fs.readFileSync(cargoTomlPath(member), 'utf8');
+    const crateName = parsePackageName(memberToml) ?? path.basename(member);
+    bins.push(...listCrateBinaries(member, rootPath, crateName));
+  }
+
+  return bins.sort(
+    (a, b) => (a.crateName ?? '').localeCompare(b.crateName ?? '') || a.name.localeCompare(b.name)
+  );
 }
 
 export function findLocalBinaryForFile(rootPath: string, filePath: string): LocalBinary | undefined {
@@ -116,7 +202,12 @@ export function findLocalBinaryForFile(rootPath: string, filePath: string): Loca
   return listLocalBinaries(rootPath).find((bin) => bin.sourcePath && normalizePath(bin.sourcePath) === target);
 }
 
-export function artifactOutputPath(rootPath: string, binName: string, targetTriple?: string): string {
+export function artifactOutputPath(
+  rootPath: string,
+  binName: string,
+  targetTriple?: string,
+  profile: BuildProfile = 'release'
+): string {
   const target = targetTriple ?? defaultArtifactTarget();
-  return path.join(rootPath, 'target', target, 'release', binName);
+  return path.join(rootPath, 'target', target, profile, binName);
 }
@@ -1,12 +1,7 @@
 #![no_std]
 #![no_main]
 
-use core::fmt::Write;
-
-use botracers_bot_sdk::{
-    driving::{CarControls, CarState},
-    log, SLOT2, SLOT3,
-};
+use botracers_bot_sdk::prelude::*;
 
 #[unsafe(export_name = "main")]
 fn main() -> ! {